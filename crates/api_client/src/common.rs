@@ -1,14 +1,63 @@
-use std::{sync::Arc, time::Duration, num::NonZeroU32};
+use std::{
+    collections::HashMap,
+    num::NonZeroU32,
+    sync::{Arc, Condvar, Mutex},
+    time::{Duration, Instant},
+};
 
+use governor::{
+    clock::{Clock, QuantaClock},
+    DefaultDirectRateLimiter, DefaultKeyedRateLimiter, Quota, RateLimiter,
+};
 use lazy_static::lazy_static;
-use governor::{RateLimiter, Quota, DefaultDirectRateLimiter, clock::{QuantaClock, Clock}};
 use thiserror::Error;
 use ureq::{Agent, AgentBuilder, Middleware};
 
 pub const USER_AGENT: &str = const_format::formatcp!("modestly-modular-modpack-modifier/{} ureq", env!("CARGO_PKG_VERSION"));
 
 lazy_static! {
-    static ref AGENT: Agent = AgentBuilder::new().user_agent(USER_AGENT).build();
+    static ref AGENT: Agent = download_agent(
+        DownloaderBuilder::DEFAULT_CONNECT_TIMEOUT,
+        DownloaderBuilder::DEFAULT_READ_TIMEOUT,
+        ConnectionPoolSettings::default()
+    );
+}
+
+fn download_agent(connect_timeout: Duration, read_timeout: Duration, connection_pool: ConnectionPoolSettings) -> Agent {
+    connection_pool
+        .apply(AgentBuilder::new())
+        .user_agent(USER_AGENT)
+        .timeout_connect(connect_timeout)
+        .timeout_read(read_timeout)
+        .build()
+}
+
+/// How many idle connections an agent keeps open for reuse. A reused connection skips the TCP and
+/// TLS handshakes, which adds up when a build makes hundreds of requests to the same few hosts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionPoolSettings {
+    /// Idle connections kept across all hosts.
+    pub max_idle: usize,
+    /// Idle connections kept for each host. Requests are made from several threads at once, so
+    /// keeping a single one, as ureq does by default, closes most of them after every burst.
+    pub max_idle_per_host: usize,
+}
+
+impl Default for ConnectionPoolSettings {
+    fn default() -> Self {
+        ConnectionPoolSettings {
+            max_idle: 100,
+            max_idle_per_host: 16,
+        }
+    }
+}
+
+impl ConnectionPoolSettings {
+    fn apply(self, builder: AgentBuilder) -> AgentBuilder {
+        builder
+            .max_idle_connections(self.max_idle)
+            .max_idle_connections_per_host(self.max_idle_per_host)
+    }
 }
 
 #[derive(Error, Debug)]
@@ -17,6 +66,33 @@ pub enum DownloadError {
     Read(std::io::Error),
     #[error("Failed to download file from URL {0}. Error: {1}")]
     Download(String, Box<ureq::Error>),
+    #[error("Cannot download file from URL {0}, network access is disabled.")]
+    Offline(String),
+}
+
+impl DownloadError {
+    /// Describe the error without the URL it was for, for URLs that may contain secrets.
+    pub fn without_url(&self) -> String {
+        match self {
+            DownloadError::Read(e) => format!("Failed to read response to bytes. Error: {e}"),
+            DownloadError::Download(_, e) => match e.as_ref() {
+                ureq::Error::Status(code, _) => format!("Failed to download file. Error: status code {code}"),
+                ureq::Error::Transport(transport) => format!("Failed to download file. Error: {}", transport.kind()),
+            },
+            DownloadError::Offline(_) => "Cannot download file, network access is disabled.".to_owned(),
+        }
+    }
+}
+
+/// Order of results from a project search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchSort {
+    /// How well each project matches the query, as judged by the platform.
+    #[default]
+    Relevance,
+    /// Total downloads, most downloaded first.
+    Downloads,
 }
 
 #[derive(Error, Debug)]
@@ -29,12 +105,33 @@ pub enum ApiError {
     Pagination,
     #[error("No data returned from request.")]
     Empty,
+    #[error("Request skipped after repeated API failures. Requests resume in {} seconds.", .0.as_secs().max(1))]
+    CircuitOpen(Duration),
+    #[error("Request skipped after repeated API failures, while a probe request checks whether the API has recovered.")]
+    CircuitProbing,
+}
+
+impl ApiError {
+    /// Whether the requested data does not exist, as opposed to the request failing. Such errors
+    /// will keep happening until the data is published.
+    pub fn is_not_found(&self) -> bool {
+        match self {
+            ApiError::Empty => true,
+            ApiError::Request(e) => matches!(e.as_ref(), ureq::Error::Status(404, _)),
+            _ => false,
+        }
+    }
 }
 
 pub fn download_file(url: &str) -> Result<Vec<u8>, DownloadError> {
+    download_with(&AGENT, url, &[])
+}
+
+fn download_with(agent: &Agent, url: &str, headers: &[(String, String)]) -> Result<Vec<u8>, DownloadError> {
     let mut response = Vec::new();
-    AGENT
-        .get(url)
+    headers
+        .iter()
+        .fold(agent.get(url), |request, (name, value)| request.set(name, value))
         .call()
         .map_err(|e| DownloadError::Download(url.to_owned(), Box::new(e)))?
         .into_reader()
@@ -43,23 +140,384 @@ pub fn download_file(url: &str) -> Result<Vec<u8>, DownloadError> {
     Ok(response)
 }
 
+/// Get the host of a URL, for grouping requests by server. Falls back to the whole URL if it has no
+/// scheme.
+fn url_host(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or(rest);
+    authority.rsplit_once('@').map_or(authority, |(_, host)| host)
+}
+
+/// Replace the host of a URL, keeping its scheme, user info, path and query.
+fn replace_host(url: &str, new_host: &str) -> String {
+    let (scheme, rest) = url.split_once("://").map_or(("", url), |(scheme, rest)| (scheme, rest));
+    let authority_end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+    let (authority, remainder) = rest.split_at(authority_end);
+    let user_info = authority.rsplit_once('@').map_or(String::new(), |(user_info, _)| format!("{user_info}@"));
+    match scheme {
+        "" => format!("{user_info}{new_host}{remainder}"),
+        _ => format!("{scheme}://{user_info}{new_host}{remainder}"),
+    }
+}
+
+/// Downloads files, bounding how many downloads may run at once and how quickly requests are made
+/// to each host.
+///
+/// Clones share the same bounds, so applications should construct a single [`Downloader`] and
+/// clone it wherever downloads are made.
+#[derive(Clone)]
+pub struct Downloader {
+    inner: Arc<DownloaderInner>,
+}
+
+struct DownloaderInner {
+    agent: Agent,
+    permits: Option<Permits>,
+    host_ratelimit: DefaultKeyedRateLimiter<String>,
+    clock: QuantaClock,
+    offline: bool,
+    // Lowercase hosts mapped to the mirror host to download from instead
+    mirrors: HashMap<String, String>,
+}
+
+/// Minimal counting semaphore for use from synchronous threads.
+struct Permits {
+    available: Mutex<usize>,
+    released: Condvar,
+}
+
+struct Permit<'a>(&'a Permits);
+
+impl Permits {
+    fn new(count: usize) -> Self {
+        Permits {
+            available: Mutex::new(count),
+            released: Condvar::new(),
+        }
+    }
+
+    fn acquire(&self) -> Permit<'_> {
+        let mut available = self.available.lock().unwrap();
+        while *available == 0 {
+            available = self.released.wait(available).unwrap();
+        }
+        *available -= 1;
+        Permit(self)
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        *self.0.available.lock().unwrap() += 1;
+        self.0.released.notify_one();
+    }
+}
+
+pub struct DownloaderBuilder {
+    max_concurrent: Option<usize>,
+    requests_per_minute_per_host: NonZeroU32,
+    offline: bool,
+    mirrors: HashMap<String, String>,
+    connect_timeout: Duration,
+    read_timeout: Duration,
+    connection_pool: ConnectionPoolSettings,
+}
+
+impl Default for DownloaderBuilder {
+    fn default() -> Self {
+        DownloaderBuilder {
+            max_concurrent: None,
+            requests_per_minute_per_host: NonZeroU32::new(Self::DEFAULT_REQUESTS_PER_MINUTE_PER_HOST).unwrap(),
+            offline: false,
+            mirrors: HashMap::new(),
+            connect_timeout: Self::DEFAULT_CONNECT_TIMEOUT,
+            read_timeout: Self::DEFAULT_READ_TIMEOUT,
+            connection_pool: ConnectionPoolSettings::default(),
+        }
+    }
+}
+
+impl DownloaderBuilder {
+    // Permissive enough to never slow down a typical pack, while keeping a very large one from
+    // looking like a flood to a CDN's firewall.
+    const DEFAULT_REQUESTS_PER_MINUTE_PER_HOST: u32 = 600;
+    const MAX_BURST: u32 = 10;
+    const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+    const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(60);
+
+    /// Limit how many requests may be made to a single host each minute. Defaults to 600.
+    pub fn requests_per_minute_per_host(mut self, requests_per_minute: u32) -> Self {
+        self.requests_per_minute_per_host = NonZeroU32::new(requests_per_minute).expect("Non-zero value required for requests_per_minute_per_host!");
+        self
+    }
+
+    /// Limit the number of downloads that may be in flight at once. Unbounded by default.
+    pub fn max_concurrent(mut self, max_concurrent: usize) -> Self {
+        self.max_concurrent = Some(max_concurrent.max(1));
+        self
+    }
+
+    /// Refuse all network access, making every download fail with [`DownloadError::Offline`].
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Download files from `to_host` instead of `from_host`, such as a regional mirror of a CDN.
+    /// Hosts are matched case-insensitively, including any port. The mirror may include a path
+    /// prefix, which is placed before the path of the original URL.
+    pub fn mirror(mut self, from_host: &str, to_host: &str) -> Self {
+        self.mirrors.insert(from_host.to_lowercase(), to_host.trim_end_matches('/').to_owned());
+        self
+    }
+
+    /// Give up on a download if connecting to the server takes longer than this. Defaults to 10
+    /// seconds.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Give up on a download if the server sends nothing for this long. The timeout applies to
+    /// each read rather than the whole download, so large files on slow connections are not cut
+    /// off while a stalled connection still fails. Defaults to 60 seconds.
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = timeout;
+        self
+    }
+
+    /// Set how many idle connections to download servers are kept open for reuse.
+    pub fn connection_pool(mut self, settings: ConnectionPoolSettings) -> Self {
+        self.connection_pool = settings;
+        self
+    }
+
+    pub fn build(self) -> Downloader {
+        let burst = NonZeroU32::new(Self::MAX_BURST).unwrap().min(self.requests_per_minute_per_host);
+        let quota = Quota::per_minute(self.requests_per_minute_per_host).allow_burst(burst);
+        Downloader {
+            inner: Arc::new(DownloaderInner {
+                agent: download_agent(self.connect_timeout, self.read_timeout, self.connection_pool),
+                permits: self.max_concurrent.map(Permits::new),
+                host_ratelimit: RateLimiter::keyed(quota),
+                clock: QuantaClock::default(),
+                offline: self.offline,
+                mirrors: self.mirrors,
+            }),
+        }
+    }
+}
+
+impl Downloader {
+    /// Download a file, waiting for a free slot first if the concurrency bound has been reached,
+    /// and for the host's rate limit to allow another request.
+    ///
+    /// If a mirror is configured for the URL's host, the file is downloaded from the mirror.
+    pub fn download_file(&self, url: &str) -> Result<Vec<u8>, DownloadError> {
+        self.download_file_with_headers(url, &[])
+    }
+
+    /// Download a file like [`Downloader::download_file`], sending extra request headers.
+    pub fn download_file_with_headers(&self, url: &str, headers: &[(String, String)]) -> Result<Vec<u8>, DownloadError> {
+        let url = &self.mirrored(url);
+        self.check_online(url)?;
+        self.wait_for_host(url);
+        let _permit = self.inner.permits.as_ref().map(Permits::acquire);
+        download_with(&self.inner.agent, url, headers)
+    }
+
+    /// Check that a file can be downloaded by making a HEAD request, without fetching its
+    /// contents. Shares the concurrency bound and rate limits with [`Downloader::download_file`].
+    pub fn check_url(&self, url: &str) -> Result<(), DownloadError> {
+        let url = &self.mirrored(url);
+        self.check_online(url)?;
+        self.wait_for_host(url);
+        let _permit = self.inner.permits.as_ref().map(Permits::acquire);
+        self.inner
+            .agent
+            .head(url)
+            .call()
+            .map_err(|e| DownloadError::Download(url.to_owned(), Box::new(e)))?;
+        Ok(())
+    }
+}
+
+impl Downloader {
+    fn check_online(&self, url: &str) -> Result<(), DownloadError> {
+        match self.inner.offline {
+            true => Err(DownloadError::Offline(url.to_owned())),
+            false => Ok(()),
+        }
+    }
+
+    /// Rewrite a URL to point at the mirror of its host, if one is configured.
+    fn mirrored(&self, url: &str) -> String {
+        match self.inner.mirrors.get(&url_host(url).to_lowercase()) {
+            Some(mirror) => replace_host(url, mirror),
+            None => url.to_owned(),
+        }
+    }
+
+    /// Wait for the rate limit of the URL's host to allow a request.
+    fn wait_for_host(&self, url: &str) {
+        let host = url_host(url).to_lowercase();
+        while let Err(not_until) = self.inner.host_ratelimit.check_key(&host) {
+            std::thread::sleep(not_until.wait_time_from(self.inner.clock.now()));
+        }
+    }
+}
+
+impl Default for Downloader {
+    fn default() -> Self {
+        DownloaderBuilder::default().build()
+    }
+}
+
 #[derive(Clone)]
 pub struct ApiClient {
     inner: Arc<Inner>,
+    breaker: Arc<CircuitBreaker>,
 }
 
 struct Inner {
-    ratelimit: DefaultDirectRateLimiter,
+    // Shared with clients rebuilt with other connection pool settings, which call the same API
+    ratelimit: Arc<DefaultDirectRateLimiter>,
     // Avoid constructing a new clock each wait period
     clock: QuantaClock,
     client: Agent,
     base_url: String,
+    // Kept to rebuild the agent with other connection pool settings
+    middleware: Vec<SharedMiddleware>,
 }
 
 pub struct ApiClientBuilder {
     requests_per_minute: NonZeroU32,
     base_url: String,
-    agent_builder: AgentBuilder,
+    middleware: Vec<SharedMiddleware>,
+    circuit_breaker: CircuitBreakerSettings,
+    connection_pool: ConnectionPoolSettings,
+}
+
+#[derive(Clone)]
+struct SharedMiddleware(Arc<dyn Middleware>);
+
+impl Middleware for SharedMiddleware {
+    fn handle(&self, request: ureq::Request, next: ureq::MiddlewareNext) -> Result<ureq::Response, ureq::Error> {
+        self.0.handle(request, next)
+    }
+}
+
+fn api_agent(middleware: &[SharedMiddleware], connection_pool: ConnectionPoolSettings) -> Agent {
+    middleware
+        .iter()
+        .fold(connection_pool.apply(AgentBuilder::new()), |builder, middleware| {
+            builder.middleware(middleware.clone())
+        })
+        .user_agent(USER_AGENT)
+        .timeout(Duration::from_secs(60))
+        .build()
+}
+
+/// When to stop sending requests to an API that keeps failing.
+///
+/// After `failures` requests in a row fail with a network error, a rate limit, or a server error,
+/// the first and last of them no more than `window` apart, every request fails immediately with
+/// [`ApiError::CircuitOpen`] for `cooldown`. After the cooldown, a single request is sent to probe
+/// the API while the others fail with [`ApiError::CircuitProbing`]. The circuit closes if the probe
+/// succeeds, and opens again straight away if it fails too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitBreakerSettings {
+    /// Number of consecutive failed requests that opens the circuit. Zero never opens it.
+    pub failures: u32,
+    pub window: Duration,
+    pub cooldown: Duration,
+}
+
+impl Default for CircuitBreakerSettings {
+    fn default() -> Self {
+        CircuitBreakerSettings {
+            failures: 5,
+            window: Duration::from_secs(60),
+            cooldown: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Shared between clones of an [`ApiClient`], so that failures of any request count towards
+/// opening the circuit for all of them.
+struct CircuitBreaker {
+    settings: CircuitBreakerSettings,
+    state: Mutex<BreakerState>,
+}
+
+#[derive(Default)]
+struct BreakerState {
+    failures: u32,
+    first_failure: Option<Instant>,
+    circuit: Circuit,
+}
+
+#[derive(Default)]
+enum Circuit {
+    #[default]
+    Closed,
+    /// Requests fail until the cooldown ends.
+    Open(Instant),
+    /// A probe request has been let through after the cooldown, and has not finished yet.
+    HalfOpen,
+}
+
+impl CircuitBreaker {
+    fn new(settings: CircuitBreakerSettings) -> Self {
+        CircuitBreaker {
+            settings,
+            state: Mutex::new(BreakerState::default()),
+        }
+    }
+
+    /// Check that a request may be sent. Returns whether the request is the probe let through
+    /// after the cooldown, which must record its outcome before any other request is sent.
+    fn check(&self) -> Result<bool, ApiError> {
+        let mut state = self.state.lock().unwrap();
+        match state.circuit {
+            Circuit::Closed => Ok(false),
+            Circuit::Open(until) if Instant::now() < until => Err(ApiError::CircuitOpen(until - Instant::now())),
+            Circuit::Open(_) => {
+                state.circuit = Circuit::HalfOpen;
+                Ok(true)
+            },
+            Circuit::HalfOpen => Err(ApiError::CircuitProbing),
+        }
+    }
+
+    fn record_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.failures = 0;
+        state.first_failure = None;
+        state.circuit = Circuit::Closed;
+    }
+
+    fn record_failure(&self) {
+        if self.settings.failures == 0 {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        if let Circuit::HalfOpen = state.circuit {
+            state.circuit = Circuit::Open(now + self.settings.cooldown);
+            return;
+        }
+        match state.first_failure {
+            Some(first) if now.duration_since(first) <= self.settings.window => state.failures += 1,
+            _ => {
+                state.failures = 1;
+                state.first_failure = Some(now);
+            },
+        }
+        if state.failures >= self.settings.failures {
+            state.circuit = Circuit::Open(now + self.settings.cooldown);
+        }
+    }
 }
 
 impl ApiClientBuilder {
@@ -69,33 +527,74 @@ impl ApiClientBuilder {
         ApiClientBuilder {
             requests_per_minute: NonZeroU32::new(requests_per_minute).expect("Non-zero value required for requests_per_minute!"),
             base_url,
-            agent_builder: AgentBuilder::new().user_agent(USER_AGENT).timeout(Duration::from_secs(60)),
+            middleware: Vec::new(),
+            circuit_breaker: CircuitBreakerSettings::default(),
+            connection_pool: ConnectionPoolSettings::default(),
         }
     }
 
+    /// Set when to stop sending requests to an API that keeps failing.
+    pub fn circuit_breaker(mut self, settings: CircuitBreakerSettings) -> Self {
+        self.circuit_breaker = settings;
+        self
+    }
+
+    /// Set how many idle connections to the API are kept open for reuse.
+    pub fn connection_pool(mut self, settings: ConnectionPoolSettings) -> Self {
+        self.connection_pool = settings;
+        self
+    }
+
     pub fn add_middleware(mut self, middleware: impl Middleware) -> Self {
-        self.agent_builder = std::mem::replace(&mut self.agent_builder, AgentBuilder::new()).middleware(middleware);
+        self.middleware.push(SharedMiddleware(Arc::new(middleware)));
         self
     }
 
     pub fn build(self) -> ApiClient {
         let q = Quota::per_minute(self.requests_per_minute).allow_burst(NonZeroU32::new(Self::MAX_BURST).unwrap());
-        let ratelimit = RateLimiter::direct(q);
+        let ratelimit = Arc::new(RateLimiter::direct(q));
 
-        let client_builder = self.agent_builder.timeout(Duration::from_secs(60));
-        let client = client_builder.build();
         ApiClient {
             inner: Arc::new(Inner {
                 ratelimit,
                 clock: QuantaClock::default(),
-                client,
+                client: api_agent(&self.middleware, self.connection_pool),
                 base_url: self.base_url,
+                middleware: self.middleware,
             }),
+            breaker: Arc::new(CircuitBreaker::new(self.circuit_breaker)),
         }
     }
 }
 
 impl ApiClient {
+    const RETRIES: u32 = 2;
+    const INITIAL_BACKOFF: Duration = Duration::from_millis(250);
+
+    /// Replace the circuit breaker settings, starting over with a closed circuit. Clones made
+    /// before this keep the previous circuit breaker.
+    pub fn with_circuit_breaker(self, settings: CircuitBreakerSettings) -> Self {
+        ApiClient {
+            breaker: Arc::new(CircuitBreaker::new(settings)),
+            ..self
+        }
+    }
+
+    /// Replace the connection pool settings. The client gets a new agent, so clones made before
+    /// this keep the previous one, but it still shares their rate limit.
+    pub fn with_connection_pool(self, settings: ConnectionPoolSettings) -> Self {
+        ApiClient {
+            inner: Arc::new(Inner {
+                ratelimit: self.inner.ratelimit.clone(),
+                clock: QuantaClock::default(),
+                client: api_agent(&self.inner.middleware, settings),
+                base_url: self.inner.base_url.clone(),
+                middleware: self.inner.middleware.clone(),
+            }),
+            ..self
+        }
+    }
+
     fn build_url(&self, path: &str) -> String {
         format!("{}{}", self.inner.base_url, path)
     }
@@ -109,38 +608,373 @@ impl ApiClient {
         }
     }
 
-    pub fn get<'a, P>(&self, path: &str, params: P) -> Result<ureq::Response, Box<ureq::Error>>
+    /// Run a request, retrying up to [`Self::RETRIES`] times with exponential backoff when it
+    /// fails with a network error, a rate limit, or a server error. Other errors, such as a 404,
+    /// are returned immediately.
+    ///
+    /// Requests are not sent while the circuit breaker is open, and a request that still fails
+    /// after its retries counts towards opening it.
+    fn with_retries<F>(&self, mut request: F) -> Result<ureq::Response, ApiError>
     where
-        P: IntoIterator<Item = (&'a str, &'a str)> + Clone,
+        F: FnMut() -> Result<ureq::Response, Box<ureq::Error>>,
     {
-        let mut retries = 2;
+        let mut backoff = Self::INITIAL_BACKOFF;
+        let mut retries = Self::RETRIES;
+        let mut probe = false;
         loop {
+            // The probe keeps its retries, as every other request is rejected until it finishes
+            if !probe {
+                probe = self.breaker.check()?;
+            }
             self.wait_for_token();
-            match self
-                .inner
-                .client
-                .get(&self.build_url(path))
-                .query_pairs(params.clone())
-                .call()
-                .map_err(Box::new)
-            {
-                Ok(response) => return Ok(response),
+            match request() {
+                Ok(response) => {
+                    self.breaker.record_success();
+                    return Ok(response);
+                },
                 Err(err) => {
-                    if retries > 0 {
-                        retries -= 1;
-                    } else {
-                        return Err(err);
+                    let transient = match *err {
+                        ureq::Error::Status(status, _) => status == 429 || status >= 500,
+                        ureq::Error::Transport(_) => true,
+                    };
+                    if !transient {
+                        // The API is up, it just rejected this request
+                        self.breaker.record_success();
+                        return Err(err.into());
+                    }
+                    if retries == 0 {
+                        self.breaker.record_failure();
+                        return Err(err.into());
                     }
+                    retries -= 1;
+                    std::thread::sleep(backoff);
+                    backoff *= 2;
                 },
             }
         }
     }
 
-    pub fn post_json<T>(&self, path: &str, body: T) -> Result<ureq::Response, Box<ureq::Error>>
+    /// Send a GET request to `path` under the base URL, with `params` as the query string.
+    ///
+    /// Fails with [`ApiError::Request`] if the request still fails after its retries, and with
+    /// [`ApiError::CircuitOpen`] or [`ApiError::CircuitProbing`], without sending anything, while
+    /// the circuit breaker is open.
+    pub fn get<'a, P>(&self, path: &str, params: P) -> Result<ureq::Response, ApiError>
+    where
+        P: IntoIterator<Item = (&'a str, &'a str)> + Clone,
+    {
+        self.with_retries(|| {
+            self.inner
+                .client
+                .get(&self.build_url(path))
+                .query_pairs(params.clone())
+                .call()
+                .map_err(Box::new)
+        })
+    }
+
+    /// Send a POST request to `path` under the base URL, with `body` serialized as JSON. Fails
+    /// like [`ApiClient::get`].
+    pub fn post_json<T>(&self, path: &str, body: T) -> Result<ureq::Response, ApiError>
     where
         T: serde::ser::Serialize,
     {
-        self.wait_for_token();
-        self.inner.client.post(&self.build_url(path)).send_json(body).map_err(Box::new)
+        self.with_retries(|| self.inner.client.post(&self.build_url(path)).send_json(&body).map_err(Box::new))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[test]
+    fn permits_bound_concurrency() {
+        let permits = Arc::new(Permits::new(2));
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let handles = (0..8)
+            .map(|_| {
+                let (permits, running, peak) = (permits.clone(), running.clone(), peak.clone());
+                std::thread::spawn(move || {
+                    let _permit = permits.acquire();
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    std::thread::sleep(Duration::from_millis(20));
+                    running.fetch_sub(1, Ordering::SeqCst);
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+        assert_eq!(*permits.available.lock().unwrap(), 2);
+    }
+
+    #[test]
+    fn host_rate_limit() {
+        assert_eq!(url_host("https://cdn.modrinth.com/data/abc/x.jar"), "cdn.modrinth.com");
+        assert_eq!(url_host("https://user@edge.forgecdn.net:443?x=1"), "edge.forgecdn.net:443");
+        assert_eq!(url_host("not-a-url"), "not-a-url");
+
+        // 600 per minute allows one request every 100ms after the burst of 10 is used up
+        let downloader = DownloaderBuilder::default().requests_per_minute_per_host(600).build();
+        for _ in 0..10 {
+            downloader.wait_for_host("https://cdn.modrinth.com/a.jar");
+        }
+        let start = std::time::Instant::now();
+        downloader.wait_for_host("https://edge.forgecdn.net/b.jar");
+        assert!(start.elapsed() < Duration::from_millis(50));
+        downloader.wait_for_host("https://CDN.modrinth.com/c.jar");
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[test]
+    fn mirrors_rewrite_host() {
+        assert_eq!(
+            replace_host("https://user@edge.forgecdn.net/files/a.jar?x=1", "mirror.example.com"),
+            "https://user@mirror.example.com/files/a.jar?x=1"
+        );
+        assert_eq!(
+            replace_host("https://cdn.modrinth.com", "mirror.example.com:8080"),
+            "https://mirror.example.com:8080"
+        );
+
+        let downloader = DownloaderBuilder::default()
+            .mirror("Edge.ForgeCDN.net", "mirror.example.com/forgecdn/")
+            .build();
+        assert_eq!(
+            downloader.mirrored("https://edge.forgecdn.net/files/4712/868/jei.jar"),
+            "https://mirror.example.com/forgecdn/files/4712/868/jei.jar"
+        );
+        assert_eq!(
+            downloader.mirrored("https://cdn.modrinth.com/data/a.jar"),
+            "https://cdn.modrinth.com/data/a.jar"
+        );
+    }
+
+    /// A canned response for [`serve_responses`].
+    #[derive(Clone, Copy)]
+    enum Reply {
+        /// Status code and body.
+        Full(u16, &'static str),
+        /// Promise a body, send part of it, then stall without closing the connection.
+        Stall,
+    }
+
+    /// Local server answering requests for [`serve_responses`].
+    struct TestServer {
+        url: String,
+        /// Header lines of each request received, lowercased.
+        requests: Arc<Mutex<Vec<Vec<String>>>>,
+        connections: Arc<AtomicUsize>,
+    }
+
+    impl TestServer {
+        fn request_count(&self) -> usize {
+            self.requests.lock().unwrap().len()
+        }
+
+        fn connection_count(&self) -> usize {
+            self.connections.load(Ordering::SeqCst)
+        }
+    }
+
+    /// Serve each request on a local port with the next response from `replies`, keeping
+    /// connections alive, and record the requests received. Connections are closed once every
+    /// reply has been sent.
+    fn serve_responses(replies: Vec<Reply>) -> TestServer {
+        use std::io::{BufRead, BufReader, Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let server = TestServer {
+            url: format!("http://{}", listener.local_addr().unwrap()),
+            requests: Arc::default(),
+            connections: Arc::default(),
+        };
+        let (requests, connections) = (server.requests.clone(), server.connections.clone());
+        let replies = Arc::new(Mutex::new(replies.into_iter()));
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                connections.fetch_add(1, Ordering::SeqCst);
+                let (requests, replies) = (requests.clone(), replies.clone());
+                std::thread::spawn(move || {
+                    let mut reader = BufReader::new(stream.unwrap());
+                    loop {
+                        let mut headers = vec![];
+                        let mut line = String::new();
+                        while reader.read_line(&mut line).unwrap_or(0) > 2 {
+                            headers.push(line.trim_end().to_lowercase());
+                            line.clear();
+                        }
+                        if headers.is_empty() {
+                            return;
+                        }
+                        let Some(reply) = replies.lock().unwrap().next() else {
+                            return;
+                        };
+                        let content_length = headers
+                            .iter()
+                            .find_map(|header| header.strip_prefix("content-length:"))
+                            .map_or(0, |length| length.trim().parse().unwrap());
+                        reader.by_ref().take(content_length).read_to_end(&mut vec![]).unwrap();
+                        requests.lock().unwrap().push(headers);
+                        let response = match reply {
+                            Reply::Full(status, body) => format!("HTTP/1.1 {status} X\r\nContent-Length: {}\r\n\r\n{body}", body.len()),
+                            Reply::Stall => "HTTP/1.1 200 OK\r\nContent-Length: 1000\r\n\r\npartial".to_owned(),
+                        };
+                        reader.get_mut().write_all(response.as_bytes()).unwrap();
+                        if let Reply::Stall = reply {
+                            std::thread::sleep(Duration::from_secs(5));
+                            return;
+                        }
+                    }
+                });
+            }
+        });
+        server
+    }
+
+    #[test]
+    fn stalled_download_times_out() {
+        let server = serve_responses(vec![Reply::Stall]);
+        let downloader = DownloaderBuilder::default().read_timeout(Duration::from_millis(200)).build();
+        let start = std::time::Instant::now();
+        assert!(downloader.download_file(&format!("{}/large.jar", server.url)).is_err());
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn post_json_retries_transient_errors() {
+        let server = serve_responses(vec![Reply::Full(503, ""), Reply::Full(200, "{\"data\":[]}")]);
+        let client = ApiClientBuilder::new(60, server.url.clone()).build();
+        let response = client.post_json("/v1/mods/files", serde_json::json!({ "fileIds": [1] })).unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(server.request_count(), 2);
+
+        let server = serve_responses(vec![Reply::Full(404, ""), Reply::Full(200, "")]);
+        let client = ApiClientBuilder::new(60, server.url.clone()).build();
+        let err = client.post_json("/v1/mods/files", serde_json::json!({ "fileIds": [1] })).unwrap_err();
+        assert!(matches!(err, ApiError::Request(ref e) if matches!(**e, ureq::Error::Status(404, _))));
+        assert_eq!(server.request_count(), 1);
+    }
+
+    #[test]
+    fn get_retries_only_transient_errors() {
+        // A 404 is an answer, not a failure, so it is returned without retrying
+        let server = serve_responses(vec![Reply::Full(404, ""), Reply::Full(200, "")]);
+        let client = ApiClientBuilder::new(600, server.url.clone()).build();
+        assert!(client.get("/v2/project/a", []).unwrap_err().is_not_found());
+        assert_eq!(server.request_count(), 1);
+
+        // Transient errors are retried after waiting 250ms, then 500ms
+        let server = serve_responses(vec![Reply::Full(503, ""), Reply::Full(429, ""), Reply::Full(200, "")]);
+        let client = ApiClientBuilder::new(600, server.url.clone()).build();
+        let start = Instant::now();
+        assert_eq!(client.get("/v2/project/a", []).unwrap().status(), 200);
+        assert_eq!(server.request_count(), 3);
+        assert!(start.elapsed() >= Duration::from_millis(750));
+    }
+
+    #[test]
+    fn connection_pool_reuses_connections() {
+        let server = serve_responses(vec![Reply::Full(200, "{}"); 6]);
+
+        struct TestHeader;
+        impl Middleware for TestHeader {
+            fn handle(&self, request: ureq::Request, next: ureq::MiddlewareNext) -> Result<ureq::Response, ureq::Error> {
+                next.handle(request.set("x-test", "1"))
+            }
+        }
+        let client = ApiClientBuilder::new(600, server.url.clone()).add_middleware(TestHeader).build();
+        for _ in 0..3 {
+            client.get("/v2/project/a", []).unwrap().into_string().unwrap();
+        }
+        assert_eq!(server.connection_count(), 1);
+
+        // Without idle connections, every request opens a new one, and middleware is kept
+        let settings = ConnectionPoolSettings {
+            max_idle: 0,
+            max_idle_per_host: 0,
+        };
+        let client = client.with_connection_pool(settings);
+        for _ in 0..3 {
+            client.get("/v2/project/a", []).unwrap().into_string().unwrap();
+        }
+        assert_eq!(server.connection_count(), 4);
+        assert_eq!(server.request_count(), 6);
+        let requests = server.requests.lock().unwrap();
+        assert!(requests.iter().all(|headers| headers.iter().any(|header| header == "x-test: 1")));
+    }
+
+    #[test]
+    fn connection_pool_keeps_rate_limit() {
+        let client = ApiClientBuilder::new(1, "http://127.0.0.1:9".into()).build();
+        for _ in 0..ApiClientBuilder::MAX_BURST {
+            client.wait_for_token();
+        }
+        // The burst is used up for the rebuilt client too
+        let client = client.with_connection_pool(ConnectionPoolSettings::default());
+        assert!(client.inner.ratelimit.check().is_err());
+    }
+
+    #[test]
+    fn circuit_breaker_opens_after_repeated_failures() {
+        let server = serve_responses(vec![Reply::Full(503, ""); 6]);
+        let settings = CircuitBreakerSettings {
+            failures: 2,
+            ..Default::default()
+        };
+        let client = ApiClientBuilder::new(600, server.url.clone()).circuit_breaker(settings).build();
+        for _ in 0..2 {
+            assert!(matches!(client.get("/v2/project/a", []), Err(ApiError::Request(_))));
+        }
+        assert_eq!(server.request_count(), 6);
+
+        // Clones share the open circuit, and no further requests are sent
+        let err = client.clone().get("/v2/project/a", []).unwrap_err();
+        assert!(matches!(err, ApiError::CircuitOpen(remaining) if remaining > Duration::from_secs(50)));
+        assert_eq!(server.request_count(), 6);
+    }
+
+    #[test]
+    fn circuit_breaker_cooldown() {
+        let breaker = CircuitBreaker::new(CircuitBreakerSettings {
+            failures: 2,
+            window: Duration::from_secs(60),
+            cooldown: Duration::from_millis(50),
+        });
+        breaker.record_failure();
+        breaker.record_success();
+        breaker.record_failure();
+        assert!(breaker.check().is_ok());
+        breaker.record_failure();
+        assert!(breaker.check().is_err());
+
+        // One probe is let through after the cooldown, and a single failure opens the circuit again
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(breaker.check().unwrap());
+        assert!(matches!(breaker.check(), Err(ApiError::CircuitProbing)));
+        breaker.record_failure();
+        assert!(matches!(breaker.check(), Err(ApiError::CircuitOpen(remaining)) if remaining > Duration::from_millis(30)));
+
+        // A successful probe closes the circuit for everyone
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(breaker.check().unwrap());
+        breaker.record_success();
+        assert!(!breaker.check().unwrap());
+        assert!(!breaker.check().unwrap());
+
+        let disabled = CircuitBreaker::new(CircuitBreakerSettings {
+            failures: 0,
+            ..Default::default()
+        });
+        for _ in 0..10 {
+            disabled.record_failure();
+        }
+        assert!(disabled.check().is_ok());
     }
 }
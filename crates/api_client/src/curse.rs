@@ -1,10 +1,11 @@
 use ureq::Middleware;
 
-use crate::common::ApiError;
+use crate::common::{ApiError, SearchSort};
+use crate::loader::ModLoader;
 
 use self::model::{File, GetModFilesRequest, Mod, Wrapper};
 
-use super::common::{ApiClient, ApiClientBuilder};
+use super::common::{ApiClient, ApiClientBuilder, CircuitBreakerSettings, ConnectionPoolSettings};
 
 static CURSEFORGE_BASE_URL: &str = "https://api.curseforge.com/v1";
 
@@ -39,6 +40,20 @@ impl CurseClient {
         }
     }
 
+    /// Set when to stop sending requests after repeated API failures.
+    pub fn circuit_breaker(self, settings: CircuitBreakerSettings) -> Self {
+        CurseClient {
+            client: self.client.with_circuit_breaker(settings),
+        }
+    }
+
+    /// Set how many idle connections to the API are kept open for reuse.
+    pub fn connection_pool(self, settings: ConnectionPoolSettings) -> Self {
+        CurseClient {
+            client: self.client.with_connection_pool(settings),
+        }
+    }
+
     /// Find a mod by its slug.
     /// The Curseforge API guarantees a unique result when searching a combination of game ID,
     /// class ID, and slug, so this function unpacks the API response to a single [`Mod`].
@@ -58,6 +73,37 @@ impl CurseClient {
             .ok_or(ApiError::Empty)
     }
 
+    /// Search mods by a free-text query, optionally limited to those with files for a game
+    /// version and mod loader. Returns at most `limit` results, in the requested order.
+    ///
+    /// Endpoint: /mods/search
+    pub fn search_mods(
+        &self,
+        query: &str,
+        sort: SearchSort,
+        game_version: Option<&str>,
+        loader: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<Mod>, ApiError> {
+        let limit = limit.to_string();
+        let mut params = Vec::from([
+            ("gameId", "432"), // Minecraft
+            ("classId", "6"),  // Mods
+            ("searchFilter", query),
+            ("pageSize", limit.as_str()),
+        ]);
+        if sort == SearchSort::Downloads {
+            params.extend([("sortField", "6"), ("sortOrder", "desc")]); // Total downloads
+        }
+        if let Some(game_version) = game_version {
+            params.push(("gameVersion", game_version));
+        }
+        if let Some(loader) = loader.and_then(|loader| loader.parse::<ModLoader>().ok()) {
+            params.push(("modLoaderType", loader.curse_mod_loader_type()));
+        }
+        Ok(self.client.get("/mods/search", params)?.into_json::<Wrapper<Vec<Mod>>>()?.data)
+    }
+
     /// Find a mod by its ID.
     ///
     /// Endpoint: /mods/{id}
@@ -102,6 +148,15 @@ impl CurseClient {
     }
 }
 
+/// Build the CDN URL CurseForge serves a file from, given its ID and file name. The ID is split
+/// into the thousands and the remainder, so file 3359843 is found under `/files/3359/843/`.
+///
+/// Useful when the API returns no download URL, as it does for mods whose authors have disabled
+/// third-party distribution.
+pub fn curse_cdn_url(file_id: u32, file_name: &str) -> String {
+    format!("https://edge.forgecdn.net/files/{}/{}/{}", file_id / 1000, file_id % 1000, file_name)
+}
+
 pub mod model {
     use serde::{Deserialize, Serialize};
     use serde_repr::{Deserialize_repr, Serialize_repr};
@@ -128,6 +183,19 @@ pub mod model {
         pub id: u32,
         pub name: String,
         pub slug: String,
+        /// Release date of the most recent file, as an RFC 3339 timestamp.
+        #[serde(rename = "dateReleased", default)]
+        pub date_released: Option<String>,
+        #[serde(rename = "primaryCategoryId", default)]
+        pub primary_category_id: Option<u32>,
+        #[serde(default)]
+        pub categories: Vec<Category>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug)]
+    pub struct Category {
+        pub id: u32,
+        pub name: String,
     }
 
     #[derive(Serialize, Deserialize, Debug)]
@@ -143,16 +211,54 @@ pub mod model {
         pub release_type: FileReleaseType,
         #[serde(rename = "fileStatus")]
         pub file_status: FileStatus,
+        /// Missing for files whose authors have disabled third-party distribution. See
+        /// [`File::download_url_or_cdn`].
         #[serde(rename = "downloadUrl")]
-        pub download_url: String,
+        pub download_url: Option<String>,
         #[serde(rename = "gameVersions")]
         pub game_versions: Vec<String>,
+        /// Typed form of `game_versions`, distinguishing Minecraft versions from loader tags.
+        /// Missing from some older responses and proxies.
+        #[serde(rename = "sortableGameVersions", default)]
+        pub sortable_game_versions: Vec<SortableGameVersion>,
         pub dependencies: Vec<FileDependency>,
         pub hashes: Vec<FileHash>,
         #[serde(rename = "fileDate")]
         pub file_date: String,
     }
 
+    impl File {
+        /// The download URL from the API, or the CDN URL built from the file ID and name when the
+        /// API returned none.
+        pub fn download_url_or_cdn(&self) -> String {
+            match self.download_url {
+                Some(ref url) => url.clone(),
+                None => super::curse_cdn_url(self.id, &self.file_name),
+            }
+        }
+    }
+
+    /// Game version type of the loader tags in [`File::sortable_game_versions`].
+    pub const MODLOADER_GAME_VERSION_TYPE_ID: u32 = 68441;
+
+    #[derive(Serialize, Deserialize, Debug)]
+    pub struct SortableGameVersion {
+        /// Display name, such as `1.20.1` or `Forge`.
+        #[serde(rename = "gameVersionName")]
+        pub game_version_name: String,
+        /// Version number, which is empty for loader and environment tags.
+        #[serde(rename = "gameVersion", default)]
+        pub game_version: String,
+        #[serde(rename = "gameVersionTypeId", default)]
+        pub game_version_type_id: Option<u32>,
+    }
+
+    impl SortableGameVersion {
+        pub fn is_modloader(&self) -> bool {
+            self.game_version_type_id == Some(MODLOADER_GAME_VERSION_TYPE_ID)
+        }
+    }
+
     #[derive(Serialize_repr, Deserialize_repr, Debug, PartialEq, Eq)]
     #[repr(u8)]
     pub enum FileReleaseType {
@@ -253,6 +359,43 @@ mod tests {
         toml::from_str::<Config>(&data).ok()
     }
 
+    #[test]
+    fn cdn_url_segments() {
+        assert_eq!(
+            curse_cdn_url(3359843, "MouseTweaks-2.10.1-mc1.12.2.jar"),
+            "https://edge.forgecdn.net/files/3359/843/MouseTweaks-2.10.1-mc1.12.2.jar"
+        );
+        // Leading zeros of the remainder are dropped
+        assert_eq!(curse_cdn_url(2724072, "a.jar"), "https://edge.forgecdn.net/files/2724/72/a.jar");
+        assert_eq!(curse_cdn_url(4773000, "a.jar"), "https://edge.forgecdn.net/files/4773/0/a.jar");
+        // Shorter and longer IDs split at the same place
+        assert_eq!(curse_cdn_url(248787, "a.jar"), "https://edge.forgecdn.net/files/248/787/a.jar");
+        assert_eq!(curse_cdn_url(10234567, "a.jar"), "https://edge.forgecdn.net/files/10234/567/a.jar");
+        assert_eq!(curse_cdn_url(999, "a.jar"), "https://edge.forgecdn.net/files/0/999/a.jar");
+    }
+
+    #[test]
+    fn missing_download_url_falls_back_to_cdn() {
+        let file: model::File = serde_json::from_value(serde_json::json!({
+            "id": 3359843,
+            "modId": 60089,
+            "displayName": "[1.12.2] Mouse Tweaks 2.10.1",
+            "fileName": "MouseTweaks-2.10.1-mc1.12.2.jar",
+            "releaseType": 1,
+            "fileStatus": 4,
+            "downloadUrl": null,
+            "gameVersions": ["1.12.2"],
+            "dependencies": [],
+            "hashes": [],
+            "fileDate": "2021-06-04T11:49:13.2Z",
+        }))
+        .unwrap();
+        assert_eq!(
+            file.download_url_or_cdn(),
+            "https://edge.forgecdn.net/files/3359/843/MouseTweaks-2.10.1-mc1.12.2.jar"
+        );
+    }
+
     #[test]
     fn search_mods() {
         let client = get_client();
@@ -261,6 +404,15 @@ mod tests {
         assert_eq!(result.name, "AppleSkin");
     }
 
+    #[test]
+    fn search_mods_by_query() {
+        let client = get_client();
+        let result = client
+            .search_mods("appleskin", SearchSort::Downloads, Some("1.12.2"), Some("forge"), 5)
+            .unwrap();
+        assert!(result.iter().any(|m| m.id == APPLESKIN_ID));
+    }
+
     #[test]
     fn get_mod() {
         let client = get_client();
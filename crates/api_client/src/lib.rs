@@ -1,3 +1,4 @@
 pub mod common;
 pub mod curse;
+pub mod loader;
 pub mod modrinth;
@@ -0,0 +1,84 @@
+use std::{fmt::Display, str::FromStr};
+
+/// A mod loader a pack can be built for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModLoader {
+    Forge,
+    NeoForge,
+    Fabric,
+    Quilt,
+}
+
+impl ModLoader {
+    pub const ALL: [ModLoader; 4] = [ModLoader::Forge, ModLoader::NeoForge, ModLoader::Fabric, ModLoader::Quilt];
+
+    /// Lowercase name of the loader, as written in the `modloader` config key and used by Modrinth
+    /// and in Curse game version tags.
+    pub fn name(self) -> &'static str {
+        match self {
+            ModLoader::Forge => "forge",
+            ModLoader::NeoForge => "neoforge",
+            ModLoader::Fabric => "fabric",
+            ModLoader::Quilt => "quilt",
+        }
+    }
+
+    /// Key of the loader's version in the `dependencies` of a Modrinth pack index.
+    pub fn mrpack_dependency(self) -> &'static str {
+        match self {
+            ModLoader::Forge => "forge",
+            ModLoader::NeoForge => "neoforge",
+            ModLoader::Fabric => "fabric-loader",
+            ModLoader::Quilt => "quilt-loader",
+        }
+    }
+
+    /// CurseForge `ModLoaderType` of the loader, as used in query parameters.
+    pub fn curse_mod_loader_type(self) -> &'static str {
+        match self {
+            ModLoader::Forge => "1",
+            ModLoader::Fabric => "4",
+            ModLoader::Quilt => "5",
+            ModLoader::NeoForge => "6",
+        }
+    }
+}
+
+impl FromStr for ModLoader {
+    type Err = String;
+
+    /// Parse a loader name, ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ModLoader::ALL
+            .into_iter()
+            .find(|loader| loader.name().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| {
+                let names = ModLoader::ALL.map(ModLoader::name).join(", ");
+                format!("expected one of {names}, got '{s}'")
+            })
+    }
+}
+
+impl Display for ModLoader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_loader() {
+        assert_eq!("NeoForge".parse(), Ok(ModLoader::NeoForge));
+        assert_eq!(" fabric ".parse(), Ok(ModLoader::Fabric));
+        assert_eq!(
+            "liteloader".parse::<ModLoader>(),
+            Err("expected one of forge, neoforge, fabric, quilt, got 'liteloader'".to_owned())
+        );
+        for loader in ModLoader::ALL {
+            assert_eq!(loader.to_string().parse(), Ok(loader));
+        }
+    }
+}
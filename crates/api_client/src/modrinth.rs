@@ -1,10 +1,11 @@
-use crate::common::ApiError;
+use crate::common::{ApiError, SearchSort};
 
-use self::model::{Project, Version};
+use self::model::{Collection, GameVersion, Project, SearchHit, SearchResults, Version};
 
-use super::common::{ApiClient, ApiClientBuilder};
+use super::common::{ApiClient, ApiClientBuilder, CircuitBreakerSettings, ConnectionPoolSettings};
 
-static MODRINTH_BASE_URL: &str = "https://api.modrinth.com/v2";
+// Most endpoints are under /v2, but some (such as collections) are only available under /v3.
+static MODRINTH_BASE_URL: &str = "https://api.modrinth.com";
 
 #[derive(Clone)]
 pub struct ModrinthClient {
@@ -43,11 +44,35 @@ impl ModrinthClient {
         }
     }
 
+    /// Get a [`ModrinthClient`] that uses a Modrinth-compatible API at another location, such as
+    /// the staging API (`https://staging-api.modrinth.com`) or a self-hosted Labrinth instance.
+    ///
+    /// A trailing API version (`/v2`) is ignored, as the client picks the version per endpoint.
+    pub fn from_base_url(base_url: &str) -> Self {
+        ModrinthClient {
+            client: ApiClientBuilder::new(285, normalize_base_url(base_url)).build(),
+        }
+    }
+
+    /// Set when to stop sending requests after repeated API failures.
+    pub fn circuit_breaker(self, settings: CircuitBreakerSettings) -> Self {
+        ModrinthClient {
+            client: self.client.with_circuit_breaker(settings),
+        }
+    }
+
+    /// Set how many idle connections to the API are kept open for reuse.
+    pub fn connection_pool(self, settings: ConnectionPoolSettings) -> Self {
+        ModrinthClient {
+            client: self.client.with_connection_pool(settings),
+        }
+    }
+
     /// Get mod info from Modrinth, given either a project slug or base-62 numeric ID.
     ///
     /// Endpoint: /project/{id|slug}
     pub fn get_mod_info(&self, id_or_slug: &str) -> Result<Project, ApiError> {
-        Ok(self.client.get(&format!("/project/{id_or_slug}"), vec![])?.into_json()?)
+        Ok(self.client.get(&format!("/v2/project/{id_or_slug}"), vec![])?.into_json()?)
     }
 
     /// Get version list of a mod from Modrinth, given either a project slug or base-62 numeric ID.
@@ -65,7 +90,7 @@ impl ModrinthClient {
         Ok(self
             .client
             .get(
-                &format!("/project/{id_or_slug}/version"),
+                &format!("/v2/project/{id_or_slug}/version"),
                 params
                     .iter()
                     // Convert a Vec<(&str, String)> to a Vec<(&str, &str)> to match ureq's API
@@ -79,8 +104,62 @@ impl ModrinthClient {
     ///
     /// Endpoint: /version/{id}
     pub fn get_version(&self, id: &str) -> Result<Version, ApiError> {
-        Ok(self.client.get(&format!("/version/{id}"), vec![])?.into_json()?)
+        Ok(self.client.get(&format!("/v2/version/{id}"), vec![])?.into_json()?)
+    }
+
+    /// Search mods by a free-text query, optionally limited to those with versions for a game
+    /// version and mod loader. Returns at most `limit` results, in the requested order.
+    ///
+    /// Endpoint: /search
+    pub fn search_projects(
+        &self,
+        query: &str,
+        sort: SearchSort,
+        game_version: Option<&str>,
+        loader: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<SearchHit>, ApiError> {
+        // Facets in the same inner array are ORed, separate arrays are ANDed
+        let mut facets = vec!["[\"project_type:mod\"]".to_owned()];
+        if let Some(game_version) = game_version {
+            facets.push(format!("[\"versions:{game_version}\"]"));
+        }
+        if let Some(loader) = loader {
+            facets.push(format!("[\"categories:{loader}\"]"));
+        }
+        let facets = format!("[{}]", facets.join(","));
+        let index = match sort {
+            SearchSort::Relevance => "relevance",
+            SearchSort::Downloads => "downloads",
+        };
+        let limit = limit.to_string();
+        let params = [("query", query), ("facets", facets.as_str()), ("index", index), ("limit", limit.as_str())];
+        Ok(self.client.get("/v2/search", params)?.into_json::<SearchResults>()?.hits)
+    }
+
+    /// Get every Minecraft version known to Modrinth, including snapshots, newest first.
+    ///
+    /// Endpoint: /tag/game_version
+    pub fn get_game_versions(&self) -> Result<Vec<GameVersion>, ApiError> {
+        Ok(self.client.get("/v2/tag/game_version", vec![])?.into_json()?)
     }
+
+    /// Get a user-curated collection of projects from Modrinth, given its ID.
+    ///
+    /// Endpoint: /v3/collection/{id}
+    pub fn get_collection(&self, id: &str) -> Result<Collection, ApiError> {
+        Ok(self.client.get(&format!("/v3/collection/{id}"), vec![])?.into_json()?)
+    }
+}
+
+/// Strip trailing slashes and API version from a base URL.
+fn normalize_base_url(base_url: &str) -> String {
+    let base_url = base_url.trim_end_matches('/');
+    base_url
+        .strip_suffix("/v2")
+        .or_else(|| base_url.strip_suffix("/v3"))
+        .unwrap_or(base_url)
+        .to_owned()
 }
 
 /// Format list of items for use as an array query parameter.
@@ -108,6 +187,30 @@ pub mod model {
         pub client_side: Sided,
         pub server_side: Sided,
         pub id: String,
+        /// Category slugs of the project, including the mod loaders it supports.
+        #[serde(default)]
+        pub categories: Vec<String>,
+        /// Loaders the project supports, which are also listed among its categories.
+        #[serde(default)]
+        pub loaders: Vec<String>,
+        #[serde(default)]
+        pub project_type: ProjectType,
+    }
+
+    /// Kind of content a project distributes.
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    pub enum ProjectType {
+        #[default]
+        Mod,
+        Modpack,
+        Resourcepack,
+        Shader,
+        Datapack,
+        Plugin,
+        /// A project type added to Modrinth after this client was written.
+        #[serde(other)]
+        Unknown,
     }
 
     #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -130,6 +233,25 @@ pub mod model {
         pub project_id: String,
         pub files: Vec<VersionFile>,
         pub date_published: String,
+        #[serde(default)]
+        pub dependencies: Vec<VersionDependency>,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct VersionDependency {
+        pub version_id: Option<String>,
+        pub project_id: Option<String>,
+        pub file_name: Option<String>,
+        pub dependency_type: DependencyType,
+    }
+
+    #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    pub enum DependencyType {
+        Required,
+        Optional,
+        Incompatible,
+        Embedded,
     }
 
     #[derive(Debug, Serialize, Deserialize)]
@@ -147,6 +269,50 @@ pub mod model {
         pub sha1: String,
     }
 
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct SearchResults {
+        pub hits: Vec<SearchHit>,
+    }
+
+    /// Summary of a project returned by a search.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct SearchHit {
+        pub project_id: String,
+        pub slug: String,
+        pub title: String,
+        pub downloads: u64,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct Collection {
+        pub id: String,
+        pub name: String,
+        /// IDs of the projects in this collection.
+        pub projects: Vec<String>,
+    }
+
+    /// A Minecraft version that files can be published for.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct GameVersion {
+        pub version: String,
+        pub version_type: GameVersionType,
+        pub date: String,
+        /// Whether this is the first release of a major version, such as 1.20.
+        pub major: bool,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    pub enum GameVersionType {
+        Release,
+        Snapshot,
+        Alpha,
+        Beta,
+        /// A version type added to Modrinth after this client was written.
+        #[serde(other)]
+        Unknown,
+    }
+
     #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
     #[serde(rename_all = "lowercase")]
     pub enum VersionType {
@@ -160,7 +326,7 @@ pub mod model {
 mod tests {
     static APPLESKIN_ID: &str = "EsAfCjCV";
     static APPLESKIN_1_12_VERSION_ID: &str = "Tsz4BT2X";
-    use crate::modrinth::model::Sided;
+    use crate::modrinth::model::{GameVersionType, ProjectType, Sided};
 
     use super::*;
 
@@ -172,6 +338,20 @@ mod tests {
         assert_eq!(project.title, "AppleSkin");
         assert_eq!(project.client_side, Sided::Optional);
         assert_eq!(project.server_side, Sided::Optional);
+        assert_eq!(project.project_type, ProjectType::Mod);
+    }
+
+    #[test]
+    fn project_types() {
+        let project = |project_type: &str| -> Project {
+            serde_json::from_str(&format!(
+                r#"{{"slug":"x","title":"X","client_side":"required","server_side":"unsupported","id":"abc","project_type":"{project_type}"}}"#
+            ))
+            .unwrap()
+        };
+        assert_eq!(project("shader").project_type, ProjectType::Shader);
+        assert_eq!(project("resourcepack").project_type, ProjectType::Resourcepack);
+        assert_eq!(project("minigame").project_type, ProjectType::Unknown);
     }
 
     #[test]
@@ -200,6 +380,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn search_projects() {
+        let client = ModrinthClient::new();
+        let hits = client
+            .search_projects("appleskin", SearchSort::Relevance, Some("1.12.2"), Some("forge"), 5)
+            .unwrap();
+        assert_eq!(hits[0].project_id, APPLESKIN_ID);
+        assert_eq!(hits[0].slug, "appleskin");
+    }
+
+    #[test]
+    fn get_game_versions() {
+        let client = ModrinthClient::new();
+        let versions = client.get_game_versions().unwrap();
+        let version = versions.iter().find(|v| v.version == "1.20.1").unwrap();
+        assert_eq!(version.version_type, GameVersionType::Release);
+        assert!(!version.major);
+    }
+
+    #[test]
+    fn test_normalize_base_url() {
+        assert_eq!(normalize_base_url("https://staging-api.modrinth.com"), "https://staging-api.modrinth.com");
+        assert_eq!(
+            normalize_base_url("https://staging-api.modrinth.com/"),
+            "https://staging-api.modrinth.com"
+        );
+        assert_eq!(normalize_base_url("https://labrinth.example.com/v2/"), "https://labrinth.example.com");
+    }
+
     #[test]
     fn test_format_params() {
         assert_eq!(format_params(Some(&["1.12.2"])).unwrap(), "[\"1.12.2\"]");
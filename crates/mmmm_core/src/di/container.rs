@@ -1,5 +1,10 @@
-use api_client::{curse::CurseClient, modrinth::ModrinthClient};
-use std::{collections::HashMap, sync::Arc};
+use api_client::{
+    common::{CircuitBreakerSettings, ConnectionPoolSettings, Downloader, DownloaderBuilder},
+    curse::CurseClient,
+    modrinth::ModrinthClient,
+};
+use rayon::ThreadPool;
+use std::{collections::HashMap, sync::Arc, time::Duration};
 use thiserror::Error;
 use tokio::sync::broadcast::{self, error::SendError};
 
@@ -42,10 +47,54 @@ pub struct DiContainer {
     // Modrinth API client
     // The Modrinth API does not require an API key, so one can always be created.
     modrinth_client: ModrinthClient,
+    // File downloader shared by all nodes, bounding concurrent downloads.
+    downloader: Downloader,
     // Cache
     cache: Option<Arc<dyn Cache>>,
+    // When set, nodes must not access the network, and may only use cached data.
+    offline: bool,
+    // When set, nodes may run external commands. Off unless the user opts in, since pack
+    // definitions may come from others.
+    allow_commands: bool,
+    // Thread pool parallel work of nodes runs on
+    pool: WorkerPool,
+}
+
+/// Resources of a [`DiContainer`] that may be reused by containers built later in the same
+/// process, so that sequential builds share stored files, caches, and API rate limits.
+#[derive(Clone)]
+pub struct SharedResources {
+    filestore: FileStore,
+    curse_client: Option<CurseClient>,
+    modrinth_client: ModrinthClient,
+    downloader: Downloader,
+    cache: Option<Arc<dyn Cache>>,
+    offline: bool,
+    pool: WorkerPool,
+}
+
+/// The rayon thread pool that nodes run parallel work on. Unless a pool is provided with
+/// [`DiContainerBuilder::thread_pool`], the global pool is used.
+#[derive(Clone, Default)]
+pub struct WorkerPool(Option<Arc<ThreadPool>>);
+
+impl WorkerPool {
+    /// Run `op` in this pool, so that any rayon parallel iterators it uses run on the pool's
+    /// threads.
+    pub fn install<R: Send>(&self, op: impl FnOnce() -> R + Send) -> R {
+        match self.0 {
+            Some(ref pool) => pool.install(op),
+            None => op(),
+        }
+    }
 }
 
+/// Sending half of a channel carrying one node output.
+///
+/// Each channel carries exactly one message: a node sends each of its outputs once, after it has
+/// been woken by [`DiContainer::run`]. A capacity of 1 is therefore enough, and receivers can never
+/// lag. Receivers only see messages sent after they subscribe, so every receiver must be obtained
+/// before the graph is run, which the waker guarantees for nodes that subscribe when spawned.
 #[derive(Debug, Clone)]
 pub enum InputType {
     Text(broadcast::Sender<String>),
@@ -97,6 +146,7 @@ impl DiContainer {
     }
 
     /// Get a receive channel by ID. Will only get messages sent after this method was called.
+    /// Returns [`None`] once the graph has been run, as the channel's message may already be sent.
     pub fn get_receiver(&self, id: &ChannelId) -> Option<OutputType> {
         self.channels.get(id).map(|c| c.subscribe())
     }
@@ -154,13 +204,53 @@ impl DiContainer {
         self.modrinth_client.clone()
     }
 
+    /// Get a file [`Downloader`]. All instances returned from the same [`DiContainer`] share the
+    /// same concurrency bound.
+    pub fn get_downloader(&self) -> Downloader {
+        self.downloader.clone()
+    }
+
     pub fn get_config(&self, key: &str) -> Option<String> {
         self.configs.get(key).cloned()
     }
 
+    /// Get a copy of all global config values.
+    pub fn get_configs(&self) -> HashMap<String, String> {
+        self.configs.clone()
+    }
+
     pub fn get_cache(&self) -> Option<Arc<dyn Cache>> {
         self.cache.clone()
     }
+
+    /// Whether network access is disabled. Resolvers must treat a cache miss as an error.
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    /// Whether nodes may run external commands, such as ShellTransform.
+    pub fn commands_allowed(&self) -> bool {
+        self.allow_commands
+    }
+
+    /// Get the [`WorkerPool`] that nodes must run parallel work on.
+    pub fn get_pool(&self) -> WorkerPool {
+        self.pool.clone()
+    }
+
+    /// Get the resources of this container that can be shared with containers built later, see
+    /// [`DiContainerBuilder::shared_resources`].
+    pub fn get_shared_resources(&self) -> SharedResources {
+        SharedResources {
+            filestore: self.filestore.clone(),
+            curse_client: self.curse_client.clone(),
+            modrinth_client: self.modrinth_client.clone(),
+            downloader: self.downloader.clone(),
+            cache: self.cache.clone(),
+            offline: self.offline,
+            pool: self.pool.clone(),
+        }
+    }
 }
 
 /// Builder for the [`DiContainer`], allowing for channels and API configuration to be set
@@ -169,8 +259,20 @@ impl DiContainer {
 pub struct DiContainerBuilder {
     channels: HashMap<ChannelId, InputType>,
     curse_client: Option<CurseClient>,
+    modrinth_base_url: Option<String>,
     configs: HashMap<String, String>,
     cache: Option<Box<dyn Cache>>,
+    download_concurrency: Option<usize>,
+    download_rate_limit: Option<u32>,
+    download_mirrors: HashMap<String, String>,
+    download_connect_timeout: Option<Duration>,
+    download_read_timeout: Option<Duration>,
+    api_circuit_breaker: Option<CircuitBreakerSettings>,
+    connection_pool: Option<ConnectionPoolSettings>,
+    offline: bool,
+    allow_commands: bool,
+    pool: WorkerPool,
+    shared: Option<SharedResources>,
 }
 
 impl DiContainerBuilder {
@@ -186,6 +288,12 @@ impl DiContainerBuilder {
         self
     }
 
+    /// Point the Modrinth API client at a Modrinth-compatible API other than the official one.
+    pub fn modrinth_base_url(mut self, base_url: &str) -> Self {
+        self.modrinth_base_url = Some(base_url.to_owned());
+        self
+    }
+
     /// Adds multiple channels sourced from a node.
     pub fn channel_from_node(mut self, channels: HashMap<ChannelId, InputType>) -> Self {
         self.channels.extend(channels);
@@ -202,17 +310,141 @@ impl DiContainerBuilder {
         self
     }
 
+    /// Limit how many file downloads may run at once across all nodes.
+    pub fn download_concurrency(mut self, max_concurrent: usize) -> Self {
+        self.download_concurrency = Some(max_concurrent);
+        self
+    }
+
+    /// Limit how many file downloads may be started against a single host each minute.
+    pub fn download_rate_limit(mut self, requests_per_minute: u32) -> Self {
+        self.download_rate_limit = Some(requests_per_minute.max(1));
+        self
+    }
+
+    /// Download files from mirror hosts instead of the hosts in their URLs, keyed by original host.
+    pub fn download_mirrors(mut self, mirrors: HashMap<String, String>) -> Self {
+        self.download_mirrors.extend(mirrors);
+        self
+    }
+
+    /// Give up on file downloads that take longer than this to connect.
+    pub fn download_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.download_connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Give up on file downloads that receive nothing from the server for this long.
+    pub fn download_read_timeout(mut self, timeout: Duration) -> Self {
+        self.download_read_timeout = Some(timeout);
+        self
+    }
+
+    /// Set when the API clients stop sending requests to an API that keeps failing.
+    pub fn api_circuit_breaker(mut self, settings: CircuitBreakerSettings) -> Self {
+        self.api_circuit_breaker = Some(settings);
+        self
+    }
+
+    /// Set how many idle connections the API clients and the downloader keep open for reuse.
+    pub fn connection_pool(mut self, settings: ConnectionPoolSettings) -> Self {
+        self.connection_pool = Some(settings);
+        self
+    }
+
+    /// Disable network access. Resolvers may only use cached data, and downloads fail.
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Allow nodes to run external commands. Disabled by default.
+    pub fn allow_commands(mut self, allow_commands: bool) -> Self {
+        self.allow_commands = allow_commands;
+        self
+    }
+
+    /// Run the parallel work of nodes on this thread pool instead of the global rayon pool, for
+    /// embedders that manage their own threads.
+    pub fn thread_pool(mut self, pool: Arc<ThreadPool>) -> Self {
+        self.pool = WorkerPool(Some(pool));
+        self
+    }
+
+    /// Reuse the file store, API clients, downloader, cache, offline setting and thread pool of
+    /// another container. These take precedence over the API, download, cache, offline and thread
+    /// pool settings of this builder.
+    pub fn shared_resources(mut self, resources: SharedResources) -> Self {
+        self.shared = Some(resources);
+        self
+    }
+
     /// Construct the [`DiContainer`].
     pub fn build(self) -> DiContainer {
+        if let Some(shared) = self.shared {
+            return DiContainer {
+                channels: self.channels,
+                filestore: shared.filestore,
+                waker: broadcast::channel(1).0,
+                waker_called: false,
+                curse_client: shared.curse_client,
+                modrinth_client: shared.modrinth_client,
+                downloader: shared.downloader,
+                configs: self.configs,
+                cache: shared.cache,
+                offline: shared.offline,
+                allow_commands: self.allow_commands,
+                pool: shared.pool,
+            };
+        }
+        let mut downloader = DownloaderBuilder::default();
+        if let Some(max_concurrent) = self.download_concurrency {
+            downloader = downloader.max_concurrent(max_concurrent);
+        }
+        if let Some(requests_per_minute) = self.download_rate_limit {
+            downloader = downloader.requests_per_minute_per_host(requests_per_minute);
+        }
+        if let Some(timeout) = self.download_connect_timeout {
+            downloader = downloader.connect_timeout(timeout);
+        }
+        if let Some(timeout) = self.download_read_timeout {
+            downloader = downloader.read_timeout(timeout);
+        }
+        if let Some(settings) = self.connection_pool {
+            downloader = downloader.connection_pool(settings);
+        }
+        let downloader = self
+            .download_mirrors
+            .iter()
+            .fold(downloader, |d, (from_host, to_host)| d.mirror(from_host, to_host))
+            .offline(self.offline)
+            .build();
+        let mut curse_client = self.curse_client;
+        let mut modrinth_client = match self.modrinth_base_url {
+            Some(ref base_url) => ModrinthClient::from_base_url(base_url),
+            None => ModrinthClient::new(),
+        };
+        if let Some(settings) = self.api_circuit_breaker {
+            curse_client = curse_client.map(|client| client.circuit_breaker(settings));
+            modrinth_client = modrinth_client.circuit_breaker(settings);
+        }
+        if let Some(settings) = self.connection_pool {
+            curse_client = curse_client.map(|client| client.connection_pool(settings));
+            modrinth_client = modrinth_client.connection_pool(settings);
+        }
         DiContainer {
             channels: self.channels,
             filestore: FileStore::new(),
             waker: broadcast::channel(1).0,
             waker_called: false,
-            curse_client: self.curse_client,
-            modrinth_client: ModrinthClient::new(),
+            curse_client,
+            modrinth_client,
+            downloader,
             configs: self.configs,
             cache: self.cache.map(Arc::from),
+            offline: self.offline,
+            allow_commands: self.allow_commands,
+            pool: self.pool,
         }
     }
 }
@@ -234,6 +466,40 @@ mod tests {
         assert!(fs2.get_file(hash).is_some());
     }
 
+    #[test]
+    fn shared_resources_reuse_filestore() {
+        let first = DiContainerBuilder::default().offline(true).build();
+        let hash = first.get_filestore().write_file("Hello World!".into());
+
+        let second = DiContainerBuilder::default()
+            .set_config("minecraft_version", "1.20.1")
+            .shared_resources(first.get_shared_resources())
+            .build();
+        assert!(second.get_filestore().get_file(hash).is_some());
+        assert!(second.is_offline());
+        assert_eq!(second.get_config("minecraft_version").as_deref(), Some("1.20.1"));
+    }
+
+    #[test]
+    fn work_runs_on_provided_pool() {
+        let thread_name = || std::thread::current().name().map(str::to_owned);
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .thread_name(|i| format!("embedder-{i}"))
+            .build()
+            .unwrap();
+        let first = DiContainerBuilder::default().thread_pool(Arc::new(pool)).build();
+        assert_eq!(first.get_pool().install(thread_name).as_deref(), Some("embedder-0"));
+
+        // Shared with later containers
+        let second = DiContainerBuilder::default().shared_resources(first.get_shared_resources()).build();
+        assert_eq!(second.get_pool().install(thread_name).as_deref(), Some("embedder-0"));
+
+        // Without a pool, work starts on the calling thread and is spread over the global pool
+        let caller = thread_name();
+        assert_eq!(DiContainerBuilder::default().build().get_pool().install(thread_name), caller);
+    }
+
     #[test]
     fn get_sender() {
         let (tx, mut rx) = broadcast::channel::<String>(1);
@@ -268,6 +534,38 @@ mod tests {
         assert_eq!(container_rx.try_recv().unwrap(), "Test");
     }
 
+    #[test]
+    fn receiver_subscribed_before_run_gets_message() {
+        let channel_id = ChannelId("node1".into(), "outputA".into());
+        let mut c = DiContainerBuilder::default()
+            .channel_from_node(HashMap::from([(channel_id.clone(), InputType::Text(broadcast::channel(1).0))]))
+            .build();
+
+        // The upstream node is spawned first, and sends as soon as it is woken
+        let tx = match c.get_sender(&channel_id).unwrap() {
+            InputType::Text(channel) => channel,
+            _ => unreachable!(),
+        };
+        let mut waker = c.get_waker();
+        let upstream = std::thread::spawn(move || {
+            if waker.blocking_recv().unwrap() {
+                tx.send("Test".into()).unwrap();
+            }
+        });
+
+        // The downstream node is spawned after the upstream node, but before the graph runs
+        let mut container_rx = match c.get_receiver(&channel_id).unwrap() {
+            OutputType::Text(channel) => channel,
+            _ => unreachable!(),
+        };
+        c.run().unwrap();
+        upstream.join().unwrap();
+
+        assert_eq!(container_rx.blocking_recv().unwrap(), "Test");
+        // Subscribing after the graph runs could miss the message, so it is not allowed
+        assert!(c.get_receiver(&channel_id).is_none());
+    }
+
     #[test]
     fn get_config() {
         let c = DiContainerBuilder::default().set_config("curse-api-key", "12345678").build();
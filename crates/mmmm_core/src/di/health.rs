@@ -0,0 +1,101 @@
+use api_client::{common::ApiError, curse::CurseClient, modrinth::ModrinthClient};
+
+use super::orch::MMMMConfig;
+
+/// Curse project fetched to check the Curse API (Just Enough Items).
+const CURSE_PROBE_PROJECT: u32 = 238222;
+/// Modrinth project fetched to check the Modrinth API (Fabric API).
+const MODRINTH_PROBE_PROJECT: &str = "fabric-api";
+
+/// How the Curse API client is configured. An API key takes precedence over a proxy, as when
+/// building a pack.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CurseMode {
+    ApiKey,
+    Proxy(String),
+    None,
+}
+
+/// Results of checking connectivity to each configured API.
+#[derive(Debug)]
+pub struct HealthReport {
+    pub curse_mode: CurseMode,
+    /// Result of fetching a known Curse project, or [`None`] if the Curse client is not configured.
+    pub curse: Option<Result<(), ApiError>>,
+    /// Base URL of the Modrinth API, or [`None`] for the official API.
+    pub modrinth_base_url: Option<String>,
+    pub modrinth: Result<(), ApiError>,
+}
+
+impl HealthReport {
+    /// Whether every configured API was reachable.
+    pub fn is_healthy(&self) -> bool {
+        self.modrinth.is_ok() && !matches!(self.curse, Some(Err(_)))
+    }
+}
+
+/// Check that the Curse and Modrinth APIs are reachable with the configured credentials, by
+/// fetching a well-known project from each.
+pub fn check_apis(global_config: &MMMMConfig) -> HealthReport {
+    let (curse_mode, curse_client) = if let Some(ref key) = global_config.curse_api_key {
+        (CurseMode::ApiKey, Some(CurseClient::from_key(key.clone())))
+    } else if let Some(ref proxy) = global_config.curse_proxy_url {
+        (CurseMode::Proxy(proxy.clone()), Some(CurseClient::from_proxy(proxy.clone())))
+    } else {
+        (CurseMode::None, None)
+    };
+    let curse = curse_client.map(|client| client.find_mod_by_id(CURSE_PROBE_PROJECT).map(|_| ()));
+
+    let modrinth_client = match global_config.modrinth_base_url {
+        Some(ref base_url) => ModrinthClient::from_base_url(base_url),
+        None => ModrinthClient::new(),
+    };
+    let modrinth = modrinth_client.get_mod_info(MODRINTH_PROBE_PROJECT).map(|_| ());
+
+    HealthReport {
+        curse_mode,
+        curse,
+        modrinth_base_url: global_config.modrinth_base_url.clone(),
+        modrinth,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::node::utils::serve_http;
+
+    use super::*;
+
+    #[test]
+    fn reports_status_per_api() {
+        let server = serve_http(HashMap::from([(
+            "/v2/project/fabric-api".to_owned(),
+            (
+                200,
+                br#"{"slug":"fabric-api","title":"Fabric API","client_side":"optional","server_side":"optional","id":"P7dR8mSH"}"#.to_vec(),
+            ),
+        )]));
+        // The proxy rejects every request, as with an invalid key
+        let config = MMMMConfig {
+            curse_proxy_url: Some(format!("{server}/curse")),
+            modrinth_base_url: Some(server.clone()),
+            ..Default::default()
+        };
+
+        let report = check_apis(&config);
+        assert_eq!(report.curse_mode, CurseMode::Proxy(format!("{server}/curse")));
+        assert!(matches!(report.curse, Some(Err(_))));
+        assert!(report.modrinth.is_ok());
+        assert!(!report.is_healthy());
+
+        let report = check_apis(&MMMMConfig {
+            modrinth_base_url: Some(server),
+            ..Default::default()
+        });
+        assert_eq!(report.curse_mode, CurseMode::None);
+        assert!(report.curse.is_none());
+        assert!(report.is_healthy());
+    }
+}
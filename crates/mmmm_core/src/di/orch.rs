@@ -1,5 +1,6 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Duration};
 
+use api_client::common::{CircuitBreakerSettings, ConnectionPoolSettings};
 use serde::Deserialize;
 use thiserror::Error;
 
@@ -7,7 +8,9 @@ use crate::{
     node::{
         config::{NodeConfig, NodeConfigEntry, NodeInitError, PackDefinition},
         source::Source,
+        utils::CACHE_PREFIX,
     },
+    template::{render, TemplateError},
     Cache,
 };
 
@@ -17,16 +20,58 @@ use super::container::{DiContainer, DiContainerBuilder, OutputType, WakeError};
 pub struct MMMMConfig {
     pub curse_api_key: Option<String>,
     pub curse_proxy_url: Option<String>,
+    /// Maximum number of file downloads to run at once. Unbounded if unset.
+    pub jobs: Option<usize>,
+    /// Maximum number of file downloads to start against a single host each minute.
+    pub download_rate_limit: Option<u32>,
+    /// Seconds to wait for a connection to a download server.
+    pub download_connect_timeout: Option<u64>,
+    /// Seconds a download may go without receiving data before it fails.
+    pub download_read_timeout: Option<u64>,
+    /// Disable network access, building only from cached data.
+    #[serde(default)]
+    pub offline: bool,
+    /// Allow nodes to run external commands, such as ShellTransform.
+    #[serde(default)]
+    pub allow_commands: bool,
+    /// Base URL of a Modrinth-compatible API to use instead of the official one.
+    pub modrinth_base_url: Option<String>,
+    /// Number of consecutive failed API requests after which further requests to that API fail
+    /// immediately. Zero disables this.
+    pub api_failure_threshold: Option<u32>,
+    /// Seconds within which the failed requests must happen to stop further requests.
+    pub api_failure_window: Option<u64>,
+    /// Seconds to stop sending requests for, once an API keeps failing.
+    pub api_failure_cooldown: Option<u64>,
+    /// Idle connections kept open for reuse across all hosts.
+    pub max_idle_connections: Option<usize>,
+    /// Idle connections kept open for reuse to each host.
+    pub max_idle_connections_per_host: Option<usize>,
+    /// Prefix for cache namespaces, isolating cached data from builds using a different prefix.
+    /// A `cache_prefix` value in the pack config takes precedence.
+    pub cache_prefix: Option<String>,
+    /// Prefix for cache namespaces that takes precedence over a `cache_prefix` value in the pack
+    /// config, such as one given on the command line. Not read from the config file.
+    #[serde(skip)]
+    pub cache_prefix_override: Option<String>,
+    /// Hosts to download files from instead of the hosts in resolved URLs, keyed by original
+    /// host. Resolved URLs and cached data are unaffected.
+    #[serde(default)]
+    pub mirror_map: HashMap<String, String>,
 }
 
 #[derive(Debug, Error)]
 pub enum BuildGraphError {
-    #[error("One or more nodes failed to initialize! Errors: {0:?}")]
+    #[error("One or more nodes failed to initialize! Errors: {}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join(" "))]
     NodeConstruction(Vec<NodeInitError>),
     #[error("Source failed to initialize! Error: {0}")]
     SourceConstruction(#[from] NodeInitError),
     #[error("Failed to obtain a channel for an output! Check your output definitions!")]
     OutputChannel,
+    #[error("Output '{0}' sets max_size, but only Files outputs can be split into archives!")]
+    SplitOutput(String),
+    #[error("Invalid output filename '{filename}'! Error: {source}")]
+    OutputFilename { filename: String, source: TemplateError },
     #[error("Failed to send signal to waker channel! Error: {0}")]
     WakeError(#[from] WakeError),
 }
@@ -34,9 +79,25 @@ pub enum BuildGraphError {
 pub struct Graph {
     pub context: DiContainer,
     pub outputs: HashMap<String, OutputType>,
+    /// Maximum archive size of each output that should be split into several archives.
+    pub max_sizes: HashMap<String, u64>,
 }
 
 pub fn build_graph(pack_definition: &str, global_config: MMMMConfig, cache: Option<Box<dyn Cache>>) -> Result<Graph, BuildGraphError> {
+    build_graph_with(pack_definition, global_config, cache, DiContainerBuilder::default())
+}
+
+/// Build a graph, starting from a prepared [`DiContainerBuilder`]. Pass a builder with
+/// [`DiContainerBuilder::shared_resources`] set to the resources of a previously built graph to
+/// share its stored files, cache, and API clients, e.g. when building client and server variants
+/// of a pack in one process, or with [`DiContainerBuilder::thread_pool`] set to run the graph's
+/// parallel work on a thread pool owned by the caller.
+pub fn build_graph_with(
+    pack_definition: &str,
+    global_config: MMMMConfig,
+    cache: Option<Box<dyn Cache>>,
+    ctx_builder: DiContainerBuilder,
+) -> Result<Graph, BuildGraphError> {
     let pack = serde_yaml::from_str::<PackDefinition>(pack_definition).unwrap();
 
     // Separate out node types
@@ -65,13 +126,30 @@ pub fn build_graph(pack_definition: &str, global_config: MMMMConfig, cache: Opti
         })
         .collect::<Vec<_>>();
 
+    // Interpolate config values into output filenames before anything is spawned
+    let output_filenames = output_nodes
+        .iter()
+        .map(|n| {
+            let lookup = |key: &str| key.strip_prefix("config.").and_then(|k| pack.config.get(k).cloned());
+            render(&n.filename, lookup, true).map_err(|source| BuildGraphError::OutputFilename {
+                filename: n.filename.clone(),
+                source,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
     // Create Source node builder
     let source_builder = Source::new(&source_nodes);
 
     // Build DiContainer
-    let mut ctx_builder = DiContainerBuilder::default();
+    let mut ctx_builder = ctx_builder;
     // Set pack config
     ctx_builder = pack.config.iter().fold(ctx_builder, |cb, (k, v)| cb.set_config(k, v));
+    match (&global_config.cache_prefix_override, &global_config.cache_prefix) {
+        (Some(prefix), _) => ctx_builder = ctx_builder.set_config(CACHE_PREFIX, prefix),
+        (None, Some(prefix)) if !pack.config.contains_key(CACHE_PREFIX) => ctx_builder = ctx_builder.set_config(CACHE_PREFIX, prefix),
+        _ => {},
+    }
     // Create and store output channels
     ctx_builder = intermediate_nodes
         .iter()
@@ -82,6 +160,49 @@ pub fn build_graph(pack_definition: &str, global_config: MMMMConfig, cache: Opti
         ctx_builder = ctx_builder.set_cache(c);
     }
 
+    if let Some(jobs) = global_config.jobs {
+        ctx_builder = ctx_builder.download_concurrency(jobs);
+    }
+
+    if let Some(requests_per_minute) = global_config.download_rate_limit {
+        ctx_builder = ctx_builder.download_rate_limit(requests_per_minute);
+    }
+
+    if let Some(seconds) = global_config.download_connect_timeout {
+        ctx_builder = ctx_builder.download_connect_timeout(Duration::from_secs(seconds));
+    }
+
+    if let Some(seconds) = global_config.download_read_timeout {
+        ctx_builder = ctx_builder.download_read_timeout(Duration::from_secs(seconds));
+    }
+
+    if !global_config.mirror_map.is_empty() {
+        ctx_builder = ctx_builder.download_mirrors(global_config.mirror_map.clone());
+    }
+
+    ctx_builder = ctx_builder.offline(global_config.offline).allow_commands(global_config.allow_commands);
+
+    if let Some(ref base_url) = global_config.modrinth_base_url {
+        ctx_builder = ctx_builder.modrinth_base_url(base_url);
+    }
+
+    if global_config.api_failure_threshold.is_some() || global_config.api_failure_window.is_some() || global_config.api_failure_cooldown.is_some() {
+        let defaults = CircuitBreakerSettings::default();
+        ctx_builder = ctx_builder.api_circuit_breaker(CircuitBreakerSettings {
+            failures: global_config.api_failure_threshold.unwrap_or(defaults.failures),
+            window: global_config.api_failure_window.map_or(defaults.window, Duration::from_secs),
+            cooldown: global_config.api_failure_cooldown.map_or(defaults.cooldown, Duration::from_secs),
+        });
+    }
+
+    if global_config.max_idle_connections.is_some() || global_config.max_idle_connections_per_host.is_some() {
+        let defaults = ConnectionPoolSettings::default();
+        ctx_builder = ctx_builder.connection_pool(ConnectionPoolSettings {
+            max_idle: global_config.max_idle_connections.unwrap_or(defaults.max_idle),
+            max_idle_per_host: global_config.max_idle_connections_per_host.unwrap_or(defaults.max_idle_per_host),
+        });
+    }
+
     // Setup Curse API client if global config specifies the required parameters
     ctx_builder = if let Some(key) = global_config.curse_api_key {
         ctx_builder.curse_client_key(&key)
@@ -96,13 +217,35 @@ pub fn build_graph(pack_definition: &str, global_config: MMMMConfig, cache: Opti
 
     // Get output channels
     let outputs: HashMap<String, OutputType> = output_nodes
-        .into_iter()
-        .map(|n| ctx.get_receiver(&n.source).map(|r| (n.filename.clone(), r)))
+        .iter()
+        .zip(output_filenames.iter())
+        .map(|(n, filename)| ctx.get_receiver(&n.source).map(|r| (filename.clone(), r)))
         .collect::<Option<Vec<_>>>()
         .ok_or(BuildGraphError::OutputChannel)?
         .into_iter()
         .collect();
 
+    let max_sizes: HashMap<String, u64> = output_nodes
+        .iter()
+        .zip(output_filenames)
+        .filter_map(|(n, filename)| n.max_size.map(|max_size| (filename, max_size)))
+        .collect();
+    if let Some(filename) = max_sizes
+        .keys()
+        .find(|filename| !matches!(outputs.get(*filename), Some(OutputType::Files(_))))
+    {
+        return Err(BuildGraphError::SplitOutput(filename.clone()));
+    }
+
+    // Check node inputs and config against their descriptions before anything is spawned
+    let errors = intermediate_nodes
+        .iter()
+        .flat_map(|node| node.kind.describe().validate(&node.input, &ctx))
+        .collect::<Vec<_>>();
+    if !errors.is_empty() {
+        return Err(BuildGraphError::NodeConstruction(errors));
+    }
+
     // Spawn nodes. Drop all returned JoinHandles for now to detach threads.
 
     // Early return if source fails to spawn thread.
@@ -119,14 +262,24 @@ pub fn build_graph(pack_definition: &str, global_config: MMMMConfig, cache: Opti
         ctx.cancel()?;
         return Err(BuildGraphError::NodeConstruction(errors));
     }
-    Ok(Graph { context: ctx, outputs })
+    Ok(Graph {
+        context: ctx,
+        outputs,
+        max_sizes,
+    })
 }
 
 #[cfg(test)]
 mod tests {
-    use std::time::Duration;
+    use std::{str::FromStr, time::Duration};
 
-    use crate::node::utils::read_channel;
+    use crate::{
+        file::filepath::FilePath,
+        node::{
+            config::{ChannelId, ResolvedMod},
+            utils::{read_channel, serve_http},
+        },
+    };
 
     use super::*;
 
@@ -174,6 +327,7 @@ nodes:
         let global_config = MMMMConfig {
             curse_proxy_url: Some("https://api.curse.tools/v1/cf".into()),
             curse_api_key: None,
+            ..Default::default()
         };
         let mut graph = build_graph(mod_config, global_config, None).unwrap();
         graph.context.run().unwrap();
@@ -259,4 +413,222 @@ nodes:
 "#;
         assert_eq!(manifest_file, expected);
     }
+
+    #[test]
+    fn test_output_filename_interpolation() {
+        let pack = |filename: &str| {
+            format!(
+                r#"---
+config:
+  pack_version: '1.4.0'
+nodes:
+  - id: readme
+    value: 'Hello'
+  - filename: '{filename}'
+    source: 'readme'
+..."#
+            )
+        };
+
+        let mut graph = build_graph(&pack("my-pack-${config.pack_version}.txt"), MMMMConfig::default(), None).unwrap();
+        graph.context.run().unwrap();
+        let channel = match graph.outputs.get_mut("my-pack-1.4.0.txt") {
+            Some(OutputType::Text(channel)) => channel,
+            _ => panic!("Output filename was not interpolated"),
+        };
+        assert_eq!(read_channel(channel, Duration::from_secs(10)).unwrap(), "Hello");
+
+        let result = build_graph(&pack("my-pack-${config.missing}.txt"), MMMMConfig::default(), None);
+        assert!(matches!(
+            result,
+            Err(BuildGraphError::OutputFilename {
+                source: TemplateError::Unresolved(ref keys),
+                ..
+            }) if keys == &["config.missing"]
+        ));
+    }
+
+    #[test]
+    fn test_build_graph_with_shared_resources() {
+        let pack = "---\nconfig: {}\nnodes:\n  - id: readme\n    value: 'Hello'\n  - filename: 'readme.txt'\n    source: 'readme'\n...";
+
+        let client = build_graph(pack, MMMMConfig::default(), None).unwrap();
+        let builder = DiContainerBuilder::default().shared_resources(client.context.get_shared_resources());
+        let mut server = build_graph_with(pack, MMMMConfig::default(), None, builder).unwrap();
+
+        let hash = client.context.get_filestore().write_file(b"shared archive".to_vec());
+        assert!(server.context.get_filestore().get_file(hash).is_some());
+
+        server.context.run().unwrap();
+        let channel = match server.outputs.get_mut("readme.txt") {
+            Some(OutputType::Text(channel)) => channel,
+            _ => panic!("Missing readme output"),
+        };
+        assert_eq!(read_channel(channel, Duration::from_secs(10)).unwrap(), "Hello");
+    }
+
+    #[test]
+    fn test_cache_prefix_precedence() {
+        let pack = |config: &str| format!("---\nconfig: {{{config}}}\nnodes:\n  - id: readme\n    value: 'Hello'\n...");
+        let prefix = |pack: &str, cache_prefix: Option<&str>, cache_prefix_override: Option<&str>| {
+            let global_config = MMMMConfig {
+                cache_prefix: cache_prefix.map(str::to_owned),
+                cache_prefix_override: cache_prefix_override.map(str::to_owned),
+                ..Default::default()
+            };
+            build_graph(pack, global_config, None).unwrap().context.get_config(CACHE_PREFIX)
+        };
+
+        assert_eq!(prefix(&pack(""), Some("global"), None).as_deref(), Some("global"));
+        assert_eq!(prefix(&pack("cache_prefix: pack"), Some("global"), None).as_deref(), Some("pack"));
+        assert_eq!(prefix(&pack("cache_prefix: pack"), Some("global"), Some("flag")).as_deref(), Some("flag"));
+        assert_eq!(prefix(&pack(""), None, Some("flag")).as_deref(), Some("flag"));
+    }
+
+    #[test]
+    fn test_max_size_requires_files_output() {
+        let pack =
+            "---\nconfig: {}\nnodes:\n  - id: readme\n    value: 'Hello'\n  - filename: 'readme.txt'\n    source: 'readme'\n    max_size: 1000\n...";
+        let result = build_graph(pack, MMMMConfig::default(), None);
+        assert!(matches!(result, Err(BuildGraphError::SplitOutput(filename)) if filename == "readme.txt"));
+    }
+
+    #[test]
+    fn test_curse_client_required() {
+        let pack = r#"---
+config: {}
+nodes:
+  - id: manifest
+    value: '{"files": []}'
+  - id: curse-mods
+    kind: CurseResolver
+    input:
+      manifest: manifest
+..."#;
+        let error = match build_graph(pack, MMMMConfig::default(), None) {
+            Err(error) => error,
+            Ok(_) => panic!("Graph built without a Curse client"),
+        };
+        assert!(matches!(error, BuildGraphError::NodeConstruction(ref errors) if matches!(errors.as_slice(), [NodeInitError::CurseClientRequired])));
+        assert!(error.to_string().contains("Set curse_api_key or curse_proxy_url"));
+    }
+
+    #[test]
+    fn test_inputs_validated_before_spawning() {
+        let pack = r#"---
+config: {}
+nodes:
+  - id: pack-url
+    value: 'https://example.com/pack.zip'
+  - id: patterns
+    value: ['overrides/**']
+  - id: filter
+    kind: FileFilter
+    input:
+      files: pack-url
+      patterns: patterns
+  - id: modlist
+    kind: ModWriter
+    input:
+      resolved: filter
+  - filename: 'mods.txt'
+    source: 'modlist'
+..."#;
+        let errors = match build_graph(pack, MMMMConfig::default(), None) {
+            Err(BuildGraphError::NodeConstruction(errors)) => errors,
+            _ => panic!("Invalid inputs were not rejected"),
+        };
+        let errors = errors.iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            vec![
+                NodeInitError::MissingInputId("pattern".into()).to_string(),
+                NodeInitError::InvalidInputType {
+                    input: "files".into(),
+                    channel: ChannelId::from_str("pack-url").unwrap(),
+                }
+                .to_string(),
+                NodeInitError::UnknownInput("patterns".into()).to_string(),
+                NodeInitError::InvalidInputType {
+                    input: "resolved".into(),
+                    channel: ChannelId::from_str("filter").unwrap(),
+                }
+                .to_string(),
+                NodeInitError::MissingConfig("minecraft_version".into()).to_string(),
+            ]
+        );
+    }
+
+    fn zip_archive(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        for (path, contents) in files {
+            writer.start_file(*path, zip::write::FileOptions::default()).unwrap();
+            std::io::Write::write_all(&mut writer, contents.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_apply_delta_example() {
+        let routes = HashMap::from([
+            (
+                "/base-pack.zip".to_owned(),
+                (
+                    200,
+                    zip_archive(&[
+                        ("manifest.json", "{}"),
+                        ("overrides/config/shared.cfg", "base"),
+                        ("overrides/config/base-only.cfg", "base"),
+                    ]),
+                ),
+            ),
+            (
+                "/delta.zip".to_owned(),
+                (
+                    200,
+                    zip_archive(&[
+                        ("overrides/config/shared.cfg", "delta"),
+                        ("overrides/config/delta-only.cfg", "delta"),
+                        ("README.md", "not part of the pack"),
+                    ]),
+                ),
+            ),
+            ("/mods/base-library.jar".to_owned(), (200, b"base-library".to_vec())),
+            ("/mods/outdated-mod.jar".to_owned(), (200, b"outdated-mod".to_vec())),
+            ("/mods/extra-mod.jar".to_owned(), (200, b"extra-mod".to_vec())),
+        ]);
+        let server = serve_http(routes);
+        let pack = include_str!("../../../../examples/apply_delta.yaml").replace("https://example.com", &server);
+
+        let mut graph = build_graph(&pack, MMMMConfig::default(), None).unwrap();
+        graph.context.run().unwrap();
+
+        let overrides = match graph.outputs.get_mut("overrides") {
+            Some(OutputType::Files(channel)) => read_channel(channel, Duration::from_secs(10)).unwrap(),
+            _ => panic!("Missing overrides output"),
+        };
+        let mut paths = overrides.list_files().into_iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                "overrides/config/base-only.cfg",
+                "overrides/config/delta-only.cfg",
+                "overrides/config/shared.cfg"
+            ]
+        );
+        let shared = overrides.get_file(&FilePath::from_str("overrides/config/shared.cfg").unwrap()).unwrap();
+        assert_eq!(shared.as_slice(), b"delta");
+
+        let manifest = match graph.outputs.get_mut("manifest.json") {
+            Some(OutputType::Text(channel)) => read_channel(channel, Duration::from_secs(10)).unwrap(),
+            _ => panic!("Missing manifest output"),
+        };
+        let names = serde_json::from_str::<Vec<ResolvedMod>>(&manifest)
+            .unwrap()
+            .into_iter()
+            .map(|m| m.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["base-library", "extra-mod"]);
+    }
 }
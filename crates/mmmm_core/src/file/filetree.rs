@@ -2,8 +2,8 @@ use super::filepath::FilePath;
 use super::filestore::FileStore;
 use std::collections::{HashMap, HashSet};
 use std::io::{Seek, Write};
+use std::str::FromStr;
 use std::sync::Arc;
-use std::u128;
 use thiserror::Error;
 use zip::result::ZipError;
 use zip::write::FileOptions;
@@ -40,6 +40,11 @@ impl FileTree {
         None
     }
 
+    /// Get the content hash of a file, which is equal for files with identical contents.
+    pub fn get_hash(&self, path: &FilePath) -> Option<u128> {
+        self.contents.get(path).copied()
+    }
+
     /// Delete a filepath from the filetree.
     ///
     /// Idempotent.
@@ -109,6 +114,26 @@ impl FileTree {
         }
     }
 
+    /// Copies the files under a top-level directory into a new [`FileTree`], with their paths made
+    /// relative to that directory. Files outside the directory are left out.
+    pub fn subtree(&self, dir: &str) -> FileTree {
+        let contents = self
+            .contents
+            .iter()
+            .filter_map(|(path, hash)| {
+                let components = path.get_components();
+                match components.split_first() {
+                    Some((first, rest)) if first == dir && !rest.is_empty() => Some((FilePath::from_str(&rest.join("/")).ok()?, *hash)),
+                    _ => None,
+                }
+            })
+            .collect();
+        FileTree {
+            contents,
+            store: self.store.clone(),
+        }
+    }
+
     /// Write all files from this [`FileTree`] to a ZIP file.
     pub fn zip<W>(&self, buffer: &mut W) -> Result<usize, FileTreeError>
     where
@@ -117,13 +142,60 @@ impl FileTree {
         let mut zip = ZipWriter::new(buffer);
         let options = FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
         let mut total_bytes = 0;
-        for (name, hash) in &self.contents {
+        // Write entries in a fixed order, so the same tree always produces the same archive
+        let mut contents = self.contents.iter().collect::<Vec<_>>();
+        contents.sort_by_cached_key(|(name, _)| name.to_string());
+        for (name, hash) in contents {
             zip.start_file(name.to_string(), options)?;
             total_bytes += zip.write(&self.store.get_file(*hash).ok_or_else(|| FileTreeError::FileNotFound(name.to_string()))?)?;
         }
         zip.finish()?;
         Ok(total_bytes)
     }
+
+    /// Split this [`FileTree`] into trees that each zip to at most `max_size` bytes, never
+    /// splitting a file between trees. Archive sizes are estimated conservatively from the
+    /// uncompressed size of each file.
+    ///
+    /// Files are packed largest first into the first tree with room for them, breaking ties by
+    /// path, so the same tree is always partitioned identically. Always returns at least one tree.
+    pub fn partition(&self, max_size: u64) -> Result<Vec<FileTree>, FileTreeError> {
+        // End of central directory record
+        const ARCHIVE_OVERHEAD: u64 = 22;
+        let mut entries = self
+            .contents
+            .iter()
+            .map(|(path, hash)| {
+                let name = path.to_string();
+                let size = self.store.get_file(*hash).ok_or_else(|| FileTreeError::FileNotFound(name.clone()))?.len() as u64;
+                // Local and central directory headers, with room for ZIP64 extra fields, and
+                // the worst case expansion of incompressible data by deflate
+                let estimate = size + size / 1000 + 128 + 2 * name.len() as u64;
+                Ok((estimate, name, path, *hash))
+            })
+            .collect::<Result<Vec<_>, FileTreeError>>()?;
+        entries.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+
+        let mut parts: Vec<(u64, FileTree)> = vec![];
+        for (estimate, name, path, hash) in entries {
+            if ARCHIVE_OVERHEAD + estimate > max_size {
+                return Err(FileTreeError::TooLarge(name, max_size));
+            }
+            let part = match parts.iter_mut().find(|(used, _)| used + estimate <= max_size) {
+                Some(part) => part,
+                None => {
+                    parts.push((ARCHIVE_OVERHEAD, FileTree::new(self.store.clone())));
+                    parts.last_mut().unwrap()
+                },
+            };
+            part.0 += estimate;
+            part.1.contents.insert(path.clone(), hash);
+        }
+        if parts.is_empty() {
+            return Ok(vec![FileTree::new(self.store.clone())]);
+        }
+        Ok(parts.into_iter().map(|(_, tree)| tree).collect())
+    }
 }
 
 #[derive(Debug, Error)]
@@ -134,12 +206,12 @@ pub enum FileTreeError {
     Zip(#[from] ZipError),
     #[error("Encountered error while adding file to zip. Error: {0}")]
     ZipWrite(#[from] std::io::Error),
+    #[error("File {0} is too large to fit in an archive of at most {1} bytes")]
+    TooLarge(String, u64),
 }
 
 #[cfg(test)]
 mod tests {
-    use std::str::FromStr;
-
     use super::*;
 
     fn get_filetree() -> FileTree {
@@ -356,6 +428,47 @@ mod tests {
         );
     }
 
+    #[test]
+    fn partition_packs_whole_files() {
+        let mut files = get_filetree();
+        for (name, size) in [("a.bin", 600), ("b.bin", 500), ("c.bin", 400), ("d.bin", 300)] {
+            files.add_file(FilePath::from_str(name).unwrap(), vec![7; size]);
+        }
+
+        let parts = files.partition(1300).unwrap();
+        let names = parts
+            .iter()
+            .map(|part| {
+                let mut names = part.list_files().into_iter().map(|p| p.to_string()).collect::<Vec<_>>();
+                names.sort();
+                names
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec![vec!["a.bin", "c.bin"], vec!["b.bin", "d.bin"]]);
+        for part in &parts {
+            let mut buffer = std::io::Cursor::new(Vec::new());
+            part.zip(&mut buffer).unwrap();
+            assert!(buffer.into_inner().len() <= 1300);
+        }
+
+        assert!(matches!(files.partition(500), Err(FileTreeError::TooLarge(name, 500)) if name == "a.bin"));
+        assert_eq!(get_filetree().partition(100).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn zip_is_deterministic() {
+        let zip = |names: &[&str]| {
+            let mut files = get_filetree();
+            for name in names {
+                files.add_file(FilePath::from_str(name).unwrap(), name.as_bytes().to_vec());
+            }
+            let mut buffer = std::io::Cursor::new(Vec::new());
+            files.zip(&mut buffer).unwrap();
+            buffer.into_inner()
+        };
+        assert_eq!(zip(&["a.txt", "b/c.txt", "d.txt", "e.txt"]), zip(&["e.txt", "d.txt", "b/c.txt", "a.txt"]));
+    }
+
     #[test]
     fn merge_distinct_store() {
         let store1 = FileStore::new();
@@ -375,4 +488,26 @@ mod tests {
             "other"
         );
     }
+
+    #[test]
+    fn subtree() {
+        let mut files = get_filetree();
+        for name in [
+            "overrides/config/jei.toml",
+            "overrides/options.txt",
+            "overrides",
+            "client_overrides/options.txt",
+            "manifest.json",
+        ] {
+            files.add_file(FilePath::from_str(name).unwrap(), name.into());
+        }
+        let subtree = files.subtree("overrides");
+        let mut paths = subtree.list_files().into_iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        paths.sort();
+        assert_eq!(paths, ["config/jei.toml", "options.txt"]);
+        assert_eq!(
+            subtree.get_file(&FilePath::from_str("options.txt").unwrap()).unwrap().as_slice(),
+            b"overrides/options.txt"
+        );
+    }
 }
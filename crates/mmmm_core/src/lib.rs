@@ -1,8 +1,16 @@
 mod di;
 mod file;
 mod node;
+mod template;
 
+pub use di::container::DiContainerBuilder;
 pub use di::container::OutputType;
+pub use di::health;
 pub use di::orch;
+pub use file::filetree::FileTree;
+pub use node::config::node_descriptions;
 pub use node::config::Cache;
 pub use node::config::CacheError;
+pub use node::config::ChannelType;
+pub use node::config::NodeDescription;
+pub use node::config::Port;
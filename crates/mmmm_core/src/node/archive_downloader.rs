@@ -1,17 +1,17 @@
 use super::{
-    config::{ChannelId, NodeConfig, NodeInitError},
-    utils::{get_input, get_output},
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port},
+    utils::{get_input, get_optional_input, get_output},
 };
 use crate::{
     di::container::{DiContainer, InputType, OutputType},
-    file::{filepath::FilePath, filetree::FileTree},
+    file::{filepath::FilePath, filestore::FileStore, filetree::FileTree},
 };
-use api_client::common::download_file;
 use serde::Deserialize;
 use std::io::Cursor;
 use std::{
     collections::HashMap,
     io::Read,
+    str::FromStr,
     thread::{spawn, JoinHandle},
 };
 use tokio::sync::broadcast::channel;
@@ -19,10 +19,15 @@ use tracing::{event, span, Level};
 use tracing_unwrap::ResultExt;
 use zip::read::ZipArchive;
 
-#[derive(Debug, Clone, Deserialize, PartialEq)]
-pub struct ArchiveDownloader;
+#[derive(Debug, Clone, Deserialize, PartialEq, Default)]
+pub struct ArchiveDownloader {
+    /// File extensions to extract, without the leading dot. Everything is extracted when empty.
+    #[serde(default)]
+    pub keep_extensions: Vec<String>,
+}
 
 const URL: &str = "url";
+const FILTER: &str = "filter";
 
 impl NodeConfig for ArchiveDownloader {
     fn validate_and_spawn(
@@ -33,7 +38,20 @@ impl NodeConfig for ArchiveDownloader {
     ) -> Result<JoinHandle<()>, NodeInitError> {
         let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Files, ctx)?;
         let mut in_channel = get_input!(URL, Text, ctx, input_ids)?;
+        // The filter input is optional, and everything is extracted when it is not connected
+        let mut filter_channel = get_optional_input!(FILTER, List, ctx, input_ids)?;
+        // Archives can be hundreds of megabytes, too large for the cache, so they are never cached
+        // and can't be used offline. Fail before the build starts rather than on the download.
+        if ctx.is_offline() {
+            return Err(NodeInitError::Offline);
+        }
+        let keep_extensions = self
+            .keep_extensions
+            .iter()
+            .map(|extension| extension.trim_start_matches('.').to_lowercase())
+            .collect::<Vec<_>>();
         let fs = ctx.get_filestore();
+        let downloader = ctx.get_downloader();
         let mut waker = ctx.get_waker();
         Ok(spawn(move || {
             let _span = span!(Level::INFO, "ArchiveDownloader", nodeid = node_id).entered();
@@ -42,24 +60,16 @@ impl NodeConfig for ArchiveDownloader {
             }
 
             let url = in_channel.blocking_recv().expect_or_log("Failed to receive on url input");
+            let filter = filter_channel
+                .as_mut()
+                .map(|channel| channel.blocking_recv().expect_or_log("Failed to receive on filter input"));
             event!(Level::INFO, "Downloading archive from {}", url);
 
-            let archive = download_file(&url).expect_or_log(&format!("Failed to download archive from URL {url}"));
-
-            let mut zip_archive = ZipArchive::new(Cursor::new(archive)).expect_or_log("Failed to read archive as ZIP");
-            let mut filetree = FileTree::new(fs);
-            for index in 0..zip_archive.len() {
-                let mut file = zip_archive.by_index(index).expect_or_log("Failed to read file from archive");
-                if file.is_file() {
-                    let mut contents: Vec<u8> = Vec::with_capacity(file.size() as usize);
-                    file.read_to_end(&mut contents).unwrap();
-                    // As in FilePath, we don't care about properly handling "interesting" paths.
-                    let filename = FilePath::try_from(file.mangled_name().as_ref())
-                        .expect_or_log(&format!("Filename from archive invalid: {}", file.mangled_name().to_string_lossy()));
-
-                    filetree.add_file(filename, contents);
-                }
-            }
+            let archive = downloader
+                .download_file(&url)
+                .expect_or_log(&format!("Failed to download archive from URL {url}"));
+
+            let filetree = extract_archive(archive, filter.as_deref(), &keep_extensions, fs);
 
             if out_channel.send(filetree).is_err() {
                 event!(Level::DEBUG, "Channel 'default' has no subscribers");
@@ -70,6 +80,100 @@ impl NodeConfig for ArchiveDownloader {
     fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
         HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::Files(channel(1).0))])
     }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "ArchiveDownloader",
+            vec![Port::required(URL, ChannelType::Text), Port::optional(FILTER, ChannelType::List)],
+            vec![Port::required("default", ChannelType::Files)],
+        )
+    }
+}
+
+/// Unpack a ZIP archive into a file tree. If `filter` is set, only entries matching one of its glob
+/// patterns are extracted. If `keep_extensions` is not empty, only entries with one of those
+/// lowercase extensions are extracted.
+pub(super) fn extract_archive(archive: Vec<u8>, filter: Option<&[String]>, keep_extensions: &[String], fs: FileStore) -> FileTree {
+    let mut zip_archive = ZipArchive::new(Cursor::new(archive)).expect_or_log("Failed to read archive as ZIP");
+    let mut filetree = FileTree::new(fs);
+    let mut skipped_extensions = 0;
+    for index in 0..zip_archive.len() {
+        let mut file = zip_archive.by_index(index).expect_or_log("Failed to read file from archive");
+        if !file.is_file() {
+            continue;
+        }
+        // The decoded name honours the archive's encoding flag, so names from archivers that write
+        // CP437 come out right. The raw bytes are only a last resort.
+        let raw_name = file.name().to_owned();
+        let sanitized = sanitize_entry_name(&raw_name).or_else(|| sanitize_entry_name(&String::from_utf8_lossy(file.name_raw())));
+        let Some(name) = sanitized else {
+            event!(Level::WARN, "Skipping archive entry with unusable path '{}'", raw_name);
+            continue;
+        };
+        if name != raw_name || name.contains(char::REPLACEMENT_CHARACTER) {
+            event!(Level::WARN, "Archive entry path '{}' sanitized to '{}'", raw_name, name);
+        }
+        let filename = match FilePath::from_str(&name) {
+            Ok(filename) => filename,
+            Err(e) => {
+                event!(Level::WARN, "Skipping archive entry with invalid path '{}': {}", raw_name, e);
+                continue;
+            },
+        };
+        // Entries are only decompressed when read, so skipped entries cost nothing
+        if filter.is_some_and(|patterns| !filename.glob_match(patterns)) {
+            continue;
+        }
+        if !keep_extensions.is_empty() && !has_extension(&name, keep_extensions) {
+            skipped_extensions += 1;
+            continue;
+        }
+        let mut contents: Vec<u8> = Vec::with_capacity(file.size() as usize);
+        file.read_to_end(&mut contents).unwrap();
+        filetree.add_file(filename, contents);
+    }
+    if skipped_extensions > 0 {
+        event!(
+            Level::INFO,
+            "Skipped {} archive entries without one of the extensions {}",
+            skipped_extensions,
+            keep_extensions.join(", ")
+        );
+    }
+    filetree
+}
+
+/// Whether the last component of `path` ends in one of the lowercase `extensions`.
+pub(super) fn has_extension(path: &str, extensions: &[String]) -> bool {
+    let filename = path.rsplit('/').next().unwrap_or(path);
+    filename
+        .rsplit_once('.')
+        .is_some_and(|(stem, extension)| !stem.is_empty() && extensions.contains(&extension.to_lowercase()))
+}
+
+/// Convert an archive entry name into a relative path. Backslashes are treated as separators, and
+/// empty, `.` and `..` components are dropped, which also strips leading slashes. A leading Windows
+/// drive such as `C:` is dropped as well. Returns [`None`] if nothing is left of the path.
+fn sanitize_entry_name(name: &str) -> Option<String> {
+    let name = name.replace('\\', "/");
+    let name = strip_drive(&name);
+    let components = name
+        .split('/')
+        .filter(|part| !part.is_empty() && *part != "." && *part != "..")
+        .collect::<Vec<_>>();
+    if components.is_empty() {
+        None
+    } else {
+        Some(components.join("/"))
+    }
+}
+
+/// Strip a leading drive letter and colon, as in `C:/Windows`.
+fn strip_drive(name: &str) -> &str {
+    match name.as_bytes() {
+        [letter, b':', ..] if letter.is_ascii_alphabetic() => &name[2..],
+        _ => name,
+    }
 }
 
 #[cfg(test)]
@@ -79,9 +183,10 @@ mod tests {
         file::{filepath::FilePath, filetree::FileTree},
         node::{
             config::{ChannelId, NodeConfigTypes},
-            utils::{get_output_test, read_channel},
+            utils::{get_output_test, read_channel, serve_http},
         },
     };
+    use std::io::Write;
     use std::{str::FromStr, time::Duration};
 
     use super::*;
@@ -93,7 +198,7 @@ mod tests {
         let url_channel = tokio::sync::broadcast::channel::<String>(1).0;
         let node_id = "archive_downloader_test";
         let input_ids = HashMap::from([("url".to_string(), ChannelId::from_str("test_node::test_output").unwrap())]);
-        let node = NodeConfigTypes::ArchiveDownloader(ArchiveDownloader);
+        let node = NodeConfigTypes::ArchiveDownloader(ArchiveDownloader::default());
         let mut ctx = DiContainerBuilder::default()
             .channel_from_node(HashMap::from([(
                 input_ids.get("url").unwrap().clone(),
@@ -114,4 +219,133 @@ mod tests {
         handle.join().unwrap();
         assert!(output.get_file(&FilePath::from_str("modrinth.index.json").unwrap()).is_some());
     }
+
+    #[test]
+    fn sanitize_entry_names() {
+        assert_eq!(sanitize_entry_name("config/mod.cfg").as_deref(), Some("config/mod.cfg"));
+        assert_eq!(sanitize_entry_name("/config//mod.cfg").as_deref(), Some("config/mod.cfg"));
+        assert_eq!(sanitize_entry_name("config\\mod.cfg").as_deref(), Some("config/mod.cfg"));
+        assert_eq!(sanitize_entry_name("../../escape.cfg").as_deref(), Some("escape.cfg"));
+        assert_eq!(sanitize_entry_name("config/../../../etc/passwd").as_deref(), Some("config/etc/passwd"));
+        assert_eq!(sanitize_entry_name("C:\\Windows\\evil.dll").as_deref(), Some("Windows/evil.dll"));
+        assert_eq!(sanitize_entry_name("c:evil.dll").as_deref(), Some("evil.dll"));
+        assert_eq!(sanitize_entry_name("/./../"), None);
+    }
+
+    #[test]
+    fn salvages_invalid_entry_names() {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for name in [
+            "config/caf#.cfg",
+            "config/na\u{ef}ve#.cfg",
+            "/absolute.cfg",
+            "../escape.cfg",
+            "C:\\drive.cfg",
+            "ok.txt",
+        ] {
+            writer.start_file(name, zip::write::FileOptions::default()).unwrap();
+            writer.write_all(name.as_bytes()).unwrap();
+        }
+        let mut archive = writer.finish().unwrap().into_inner();
+        // Replace the placeholders with a non-ASCII byte. ASCII names are written without the UTF-8
+        // flag, so that entry is read as CP437, like names from legacy archivers. The other name
+        // has the flag set and becomes invalid UTF-8.
+        archive.iter_mut().filter(|b| **b == b'#').for_each(|b| *b = 0xE9);
+        let server = serve_http(HashMap::from([("/pack.zip".to_owned(), (200, archive))]));
+
+        let url_channel = tokio::sync::broadcast::channel::<String>(1).0;
+        let node_id = "archive_downloader_test";
+        let input_ids = HashMap::from([("url".to_string(), ChannelId::from_str("test_node::test_output").unwrap())]);
+        let node = NodeConfigTypes::ArchiveDownloader(ArchiveDownloader::default());
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(HashMap::from([(
+                input_ids.get("url").unwrap().clone(),
+                InputType::Text(url_channel.clone()),
+            )]))
+            .channel_from_node(node.generate_channels(node_id))
+            .build();
+        let mut output_rx = get_output_test!(ChannelId::from_str(node_id).unwrap(), Files, ctx);
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+
+        url_channel.send(format!("{server}/pack.zip")).unwrap();
+        ctx.run().unwrap();
+
+        let output: FileTree = read_channel(&mut output_rx, Duration::from_secs(10)).unwrap();
+        handle.join().unwrap();
+        let mut paths = output.list_files().into_iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                "absolute.cfg",
+                "config/caf\u{398}.cfg",
+                "config/na\u{ef}ve\u{FFFD}.cfg",
+                "drive.cfg",
+                "escape.cfg",
+                "ok.txt"
+            ]
+        );
+    }
+
+    #[test]
+    fn extracts_only_filtered_entries() {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for name in ["manifest.json", "overrides/config/mod.cfg", "overrides/mods/mod.jar"] {
+            writer.start_file(name, zip::write::FileOptions::default()).unwrap();
+            writer.write_all(name.as_bytes()).unwrap();
+        }
+        let archive = writer.finish().unwrap().into_inner();
+        let server = serve_http(HashMap::from([("/pack.zip".to_owned(), (200, archive))]));
+
+        let url_channel = tokio::sync::broadcast::channel::<String>(1).0;
+        let filter_channel = tokio::sync::broadcast::channel::<Vec<String>>(1).0;
+        let node_id = "archive_downloader_test";
+        let input_ids = HashMap::from([
+            ("url".to_string(), ChannelId::from_str("url-source").unwrap()),
+            ("filter".to_string(), ChannelId::from_str("filter-source").unwrap()),
+        ]);
+        let node = NodeConfigTypes::ArchiveDownloader(ArchiveDownloader::default());
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(HashMap::from([
+                (ChannelId::from_str("url-source").unwrap(), InputType::Text(url_channel.clone())),
+                (ChannelId::from_str("filter-source").unwrap(), InputType::List(filter_channel.clone())),
+            ]))
+            .channel_from_node(node.generate_channels(node_id))
+            .build();
+        let mut output_rx = get_output_test!(ChannelId::from_str(node_id).unwrap(), Files, ctx);
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+
+        url_channel.send(format!("{server}/pack.zip")).unwrap();
+        filter_channel
+            .send(vec!["manifest.json".to_owned(), "overrides/config/**".to_owned()])
+            .unwrap();
+        ctx.run().unwrap();
+
+        let output: FileTree = read_channel(&mut output_rx, Duration::from_secs(10)).unwrap();
+        handle.join().unwrap();
+        let mut paths = output.list_files().into_iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        paths.sort();
+        assert_eq!(paths, vec!["manifest.json", "overrides/config/mod.cfg"]);
+    }
+
+    #[test]
+    fn keeps_only_listed_extensions() {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for name in [
+            "manifest.json",
+            "overrides/config/mod.CFG",
+            "overrides/mods/mod.jar",
+            "overrides/.toml",
+            "README",
+        ] {
+            writer.start_file(name, zip::write::FileOptions::default()).unwrap();
+            writer.write_all(name.as_bytes()).unwrap();
+        }
+        let archive = writer.finish().unwrap().into_inner();
+
+        let output = extract_archive(archive, None, &["json".to_owned(), "cfg".to_owned()], FileStore::new());
+        let mut paths = output.list_files().into_iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        paths.sort();
+        assert_eq!(paths, vec!["manifest.json", "overrides/config/mod.CFG"]);
+    }
 }
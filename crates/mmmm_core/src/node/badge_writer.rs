@@ -0,0 +1,210 @@
+use std::{
+    collections::HashMap,
+    thread::{spawn, JoinHandle},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::di::container::{DiContainer, InputType, OutputType};
+
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port, ResolvedMod},
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct BadgeWriter {
+    /// Text on the left side of the badge.
+    #[serde(default = "default_label")]
+    pub label: String,
+    /// Color of the right side of the badge, as a shields.io color name or hex code.
+    #[serde(default = "default_color")]
+    pub color: String,
+    /// What the badge message shows.
+    #[serde(default)]
+    pub metric: BadgeMetric,
+}
+
+fn default_label() -> String {
+    "mods".into()
+}
+
+fn default_color() -> String {
+    "blue".into()
+}
+
+impl Default for BadgeWriter {
+    fn default() -> Self {
+        BadgeWriter {
+            label: default_label(),
+            color: default_color(),
+            metric: BadgeMetric::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum BadgeMetric {
+    /// The number of mods, counting client and server mods alike.
+    #[default]
+    Count,
+    /// The combined size of all mod files.
+    Size,
+}
+
+/// A shields.io endpoint badge, see <https://shields.io/badges/endpoint-badge>.
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct Badge<'a> {
+    schema_version: u32,
+    label: &'a str,
+    message: String,
+    color: &'a str,
+}
+
+impl NodeConfig for BadgeWriter {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut mods_channel = get_input!("mods", ResolvedMods, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Text, ctx)?;
+        if self.color.trim().is_empty() {
+            return Err(NodeInitError::InvalidOption {
+                option: "color".into(),
+                reason: "must not be empty".into(),
+            });
+        }
+        let (label, color, metric) = (self.label.clone(), self.color.clone(), self.metric);
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "BadgeWriter", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let mods = mods_channel.blocking_recv().expect_or_log("Failed to receive on mods input");
+
+            let badge = Badge {
+                schema_version: 1,
+                label: &label,
+                message: badge_message(&mods, metric),
+                color: &color,
+            };
+            event!(Level::INFO, "Badge message is '{}'", badge.message);
+            let json = serde_json::to_string_pretty(&badge).expect_or_log("Failed to serialize badge");
+
+            if out_channel.send(json).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::Text(channel(1).0))])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "BadgeWriter",
+            vec![Port::required("mods", ChannelType::ResolvedMods)],
+            vec![Port::required("default", ChannelType::Text)],
+        )
+    }
+}
+
+fn badge_message(mods: &[ResolvedMod], metric: BadgeMetric) -> String {
+    match metric {
+        BadgeMetric::Count => mods.len().to_string(),
+        BadgeMetric::Size => format_size(mods.iter().map(|m| m.size).sum()),
+    }
+}
+
+/// Format a size in bytes with binary units, such as `1.5 MiB`.
+pub(super) fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel, test_mod},
+        },
+    };
+
+    use super::*;
+
+    fn run(node: BadgeWriter, mods: Vec<ResolvedMod>) -> serde_json::Value {
+        let node_id = "badge";
+        let mods_channel = channel(1).0;
+        let input_ids = HashMap::from([("mods".into(), ChannelId::from_str("resolver").unwrap())]);
+        let node = NodeConfigTypes::BadgeWriter(node);
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("resolver").unwrap(),
+                InputType::ResolvedMods(mods_channel.clone()),
+            )]))
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Text, ctx);
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        mods_channel.send(mods).unwrap();
+        handle.join().unwrap();
+
+        serde_json::from_str(&read_channel(&mut out_channel, Duration::from_secs(10)).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_badge_writer() {
+        let sized = |name: &str, size: u64| ResolvedMod { size, ..test_mod(name) };
+        let mods = vec![sized("jei", 1024 * 1024), sized("sodium", 512 * 1024), sized("lithium", 0)];
+
+        assert_eq!(
+            run(BadgeWriter::default(), mods.clone()),
+            serde_json::json!({"schemaVersion": 1, "label": "mods", "message": "3", "color": "blue"})
+        );
+        let size_badge = BadgeWriter {
+            label: "download size".into(),
+            color: "#4c1".into(),
+            metric: BadgeMetric::Size,
+        };
+        assert_eq!(
+            run(size_badge, mods),
+            serde_json::json!({"schemaVersion": 1, "label": "download size", "message": "1.5 MiB", "color": "#4c1"})
+        );
+    }
+
+    #[test]
+    fn size_formatting() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(300 * 1024 * 1024), "300.0 MiB");
+        assert_eq!(format_size(5 * 1024 * 1024 * 1024), "5.0 GiB");
+    }
+}
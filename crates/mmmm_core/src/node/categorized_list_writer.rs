@@ -0,0 +1,290 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+    thread::{spawn, JoinHandle},
+};
+
+use api_client::{curse::CurseClient, modrinth::ModrinthClient};
+use rayon::prelude::*;
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::di::container::{DiContainer, InputType, OutputType};
+
+use super::{
+    config::{ChannelId, ChannelType, ModSource, NodeConfig, NodeDescription, NodeInitError, Port, ResolvedMod},
+    utils::{get_input, get_output},
+};
+
+const UNCATEGORIZED: &str = "Uncategorized";
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct CategorizedListWriter {
+    /// Heading written at the top of the document.
+    #[serde(default = "default_heading")]
+    pub heading: String,
+}
+
+fn default_heading() -> String {
+    "## Mods".into()
+}
+
+impl Default for CategorizedListWriter {
+    fn default() -> Self {
+        Self { heading: default_heading() }
+    }
+}
+
+impl NodeConfig for CategorizedListWriter {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut mods_channel = get_input!("mods", ResolvedMods, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Text, ctx)?;
+        // Categories are not cached, so they can never be looked up offline.
+        if ctx.is_offline() {
+            return Err(NodeInitError::Offline);
+        }
+        let curse_client = ctx.get_curse_client();
+        let modrinth_client = ctx.get_modrinth_client();
+        let heading = self.heading.clone();
+        let pool = ctx.get_pool();
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "CategorizedListWriter", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let mods = mods_channel.blocking_recv().expect_or_log("Failed to receive on mods input");
+            event!(Level::INFO, "Looking up categories of {} mods", mods.len());
+
+            let categories: Vec<Option<String>> = pool.install(|| {
+                mods.par_iter()
+                    .map(|m| {
+                        primary_category(m, &curse_client, &modrinth_client).unwrap_or_else(|e| {
+                            event!(Level::WARN, "Could not determine category of {}: {}", m.name, e);
+                            None
+                        })
+                    })
+                    .collect()
+            });
+
+            let list = write_list(&heading, mods.iter().zip(categories).collect());
+
+            if out_channel.send(list).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::Text(channel(1).0))])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "CategorizedListWriter",
+            vec![Port::required("mods", ChannelType::ResolvedMods)],
+            vec![Port::required("default", ChannelType::Text)],
+        )
+    }
+}
+
+/// Get the display name of the primary category of a mod's project, or [`None`] if the mod has
+/// no category or was not resolved from a platform that tracks categories.
+fn primary_category(m: &ResolvedMod, curse: &Option<CurseClient>, modrinth: &ModrinthClient) -> Result<Option<String>, String> {
+    match m.source {
+        Some(ModSource::Curse { project_id, .. }) => {
+            let client = curse.as_ref().ok_or("Curse client is not configured")?;
+            let project = client.find_mod_by_id(project_id).map_err(|e| e.to_string())?;
+            let primary = project
+                .categories
+                .iter()
+                .find(|c| Some(c.id) == project.primary_category_id)
+                .or(project.categories.first());
+            Ok(primary.map(|c| c.name.clone()))
+        },
+        Some(ModSource::Modrinth { ref project_id, .. }) => {
+            let project = modrinth.get_mod_info(project_id).map_err(|e| e.to_string())?;
+            // Modrinth has no primary category, so the first listed category that is not a loader is used
+            Ok(project.categories.iter().find(|c| !project.loaders.contains(c)).map(|c| title_case(c)))
+        },
+        Some(ModSource::Url) | None => Ok(None),
+    }
+}
+
+/// Convert a category slug such as `game-mechanics` into a heading such as `Game Mechanics`.
+fn title_case(slug: &str) -> String {
+    slug.split(['-', '_'])
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|c| c.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Write a Markdown list of mods grouped under category headings, sorted alphabetically, with
+/// uncategorized mods last.
+fn write_list(heading: &str, mods: Vec<(&ResolvedMod, Option<String>)>) -> String {
+    let mut grouped: BTreeMap<String, Vec<&ResolvedMod>> = BTreeMap::new();
+    let mut uncategorized = vec![];
+    for (m, category) in mods {
+        match category {
+            Some(category) => grouped.entry(category).or_default().push(m),
+            None => uncategorized.push(m),
+        }
+    }
+
+    let mut list = format!("{heading}\n");
+    for (category, mut mods) in grouped
+        .into_iter()
+        .chain((!uncategorized.is_empty()).then(|| (UNCATEGORIZED.to_owned(), uncategorized)))
+    {
+        mods.sort_by(|a, b| a.title.to_lowercase().cmp(&b.title.to_lowercase()).then_with(|| a.name.cmp(&b.name)));
+        writeln!(list, "\n### {category}\n").unwrap();
+        for m in mods {
+            writeln!(list, "- {}", m.title).unwrap();
+        }
+    }
+    list
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel, serve_http, test_mod},
+        },
+    };
+
+    use super::*;
+
+    fn project(id: &str, categories: &[&str], loaders: &[&str]) -> Vec<u8> {
+        format!(
+            r#"{{"slug":"{id}","title":"{id}","client_side":"optional","server_side":"optional","id":"{id}","categories":{},"loaders":{}}}"#,
+            serde_json::to_string(categories).unwrap(),
+            serde_json::to_string(loaders).unwrap()
+        )
+        .into_bytes()
+    }
+
+    #[test]
+    fn test_categorized_list_writer() {
+        let node_id = "categories";
+        let base_url = serve_http(HashMap::from([
+            (
+                "/v2/project/sodium".to_owned(),
+                (200, project("sodium", &["fabric", "optimization"], &["fabric"])),
+            ),
+            (
+                "/v2/project/lithium".to_owned(),
+                (200, project("lithium", &["optimization", "fabric"], &["fabric"])),
+            ),
+            (
+                "/v2/project/create".to_owned(),
+                (200, project("create", &["forge", "technology", "decoration"], &["forge"])),
+            ),
+            ("/v2/project/ctm".to_owned(), (200, project("ctm", &["game-mechanics"], &[]))),
+            (
+                "/v2/project/library".to_owned(),
+                (200, project("library", &["fabric", "quilt"], &["fabric", "quilt"])),
+            ),
+        ]));
+        let mods_channel = channel(1).0;
+        let input_ids = HashMap::from([("mods".into(), ChannelId::from_str("mod-source").unwrap())]);
+        let node = NodeConfigTypes::CategorizedListWriter(CategorizedListWriter::default());
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("mod-source").unwrap(),
+                InputType::ResolvedMods(mods_channel.clone()),
+            )]))
+            .modrinth_base_url(&base_url)
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Text, ctx);
+
+        let with_source = |name: &str, title: &str, source: Option<ModSource>| {
+            let mut m = test_mod(name);
+            m.title = title.to_owned();
+            m.source = source;
+            m
+        };
+        let modrinth = |name: &str, title: &str| {
+            with_source(
+                name,
+                title,
+                Some(ModSource::Modrinth {
+                    project_id: name.to_owned(),
+                    version_id: "v".to_owned(),
+                }),
+            )
+        };
+        let mods = vec![
+            modrinth("sodium", "Sodium"),
+            modrinth("lithium", "Lithium"),
+            modrinth("create", "Create"),
+            modrinth("ctm", "Connected Textures"),
+            modrinth("library", "Library"),
+            with_source("url-mod", "A URL Mod", Some(ModSource::Url)),
+            with_source(
+                "curse-mod",
+                "Curse Mod",
+                Some(ModSource::Curse {
+                    project_id: 1234,
+                    file_id: 5678,
+                }),
+            ),
+        ];
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        mods_channel.send(mods).unwrap();
+        handle.join().unwrap();
+
+        let expected = "## Mods
+
+### Game Mechanics
+
+- Connected Textures
+
+### Optimization
+
+- Lithium
+- Sodium
+
+### Technology
+
+- Create
+
+### Uncategorized
+
+- A URL Mod
+- Curse Mod
+- Library
+";
+        assert_eq!(read_channel(&mut out_channel, Duration::from_secs(10)).unwrap(), expected);
+    }
+
+    #[test]
+    fn title_case_slugs() {
+        assert_eq!(title_case("game-mechanics"), "Game Mechanics");
+        assert_eq!(title_case("worldgen"), "Worldgen");
+    }
+}
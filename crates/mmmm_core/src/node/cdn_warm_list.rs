@@ -0,0 +1,155 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    thread::{spawn, JoinHandle},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::di::container::{DiContainer, InputType, OutputType};
+
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port, ResolvedMod},
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct CdnWarmList;
+
+#[derive(Debug, Serialize, PartialEq)]
+struct WarmEntry<'a> {
+    url: &'a str,
+    sha256: &'a str,
+    size: u64,
+}
+
+impl NodeConfig for CdnWarmList {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut mods_channel = get_input!("mods", ResolvedMods, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Text, ctx)?;
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "CdnWarmList", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let mods = mods_channel.blocking_recv().expect_or_log("Failed to receive on mods input");
+
+            let entries = warm_entries(&mods);
+            event!(Level::INFO, "Collected {} unique files from {} mods", entries.len(), mods.len());
+            let json = serde_json::to_string_pretty(&entries).expect_or_log("Failed to serialize warm list");
+
+            if out_channel.send(json).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::Text(channel(1).0))])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "CdnWarmList",
+            vec![Port::required("mods", ChannelType::ResolvedMods)],
+            vec![Port::required("default", ChannelType::Text)],
+        )
+    }
+}
+
+/// One entry per distinct file, sorted by SHA-256 hash. When several URLs serve the same content,
+/// the first in sort order is kept. Mods without a hash can't be deduplicated by content, so they
+/// are keyed by their URL, and sort first.
+fn warm_entries(mods: &[ResolvedMod]) -> Vec<WarmEntry<'_>> {
+    let mut entries: BTreeMap<(&str, &str), WarmEntry> = BTreeMap::new();
+    for m in mods {
+        let key = match m.sha256.is_empty() {
+            true => ("", m.src.as_str()),
+            false => (m.sha256.as_str(), ""),
+        };
+        let entry = WarmEntry {
+            url: &m.src,
+            sha256: &m.sha256,
+            size: m.size,
+        };
+        match entries.get(&key) {
+            Some(existing) if existing.url <= entry.url => {},
+            _ => {
+                entries.insert(key, entry);
+            },
+        }
+    }
+    entries.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel, test_mod},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_cdn_warm_list() {
+        let node_id = "warm";
+        let mods_channel = channel(1).0;
+        let input_ids = HashMap::from([("mods".into(), ChannelId::from_str("resolver").unwrap())]);
+        let node = NodeConfigTypes::CdnWarmList(CdnWarmList);
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("resolver").unwrap(),
+                InputType::ResolvedMods(mods_channel.clone()),
+            )]))
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Text, ctx);
+
+        let hashed = |name: &str, sha256: &str, size: u64| ResolvedMod {
+            sha256: sha256.into(),
+            size,
+            ..test_mod(name)
+        };
+        // The same library shipped under two names is fetched once
+        let mods = vec![
+            hashed("sodium", "bbbb", 20),
+            hashed("library", "aaaa", 10),
+            hashed("jei", "cccc", 30),
+            hashed("bundled-library", "aaaa", 10),
+            test_mod("unhashed"),
+        ];
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        mods_channel.send(mods).unwrap();
+        handle.join().unwrap();
+
+        let json: serde_json::Value = serde_json::from_str(&read_channel(&mut out_channel, Duration::from_secs(10)).unwrap()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([
+                {"url": "https://example.com/unhashed.jar", "sha256": "", "size": 0},
+                {"url": "https://example.com/bundled-library.jar", "sha256": "aaaa", "size": 10},
+                {"url": "https://example.com/sodium.jar", "sha256": "bbbb", "size": 20},
+                {"url": "https://example.com/jei.jar", "sha256": "cccc", "size": 30},
+            ])
+        );
+    }
+}
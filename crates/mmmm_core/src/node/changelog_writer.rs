@@ -0,0 +1,227 @@
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    thread::{spawn, JoinHandle},
+};
+
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::{
+    di::container::{DiContainer, InputType, OutputType},
+    template::render,
+};
+
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port, ResolvedMod},
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct ChangelogWriter {
+    /// Template for the section heading. `${date}` and `${config.<key>}` are replaced.
+    #[serde(default = "default_heading")]
+    pub heading: String,
+    /// `strftime`-style format used for `${date}`.
+    #[serde(default = "default_date_format")]
+    pub date_format: String,
+}
+
+fn default_heading() -> String {
+    "## ${date}".into()
+}
+
+fn default_date_format() -> String {
+    "%Y-%m-%d".into()
+}
+
+impl Default for ChangelogWriter {
+    fn default() -> Self {
+        ChangelogWriter {
+            heading: default_heading(),
+            date_format: default_date_format(),
+        }
+    }
+}
+
+impl NodeConfig for ChangelogWriter {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut old_channel = get_input!("old", ResolvedMods, ctx, input_ids)?;
+        let mut new_channel = get_input!("new", ResolvedMods, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Text, ctx)?;
+
+        let date = chrono::Local::now().format(&self.date_format).to_string();
+        let configs = ctx.get_configs();
+        let lookup = |key: &str| match key {
+            "date" => Some(date.clone()),
+            _ => key.strip_prefix("config.").and_then(|k| configs.get(k).cloned()),
+        };
+        let heading = render(&self.heading, lookup, true).map_err(|e| NodeInitError::InvalidOption {
+            option: "heading".into(),
+            reason: e.to_string(),
+        })?;
+
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "ChangelogWriter", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let old = old_channel.blocking_recv().expect_or_log("Failed to receive on old input");
+            let new = new_channel.blocking_recv().expect_or_log("Failed to receive on new input");
+
+            if out_channel.send(write_changelog(&heading, &old, &new)).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::Text(channel(1).0))])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "ChangelogWriter",
+            vec![
+                Port::required("old", ChannelType::ResolvedMods),
+                Port::required("new", ChannelType::ResolvedMods),
+            ],
+            vec![Port::required("default", ChannelType::Text)],
+        )
+    }
+}
+
+/// Render a Markdown changelog section describing how the mod list changed from `old` to `new`.
+/// Mods are matched by name, and a mod counts as updated if its file changed.
+fn write_changelog(heading: &str, old: &[ResolvedMod], new: &[ResolvedMod]) -> String {
+    let old_by_name: HashMap<&str, &ResolvedMod> = old.iter().map(|m| (m.name.as_str(), m)).collect();
+    let new_by_name: HashMap<&str, &ResolvedMod> = new.iter().map(|m| (m.name.as_str(), m)).collect();
+
+    let by_title = |a: &&ResolvedMod, b: &&ResolvedMod| a.title.to_lowercase().cmp(&b.title.to_lowercase()).then_with(|| a.name.cmp(&b.name));
+    let mut added: Vec<&ResolvedMod> = new.iter().filter(|m| !old_by_name.contains_key(m.name.as_str())).collect();
+    let mut removed: Vec<&ResolvedMod> = old.iter().filter(|m| !new_by_name.contains_key(m.name.as_str())).collect();
+    let mut updated: Vec<(&ResolvedMod, &ResolvedMod)> = new
+        .iter()
+        .filter_map(|m| old_by_name.get(m.name.as_str()).map(|o| (*o, m)))
+        .filter(|(o, m)| o.sha256 != m.sha256 || o.filename != m.filename)
+        .collect();
+    added.sort_by(by_title);
+    removed.sort_by(by_title);
+    updated.sort_by(|a, b| by_title(&a.1, &b.1));
+
+    let mut changelog = format!("{heading}\n");
+    if added.is_empty() && updated.is_empty() && removed.is_empty() {
+        changelog.push_str("\nNo mod changes.\n");
+        return changelog;
+    }
+    if !added.is_empty() {
+        changelog.push_str("\n### Added\n\n");
+        for m in added {
+            writeln!(changelog, "- {} (`{}`)", m.title, m.filename).unwrap();
+        }
+    }
+    if !updated.is_empty() {
+        changelog.push_str("\n### Updated\n\n");
+        for (o, m) in updated {
+            writeln!(changelog, "- {}: `{}` → `{}`", m.title, o.filename, m.filename).unwrap();
+        }
+    }
+    if !removed.is_empty() {
+        changelog.push_str("\n### Removed\n\n");
+        for m in removed {
+            writeln!(changelog, "- {}", m.title).unwrap();
+        }
+    }
+    changelog
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel, test_mod},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_changelog_writer() {
+        let node_id = "changelog";
+        let old_channel = channel(1).0;
+        let new_channel = channel(1).0;
+        let input_ids = HashMap::from([
+            ("old".into(), ChannelId::from_str("old-lock").unwrap()),
+            ("new".into(), ChannelId::from_str("new-lock").unwrap()),
+        ]);
+        let node = NodeConfigTypes::ChangelogWriter(ChangelogWriter {
+            heading: "## ${config.pack_version}".into(),
+            ..Default::default()
+        });
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([
+                (ChannelId::from_str("old-lock").unwrap(), InputType::ResolvedMods(old_channel.clone())),
+                (ChannelId::from_str("new-lock").unwrap(), InputType::ResolvedMods(new_channel.clone())),
+            ]))
+            .set_config("pack_version", "1.4.0")
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Text, ctx);
+
+        let titled = |name: &str, title: &str, filename: &str| ResolvedMod {
+            title: title.into(),
+            filename: filename.into(),
+            sha256: filename.into(),
+            ..test_mod(name)
+        };
+        let old = vec![
+            titled("sodium", "Sodium", "sodium-0.5.3.jar"),
+            titled("appleskin", "AppleSkin", "appleskin-2.5.1.jar"),
+            titled("jei", "Just Enough Items", "jei-15.2.0.jar"),
+        ];
+        let new = vec![
+            titled("sodium", "Sodium", "sodium-0.5.8.jar"),
+            titled("jei", "Just Enough Items", "jei-15.2.0.jar"),
+            titled("modmenu", "Mod Menu", "modmenu-8.0.0.jar"),
+        ];
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        old_channel.send(old).unwrap();
+        new_channel.send(new).unwrap();
+        handle.join().unwrap();
+
+        let output = read_channel(&mut out_channel, Duration::from_secs(30)).unwrap();
+        let expected = "## 1.4.0
+
+### Added
+
+- Mod Menu (`modmenu-8.0.0.jar`)
+
+### Updated
+
+- Sodium: `sodium-0.5.3.jar` → `sodium-0.5.8.jar`
+
+### Removed
+
+- AppleSkin
+";
+        assert_eq!(output, expected);
+        assert_eq!(write_changelog("## Empty", &[], &[]), "## Empty\n\nNo mod changes.\n");
+    }
+}
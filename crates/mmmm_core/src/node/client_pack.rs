@@ -0,0 +1,198 @@
+use std::{
+    collections::HashMap,
+    thread::{spawn, JoinHandle},
+};
+
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::di::container::{DiContainer, InputType, OutputType};
+
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port, Side},
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct ClientPack {
+    /// Glob patterns of files only used by a server. Replaces [`DEFAULT_SERVER_PATTERNS`].
+    #[serde(default = "default_server_patterns")]
+    pub server_patterns: Vec<String>,
+}
+
+fn default_server_patterns() -> Vec<String> {
+    DEFAULT_SERVER_PATTERNS.iter().map(|p| p.to_string()).collect()
+}
+
+impl Default for ClientPack {
+    fn default() -> Self {
+        ClientPack {
+            server_patterns: default_server_patterns(),
+        }
+    }
+}
+
+/// Files that only a dedicated server reads, and that should not be distributed to players.
+pub const DEFAULT_SERVER_PATTERNS: &[&str] = &[
+    "**/server-overrides/**",
+    "**/server.properties",
+    "**/eula.txt",
+    "**/ops.json",
+    "**/whitelist.json",
+    "**/banned-ips.json",
+    "**/banned-players.json",
+];
+
+impl NodeConfig for ClientPack {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut mods_channel = get_input!("mods", ResolvedMods, ctx, input_ids)?;
+        let mut files_channel = get_input!("files", Files, ctx, input_ids)?;
+        let mods_out_channel = get_output!(ChannelId(node_id.clone(), "mods".into()), ResolvedMods, ctx)?;
+        let files_out_channel = get_output!(ChannelId(node_id.clone(), "files".into()), Files, ctx)?;
+        let patterns = self.server_patterns.clone();
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "ClientPack", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let mods = mods_channel.blocking_recv().expect_or_log("Failed to receive on mods input");
+            let files = files_channel.blocking_recv().expect_or_log("Failed to receive on files input");
+
+            let (client_mods, server_mods): (Vec<_>, Vec<_>) = mods.into_iter().partition(|m| m.side != Side::Server);
+            let (server_files, client_files) = files.filter_files(&patterns);
+
+            let mut dropped_mods = server_mods.iter().map(|m| m.name.as_str()).collect::<Vec<_>>();
+            dropped_mods.sort();
+            let mut dropped_files = server_files.list_files().into_iter().map(|p| p.to_string()).collect::<Vec<_>>();
+            dropped_files.sort();
+            if !dropped_mods.is_empty() {
+                event!(
+                    Level::INFO,
+                    "Removed {} server-only mods: {}",
+                    dropped_mods.len(),
+                    dropped_mods.join(", ")
+                );
+            }
+            if !dropped_files.is_empty() {
+                event!(
+                    Level::INFO,
+                    "Removed {} server-only files: {}",
+                    dropped_files.len(),
+                    dropped_files.join(", ")
+                );
+            }
+
+            if mods_out_channel.send(client_mods).is_err() {
+                event!(Level::DEBUG, "Channel 'mods' has no subscribers");
+            }
+            if files_out_channel.send(client_files).is_err() {
+                event!(Level::DEBUG, "Channel 'files' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([
+            (ChannelId(node_id.to_owned(), "mods".into()), InputType::ResolvedMods(channel(1).0)),
+            (ChannelId(node_id.to_owned(), "files".into()), InputType::Files(channel(1).0)),
+        ])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "ClientPack",
+            vec![
+                Port::required("mods", ChannelType::ResolvedMods),
+                Port::required("files", ChannelType::Files),
+            ],
+            vec![
+                Port::required("mods", ChannelType::ResolvedMods),
+                Port::required("files", ChannelType::Files),
+            ],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        file::{filepath::FilePath, filetree::FileTree},
+        node::{
+            config::{NodeConfigTypes, ResolvedMod},
+            utils::{get_output_test, read_channel, test_mod},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_client_pack() {
+        let node_id = "client";
+        let mods_channel = channel(1).0;
+        let files_channel = channel::<FileTree>(1).0;
+        let input_ids = HashMap::from([
+            ("mods".into(), ChannelId::from_str("resolver").unwrap()),
+            ("files".into(), ChannelId::from_str("overrides").unwrap()),
+        ]);
+        let node = NodeConfigTypes::ClientPack(ClientPack::default());
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([
+                (ChannelId::from_str("resolver").unwrap(), InputType::ResolvedMods(mods_channel.clone())),
+                (ChannelId::from_str("overrides").unwrap(), InputType::Files(files_channel.clone())),
+            ]))
+            .build();
+
+        let mut mods_out = get_output_test!(ChannelId::from_str("client::mods").unwrap(), ResolvedMods, ctx);
+        let mut files_out = get_output_test!(ChannelId::from_str("client::files").unwrap(), Files, ctx);
+
+        let with_side = |name: &str, side: Side| ResolvedMod { side, ..test_mod(name) };
+        let mods = vec![
+            with_side("jei", Side::Both),
+            with_side("luckperms", Side::Server),
+            with_side("sodium", Side::Client),
+        ];
+        let mut tree = FileTree::new(ctx.get_filestore());
+        for path in [
+            "overrides/config/jei.toml",
+            "overrides/options.txt",
+            "overrides/server.properties",
+            "server-overrides/config/luckperms.conf",
+        ] {
+            tree.add_file(FilePath::from_str(path).unwrap(), path.into());
+        }
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        mods_channel.send(mods).unwrap();
+        files_channel.send(tree).unwrap();
+        handle.join().unwrap();
+
+        let timeout = Duration::from_secs(10);
+        assert_eq!(
+            read_channel(&mut mods_out, timeout).unwrap(),
+            vec![with_side("jei", Side::Both), with_side("sodium", Side::Client)]
+        );
+        let mut files = read_channel(&mut files_out, timeout)
+            .unwrap()
+            .list_files()
+            .into_iter()
+            .map(|p| p.to_string())
+            .collect::<Vec<_>>();
+        files.sort();
+        assert_eq!(files, vec!["overrides/config/jei.toml", "overrides/options.txt"]);
+    }
+}
@@ -1,9 +1,76 @@
-use std::{collections::HashMap, fmt::Display, str::FromStr, thread::JoinHandle};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    str::FromStr,
+    thread::JoinHandle,
+};
 
 use super::{
-    archive_downloader::ArchiveDownloader, curse_resolver::CurseResolver, dir_merge::DirectoryMerger, file_filter::FileFilter,
-    file_picker::FilePicker, mod_filter::ModFilter, mod_merge::ModMerger, mod_overrider::ModOverrider, mod_resolver::ModResolver,
+    archive_downloader::ArchiveDownloader,
+    badge_writer::BadgeWriter,
+    categorized_list_writer::CategorizedListWriter,
+    cdn_warm_list::CdnWarmList,
+    changelog_writer::ChangelogWriter,
+    client_pack::ClientPack,
+    config_lint::ConfigLint,
+    curse_manifest_diff::CurseManifestDiff,
+    curse_resolver::CurseResolver,
+    dependency_graph::DependencyGraph,
+    dir_merge::DirectoryMerger,
+    extension_whitelist::ExtensionWhitelist,
+    file_count_guard::FileCountGuard,
+    file_filter::FileFilter,
+    file_picker::FilePicker,
+    group_split::GroupSplit,
+    hash_drift_check::HashDriftCheck,
+    html_viewer::HtmlViewer,
+    incompatibility_resolver::{IncompatibilityResolver, KeepPolicy},
+    install_instructions::InstallInstructions,
+    jar_recompress::JarRecompress,
+    jar_validate::JarValidate,
+    json_canonicalize::JsonCanonicalize,
+    json_value_replace::JsonValueReplace,
+    junk_filter::JunkFilter,
+    known_issues::KnownIssues,
+    link_check::LinkCheck,
+    list_merge::ListMerge,
+    loader_consistency_check::LoaderConsistencyCheck,
+    loader_meta_fixup::LoaderMetaFixup,
+    lock_verify::LockVerify,
+    manifest_writer::{ManifestWriter, OutputFormat},
+    meta_overrider::MetaOverrider,
+    mod_dedup::ModDedup,
+    mod_filter::ModFilter,
+    mod_merge::ModMerger,
+    mod_overrider::ModOverrider,
+    mod_resolver::ModResolver,
+    mod_search::ModSearch,
     mod_writer::ModWriter,
+    modlist_html_writer::ModlistHtmlWriter,
+    modlist_to_source::ModlistToSource,
+    modrinth_collection::ModrinthCollection,
+    modrinth_pack_source::ModrinthPackSource,
+    mrpack_builder::MrpackBuilder,
+    optional_mod_index::OptionalModIndex,
+    pack_assembler::PackAssembler,
+    pack_format_fixup::PackFormatFixup,
+    pack_meta_reader::PackMetaReader,
+    pack_stats::PackStats,
+    pin_audit::PinAudit,
+    remote_mod_list::RemoteModList,
+    require_mods::RequireMods,
+    resource_pack_merge::ResourcePackMerge,
+    server_script_writer::ServerScriptWriter,
+    shadow_report::ShadowReport,
+    shell_transform::ShellTransform,
+    side_override_split::SideOverrideSplit,
+    staleness_filter::StalenessFilter,
+    template_render::TemplateRender,
+    three_way_merge::ThreeWayMerge,
+    tree_diff::TreeDiff,
+    url_list_expander::UrlListExpander,
+    url_proxy::UrlProxy,
+    verified_downloader::VerifiedDownloader,
 };
 use crate::di::container::{DiContainer, InputType};
 use enum_dispatch::enum_dispatch;
@@ -18,6 +85,142 @@ pub trait NodeConfig {
     fn validate_and_spawn(&self, node_id: String, input_ids: &HashMap<String, ChannelId>, ctx: &DiContainer)
         -> Result<JoinHandle<()>, NodeInitError>;
     fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType>;
+    /// Describe the inputs the node reads, the outputs it sends, and the config keys it requires.
+    /// Inputs and outputs do not depend on the options, but required config keys may.
+    fn describe(&self) -> NodeDescription;
+}
+
+/// The inputs and outputs of a kind of node, for listing what each kind accepts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeDescription {
+    /// Name of the node kind, as written in the `kind` field of a pack definition.
+    pub kind: &'static str,
+    pub inputs: Vec<Port>,
+    /// Set for nodes that accept any number of inputs with any name, all of this type.
+    pub any_inputs: Option<ChannelType>,
+    pub outputs: Vec<Port>,
+    /// Set for nodes whose outputs are named by their options, all of this type.
+    pub any_outputs: Option<ChannelType>,
+    /// Config keys the node cannot be spawned without.
+    pub config_keys: Vec<&'static str>,
+}
+
+impl NodeDescription {
+    /// Describe a node with fixed inputs and outputs and no required config keys.
+    pub fn new(kind: &'static str, inputs: Vec<Port>, outputs: Vec<Port>) -> Self {
+        NodeDescription {
+            kind,
+            inputs,
+            any_inputs: None,
+            outputs,
+            any_outputs: None,
+            config_keys: vec![],
+        }
+    }
+
+    /// Accept any number of inputs with any name, all of this type, besides the listed inputs.
+    pub fn with_any_inputs(mut self, kind: ChannelType) -> Self {
+        self.any_inputs = Some(kind);
+        self
+    }
+
+    /// Send outputs named by the node's options, all of this type, besides the listed outputs.
+    pub fn with_any_outputs(mut self, kind: ChannelType) -> Self {
+        self.any_outputs = Some(kind);
+        self
+    }
+
+    /// Require these config keys to be set before the node is spawned.
+    pub fn with_config_keys(mut self, config_keys: Vec<&'static str>) -> Self {
+        self.config_keys = config_keys;
+        self
+    }
+
+    /// Check the inputs provided to a node against this description, and that its config keys are
+    /// set, so that a misconfigured graph is rejected before any node is spawned.
+    pub fn validate(&self, input_ids: &HashMap<String, ChannelId>, ctx: &DiContainer) -> Vec<NodeInitError> {
+        let mut errors = vec![];
+        for port in self.inputs.iter().filter(|port| !port.optional) {
+            if !input_ids.contains_key(port.name) {
+                errors.push(NodeInitError::MissingInputId(port.name.into()));
+            }
+        }
+        let mut inputs = input_ids.iter().collect::<Vec<_>>();
+        inputs.sort_by_key(|(name, _)| *name);
+        for (name, channel_id) in inputs {
+            let expected = match self.inputs.iter().find(|port| port.name == name) {
+                Some(port) => port.kind,
+                None => match self.any_inputs {
+                    Some(kind) => kind,
+                    None => {
+                        errors.push(NodeInitError::UnknownInput(name.clone()));
+                        continue;
+                    },
+                },
+            };
+            match ctx.get_sender(channel_id) {
+                Some(channel) if ChannelType::from(&channel) == expected => {},
+                Some(_) => errors.push(NodeInitError::InvalidInputType {
+                    input: name.clone(),
+                    channel: channel_id.clone(),
+                }),
+                None => errors.push(NodeInitError::MissingChannel(channel_id.clone())),
+            }
+        }
+        for key in &self.config_keys {
+            if ctx.get_config(key).is_none() {
+                errors.push(NodeInitError::MissingConfig((*key).into()));
+            }
+        }
+        errors
+    }
+}
+
+/// A named input or output of a node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Port {
+    pub name: &'static str,
+    pub kind: ChannelType,
+    /// Optional inputs may be left unconnected, and optional outputs only exist with some options.
+    pub optional: bool,
+}
+
+impl Port {
+    pub fn required(name: &'static str, kind: ChannelType) -> Self {
+        Port { name, kind, optional: false }
+    }
+
+    pub fn optional(name: &'static str, kind: ChannelType) -> Self {
+        Port { name, kind, optional: true }
+    }
+}
+
+/// The type of data carried by a channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelType {
+    Text,
+    Files,
+    List,
+    Mods,
+    ResolvedMods,
+}
+
+impl Display for ChannelType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl From<&InputType> for ChannelType {
+    fn from(value: &InputType) -> Self {
+        match value {
+            InputType::Text(_) => ChannelType::Text,
+            InputType::Files(_) => ChannelType::Files,
+            InputType::List(_) => ChannelType::List,
+            InputType::Mods(_) => ChannelType::Mods,
+            InputType::ResolvedMods(_) => ChannelType::ResolvedMods,
+        }
+    }
 }
 
 pub trait Cache: Send + Sync {
@@ -39,12 +242,20 @@ pub enum NodeInitError {
     InvalidOutputType(ChannelId),
     #[error("No identifier found for required input {0}!")]
     MissingInputId(String),
+    #[error("Input {0} is not accepted by this node!")]
+    UnknownInput(String),
     #[error("Could not find channel in context for id {0:?}!")]
     MissingChannel(ChannelId),
     #[error("Could not find config value named {0}!")]
     MissingConfig(String),
-    #[error("Curse client must be configured!")]
+    #[error("Curse credentials are required! Set curse_api_key or curse_proxy_url in mmmm.toml.")]
     CurseClientRequired,
+    #[error("Invalid value for option {option}: {reason}")]
+    InvalidOption { option: String, reason: String },
+    #[error("Node requires network access, which is disabled in offline mode!")]
+    Offline,
+    #[error("Node runs external commands, which is disabled! Set allow_commands = true in mmmm.toml to enable it.")]
+    CommandsDisabled,
 }
 
 #[derive(Debug, Clone, Deserialize, PartialEq)]
@@ -67,6 +278,179 @@ pub enum NodeConfigTypes {
     CurseResolver,
     ModWriter,
     ModMerger,
+    TemplateRender,
+    LinkCheck,
+    LoaderMetaFixup,
+    PackStats,
+    ModrinthCollection,
+    MetaOverrider,
+    JunkFilter,
+    ChangelogWriter,
+    ModDedup,
+    PinAudit,
+    ResourcePackMerge,
+    StalenessFilter,
+    ConfigLint,
+    CategorizedListWriter,
+    UrlListExpander,
+    DependencyGraph,
+    PackFormatFixup,
+    TreeDiff,
+    ModSearch,
+    FileCountGuard,
+    ThreeWayMerge,
+    ServerScriptWriter,
+    MrpackBuilder,
+    PackAssembler,
+    VerifiedDownloader,
+    ModlistToSource,
+    JsonValueReplace,
+    ManifestWriter,
+    PackMetaReader,
+    ListMerge,
+    RequireMods,
+    HashDriftCheck,
+    ClientPack,
+    CdnWarmList,
+    LoaderConsistencyCheck,
+    OptionalModIndex,
+    ModrinthPackSource,
+    ExtensionWhitelist,
+    BadgeWriter,
+    UrlProxy,
+    GroupSplit,
+    ShadowReport,
+    LockVerify,
+    ShellTransform,
+    InstallInstructions,
+    CurseManifestDiff,
+    ModlistHtmlWriter,
+    JsonCanonicalize,
+    SideOverrideSplit,
+    RemoteModList,
+    IncompatibilityResolver,
+    JarRecompress,
+    JarValidate,
+    KnownIssues,
+    HtmlViewer,
+}
+
+/// Build [`example_nodes`] from a list of kinds and their examples. Fails to compile if a kind of
+/// node is missing from the list.
+macro_rules! example_nodes {
+    ($($kind:ident => $example:expr),+ $(,)?) => {
+        /// One node of every kind, with default options. Nodes with required options are built
+        /// with placeholder values, which do not change their description.
+        fn example_nodes() -> Vec<NodeConfigTypes> {
+            let _exhaustive = |node: &NodeConfigTypes| match node {
+                $(NodeConfigTypes::$kind(_) => (),)+
+            };
+            vec![$(NodeConfigTypes::$kind($example),)+]
+        }
+    };
+}
+
+example_nodes! {
+    ArchiveDownloader => ArchiveDownloader::default(),
+    FileFilter => FileFilter,
+    FilePicker => FilePicker,
+    DirectoryMerger => DirectoryMerger,
+    ModResolver => ModResolver::default(),
+    ModOverrider => ModOverrider,
+    ModFilter => ModFilter::default(),
+    CurseResolver => CurseResolver,
+    ModWriter => ModWriter::default(),
+    ModMerger => ModMerger,
+    TemplateRender => TemplateRender::default(),
+    LinkCheck => LinkCheck::default(),
+    LoaderMetaFixup => LoaderMetaFixup::default(),
+    PackStats => PackStats,
+    ModrinthCollection => ModrinthCollection::default(),
+    MetaOverrider => MetaOverrider,
+    JunkFilter => JunkFilter::default(),
+    ChangelogWriter => ChangelogWriter::default(),
+    ModDedup => ModDedup,
+    PinAudit => PinAudit::default(),
+    ResourcePackMerge => ResourcePackMerge::default(),
+    StalenessFilter => StalenessFilter::default(),
+    ConfigLint => ConfigLint::default(),
+    CategorizedListWriter => CategorizedListWriter::default(),
+    UrlListExpander => UrlListExpander,
+    DependencyGraph => DependencyGraph,
+    PackFormatFixup => PackFormatFixup::default(),
+    TreeDiff => TreeDiff::default(),
+    ModSearch => ModSearch::default(),
+    FileCountGuard => FileCountGuard {
+        max_files: 0,
+        top: 0,
+        strict: false,
+    },
+    ThreeWayMerge => ThreeWayMerge,
+    ServerScriptWriter => ServerScriptWriter::default(),
+    MrpackBuilder => MrpackBuilder {
+        name: String::new(),
+        version: String::new(),
+        summary: None,
+    },
+    PackAssembler => PackAssembler::default(),
+    VerifiedDownloader => VerifiedDownloader,
+    ModlistToSource => ModlistToSource,
+    JsonValueReplace => JsonValueReplace {
+        pattern: String::new(),
+        replacement: String::new(),
+    },
+    ManifestWriter => ManifestWriter {
+        formats: vec![
+            OutputFormat::Nix,
+            OutputFormat::Json,
+            OutputFormat::MrpackIndex,
+            OutputFormat::CurseManifest,
+            OutputFormat::PackwizIndex,
+        ],
+        name: None,
+        version: None,
+    },
+    PackMetaReader => PackMetaReader,
+    ListMerge => ListMerge,
+    RequireMods => RequireMods::default(),
+    HashDriftCheck => HashDriftCheck::default(),
+    ClientPack => ClientPack::default(),
+    CdnWarmList => CdnWarmList,
+    LoaderConsistencyCheck => LoaderConsistencyCheck::default(),
+    OptionalModIndex => OptionalModIndex,
+    ModrinthPackSource => ModrinthPackSource,
+    ExtensionWhitelist => ExtensionWhitelist::default(),
+    BadgeWriter => BadgeWriter::default(),
+    UrlProxy => UrlProxy {
+        template: "http://cache.internal/?url=${url}".into(),
+    },
+    GroupSplit => GroupSplit { groups: vec!["shaders".into()] },
+    ShadowReport => ShadowReport,
+    LockVerify => LockVerify,
+    ShellTransform => ShellTransform {
+        command: vec!["jq".into(), ".".into()],
+        patterns: vec!["config/**/*.json".into()],
+        on_error: Default::default(),
+    },
+    InstallInstructions => InstallInstructions::default(),
+    CurseManifestDiff => CurseManifestDiff::default(),
+    ModlistHtmlWriter => ModlistHtmlWriter,
+    JsonCanonicalize => JsonCanonicalize,
+    SideOverrideSplit => SideOverrideSplit,
+    RemoteModList => RemoteModList::default(),
+    IncompatibilityResolver => IncompatibilityResolver { keep: KeepPolicy::Required },
+    JarRecompress => JarRecompress {
+        patterns: vec!["mods/*.jar".into()],
+        strip_entries: vec![".git/**".into()],
+    },
+    JarValidate => JarValidate::default(),
+    KnownIssues => KnownIssues::default(),
+    HtmlViewer => HtmlViewer::default(),
+}
+
+/// Describe every kind of node.
+pub fn node_descriptions() -> Vec<NodeDescription> {
+    example_nodes().iter().map(NodeConfig::describe).collect()
 }
 
 #[derive(Debug, Clone, Deserialize, PartialEq)]
@@ -82,6 +466,8 @@ pub struct NodeDefinition {
     #[serde(flatten)]
     pub kind: NodeConfigTypes,
     pub id: String,
+    /// Nodes that only read config, such as ServerScriptWriter, may omit their inputs.
+    #[serde(default)]
     pub input: HashMap<String, ChannelId>,
 }
 
@@ -95,6 +481,9 @@ pub struct SourceDefinition {
 pub struct OutputDefinition {
     pub filename: String,
     pub source: ChannelId,
+    /// Split a *Files* output into archives of at most this many bytes each.
+    #[serde(default)]
+    pub max_size: Option<u64>,
 }
 
 #[derive(Debug, Clone, Deserialize, PartialEq)]
@@ -112,18 +501,33 @@ pub enum ModDefinition {
     Modrinth {
         id: Option<String>,
         file_id: Option<String>,
+        /// Patterns for versions to skip when picking the newest file, matched case-insensitively
+        /// as regular expressions against the version number and filenames. Ignored when pinned
+        /// to `file_id`.
+        #[serde(default)]
+        exclude_versions: Vec<String>,
         #[serde(flatten)]
         fields: ModDefinitionFields,
     },
     Curse {
         id: Option<u32>,
         file_id: Option<u32>,
+        /// Patterns for versions to skip when picking the newest file, matched like the Modrinth
+        /// `exclude_versions` against the display name and filename.
+        #[serde(default)]
+        exclude_versions: Vec<String>,
         #[serde(flatten)]
         fields: ModDefinitionFields,
     },
     Url {
+        /// May reference environment variables as `${env.VAR}`, which are resolved when the mod
+        /// is resolved and never written to outputs.
         location: String,
         filename: Option<String>,
+        /// Extra request headers, such as credentials for a private mirror. Values may reference
+        /// environment variables like `location`.
+        #[serde(default)]
+        headers: BTreeMap<String, String>,
         #[serde(flatten)]
         fields: ModDefinitionFields,
     },
@@ -146,6 +550,8 @@ pub struct ModDefinitionFields {
     pub side: Side,
     pub required: Option<bool>,
     pub default: Option<bool>,
+    /// Author-assigned label for splitting a pack into separately shipped sets with GroupSplit.
+    pub group: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord, Default, Hash, Copy)]
@@ -214,6 +620,68 @@ pub struct ResolvedMod {
     pub size: u64,
     pub md5: String,
     pub sha256: String,
+    /// Where the mod was resolved from. Not written to the Nix manifest.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<ModSource>,
+    /// What the file is, if not a mod. Not written to the Nix manifest.
+    #[serde(default, skip_serializing_if = "ProjectKind::is_mod")]
+    pub kind: ProjectKind,
+    /// Group from the mod definition. Not written to the Nix manifest.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+}
+
+/// Kind of content a [`ResolvedMod`] holds. Only Modrinth projects are resolved as anything other
+/// than mods.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize, PartialOrd, Ord, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectKind {
+    #[default]
+    Mod,
+    ResourcePack,
+    Shader,
+    DataPack,
+}
+
+impl ProjectKind {
+    pub fn is_mod(&self) -> bool {
+        *self == ProjectKind::Mod
+    }
+
+    /// Directory of a game instance the file is installed to. Data packs are only loaded from
+    /// `datapacks` with a mod that applies them globally.
+    pub fn directory(&self) -> &'static str {
+        match self {
+            ProjectKind::Mod => "mods",
+            ProjectKind::ResourcePack => "resourcepacks",
+            ProjectKind::Shader => "shaderpacks",
+            ProjectKind::DataPack => "datapacks",
+        }
+    }
+}
+
+impl Display for ProjectKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                ProjectKind::Mod => "mod",
+                ProjectKind::ResourcePack => "resource pack",
+                ProjectKind::Shader => "shader",
+                ProjectKind::DataPack => "data pack",
+            }
+        )
+    }
+}
+
+/// Provenance of a [`ResolvedMod`], carrying the platform IDs it was resolved with.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize, PartialOrd, Ord)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ModSource {
+    Curse { project_id: u32, file_id: u32 },
+    Modrinth { project_id: String, version_id: String },
+    Url,
 }
 
 impl Display for ResolvedMod {
@@ -280,6 +748,7 @@ mod tests {
     side: client
     required: false
     default: true
+    group: client-extras
   - name: waystones
     source: curse
   - name: patchouli
@@ -312,47 +781,55 @@ mod tests {
                     ModDefinition::Modrinth {
                         id: Some("EsAfCjCV".into()),
                         file_id: Some("pyRMqaEV".into()),
+                        exclude_versions: Vec::new(),
                         fields: ModDefinitionFields {
                             name: "appleskin".into(),
                             side: Side::Both,
                             required: Some(true),
                             default: Some(true),
+                            group: None,
                         },
                     },
                     ModDefinition::Curse {
                         id: Some(60089),
                         file_id: Some(4581240),
+                        exclude_versions: Vec::new(),
                         fields: ModDefinitionFields {
                             name: "mouse-tweaks".into(),
                             side: Side::Client,
                             required: Some(false),
                             default: Some(true),
+                            group: Some("client-extras".into()),
                         },
                     },
                     ModDefinition::Curse {
                         id: None,
                         file_id: None,
+                        exclude_versions: Vec::new(),
                         fields: ModDefinitionFields {
                             name: "waystones".into(),
                             side: Side::Both,
                             required: None,
                             default: None,
+                            group: None,
                         },
                     },
                     ModDefinition::Url {
                         location: "https://github.com/VazkiiMods/Patchouli/releases/download/release-1.20.1-81/Patchouli-1.20.1-81-FORGE.jar".into(),
                         filename: None,
+                        headers: BTreeMap::new(),
                         fields: ModDefinitionFields {
                             name: "patchouli".into(),
                             side: Side::Both,
                             required: None,
                             default: None,
+                            group: None,
                         },
                     },
                 ]),
             }),
             NodeConfigEntry::Node(NodeDefinition {
-                kind: NodeConfigTypes::ArchiveDownloader(ArchiveDownloader),
+                kind: NodeConfigTypes::ArchiveDownloader(ArchiveDownloader::default()),
                 id: "download".into(),
                 input: HashMap::from([("url".into(), ChannelId::from_str("pack-url").unwrap())]),
             }),
@@ -367,6 +844,7 @@ mod tests {
             NodeConfigEntry::Output(OutputDefinition {
                 filename: "my-pack".into(),
                 source: ChannelId::from_str("filter").unwrap(),
+                max_size: None,
             }),
         ];
         println!("{:?}", nodes);
@@ -375,4 +853,21 @@ mod tests {
             assert_eq!(a, e);
         }
     }
+
+    #[test]
+    fn descriptions_match_channels() {
+        for node in example_nodes() {
+            let description = node.describe();
+            assert!(format!("{node:?}").starts_with(&format!("{}(", description.kind)));
+            let channels = node.generate_channels("node");
+            for (id, channel) in &channels {
+                let port = description.outputs.iter().find(|port| port.name == id.1);
+                let kind = port.map(|port| port.kind).or(description.any_outputs);
+                assert_eq!(kind, Some(channel.into()), "{} output {}", description.kind, id.1);
+            }
+            for port in description.outputs.iter().filter(|port| !port.optional) {
+                assert!(channels.contains_key(&ChannelId("node".into(), port.name.into())));
+            }
+        }
+    }
 }
@@ -0,0 +1,222 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    thread::{spawn, JoinHandle},
+};
+
+use rayon::prelude::*;
+use serde::{de::IgnoredAny, Deserialize};
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::di::container::{DiContainer, InputType, OutputType};
+
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port},
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct ConfigLint {
+    /// File extensions to lint, and the format to parse each as.
+    #[serde(default = "default_extensions")]
+    pub extensions: BTreeMap<String, ConfigFormat>,
+    /// Fail if any file is invalid, instead of only reporting it.
+    #[serde(default)]
+    pub strict: bool,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+fn default_extensions() -> BTreeMap<String, ConfigFormat> {
+    BTreeMap::from([
+        ("json".to_owned(), ConfigFormat::Json),
+        ("toml".to_owned(), ConfigFormat::Toml),
+        ("yaml".to_owned(), ConfigFormat::Yaml),
+        ("yml".to_owned(), ConfigFormat::Yaml),
+    ])
+}
+
+impl Default for ConfigLint {
+    fn default() -> Self {
+        ConfigLint {
+            extensions: default_extensions(),
+            strict: false,
+        }
+    }
+}
+
+impl NodeConfig for ConfigLint {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut files_channel = get_input!("files", Files, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Files, ctx)?;
+        let report_channel = get_output!(ChannelId(node_id.clone(), "report".into()), Text, ctx)?;
+        // Extensions are matched case-insensitively
+        let extensions: HashMap<String, ConfigFormat> = self.extensions.iter().map(|(ext, format)| (ext.to_lowercase(), *format)).collect();
+        let strict = self.strict;
+        let pool = ctx.get_pool();
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "ConfigLint", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let files = files_channel.blocking_recv().expect_or_log("Failed to receive on files input");
+
+            let targets = files
+                .list_files()
+                .into_iter()
+                .filter_map(|path| {
+                    let (_, ext) = path.get_filename().rsplit_once('.')?;
+                    extensions.get(&ext.to_lowercase()).map(|format| (path, *format))
+                })
+                .collect::<Vec<_>>();
+            let mut failures: Vec<String> = pool.install(|| {
+                targets
+                    .par_iter()
+                    .filter_map(|(path, format)| {
+                        let data = files.get_file(path)?;
+                        // Some parsers produce multi-line messages, keep one line per file in the report
+                        let error = lint(&data, *format).err()?;
+                        let error = error.lines().map(str::trim).filter(|line| !line.is_empty()).collect::<Vec<_>>().join(" ");
+                        Some(format!("- {path}: {error}"))
+                    })
+                    .collect()
+            });
+            failures.sort();
+
+            let report = if failures.is_empty() {
+                format!("Linted {} files, all valid.\n", targets.len())
+            } else {
+                format!("Linted {} files, {} invalid:\n{}\n", targets.len(), failures.len(), failures.join("\n"))
+            };
+
+            if !failures.is_empty() {
+                event!(Level::WARN, "{report}");
+                if strict {
+                    panic!("Invalid config files found in strict mode");
+                }
+            }
+
+            if out_channel.send(files).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+
+            if report_channel.send(report).is_err() {
+                event!(Level::DEBUG, "Channel 'report' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([
+            (ChannelId(node_id.to_owned(), "default".into()), InputType::Files(channel(1).0)),
+            (ChannelId(node_id.to_owned(), "report".into()), InputType::Text(channel(1).0)),
+        ])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "ConfigLint",
+            vec![Port::required("files", ChannelType::Files)],
+            vec![Port::required("default", ChannelType::Files), Port::required("report", ChannelType::Text)],
+        )
+    }
+}
+
+/// Check that a file parses as the given format, without keeping the parsed data.
+fn lint(data: &[u8], format: ConfigFormat) -> Result<(), String> {
+    match format {
+        ConfigFormat::Json => serde_json::from_slice::<IgnoredAny>(data).map(|_| ()).map_err(|e| e.to_string()),
+        ConfigFormat::Toml => {
+            let text = std::str::from_utf8(data).map_err(|e| e.to_string())?;
+            toml::from_str::<IgnoredAny>(text).map(|_| ()).map_err(|e| e.to_string())
+        },
+        // YAML files may contain multiple documents
+        ConfigFormat::Yaml => serde_yaml::Deserializer::from_slice(data)
+            .try_for_each(|document| IgnoredAny::deserialize(document).map(|_| ()).map_err(|e| e.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        file::{filepath::FilePath, filestore::FileStore, filetree::FileTree},
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_config_lint() {
+        let node_id = "lint";
+        let mut tree = FileTree::new(FileStore::new());
+        for (path, contents) in [
+            ("config/valid.json", r#"{"enabled": true}"#),
+            ("config/broken.json", r#"{"enabled": true,"#),
+            ("config/valid.toml", "[general]\nenabled = true\n"),
+            ("config/broken.TOML", "[general\n"),
+            ("config/multi.yml", "a: 1\n---\nb: 2\n"),
+            ("config/broken.yaml", "a: [1, 2\n"),
+            ("config/pack.mcmeta", "not json"),
+            ("config/ignored.cfg", "{{{"),
+        ] {
+            tree.add_file(FilePath::from_str(path).unwrap(), contents.into());
+        }
+        let files_channel = channel(1).0;
+        let input_ids = HashMap::from([("files".into(), ChannelId::from_str("file-source").unwrap())]);
+        let mut node = ConfigLint::default();
+        node.extensions.insert("MCMETA".to_owned(), ConfigFormat::Json);
+        let node = NodeConfigTypes::ConfigLint(node);
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("file-source").unwrap(),
+                InputType::Files(files_channel.clone()),
+            )]))
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Files, ctx);
+        let mut report_channel = get_output_test!(ChannelId::from_str("lint::report").unwrap(), Text, ctx);
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        files_channel.send(tree).unwrap();
+        handle.join().unwrap();
+
+        let timeout = Duration::from_secs(10);
+        assert_eq!(read_channel(&mut out_channel, timeout).unwrap().list_files().len(), 8);
+        let report = read_channel(&mut report_channel, timeout).unwrap();
+        let lines = report.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "Linted 7 files, 4 invalid:");
+        let failed = lines[1..].iter().map(|line| line.split(':').next().unwrap()).collect::<Vec<_>>();
+        assert_eq!(
+            failed,
+            vec![
+                "- config/broken.TOML",
+                "- config/broken.json",
+                "- config/broken.yaml",
+                "- config/pack.mcmeta"
+            ]
+        );
+    }
+}
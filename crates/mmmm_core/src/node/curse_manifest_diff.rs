@@ -0,0 +1,236 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    thread::{spawn, JoinHandle},
+};
+
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::di::container::{DiContainer, InputType, OutputType};
+
+use super::{
+    config::{ChannelId, ChannelType, ModSource, NodeConfig, NodeDescription, NodeInitError, Port, ResolvedMod},
+    curse_resolver::CurseManifest,
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Default)]
+pub struct CurseManifestDiff {
+    /// Fail if the pack differs from the manifest, instead of only reporting it.
+    #[serde(default)]
+    pub strict: bool,
+}
+
+impl NodeConfig for CurseManifestDiff {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut manifest_channel = get_input!("manifest", Text, ctx, input_ids)?;
+        let mut mods_channel = get_input!("mods", ResolvedMods, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Text, ctx)?;
+        let strict = self.strict;
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "CurseManifestDiff", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let manifest = manifest_channel.blocking_recv().expect_or_log("Failed to receive on manifest input");
+            let mods = mods_channel.blocking_recv().expect_or_log("Failed to receive on mods input");
+
+            let manifest = serde_json::from_str::<CurseManifest>(&manifest).expect_or_log("Failed to deserialize Curse manifest!");
+            let manifest_files = manifest
+                .files
+                .iter()
+                .map(|file| (file.project_id, file.file_id))
+                .collect::<BTreeMap<_, _>>();
+            let diff = diff(&manifest_files, &mods);
+            let report = diff.report(manifest_files.len(), mods.len());
+
+            if !diff.is_empty() {
+                event!(Level::WARN, "{report}");
+                if strict {
+                    panic!("Pack differs from the Curse manifest in strict mode");
+                }
+            }
+
+            if out_channel.send(report).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::Text(channel(1).0))])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "CurseManifestDiff",
+            vec![
+                Port::required("manifest", ChannelType::Text),
+                Port::required("mods", ChannelType::ResolvedMods),
+            ],
+            vec![Port::required("default", ChannelType::Text)],
+        )
+    }
+}
+
+/// Differences between a Curse manifest and a pack, as lines of the report.
+#[derive(Debug, Default, PartialEq)]
+struct Diff {
+    /// Projects in the manifest that no mod was resolved from.
+    missing: Vec<String>,
+    /// Curse mods whose project is not in the manifest.
+    extra: Vec<String>,
+    /// Curse mods resolved to another file than the manifest lists.
+    changed: Vec<String>,
+    /// Mods without a Curse project, which cannot be matched against the manifest.
+    unmatched: Vec<String>,
+}
+
+impl Diff {
+    fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty() && self.changed.is_empty() && self.unmatched.is_empty()
+    }
+
+    fn report(&self, manifest_len: usize, pack_len: usize) -> String {
+        let mut report = format!("Compared {manifest_len} files in the Curse manifest with {pack_len} mods in the pack");
+        if self.is_empty() {
+            report.push_str(", the pack matches the manifest.\n");
+            return report;
+        }
+        report.push_str(".\n");
+        for (heading, lines) in [
+            ("In the manifest but not the pack", &self.missing),
+            ("In the pack but not the manifest", &self.extra),
+            ("Resolved to a different file", &self.changed),
+            ("Not from CurseForge, so not matched", &self.unmatched),
+        ] {
+            if !lines.is_empty() {
+                report.push_str(&format!("\n{heading} ({}):\n{}\n", lines.len(), lines.join("\n")));
+            }
+        }
+        report
+    }
+}
+
+/// Compare a manifest's files, keyed by project ID, against the pack's mods by Curse project ID.
+fn diff(manifest_files: &BTreeMap<u32, u32>, mods: &[ResolvedMod]) -> Diff {
+    let mut diff = Diff::default();
+    let mut pack_projects = BTreeMap::new();
+    for m in mods {
+        match m.source {
+            Some(ModSource::Curse { project_id, file_id }) => {
+                pack_projects.insert(project_id, (m, file_id));
+            },
+            _ => diff.unmatched.push(format!("- {}", m.name)),
+        }
+    }
+    for (project_id, file_id) in manifest_files {
+        match pack_projects.get(project_id) {
+            None => diff.missing.push(format!("- project {project_id} (file {file_id})")),
+            Some((m, pack_file_id)) if pack_file_id != file_id => diff.changed.push(format!(
+                "- {} (project {project_id}): manifest file {file_id}, pack file {pack_file_id}",
+                m.name
+            )),
+            Some(_) => {},
+        }
+    }
+    for (project_id, (m, _)) in &pack_projects {
+        if !manifest_files.contains_key(project_id) {
+            diff.extra.push(format!("- {} (project {project_id})", m.name));
+        }
+    }
+    diff.unmatched.sort();
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use tokio::sync::broadcast;
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel, test_mod},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_curse_manifest_diff() {
+        let node_id = "diff";
+        let manifest_channel = broadcast::channel(1).0;
+        let mods_channel = broadcast::channel(1).0;
+        let input_ids = HashMap::from([
+            ("manifest".into(), ChannelId::from_str("manifest-source").unwrap()),
+            ("mods".into(), ChannelId::from_str("mod-source").unwrap()),
+        ]);
+        let node = NodeConfigTypes::CurseManifestDiff(CurseManifestDiff::default());
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([
+                (ChannelId::from_str("manifest-source").unwrap(), InputType::Text(manifest_channel.clone())),
+                (ChannelId::from_str("mod-source").unwrap(), InputType::ResolvedMods(mods_channel.clone())),
+            ]))
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Text, ctx);
+
+        let curse = |name: &str, project_id: u32, file_id: u32| ResolvedMod {
+            source: Some(ModSource::Curse { project_id, file_id }),
+            ..test_mod(name)
+        };
+        let manifest = r#"{"minecraft":{"version":"1.20.1"},"files":[
+            {"projectID":238222,"fileID":4712868,"required":true},
+            {"projectID":60089,"fileID":4581240,"required":true},
+            {"projectID":248787,"fileID":4770828,"required":false}
+        ]}"#;
+        let mods = vec![
+            curse("jei", 238222, 4712868),
+            curse("mouse-tweaks", 60089, 4600000),
+            curse("waystones", 245755, 4669369),
+            test_mod("sodium"),
+        ];
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        manifest_channel.send(manifest.into()).unwrap();
+        mods_channel.send(mods).unwrap();
+
+        let report = read_channel(&mut out_channel, Duration::from_secs(5)).unwrap();
+        assert_eq!(
+            report,
+            "Compared 3 files in the Curse manifest with 4 mods in the pack.\n\
+             \n\
+             In the manifest but not the pack (1):\n\
+             - project 248787 (file 4770828)\n\
+             \n\
+             In the pack but not the manifest (1):\n\
+             - waystones (project 245755)\n\
+             \n\
+             Resolved to a different file (1):\n\
+             - mouse-tweaks (project 60089): manifest file 4581240, pack file 4600000\n\
+             \n\
+             Not from CurseForge, so not matched (1):\n\
+             - sodium\n"
+        );
+        handle.join().unwrap();
+
+        let matching = diff(&BTreeMap::from([(238222, 4712868)]), &[curse("jei", 238222, 4712868)]);
+        assert!(matching.is_empty());
+        assert!(matching.report(1, 1).ends_with("the pack matches the manifest.\n"));
+    }
+}
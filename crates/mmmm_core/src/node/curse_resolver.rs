@@ -1,11 +1,12 @@
 use std::{
     collections::HashMap,
+    fmt::Display,
     sync::Arc,
     thread::{spawn, JoinHandle},
 };
 
 use api_client::{
-    common::{download_file, ApiError, DownloadError},
+    common::{ApiError, DownloadError, Downloader},
     curse::{model::HashAlgo, CurseClient},
 };
 use digest::Digest;
@@ -25,8 +26,8 @@ use crate::{
 };
 
 use super::{
-    config::{ChannelId, NodeConfig, NodeInitError, ResolvedMod, Side},
-    utils::{get_input, get_output},
+    config::{ChannelId, ChannelType, ModSource, NodeConfig, NodeDescription, NodeInitError, Port, ProjectKind, ResolvedMod, Side},
+    utils::{cache_namespace, get_input, get_output, CACHE_PREFIX},
 };
 
 #[derive(Debug, Clone, Deserialize, PartialEq)]
@@ -42,10 +43,14 @@ impl NodeConfig for CurseResolver {
         let mut manifest_channel = get_input!("manifest", Text, ctx, input_ids)?;
         let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), ResolvedMods, ctx)?;
 
+        let pool = ctx.get_pool();
         let mut waker = ctx.get_waker();
 
-        let curse_client = ctx.get_curse_client().ok_or_else(|| NodeInitError::CurseClientRequired)?;
+        let curse_client = ctx.get_curse_client().ok_or(NodeInitError::CurseClientRequired)?;
         let cache = ctx.get_cache();
+        let downloader = ctx.get_downloader();
+        let offline = ctx.is_offline();
+        let cache_prefix = ctx.get_config(CACHE_PREFIX).filter(|prefix| !prefix.is_empty());
         Ok(spawn(move || {
             let _span = span!(Level::INFO, "CurseResolver", nodeid = node_id).entered();
             if !waker.blocking_recv().unwrap_or_log() {
@@ -56,10 +61,23 @@ impl NodeConfig for CurseResolver {
             event!(Level::INFO, "Got {} mods to resolve", manifest.len());
 
             let manifest_mods = serde_json::from_str::<CurseManifest>(&manifest).expect_or_log("Failed to deserialize Curse manifest!").files;
-            let resolved: Vec<ResolvedMod> = manifest_mods.par_iter()
-                .map(|manifest_mod| resolve_curse(&curse_client, manifest_mod.project_id, manifest_mod.file_id, &cache)
-                .expect_or_log("Failed to resolve Curse mod"))
-                .collect();
+            let resolved: Vec<ResolvedMod> = pool.install(|| {
+                manifest_mods
+                    .par_iter()
+                    .map(|manifest_mod| {
+                        resolve_curse(
+                            &curse_client,
+                            &downloader,
+                            manifest_mod.project_id,
+                            manifest_mod.file_id,
+                            &cache,
+                            cache_prefix.as_deref(),
+                            offline,
+                        )
+                        .expect_or_log("Failed to resolve Curse mod")
+                    })
+                    .collect()
+            });
 
             if out_channel.send(resolved).is_err() {
                 event!(Level::DEBUG, "Channel 'default' has no subscribers");
@@ -72,6 +90,14 @@ impl NodeConfig for CurseResolver {
             (ChannelId(node_id.to_owned(), "default".into()), InputType::ResolvedMods(channel(1).0)),
         ])
     }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "CurseResolver",
+            vec![Port::required("manifest", ChannelType::Text)],
+            vec![Port::required("default", ChannelType::ResolvedMods)],
+        )
+    }
 }
 
 #[derive(Error, Debug)]
@@ -86,20 +112,23 @@ enum ResolveError {
     Cache(#[from] CacheError),
     #[error("Failed to deserialize cached data! Error: {0}")]
     CacheDeserialize(#[from] serde_json::Error),
+    #[error("No cached data for {0}, and network access is disabled in offline mode!")]
+    OfflineCacheMiss(String),
 }
 
 #[derive(Serialize, Deserialize)]
-struct CurseManifest {
-    files: Vec<CurseManifestFile>,
+pub(super) struct CurseManifest {
+    pub(super) files: Vec<CurseManifestFile>,
 }
 
+/// A mod listed in a Curse manifest.
 #[derive(Serialize, Deserialize)]
-struct CurseManifestFile {
+pub(super) struct CurseManifestFile {
     #[serde(rename = "projectID")]
-    project_id: u32,
+    pub(super) project_id: u32,
     #[serde(rename = "fileID")]
-    file_id: u32,
-    required: bool,
+    pub(super) file_id: u32,
+    pub(super) required: bool,
 }
 
 struct CacheKey<'a> {
@@ -107,66 +136,82 @@ struct CacheKey<'a> {
     file_id: &'a str,
 }
 
-impl ToString for CacheKey<'_> {
-    fn to_string(&self) -> String {
-        format!("{}::{}", self.id, self.file_id)
+impl Display for CacheKey<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}::{}", self.id, self.file_id)
     }
 }
 
+/// Look up a resolved mod in the cache. In offline mode a miss is an error, as the mod cannot be
+/// resolved over the network instead.
 fn get_from_cache(
     cache: &Option<Arc<dyn Cache>>,
+    prefix: Option<&str>,
     namespace: &str,
     key: &CacheKey,
+    offline: bool,
 ) -> Result<Option<ResolvedMod>, ResolveError> {
-    match cache {
+    let namespace = cache_namespace(prefix, namespace);
+    let cached = match cache {
         Some(cache) => {
-            let cache_data = cache.get(namespace, &key.to_string())?;
+            let cache_data = cache.get(&namespace, &key.to_string())?;
             match cache_data {
-                Some(cache_data) => {
-                    let resolved: Option<ResolvedMod> = serde_json::from_str(&cache_data)?;
-                    Ok(resolved)
-                },
-                None => Ok(None),
+                Some(cache_data) => serde_json::from_str(&cache_data)?,
+                None => None,
             }
         },
-        None => Ok(None),
+        None => None,
+    };
+    if cached.is_none() && offline {
+        return Err(ResolveError::OfflineCacheMiss(format!("{namespace}::{key}")));
     }
+    Ok(cached)
 }
 
-fn store_in_cache(cache: &Option<Arc<dyn Cache>>, namespace: &str, key: &CacheKey, value: &ResolvedMod) -> Result<(), ResolveError> {
+fn store_in_cache(
+    cache: &Option<Arc<dyn Cache>>,
+    prefix: Option<&str>,
+    namespace: &str,
+    key: &CacheKey,
+    value: &ResolvedMod,
+) -> Result<(), ResolveError> {
     match cache {
         Some(cache) => {
             let serialized = serde_json::to_string(value)?;
-            cache.put(namespace, &key.to_string(), &serialized)?;
+            cache.put(&cache_namespace(prefix, namespace), &key.to_string(), &serialized)?;
             Ok(())
         },
         None => Ok(()),
     }
 }
 
-const CURSE_CACHE_NAMESPACE: &str = "CurseResolver";
+const CURSE_CACHE_NAMESPACE: &str = "CurseResolver::v2";
 
 fn resolve_curse(
     client: &CurseClient,
+    downloader: &Downloader,
     mod_id: u32,
     file_id: u32,
     cache: &Option<Arc<dyn Cache>>,
+    cache_prefix: Option<&str>,
+    offline: bool,
 ) -> Result<ResolvedMod, ResolveError> {
     let _span = span!(Level::INFO, "Curse", mod_id = mod_id, file_id = file_id).entered();
     let cache_key = CacheKey {
         id: &mod_id.to_string(),
         file_id: &file_id.to_string(),
     };
-    if let Some(cached) = get_from_cache(cache, CURSE_CACHE_NAMESPACE, &cache_key)? {
+    if let Some(cached) = get_from_cache(cache, cache_prefix, CURSE_CACHE_NAMESPACE, &cache_key, offline)? {
         return Ok(cached);
     }
     let mod_response = client.find_mod_by_id(mod_id)?;
     let file_response = client.get_files(&[file_id])?
         .pop()
         .ok_or_else(|| ResolveError::EmptyOption("popping single file from Curse files by IDs response".to_owned()))?;
-    let file_data = download_file(&file_response.download_url)?;
+    let file_data = downloader.download_file(&file_response.download_url_or_cdn())?;
 
     let sha256hash = sha256hash(&file_data);
+    let src = encode_spaces(&file_response.download_url_or_cdn());
     let md5hash = {
         match file_response.hashes.into_iter().find(|h| h.algo == HashAlgo::Md5) {
             Some(hash) => hash.value,
@@ -177,7 +222,7 @@ fn resolve_curse(
         default: true,
         encoded: encode(&file_response.file_name).into_owned(),
         filename: file_response.file_name,
-        src: encode_spaces(&file_response.download_url),
+        src,
         md5: md5hash,
         side: Side::Both,
         title: mod_response.name,
@@ -185,8 +230,11 @@ fn resolve_curse(
         size: file_data.len() as u64,
         sha256: sha256hash,
         required: true,
+        source: Some(ModSource::Curse { project_id: mod_id, file_id }),
+        kind: ProjectKind::Mod,
+        group: None,
     };
-    store_in_cache(cache, CURSE_CACHE_NAMESPACE, &cache_key, &resolved)?;
+    store_in_cache(cache, cache_prefix, CURSE_CACHE_NAMESPACE, &cache_key, &resolved)?;
     Ok(resolved)
 }
 
@@ -277,6 +325,12 @@ mod tests {
             size: 1119478,
             md5: "9df0dc628ebcd787270f487fbbf8157a".to_owned(),
             sha256: "17c589aad9907d4ba56d578d502afa80aac1ba2fa8677e8b4d06c019c41d7731".to_owned(),
+            source: Some(ModSource::Curse {
+                project_id: 357178,
+                file_id: 3437402,
+            }),
+            kind: ProjectKind::Mod,
+            group: None,
         }];
 
         assert_eq!(output, expected);
@@ -0,0 +1,318 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::{Display, Write},
+    thread::{spawn, JoinHandle},
+};
+
+use api_client::{
+    curse::{model::FileRelationType, CurseClient},
+    modrinth::{model::DependencyType, ModrinthClient},
+};
+use rayon::prelude::*;
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::di::container::{DiContainer, InputType, OutputType};
+
+use super::{
+    config::{ChannelId, ChannelType, ModSource, NodeConfig, NodeDescription, NodeInitError, Port, ResolvedMod},
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct DependencyGraph;
+
+/// Project a dependency points to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(super) enum Project {
+    Curse(u32),
+    Modrinth(String),
+}
+
+impl Display for Project {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Project::Curse(id) => write!(f, "curse:{id}"),
+            Project::Modrinth(id) => write!(f, "modrinth:{id}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(super) enum Relation {
+    Required,
+    Optional,
+    /// The mods crash or misbehave when installed together.
+    Incompatible,
+}
+
+/// Get the project a mod was resolved from, if it came from Curse or Modrinth.
+pub(super) fn project(m: &ResolvedMod) -> Option<Project> {
+    match m.source {
+        Some(ModSource::Curse { project_id, .. }) => Some(Project::Curse(project_id)),
+        Some(ModSource::Modrinth { ref project_id, .. }) => Some(Project::Modrinth(project_id.clone())),
+        Some(ModSource::Url) | None => None,
+    }
+}
+
+impl NodeConfig for DependencyGraph {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut mods_channel = get_input!("mods", ResolvedMods, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Text, ctx)?;
+        // Dependencies are not cached, so they can never be looked up offline.
+        if ctx.is_offline() {
+            return Err(NodeInitError::Offline);
+        }
+        let curse_client = ctx.get_curse_client();
+        let modrinth_client = ctx.get_modrinth_client();
+        let pool = ctx.get_pool();
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "DependencyGraph", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let mods = mods_channel.blocking_recv().expect_or_log("Failed to receive on mods input");
+            event!(Level::INFO, "Looking up dependencies of {} mods", mods.len());
+
+            let dependencies: Vec<Vec<(Project, Relation)>> = pool.install(|| {
+                mods.par_iter()
+                    .map(|m| {
+                        file_dependencies(m, &curse_client, &modrinth_client)
+                            .unwrap_or_else(|e| {
+                                event!(Level::WARN, "Could not determine dependencies of {}: {}", m.name, e);
+                                vec![]
+                            })
+                            .into_iter()
+                            .filter(|(_, relation)| *relation != Relation::Incompatible)
+                            .collect()
+                    })
+                    .collect()
+            });
+
+            let graph = write_dot(mods.iter().zip(dependencies).collect());
+
+            if out_channel.send(graph).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::Text(channel(1).0))])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "DependencyGraph",
+            vec![Port::required("mods", ChannelType::ResolvedMods)],
+            vec![Port::required("default", ChannelType::Text)],
+        )
+    }
+}
+
+/// Get the dependencies and incompatibilities declared by the file a mod was resolved to. Mods
+/// that were not resolved from Curse or Modrinth have no known dependencies.
+pub(super) fn file_dependencies(m: &ResolvedMod, curse: &Option<CurseClient>, modrinth: &ModrinthClient) -> Result<Vec<(Project, Relation)>, String> {
+    match m.source {
+        Some(ModSource::Curse { file_id, .. }) => {
+            let client = curse.as_ref().ok_or("Curse client is not configured")?;
+            let file = client
+                .get_files(&[file_id])
+                .map_err(|e| e.to_string())?
+                .pop()
+                .ok_or("Curse file not found")?;
+            Ok(file
+                .dependencies
+                .into_iter()
+                .filter_map(|d| match d.relation_type {
+                    FileRelationType::RequiredDependency => Some((Project::Curse(d.mod_id), Relation::Required)),
+                    FileRelationType::OptionalDependency => Some((Project::Curse(d.mod_id), Relation::Optional)),
+                    FileRelationType::Incompatible => Some((Project::Curse(d.mod_id), Relation::Incompatible)),
+                    _ => None,
+                })
+                .collect())
+        },
+        Some(ModSource::Modrinth { ref version_id, .. }) => {
+            let version = modrinth.get_version(version_id).map_err(|e| e.to_string())?;
+            let mut dependencies = vec![];
+            for d in version.dependencies {
+                let relation = match d.dependency_type {
+                    DependencyType::Required => Relation::Required,
+                    DependencyType::Optional => Relation::Optional,
+                    DependencyType::Incompatible => Relation::Incompatible,
+                    DependencyType::Embedded => continue,
+                };
+                // Dependencies on a specific version may omit the project
+                let project_id = match (d.project_id, d.version_id) {
+                    (Some(project_id), _) => project_id,
+                    (None, Some(version_id)) => modrinth.get_version(&version_id).map_err(|e| e.to_string())?.project_id,
+                    (None, None) => continue,
+                };
+                dependencies.push((Project::Modrinth(project_id), relation));
+            }
+            Ok(dependencies)
+        },
+        Some(ModSource::Url) | None => Ok(vec![]),
+    }
+}
+
+/// Write a Graphviz DOT digraph with a node per mod and an edge from each mod to each of its
+/// dependencies. Optional dependencies are dashed, and dependencies not in the pack are grey.
+fn write_dot(mods: Vec<(&ResolvedMod, Vec<(Project, Relation)>)>) -> String {
+    let in_pack: HashMap<Project, &str> = mods.iter().filter_map(|(m, _)| Some((project(m)?, m.name.as_str()))).collect();
+
+    let mut nodes = mods.iter().map(|(m, _)| (m.name.as_str(), m.title.as_str())).collect::<Vec<_>>();
+    nodes.sort();
+    let mut external = BTreeSet::new();
+    let mut edges = BTreeSet::new();
+    for (m, dependencies) in &mods {
+        for (project, relation) in dependencies {
+            let target = match in_pack.get(project) {
+                Some(name) => name.to_string(),
+                None => {
+                    external.insert(project.to_string());
+                    project.to_string()
+                },
+            };
+            // A mod may list the same dependency more than once, keep the strongest relation
+            if *relation == Relation::Optional && edges.contains(&(m.name.clone(), target.clone(), Relation::Required)) {
+                continue;
+            }
+            edges.remove(&(m.name.clone(), target.clone(), Relation::Optional));
+            edges.insert((m.name.clone(), target, *relation));
+        }
+    }
+
+    let mut dot = "digraph dependencies {\n".to_owned();
+    for (name, title) in nodes {
+        writeln!(dot, "    {} [label={}];", quote(name), quote(title)).unwrap();
+    }
+    for project in external {
+        writeln!(dot, "    {} [color=grey, fontcolor=grey];", quote(&project)).unwrap();
+    }
+    for (from, to, relation) in edges {
+        match relation {
+            Relation::Required => writeln!(dot, "    {} -> {};", quote(&from), quote(&to)).unwrap(),
+            Relation::Optional => writeln!(dot, "    {} -> {} [style=dashed];", quote(&from), quote(&to)).unwrap(),
+            // Incompatibilities are not dependencies, and are left out before the graph is drawn
+            Relation::Incompatible => {},
+        }
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// Quote a string as a DOT identifier.
+fn quote(id: &str) -> String {
+    format!("\"{}\"", id.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel, serve_http, test_mod},
+        },
+    };
+
+    use super::*;
+
+    fn version(id: &str, project_id: &str, dependencies: &str) -> Vec<u8> {
+        format!(
+            r#"{{"name":"v","version_number":"1","game_versions":[],"version_type":"release","loaders":[],"id":"{id}","project_id":"{project_id}","files":[],"date_published":"2023-01-01T00:00:00Z","dependencies":[{dependencies}]}}"#
+        )
+        .into_bytes()
+    }
+
+    #[test]
+    fn test_dependency_graph() {
+        let node_id = "deps";
+        let base_url = serve_http(HashMap::from([
+            (
+                "/v2/version/createv1".to_owned(),
+                (
+                    200,
+                    version(
+                        "createv1",
+                        "create-id",
+                        r#"{"project_id":"flywheel-id","dependency_type":"required"},
+                        {"project_id":"jei-id","dependency_type":"optional"},
+                        {"project_id":"flywheel-id","dependency_type":"optional"},
+                        {"project_id":"optifine","dependency_type":"incompatible"},
+                        {"version_id":"libv0001","dependency_type":"required"}"#,
+                    ),
+                ),
+            ),
+            ("/v2/version/flywv001".to_owned(), (200, version("flywv001", "flywheel-id", ""))),
+            ("/v2/version/libv0001".to_owned(), (200, version("libv0001", "lib-id", ""))),
+        ]));
+        let mods_channel = channel(1).0;
+        let input_ids = HashMap::from([("mods".into(), ChannelId::from_str("mod-source").unwrap())]);
+        let node = NodeConfigTypes::DependencyGraph(DependencyGraph);
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("mod-source").unwrap(),
+                InputType::ResolvedMods(mods_channel.clone()),
+            )]))
+            .modrinth_base_url(&base_url)
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Text, ctx);
+
+        let with_source = |name: &str, title: &str, source: Option<ModSource>| {
+            let mut m = test_mod(name);
+            m.title = title.to_owned();
+            m.source = source;
+            m
+        };
+        let modrinth = |name: &str, title: &str, project_id: &str, version_id: &str| {
+            with_source(
+                name,
+                title,
+                Some(ModSource::Modrinth {
+                    project_id: project_id.to_owned(),
+                    version_id: version_id.to_owned(),
+                }),
+            )
+        };
+        let mods = vec![
+            modrinth("create", "Create", "create-id", "createv1"),
+            modrinth("flywheel", "Flywheel", "flywheel-id", "flywv001"),
+            modrinth("library", "A \"Library\"", "lib-id", "libv0001"),
+            with_source("url-mod", "URL Mod", Some(ModSource::Url)),
+        ];
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        mods_channel.send(mods).unwrap();
+        handle.join().unwrap();
+
+        let expected = r#"digraph dependencies {
+    "create" [label="Create"];
+    "flywheel" [label="Flywheel"];
+    "library" [label="A \"Library\""];
+    "url-mod" [label="URL Mod"];
+    "modrinth:jei-id" [color=grey, fontcolor=grey];
+    "create" -> "flywheel";
+    "create" -> "library";
+    "create" -> "modrinth:jei-id" [style=dashed];
+}
+"#;
+        assert_eq!(read_channel(&mut out_channel, Duration::from_secs(10)).unwrap(), expected);
+    }
+}
@@ -11,7 +11,7 @@ use tracing_unwrap::{OptionExt, ResultExt};
 use crate::di::container::{DiContainer, InputType, OutputType};
 
 use super::{
-    config::{ChannelId, NodeConfig, NodeInitError},
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port},
     utils::{get_input, get_output},
 };
 
@@ -64,6 +64,10 @@ impl NodeConfig for DirectoryMerger {
     fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
         HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::Files(channel(1).0))])
     }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new("DirectoryMerger", vec![], vec![Port::required("default", ChannelType::Files)]).with_any_inputs(ChannelType::Files)
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,238 @@
+use std::{
+    collections::HashMap,
+    thread::{spawn, JoinHandle},
+};
+
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::di::container::{DiContainer, InputType, OutputType};
+
+use super::{
+    archive_downloader::has_extension,
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port},
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct ExtensionWhitelist {
+    /// File extensions to keep. Replaces [`DEFAULT_ALLOWED_EXTENSIONS`].
+    #[serde(default = "default_allowed_extensions")]
+    pub allowed_extensions: Vec<String>,
+}
+
+fn default_allowed_extensions() -> Vec<String> {
+    DEFAULT_ALLOWED_EXTENSIONS.iter().map(|e| e.to_string()).collect()
+}
+
+impl Default for ExtensionWhitelist {
+    fn default() -> Self {
+        ExtensionWhitelist {
+            allowed_extensions: default_allowed_extensions(),
+        }
+    }
+}
+
+/// Extensions of the mods, configs, scripts and assets usually found in a pack. Executables and
+/// shell scripts are deliberately left out.
+pub const DEFAULT_ALLOWED_EXTENSIONS: &[&str] = &[
+    "jar",
+    "zip",
+    "json",
+    "json5",
+    "toml",
+    "cfg",
+    "conf",
+    "properties",
+    "txt",
+    "yml",
+    "yaml",
+    "snbt",
+    "nbt",
+    "dat",
+    "mcmeta",
+    "mcfunction",
+    "lang",
+    "png",
+    "jpg",
+    "ogg",
+    "js",
+    "zs",
+    "md",
+];
+
+impl NodeConfig for ExtensionWhitelist {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut files_channel = get_input!("files", Files, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Files, ctx)?;
+        let inverse_channel = get_output!(ChannelId(node_id.clone(), "inverse".into()), Files, ctx)?;
+        let report_channel = get_output!(ChannelId(node_id.clone(), "report".into()), Text, ctx)?;
+        if self.allowed_extensions.is_empty() {
+            return Err(NodeInitError::InvalidOption {
+                option: "allowed_extensions".into(),
+                reason: "at least one extension must be allowed".into(),
+            });
+        }
+        let allowed = self
+            .allowed_extensions
+            .iter()
+            .map(|extension| extension.trim_start_matches('.').to_lowercase())
+            .collect::<Vec<_>>();
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "ExtensionWhitelist", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let files = files_channel.blocking_recv().expect_or_log("Failed to receive on files input");
+
+            let mut kept = files.clone();
+            let mut removed = files;
+            let mut removed_paths = Vec::new();
+            for path in kept.list_files().into_iter().cloned().collect::<Vec<_>>() {
+                if has_extension(&path.to_string(), &allowed) {
+                    removed.delete_file(&path);
+                } else {
+                    kept.delete_file(&path);
+                    removed_paths.push(path.to_string());
+                }
+            }
+            removed_paths.sort();
+
+            let report = if removed_paths.is_empty() {
+                format!("All files have an allowed extension ({}).\n", allowed.join(", "))
+            } else {
+                format!(
+                    "Removed {} files without an allowed extension ({}):\n{}\n",
+                    removed_paths.len(),
+                    allowed.join(", "),
+                    removed_paths.iter().map(|path| format!("- {path}")).collect::<Vec<_>>().join("\n")
+                )
+            };
+            if !removed_paths.is_empty() {
+                event!(Level::WARN, "{report}");
+            }
+
+            if out_channel.send(kept).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+            if inverse_channel.send(removed).is_err() {
+                event!(Level::DEBUG, "Channel 'inverse' has no subscribers");
+            }
+            if report_channel.send(report).is_err() {
+                event!(Level::DEBUG, "Channel 'report' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([
+            (ChannelId(node_id.to_owned(), "default".into()), InputType::Files(channel(1).0)),
+            (ChannelId(node_id.to_owned(), "inverse".into()), InputType::Files(channel(1).0)),
+            (ChannelId(node_id.to_owned(), "report".into()), InputType::Text(channel(1).0)),
+        ])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "ExtensionWhitelist",
+            vec![Port::required("files", ChannelType::Files)],
+            vec![
+                Port::required("default", ChannelType::Files),
+                Port::required("inverse", ChannelType::Files),
+                Port::required("report", ChannelType::Text),
+            ],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        file::{filepath::FilePath, filetree::FileTree},
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_extension_whitelist() {
+        let node_id = "whitelist";
+        let files_channel = channel::<FileTree>(1).0;
+        let input_ids = HashMap::from([("files".into(), ChannelId::from_str("pack-files").unwrap())]);
+        let node = NodeConfigTypes::ExtensionWhitelist(ExtensionWhitelist::default());
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("pack-files").unwrap(),
+                InputType::Files(files_channel.clone()),
+            )]))
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Files, ctx);
+        let mut inverse_channel = get_output_test!(ChannelId::from_str("whitelist::inverse").unwrap(), Files, ctx);
+        let mut report_channel = get_output_test!(ChannelId::from_str("whitelist::report").unwrap(), Text, ctx);
+
+        let mut tree = FileTree::new(ctx.get_filestore());
+        for path in [
+            "overrides/config/mymod.toml",
+            "overrides/config/Other.CFG",
+            "overrides/kubejs/server_scripts/recipes.js",
+            "overrides/mods/extra.jar",
+            "overrides/start.bat",
+            "overrides/scripts/install.sh",
+            "overrides/tools/updater.exe",
+            "overrides/LICENSE",
+        ] {
+            tree.add_file(FilePath::from_str(path).unwrap(), path.into());
+        }
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        files_channel.send(tree).unwrap();
+        handle.join().unwrap();
+
+        let timeout = Duration::from_secs(30);
+        let paths = |tree: FileTree| {
+            let mut paths = tree.list_files().into_iter().map(|p| p.to_string()).collect::<Vec<_>>();
+            paths.sort();
+            paths
+        };
+        assert_eq!(
+            paths(read_channel(&mut out_channel, timeout).unwrap()),
+            vec![
+                "overrides/config/Other.CFG",
+                "overrides/config/mymod.toml",
+                "overrides/kubejs/server_scripts/recipes.js",
+                "overrides/mods/extra.jar",
+            ]
+        );
+        assert_eq!(
+            paths(read_channel(&mut inverse_channel, timeout).unwrap()),
+            vec![
+                "overrides/LICENSE",
+                "overrides/scripts/install.sh",
+                "overrides/start.bat",
+                "overrides/tools/updater.exe"
+            ]
+        );
+        let report = read_channel(&mut report_channel, timeout).unwrap();
+        assert!(report.starts_with("Removed 4 files without an allowed extension"));
+        assert!(report.ends_with("- overrides/LICENSE\n- overrides/scripts/install.sh\n- overrides/start.bat\n- overrides/tools/updater.exe\n"));
+    }
+}
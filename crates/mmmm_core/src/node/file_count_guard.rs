@@ -0,0 +1,212 @@
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    thread::{spawn, JoinHandle},
+};
+
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::{
+    di::container::{DiContainer, InputType, OutputType},
+    file::filetree::FileTree,
+};
+
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port},
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct FileCountGuard {
+    /// Largest number of files the tree may contain.
+    pub max_files: usize,
+    /// Number of directories listed in the report.
+    #[serde(default = "default_top")]
+    pub top: usize,
+    /// Fail if the tree has too many files, instead of only reporting it.
+    #[serde(default)]
+    pub strict: bool,
+}
+
+fn default_top() -> usize {
+    10
+}
+
+impl NodeConfig for FileCountGuard {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut files_channel = get_input!("files", Files, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Files, ctx)?;
+        let report_channel = get_output!(ChannelId(node_id.clone(), "report".into()), Text, ctx)?;
+        let max_files = self.max_files;
+        let top = self.top;
+        let strict = self.strict;
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "FileCountGuard", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let files = files_channel.blocking_recv().expect_or_log("Failed to receive on files input");
+
+            let count = files.list_files().len();
+            let mut report = match count > max_files {
+                true => format!("{count} files, over the limit of {max_files}.\n"),
+                false => format!("{count} files, within the limit of {max_files}.\n"),
+            };
+            let directories = largest_directories(&files, top);
+            if !directories.is_empty() {
+                report.push_str("Largest directories:\n");
+                for (directory, count) in directories {
+                    writeln!(report, "- {directory}: {count}").unwrap();
+                }
+            }
+
+            if count > max_files {
+                event!(Level::WARN, "{report}");
+                if strict {
+                    panic!("Too many files in strict mode");
+                }
+            }
+
+            if out_channel.send(files).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+
+            if report_channel.send(report).is_err() {
+                event!(Level::DEBUG, "Channel 'report' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([
+            (ChannelId(node_id.to_owned(), "default".into()), InputType::Files(channel(1).0)),
+            (ChannelId(node_id.to_owned(), "report".into()), InputType::Text(channel(1).0)),
+        ])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "FileCountGuard",
+            vec![Port::required("files", ChannelType::Files)],
+            vec![Port::required("default", ChannelType::Files), Port::required("report", ChannelType::Text)],
+        )
+    }
+}
+
+/// Count the files under each directory, including those in its subdirectories, and return the
+/// `top` directories with the most files. Nested directories are listed alongside their parents,
+/// so the bloat can be narrowed down to the deepest directory that holds most of it.
+fn largest_directories(files: &FileTree, top: usize) -> Vec<(String, usize)> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for path in files.list_files() {
+        let components = path.get_components();
+        for depth in 1..components.len() {
+            *counts.entry(components[..depth].join("/")).or_default() += 1;
+        }
+    }
+    let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts.truncate(top);
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        file::{filepath::FilePath, filestore::FileStore},
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel},
+        },
+    };
+
+    use super::*;
+
+    fn tree() -> FileTree {
+        let mut tree = FileTree::new(FileStore::new());
+        let mut paths = vec!["mods/a.jar".to_owned(), "mods/b.jar".to_owned(), "options.txt".to_owned()];
+        paths.extend((0..4).map(|i| format!("config/workspace/build/classes/C{i}.class")));
+        paths.push("config/workspace/build.gradle".to_owned());
+        for path in paths {
+            tree.add_file(FilePath::from_str(&path).unwrap(), path.clone().into_bytes());
+        }
+        tree
+    }
+
+    #[test]
+    fn test_file_count_guard() {
+        let node_id = "guard";
+        let files_channel = channel(1).0;
+        let input_ids = HashMap::from([("files".into(), ChannelId::from_str("file-source").unwrap())]);
+        let node = NodeConfigTypes::FileCountGuard(FileCountGuard {
+            max_files: 5,
+            top: 4,
+            strict: false,
+        });
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("file-source").unwrap(),
+                InputType::Files(files_channel.clone()),
+            )]))
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Files, ctx);
+        let mut report_channel = get_output_test!(ChannelId::from_str("guard::report").unwrap(), Text, ctx);
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        files_channel.send(tree()).unwrap();
+        handle.join().unwrap();
+
+        let timeout = Duration::from_secs(10);
+        assert_eq!(read_channel(&mut out_channel, timeout).unwrap().list_files().len(), 8);
+        let expected = "8 files, over the limit of 5.
+Largest directories:
+- config: 5
+- config/workspace: 5
+- config/workspace/build: 4
+- config/workspace/build/classes: 4
+";
+        assert_eq!(read_channel(&mut report_channel, timeout).unwrap(), expected);
+    }
+
+    #[test]
+    fn strict_fails_over_limit() {
+        let node_id = "guard";
+        let files_channel = channel(1).0;
+        let input_ids = HashMap::from([("files".into(), ChannelId::from_str("file-source").unwrap())]);
+        let node = NodeConfigTypes::FileCountGuard(FileCountGuard {
+            max_files: 5,
+            top: default_top(),
+            strict: true,
+        });
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("file-source").unwrap(),
+                InputType::Files(files_channel.clone()),
+            )]))
+            .build();
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        files_channel.send(tree()).unwrap();
+        assert!(handle.join().is_err());
+    }
+}
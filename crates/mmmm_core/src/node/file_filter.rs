@@ -9,7 +9,7 @@ use tokio::sync::broadcast::channel;
 use tracing::{event, span, Level};
 use tracing_unwrap::ResultExt;
 
-use super::config::{NodeConfig, NodeInitError};
+use super::config::{ChannelType, NodeConfig, NodeDescription, NodeInitError, Port};
 use super::{config::ChannelId, utils};
 
 #[derive(Debug, Clone, Deserialize, PartialEq)]
@@ -56,6 +56,17 @@ impl NodeConfig for FileFilter {
             (ChannelId(node_id.to_owned(), "inverse".into()), InputType::Files(channel(1).0)),
         ])
     }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "FileFilter",
+            vec![Port::required(FILES, ChannelType::Files), Port::required(PATTERN, ChannelType::List)],
+            vec![
+                Port::required("default", ChannelType::Files),
+                Port::required("inverse", ChannelType::Files),
+            ],
+        )
+    }
 }
 
 #[cfg(test)]
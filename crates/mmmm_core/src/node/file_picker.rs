@@ -7,7 +7,10 @@ use tracing_unwrap::{OptionExt, ResultExt};
 
 use crate::{di::container::{DiContainer, InputType, OutputType}, file::filepath::FilePath};
 
-use super::{config::{ChannelId, NodeConfig, NodeInitError}, utils};
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port},
+    utils,
+};
 
 #[derive(Debug, Clone, Deserialize, PartialEq)]
 pub struct FilePicker;
@@ -48,6 +51,14 @@ impl NodeConfig for FilePicker {
     fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
         HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::Text(channel(1).0))])
     }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "FilePicker",
+            vec![Port::required(FILES, ChannelType::Files), Port::required(PATH, ChannelType::Text)],
+            vec![Port::required("default", ChannelType::Text)],
+        )
+    }
 }
 
 #[cfg(test)]
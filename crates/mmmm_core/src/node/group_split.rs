@@ -0,0 +1,185 @@
+use std::{
+    collections::HashMap,
+    thread::{spawn, JoinHandle},
+};
+
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::di::container::{DiContainer, InputType, OutputType};
+
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port, ResolvedMod},
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Default)]
+pub struct GroupSplit {
+    /// Groups to split out, each sent on an output of the same name. Mods without a group are sent
+    /// on `default`.
+    pub groups: Vec<String>,
+}
+
+impl GroupSplit {
+    fn validate(&self) -> Result<(), NodeInitError> {
+        let invalid = |reason: String| NodeInitError::InvalidOption {
+            option: "groups".into(),
+            reason,
+        };
+        if self.groups.is_empty() {
+            return Err(invalid("at least one group must be listed".into()));
+        }
+        for (i, group) in self.groups.iter().enumerate() {
+            if group.is_empty() || group.contains("::") {
+                return Err(invalid(format!("'{group}' is not a valid output name")));
+            }
+            if group == "default" {
+                return Err(invalid("'default' is reserved for mods without a group".into()));
+            }
+            if self.groups[..i].contains(group) {
+                return Err(invalid(format!("'{group}' is listed more than once")));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl NodeConfig for GroupSplit {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        self.validate()?;
+        let mut mods_channel = get_input!("mods", ResolvedMods, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), ResolvedMods, ctx)?;
+        let mut group_channels = HashMap::new();
+        for group in &self.groups {
+            let group_channel = get_output!(ChannelId(node_id.clone(), group.clone()), ResolvedMods, ctx)?;
+            group_channels.insert(group.clone(), group_channel);
+        }
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "GroupSplit", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let mods = mods_channel.blocking_recv().expect_or_log("Failed to receive on mods input");
+
+            let mut ungrouped = vec![];
+            let mut grouped: HashMap<String, Vec<ResolvedMod>> = HashMap::new();
+            let mut unknown = vec![];
+            for m in mods {
+                match &m.group {
+                    None => ungrouped.push(m),
+                    Some(group) if group_channels.contains_key(group) => grouped.entry(group.clone()).or_default().push(m),
+                    Some(group) => unknown.push(format!("{} ({group})", m.name)),
+                }
+            }
+            if !unknown.is_empty() {
+                event!(Level::ERROR, "Mods assigned to groups that are not listed: {}", unknown.join(", "));
+                panic!()
+            }
+
+            if out_channel.send(ungrouped).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+            for (group, group_channel) in group_channels {
+                if group_channel.send(grouped.remove(&group).unwrap_or_default()).is_err() {
+                    event!(Level::DEBUG, "Channel '{}' has no subscribers", group);
+                }
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        let mut channels = HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::ResolvedMods(channel(1).0))]);
+        for group in &self.groups {
+            channels.insert(ChannelId(node_id.to_owned(), group.clone()), InputType::ResolvedMods(channel(1).0));
+        }
+        channels
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "GroupSplit",
+            vec![Port::required("mods", ChannelType::ResolvedMods)],
+            vec![Port::required("default", ChannelType::ResolvedMods)],
+        )
+        .with_any_outputs(ChannelType::ResolvedMods)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use tokio::sync::broadcast;
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel, test_mod},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_group_split() {
+        let node_id = "split";
+        let mods_channel = broadcast::channel(1).0;
+        let input_ids = HashMap::from([("mods".into(), ChannelId::from_str("mod-source").unwrap())]);
+        let node = NodeConfigTypes::GroupSplit(GroupSplit {
+            groups: vec!["shaders".into(), "optional".into()],
+        });
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("mod-source").unwrap(),
+                InputType::ResolvedMods(mods_channel.clone()),
+            )]))
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str("split").unwrap(), ResolvedMods, ctx);
+        let mut shaders_channel = get_output_test!(ChannelId::from_str("split::shaders").unwrap(), ResolvedMods, ctx);
+        let mut optional_channel = get_output_test!(ChannelId::from_str("split::optional").unwrap(), ResolvedMods, ctx);
+
+        let grouped = |name: &str, group: &str| ResolvedMod {
+            group: Some(group.into()),
+            ..test_mod(name)
+        };
+        let mods = vec![
+            test_mod("jei"),
+            grouped("iris", "shaders"),
+            test_mod("appleskin"),
+            grouped("oculus", "shaders"),
+        ];
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        mods_channel.send(mods).unwrap();
+
+        let names = |mods: Vec<ResolvedMod>| mods.into_iter().map(|m| m.name).collect::<Vec<_>>();
+        assert_eq!(
+            names(read_channel(&mut out_channel, Duration::from_secs(5)).unwrap()),
+            ["jei", "appleskin"]
+        );
+        assert_eq!(
+            names(read_channel(&mut shaders_channel, Duration::from_secs(5)).unwrap()),
+            ["iris", "oculus"]
+        );
+        assert!(read_channel(&mut optional_channel, Duration::from_secs(5)).unwrap().is_empty());
+        handle.join().unwrap();
+
+        for groups in [vec![], vec!["default".to_owned()], vec!["a".into(), "a".into()], vec!["a::b".into()]] {
+            assert!(matches!(GroupSplit { groups }.validate(), Err(NodeInitError::InvalidOption { .. })));
+        }
+    }
+}
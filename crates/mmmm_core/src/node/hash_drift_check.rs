@@ -0,0 +1,242 @@
+use std::{
+    collections::HashMap,
+    thread::{spawn, JoinHandle},
+};
+
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::di::container::{DiContainer, InputType, OutputType};
+
+use super::{
+    config::{ChannelId, ChannelType, ModSource, NodeConfig, NodeDescription, NodeInitError, Port, ResolvedMod},
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Default)]
+pub struct HashDriftCheck {
+    /// Fail if any mod changed hash, instead of only reporting it.
+    #[serde(default)]
+    pub strict: bool,
+}
+
+impl NodeConfig for HashDriftCheck {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut expected_channel = get_input!("expected", ResolvedMods, ctx, input_ids)?;
+        let mut resolved_channel = get_input!("resolved", ResolvedMods, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), ResolvedMods, ctx)?;
+        let report_channel = get_output!(ChannelId(node_id.clone(), "report".into()), Text, ctx)?;
+        let strict = self.strict;
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "HashDriftCheck", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let expected = expected_channel.blocking_recv().expect_or_log("Failed to receive on expected input");
+            let resolved = resolved_channel.blocking_recv().expect_or_log("Failed to receive on resolved input");
+
+            let drifted = drifted_mods(&expected, &resolved);
+            let report = if drifted.is_empty() {
+                format!("Checked {} mods, no hash changes.\n", resolved.len())
+            } else {
+                let lines = drifted
+                    .iter()
+                    .map(|(old, new)| format!("- {} ({}): {} → {}", new.name, new.filename, hash(old), hash(new)))
+                    .collect::<Vec<_>>();
+                format!(
+                    "Checked {} mods, {} changed hash without changing version:\n{}\n",
+                    resolved.len(),
+                    drifted.len(),
+                    lines.join("\n")
+                )
+            };
+
+            if !drifted.is_empty() {
+                event!(Level::WARN, "{report}");
+                if strict {
+                    panic!("Mods changed hash in strict mode");
+                }
+            }
+
+            if out_channel.send(resolved).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+
+            if report_channel.send(report).is_err() {
+                event!(Level::DEBUG, "Channel 'report' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([
+            (ChannelId(node_id.to_owned(), "default".into()), InputType::ResolvedMods(channel(1).0)),
+            (ChannelId(node_id.to_owned(), "report".into()), InputType::Text(channel(1).0)),
+        ])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "HashDriftCheck",
+            vec![
+                Port::required("expected", ChannelType::ResolvedMods),
+                Port::required("resolved", ChannelType::ResolvedMods),
+            ],
+            vec![
+                Port::required("default", ChannelType::ResolvedMods),
+                Port::required("report", ChannelType::Text),
+            ],
+        )
+    }
+}
+
+/// Identifies the version of a mod: the platform file or version ID it was resolved with, or its
+/// filename for mods without one.
+pub(super) fn version(m: &ResolvedMod) -> String {
+    match &m.source {
+        Some(ModSource::Curse { file_id, .. }) => file_id.to_string(),
+        Some(ModSource::Modrinth { version_id, .. }) => version_id.clone(),
+        Some(ModSource::Url) | None => m.filename.clone(),
+    }
+}
+
+/// The strongest hash recorded for a mod, or an empty string if it has none.
+pub(super) fn hash(m: &ResolvedMod) -> &str {
+    if m.sha256.is_empty() {
+        &m.md5
+    } else {
+        &m.sha256
+    }
+}
+
+/// Pairs of expected and resolved mods with the same name and version but a different hash,
+/// sorted by name. Hashes are only compared when both sides record the same kind of hash.
+pub(super) fn drifted_mods<'a>(expected: &'a [ResolvedMod], resolved: &'a [ResolvedMod]) -> Vec<(&'a ResolvedMod, &'a ResolvedMod)> {
+    let expected_by_version: HashMap<(&str, String), &ResolvedMod> = expected.iter().map(|m| ((m.name.as_str(), version(m)), m)).collect();
+    let mut drifted = resolved
+        .iter()
+        .filter_map(|m| expected_by_version.get(&(m.name.as_str(), version(m))).map(|old| (*old, m)))
+        .filter(|(old, new)| {
+            if !old.sha256.is_empty() && !new.sha256.is_empty() {
+                old.sha256 != new.sha256
+            } else if !old.md5.is_empty() && !new.md5.is_empty() {
+                old.md5 != new.md5
+            } else {
+                event!(Level::DEBUG, "No comparable hashes recorded for {}, skipping", new.name);
+                false
+            }
+        })
+        .collect::<Vec<_>>();
+    drifted.sort_by(|a, b| a.1.name.cmp(&b.1.name));
+    drifted
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use tokio::sync::broadcast::Sender;
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel, test_mod},
+        },
+    };
+
+    use super::*;
+
+    type Senders = (Sender<Vec<ResolvedMod>>, Sender<Vec<ResolvedMod>>);
+
+    fn context(node: &NodeConfigTypes) -> (DiContainer, Senders, HashMap<String, ChannelId>) {
+        let expected_channel = channel(1).0;
+        let resolved_channel = channel(1).0;
+        let ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels("drift"))
+            .channel_from_node(HashMap::from([
+                (ChannelId::from_str("lock").unwrap(), InputType::ResolvedMods(expected_channel.clone())),
+                (
+                    ChannelId::from_str("resolver").unwrap(),
+                    InputType::ResolvedMods(resolved_channel.clone()),
+                ),
+            ]))
+            .build();
+        let input_ids = HashMap::from([
+            ("expected".into(), ChannelId::from_str("lock").unwrap()),
+            ("resolved".into(), ChannelId::from_str("resolver").unwrap()),
+        ]);
+        (ctx, (expected_channel, resolved_channel), input_ids)
+    }
+
+    fn hashed_mod(name: &str, file_id: u32, sha256: &str) -> ResolvedMod {
+        ResolvedMod {
+            sha256: sha256.into(),
+            source: Some(ModSource::Curse { project_id: 1, file_id }),
+            ..test_mod(name)
+        }
+    }
+
+    #[test]
+    fn test_hash_drift_check() {
+        let node = NodeConfigTypes::HashDriftCheck(HashDriftCheck::default());
+        let (mut ctx, (expected_channel, resolved_channel), input_ids) = context(&node);
+        let mut out_channel = get_output_test!(ChannelId::from_str("drift").unwrap(), ResolvedMods, ctx);
+        let mut report_channel = get_output_test!(ChannelId::from_str("drift::report").unwrap(), Text, ctx);
+
+        let expected = vec![
+            hashed_mod("jei", 100, "aaaa"),
+            hashed_mod("sodium", 200, "bbbb"),
+            hashed_mod("lithium", 300, "cccc"),
+            ResolvedMod {
+                md5: "dddd".into(),
+                ..test_mod("custom")
+            },
+        ];
+        // jei was updated, so its new hash is expected. sodium was re-uploaded under the same file.
+        let resolved = vec![
+            hashed_mod("jei", 101, "eeee"),
+            hashed_mod("sodium", 200, "ffff"),
+            hashed_mod("lithium", 300, "cccc"),
+            ResolvedMod {
+                md5: "0000".into(),
+                ..test_mod("custom")
+            },
+        ];
+
+        let handle = node.validate_and_spawn("drift".into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        expected_channel.send(expected).unwrap();
+        resolved_channel.send(resolved.clone()).unwrap();
+        handle.join().unwrap();
+
+        assert_eq!(read_channel(&mut out_channel, Duration::from_secs(10)).unwrap(), resolved);
+        assert_eq!(
+            read_channel(&mut report_channel, Duration::from_secs(10)).unwrap(),
+            "Checked 4 mods, 2 changed hash without changing version:\n\
+             - custom (custom.jar): dddd → 0000\n\
+             - sodium (sodium.jar): bbbb → ffff\n"
+        );
+    }
+
+    #[test]
+    fn strict_fails_on_drift() {
+        let node = NodeConfigTypes::HashDriftCheck(HashDriftCheck { strict: true });
+        let (mut ctx, (expected_channel, resolved_channel), input_ids) = context(&node);
+
+        let handle = node.validate_and_spawn("drift".into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        expected_channel.send(vec![hashed_mod("sodium", 200, "bbbb")]).unwrap();
+        resolved_channel.send(vec![hashed_mod("sodium", 200, "ffff")]).unwrap();
+        assert!(handle.join().is_err());
+    }
+}
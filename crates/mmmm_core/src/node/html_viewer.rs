@@ -0,0 +1,211 @@
+use std::{
+    collections::HashMap,
+    thread::{spawn, JoinHandle},
+};
+
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::di::container::{DiContainer, InputType, OutputType};
+
+use super::{
+    badge_writer::format_size,
+    config::{ChannelId, ChannelType, ModSource, NodeConfig, NodeDescription, NodeInitError, Port, ResolvedMod},
+    modlist_html_writer::{escape, project_url},
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct HtmlViewer {
+    /// Heading and title of the page.
+    #[serde(default = "default_title")]
+    pub title: String,
+}
+
+fn default_title() -> String {
+    "Mod list".to_owned()
+}
+
+impl Default for HtmlViewer {
+    fn default() -> Self {
+        HtmlViewer { title: default_title() }
+    }
+}
+
+/// Styles and script of the page, inlined so that the page is a single file with no requests to
+/// other sites.
+const STYLE: &str = "body { font-family: sans-serif; margin: 2em auto; max-width: 60em; padding: 0 1em; }
+input { box-sizing: border-box; font-size: 1em; margin-bottom: 1em; padding: 0.4em; width: 100%; }
+table { border-collapse: collapse; width: 100%; }
+th, td { border-bottom: 1px solid #ddd; padding: 0.4em; text-align: left; }
+td.size { text-align: right; white-space: nowrap; }
+";
+const SCRIPT: &str = "const search = document.getElementById('search');
+const rows = document.querySelectorAll('tbody tr');
+search.addEventListener('input', () => {
+  const query = search.value.toLowerCase();
+  rows.forEach(row => { row.hidden = !row.textContent.toLowerCase().includes(query); });
+});
+";
+
+impl NodeConfig for HtmlViewer {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut mods_channel = get_input!("mods", ResolvedMods, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Text, ctx)?;
+        let title = self.title.clone();
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "HtmlViewer", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let mut mods = mods_channel.blocking_recv().expect_or_log("Failed to receive on mods input");
+            mods.sort_by_key(|m| m.title.to_lowercase());
+
+            if out_channel.send(viewer_html(&title, &mods)).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::Text(channel(1).0))])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "HtmlViewer",
+            vec![Port::required("mods", ChannelType::ResolvedMods)],
+            vec![Port::required("default", ChannelType::Text)],
+        )
+    }
+}
+
+/// Write a standalone page with a searchable table of the mods, one row per line.
+fn viewer_html(title: &str, mods: &[ResolvedMod]) -> String {
+    let title = escape(title);
+    let rows = mods.iter().map(table_row).collect::<String>();
+    let summary = format!("{} mods, {} in total", mods.len(), format_size(mods.iter().map(|m| m.size).sum()));
+    format!(
+        "<!DOCTYPE html>\n\
+         <html lang=\"en\">\n\
+         <head>\n\
+         <meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{title}</title>\n\
+         <style>\n{STYLE}</style>\n\
+         </head>\n\
+         <body>\n\
+         <h1>{title}</h1>\n\
+         <p>{summary}</p>\n\
+         <input id=\"search\" type=\"search\" placeholder=\"Search mods\" aria-label=\"Search mods\">\n\
+         <table>\n\
+         <thead><tr><th>Title</th><th>Name</th><th>Side</th><th>Size</th><th>Source</th></tr></thead>\n\
+         <tbody>\n{rows}</tbody>\n\
+         </table>\n\
+         <script>\n{SCRIPT}</script>\n\
+         </body>\n\
+         </html>\n"
+    )
+}
+
+/// The title links to the mod's project page, and the source to the file itself.
+fn table_row(m: &ResolvedMod) -> String {
+    let source = match m.source {
+        Some(ModSource::Curse { .. }) => "CurseForge",
+        Some(ModSource::Modrinth { .. }) => "Modrinth",
+        Some(ModSource::Url) | None => "URL",
+    };
+    format!(
+        "<tr><td><a href=\"{}\">{}</a></td><td>{}</td><td>{}</td><td class=\"size\">{}</td><td><a href=\"{}\">{source}</a></td></tr>\n",
+        escape(&project_url(m)),
+        escape(&m.title),
+        escape(&m.name),
+        m.side,
+        format_size(m.size),
+        escape(&m.src),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        node::{
+            config::{NodeConfigTypes, Side},
+            utils::{get_output_test, read_channel, test_mod},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_html_viewer() {
+        let node_id = "viewer";
+        let mods_channel = channel(1).0;
+        let input_ids = HashMap::from([("mods".into(), ChannelId::from_str("mod-source").unwrap())]);
+        let node = NodeConfigTypes::HtmlViewer(HtmlViewer {
+            title: "Pack & Friends".into(),
+        });
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("mod-source").unwrap(),
+                InputType::ResolvedMods(mods_channel.clone()),
+            )]))
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Text, ctx);
+
+        let mods = vec![
+            ResolvedMod {
+                title: "Sodium".into(),
+                side: Side::Client,
+                size: 1536 * 1024,
+                source: Some(ModSource::Modrinth {
+                    project_id: "AANobbMI".into(),
+                    version_id: "b4hTi3mo".into(),
+                }),
+                ..test_mod("sodium")
+            },
+            ResolvedMod {
+                title: "Tom's <Storage>".into(),
+                size: 512,
+                ..test_mod("toms-storage")
+            },
+        ];
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        mods_channel.send(mods).unwrap();
+        handle.join().unwrap();
+
+        let html = read_channel(&mut out_channel, Duration::from_secs(5)).unwrap();
+        assert!(html.contains("<title>Pack &amp; Friends</title>"));
+        assert!(html.contains("<p>2 mods, 1.5 MiB in total</p>"));
+        let rows = html.lines().filter(|line| line.starts_with("<tr><td>")).collect::<Vec<_>>();
+        assert_eq!(
+            rows,
+            [
+                "<tr><td><a href=\"https://modrinth.com/project/AANobbMI\">Sodium</a></td><td>sodium</td><td>client</td>\
+                 <td class=\"size\">1.5 MiB</td><td><a href=\"https://example.com/sodium.jar\">Modrinth</a></td></tr>",
+                "<tr><td><a href=\"https://example.com/toms-storage.jar\">Tom's &lt;Storage&gt;</a></td><td>toms-storage</td>\
+                 <td>both</td><td class=\"size\">512 B</td><td><a href=\"https://example.com/toms-storage.jar\">URL</a></td></tr>",
+            ]
+        );
+        // The page is self-contained
+        assert!(!html.contains(" src="));
+        assert!(!html.contains("<link"));
+    }
+}
@@ -0,0 +1,265 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    thread::{spawn, JoinHandle},
+};
+
+use rayon::prelude::*;
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::di::container::{DiContainer, InputType, OutputType};
+
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port, ResolvedMod},
+    dependency_graph::{file_dependencies, project, Project, Relation},
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct IncompatibilityResolver {
+    /// Which mod of an incompatible pair stays in the pack.
+    pub keep: KeepPolicy,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum KeepPolicy {
+    /// Keep the mod listed first in the input.
+    First,
+    /// Keep the mod marked as required, or the one listed first if both or neither are.
+    Required,
+}
+
+impl NodeConfig for IncompatibilityResolver {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut mods_channel = get_input!("mods", ResolvedMods, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), ResolvedMods, ctx)?;
+        let report_channel = get_output!(ChannelId(node_id.clone(), "report".into()), Text, ctx)?;
+        // Incompatibilities are not cached, so they can never be looked up offline.
+        if ctx.is_offline() {
+            return Err(NodeInitError::Offline);
+        }
+        let keep = self.keep;
+        let curse_client = ctx.get_curse_client();
+        let modrinth_client = ctx.get_modrinth_client();
+        let pool = ctx.get_pool();
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "IncompatibilityResolver", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let mods = mods_channel.blocking_recv().expect_or_log("Failed to receive on mods input");
+            event!(Level::INFO, "Looking up incompatibilities of {} mods", mods.len());
+
+            let incompatibilities: Vec<Vec<Project>> = pool.install(|| {
+                mods.par_iter()
+                    .map(|m| {
+                        file_dependencies(m, &curse_client, &modrinth_client)
+                            .unwrap_or_else(|e| {
+                                event!(Level::WARN, "Could not determine incompatibilities of {}: {}", m.name, e);
+                                vec![]
+                            })
+                            .into_iter()
+                            .filter(|(_, relation)| *relation == Relation::Incompatible)
+                            .map(|(project, _)| project)
+                            .collect()
+                    })
+                    .collect()
+            });
+
+            let (dropped, lines) = resolve(&mods, &incompatibilities, keep);
+            let report = match lines.is_empty() {
+                true => format!("Found no incompatible mods among {} mods.\n", mods.len()),
+                false => format!(
+                    "Dropped {} of {} mods to resolve incompatibilities:\n{}\n",
+                    dropped.len(),
+                    mods.len(),
+                    lines.join("\n")
+                ),
+            };
+            let kept = mods
+                .into_iter()
+                .enumerate()
+                .filter(|(i, _)| !dropped.contains(i))
+                .map(|(_, m)| m)
+                .collect::<Vec<_>>();
+
+            if out_channel.send(kept).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+            if report_channel.send(report).is_err() {
+                event!(Level::DEBUG, "Channel 'report' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([
+            (ChannelId(node_id.to_owned(), "default".into()), InputType::ResolvedMods(channel(1).0)),
+            (ChannelId(node_id.to_owned(), "report".into()), InputType::Text(channel(1).0)),
+        ])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "IncompatibilityResolver",
+            vec![Port::required("mods", ChannelType::ResolvedMods)],
+            vec![
+                Port::required("default", ChannelType::ResolvedMods),
+                Port::required("report", ChannelType::Text),
+            ],
+        )
+    }
+}
+
+/// Decide which mods to drop, given the projects each mod declares itself incompatible with.
+/// Pairs are settled in the order their first mod is listed, and pairs with a mod that was
+/// already dropped need no settling. Returns the indices of the dropped mods, and a line of the
+/// report for each.
+fn resolve(mods: &[ResolvedMod], incompatibilities: &[Vec<Project>], keep: KeepPolicy) -> (BTreeSet<usize>, Vec<String>) {
+    let in_pack: HashMap<Project, usize> = mods.iter().enumerate().filter_map(|(i, m)| Some((project(m)?, i))).collect();
+    // Mods in each incompatible pair, by index, with the mods that declared it
+    let mut pairs: BTreeMap<(usize, usize), BTreeSet<usize>> = BTreeMap::new();
+    for (i, projects) in incompatibilities.iter().enumerate() {
+        for j in projects.iter().filter_map(|project| in_pack.get(project)).filter(|j| **j != i) {
+            pairs.entry((i.min(*j), i.max(*j))).or_default().insert(i);
+        }
+    }
+
+    let mut dropped = BTreeSet::new();
+    let mut lines = vec![];
+    for ((first, second), declared_by) in pairs {
+        if dropped.contains(&first) || dropped.contains(&second) {
+            continue;
+        }
+        let (winner, loser, reason) = match (keep, mods[first].required, mods[second].required) {
+            (KeepPolicy::Required, false, true) => (second, first, "which is required"),
+            (KeepPolicy::Required, true, false) => (first, second, "which is required"),
+            (KeepPolicy::Required, true, true) => (first, second, "which is listed first, as both are required"),
+            (KeepPolicy::Required, false, false) => (first, second, "which is listed first, as neither is required"),
+            (KeepPolicy::First, _, _) => (first, second, "which is listed first"),
+        };
+        let (winner_name, loser_name) = (&mods[winner].name, &mods[loser].name);
+        let declared_by = declared_by.iter().map(|i| mods[*i].name.as_str()).collect::<Vec<_>>().join(" and ");
+        let mut line = format!("- {loser_name}: incompatible with {winner_name} (declared by {declared_by}), kept {winner_name}, {reason}");
+        if mods[loser].required {
+            event!(Level::WARN, "Dropped required mod {loser_name}, which is incompatible with {winner_name}");
+            line.push_str(". The dropped mod was required");
+        }
+        lines.push(line);
+        dropped.insert(loser);
+    }
+    (dropped, lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        node::{
+            config::{ModSource, NodeConfigTypes},
+            utils::{get_output_test, read_channel, serve_http, test_mod},
+        },
+    };
+
+    use super::*;
+
+    fn modrinth(name: &str, required: bool) -> ResolvedMod {
+        ResolvedMod {
+            required,
+            source: Some(ModSource::Modrinth {
+                project_id: format!("{name}-id"),
+                version_id: format!("{name}-v1"),
+            }),
+            ..test_mod(name)
+        }
+    }
+
+    fn version(name: &str, incompatible: &[&str]) -> Vec<u8> {
+        let dependencies = incompatible
+            .iter()
+            .map(|project| format!(r#"{{"project_id":"{project}-id","dependency_type":"incompatible"}}"#))
+            .collect::<Vec<_>>()
+            .join(",");
+        format!(
+            r#"{{"name":"v","version_number":"1","game_versions":[],"version_type":"release","loaders":[],"id":"{name}-v1","project_id":"{name}-id","files":[],"date_published":"2023-01-01T00:00:00Z","dependencies":[{dependencies}]}}"#
+        )
+        .into_bytes()
+    }
+
+    #[test]
+    fn test_incompatibility_resolver() {
+        let node_id = "incompatibilities";
+        let base_url = serve_http(HashMap::from([
+            ("/v2/version/optifine-v1".to_owned(), (200, version("optifine", &[]))),
+            ("/v2/version/sodium-v1".to_owned(), (200, version("sodium", &["optifine"]))),
+            ("/v2/version/jei-v1".to_owned(), (200, version("jei", &["missing"]))),
+        ]));
+        let mods_channel = channel(1).0;
+        let input_ids = HashMap::from([("mods".into(), ChannelId::from_str("mod-source").unwrap())]);
+        let node = NodeConfigTypes::IncompatibilityResolver(IncompatibilityResolver { keep: KeepPolicy::Required });
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("mod-source").unwrap(),
+                InputType::ResolvedMods(mods_channel.clone()),
+            )]))
+            .modrinth_base_url(&base_url)
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), ResolvedMods, ctx);
+        let mut report_channel = get_output_test!(ChannelId::from_str("incompatibilities::report").unwrap(), Text, ctx);
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        // The required mod is kept, even though it is listed second
+        mods_channel
+            .send(vec![modrinth("optifine", false), modrinth("sodium", true), modrinth("jei", true)])
+            .unwrap();
+        handle.join().unwrap();
+
+        let timeout = Duration::from_secs(5);
+        let kept = read_channel(&mut out_channel, timeout).unwrap();
+        assert_eq!(kept.iter().map(|m| m.name.as_str()).collect::<Vec<_>>(), ["sodium", "jei"]);
+        assert_eq!(
+            read_channel(&mut report_channel, timeout).unwrap(),
+            "Dropped 1 of 3 mods to resolve incompatibilities:\n\
+             - optifine: incompatible with sodium (declared by sodium), kept sodium, which is required\n"
+        );
+    }
+
+    #[test]
+    fn resolve_pairs() {
+        let project = |name: &str| Project::Modrinth(format!("{name}-id"));
+        let mods = [modrinth("a", true), modrinth("b", true), modrinth("c", false)];
+        // a and b declare each other incompatible, as do b and c
+        let incompatibilities = [vec![project("b")], vec![project("a"), project("c")], vec![]];
+
+        // Dropping b settles both pairs, and c stays
+        let (dropped, lines) = resolve(&mods, &incompatibilities, KeepPolicy::First);
+        assert_eq!(dropped, BTreeSet::from([1]));
+        assert_eq!(
+            lines,
+            ["- b: incompatible with a (declared by a and b), kept a, which is listed first. The dropped mod was required"]
+        );
+
+        let (dropped, lines) = resolve(&mods, &incompatibilities, KeepPolicy::Required);
+        assert_eq!(dropped, BTreeSet::from([1]));
+        assert!(lines[0].ends_with("kept a, which is listed first, as both are required. The dropped mod was required"));
+
+        let (dropped, _) = resolve(&mods, &[vec![], vec![], vec![]], KeepPolicy::First);
+        assert!(dropped.is_empty());
+    }
+}
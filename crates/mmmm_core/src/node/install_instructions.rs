@@ -0,0 +1,237 @@
+use std::{
+    collections::HashMap,
+    thread::{spawn, JoinHandle},
+};
+
+use api_client::loader::ModLoader;
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::{
+    di::container::{DiContainer, InputType, OutputType},
+    template::render,
+};
+
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port, ResolvedMod, Side},
+    utils::{get_input, get_output, parse_loader},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Default)]
+pub struct InstallInstructions {
+    /// Template to render instead of [`DEFAULT_TEMPLATE`].
+    pub template: Option<String>,
+}
+
+/// Markdown install guide used unless a template is configured.
+pub const DEFAULT_TEMPLATE: &str = "# Installation
+
+This pack runs on Minecraft ${config.minecraft_version} with ${loader.name}.
+
+## 1. Install ${loader.name}
+
+${loader.install}
+
+## 2. Add the mods
+
+Copy the ${mods.count} mods of the pack into the `mods/` folder of your game directory, creating it if it does not exist yet.
+Copy the pack's other folders, such as `config/`, into the game directory as well, replacing any files already there.
+
+## 3. Launch the game
+
+Select the ${loader.name} profile or instance in your launcher and start the game.
+Most packs need at least 4 GB of memory, which can be set in the launcher's Java arguments as `-Xmx4G`.
+
+## Mods
+
+${mods.list}
+";
+
+impl NodeConfig for InstallInstructions {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut mods_channel = get_input!("mods", ResolvedMods, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Text, ctx)?;
+        let config = |key: &str| ctx.get_config(key).ok_or_else(|| NodeInitError::MissingConfig(key.into()));
+        let minecraft_version = config("minecraft_version")?;
+        let modloader = parse_loader("modloader", &config("modloader")?)?;
+        let loader = Loader::new(modloader, &minecraft_version, ctx.get_config("modloader_version").as_deref());
+        let template = self.template.clone().unwrap_or_else(|| DEFAULT_TEMPLATE.to_owned());
+        let configs = ctx.get_configs();
+        // Check every placeholder can be resolved before any mod is resolved
+        render(&template, |key| lookup(key, &configs, &loader, &[]), true).map_err(|e| NodeInitError::InvalidOption {
+            option: "template".into(),
+            reason: e.to_string(),
+        })?;
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "InstallInstructions", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let mods = mods_channel.blocking_recv().expect_or_log("Failed to receive on mods input");
+
+            let instructions =
+                render(&template, |key| lookup(key, &configs, &loader, &mods), true).expect_or_log("Failed to render install instructions");
+
+            if out_channel.send(instructions).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::Text(channel(1).0))])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "InstallInstructions",
+            vec![Port::required("mods", ChannelType::ResolvedMods)],
+            vec![Port::required("default", ChannelType::Text)],
+        )
+        .with_config_keys(vec!["minecraft_version", "modloader"])
+    }
+}
+
+/// Display name of a mod loader, and instructions for installing it.
+struct Loader {
+    name: &'static str,
+    install: String,
+}
+
+impl Loader {
+    fn new(modloader: ModLoader, minecraft_version: &str, loader_version: Option<&str>) -> Loader {
+        let version = |name: &str, latest: &str| match loader_version {
+            Some(version) => format!("{name} {version}"),
+            None => latest.to_owned(),
+        };
+        match modloader {
+            ModLoader::Forge => Loader {
+                name: "Forge",
+                install: format!(
+                    "Download the installer for {} for Minecraft {minecraft_version} from https://files.minecraftforge.net/, run it, and choose *Install client*.\n\
+                     This adds a Forge profile to the official Minecraft launcher. Launchers such as Prism Launcher can install Forge for an instance instead.",
+                    version("Forge", "the latest Forge release")
+                ),
+            },
+            ModLoader::NeoForge => Loader {
+                name: "NeoForge",
+                install: format!(
+                    "Download the installer for {} from https://neoforged.net/, run it, and choose *Install client*.\n\
+                     This adds a NeoForge profile to the official Minecraft launcher. Launchers such as Prism Launcher can install NeoForge for an instance instead.",
+                    version("NeoForge", "the latest NeoForge release")
+                ),
+            },
+            ModLoader::Fabric => Loader {
+                name: "Fabric",
+                install: format!(
+                    "Download the Fabric installer from https://fabricmc.net/use/installer/ and run it.\n\
+                     Select Minecraft {minecraft_version} and {}, then choose *Install*. This adds a Fabric profile to the official Minecraft launcher.",
+                    version("loader version", "the latest loader version")
+                ),
+            },
+            ModLoader::Quilt => Loader {
+                name: "Quilt",
+                install: format!(
+                    "Download the Quilt installer from https://quiltmc.org/install/ and run it.\n\
+                     Select Minecraft {minecraft_version} and {}, then choose *Install*. This adds a Quilt profile to the official Minecraft launcher.",
+                    version("loader version", "the latest loader version")
+                ),
+            },
+        }
+    }
+}
+
+/// Resolve a template placeholder: `config.*` keys from the pack config, `loader.name` and
+/// `loader.install` from the mod loader, and `mods.count` and `mods.list` from the mods.
+fn lookup(key: &str, configs: &HashMap<String, String>, loader: &Loader, mods: &[ResolvedMod]) -> Option<String> {
+    match key {
+        "loader.name" => Some(loader.name.to_owned()),
+        "loader.install" => Some(loader.install.clone()),
+        "mods.count" => Some(mods.len().to_string()),
+        "mods.list" => {
+            let mut lines = mods
+                .iter()
+                .map(|m| match m.side {
+                    Side::Both => format!("- {}", m.title),
+                    side => format!("- {} ({side} only)", m.title),
+                })
+                .collect::<Vec<_>>();
+            lines.sort_by_key(|line| line.to_lowercase());
+            Some(lines.join("\n"))
+        },
+        _ => key.strip_prefix("config.").and_then(|k| configs.get(k).cloned()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use tokio::sync::broadcast;
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel, test_mod},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_install_instructions() {
+        let node_id = "install";
+        let mods_channel = broadcast::channel(1).0;
+        let input_ids = HashMap::from([("mods".into(), ChannelId::from_str("mod-source").unwrap())]);
+        let builder = || {
+            DiContainerBuilder::default()
+                .set_config("minecraft_version", "1.20.1")
+                .set_config("modloader", "fabric")
+                .set_config("name", "My Pack")
+                .channel_from_node(HashMap::from([(
+                    ChannelId::from_str("mod-source").unwrap(),
+                    InputType::ResolvedMods(mods_channel.clone()),
+                )]))
+        };
+        let node = NodeConfigTypes::InstallInstructions(InstallInstructions::default());
+        let mut ctx = builder().channel_from_node(node.generate_channels(node_id)).build();
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Text, ctx);
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        let sodium = ResolvedMod {
+            title: "Sodium".into(),
+            side: Side::Client,
+            ..test_mod("sodium")
+        };
+        mods_channel.send(vec![test_mod("lithium"), sodium]).unwrap();
+
+        let instructions = read_channel(&mut out_channel, Duration::from_secs(5)).unwrap();
+        assert!(instructions.contains("This pack runs on Minecraft 1.20.1 with Fabric."));
+        assert!(instructions.contains("Select Minecraft 1.20.1 and the latest loader version, then choose *Install*."));
+        assert!(instructions.contains("Copy the 2 mods of the pack into the `mods/` folder"));
+        assert!(instructions.ends_with("## Mods\n\n- lithium\n- Sodium (client only)\n"));
+        handle.join().unwrap();
+
+        // Templates are checked when the node is spawned
+        let custom = |template: &str| {
+            let node = NodeConfigTypes::InstallInstructions(InstallInstructions {
+                template: Some(template.into()),
+            });
+            let ctx = builder().channel_from_node(node.generate_channels(node_id)).build();
+            node.validate_and_spawn(node_id.into(), &input_ids, &ctx).map(|_| ())
+        };
+        assert!(custom("# ${config.name}\n\n${loader.install}").is_ok());
+        assert!(matches!(custom("${config.missing}"), Err(NodeInitError::InvalidOption { .. })));
+    }
+}
@@ -0,0 +1,300 @@
+use std::{
+    collections::HashMap,
+    io::{Cursor, Read, Write},
+    thread::{spawn, JoinHandle},
+};
+
+use glob_match::glob_match;
+use rayon::prelude::*;
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::{OptionExt, ResultExt};
+use zip::{read::ZipArchive, write::FileOptions, CompressionMethod, ZipWriter};
+
+use crate::{
+    di::container::{DiContainer, InputType, OutputType},
+    file::filepath::FilePath,
+};
+
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port},
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Default)]
+pub struct JarRecompress {
+    /// Glob patterns selecting the jars to recompress.
+    pub patterns: Vec<String>,
+    /// Glob patterns of entries to remove from each jar, matched against paths inside the jar.
+    #[serde(default)]
+    pub strip_entries: Vec<String>,
+}
+
+const MANIFEST: &str = "META-INF/MANIFEST.MF";
+
+/// Result of recompressing a single jar.
+enum Outcome {
+    /// The recompressed jar, and the number of entries stripped from it.
+    Smaller(Vec<u8>, usize),
+    /// Recompressing did not make the jar smaller.
+    NotSmaller,
+    /// The jar is signed, so it is left untouched.
+    Signed,
+}
+
+impl NodeConfig for JarRecompress {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        if self.patterns.is_empty() {
+            return Err(NodeInitError::InvalidOption {
+                option: "patterns".into(),
+                reason: "at least one pattern must be given".into(),
+            });
+        }
+        if let Some(pattern) = self.strip_entries.iter().find(|pattern| glob_match(pattern, MANIFEST)) {
+            return Err(NodeInitError::InvalidOption {
+                option: "strip_entries".into(),
+                reason: format!("'{pattern}' would strip {MANIFEST}, which every jar needs"),
+            });
+        }
+        let mut files_channel = get_input!("files", Files, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Files, ctx)?;
+        let patterns = self.patterns.clone();
+        let strip_entries = self.strip_entries.clone();
+        let pool = ctx.get_pool();
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "JarRecompress", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let mut files = files_channel.blocking_recv().expect_or_log("Failed to receive on files input");
+
+            let jars = files
+                .list_files()
+                .into_iter()
+                .filter(|path| path.glob_match(&patterns))
+                .cloned()
+                .collect::<Vec<FilePath>>();
+            let results: Vec<(FilePath, usize, Result<Outcome, String>)> = pool.install(|| {
+                jars.into_par_iter()
+                    .map(|path| {
+                        let data = files.get_file(&path).expect_or_log("File listed in tree is missing from store");
+                        let outcome = recompress(&data, &strip_entries);
+                        (path, data.len(), outcome)
+                    })
+                    .collect()
+            });
+
+            let (mut recompressed, mut original_bytes, mut new_bytes) = (0, 0, 0);
+            for (path, size, outcome) in results {
+                match outcome {
+                    Ok(Outcome::Smaller(data, stripped)) => {
+                        event!(
+                            Level::DEBUG,
+                            "Recompressed {path} from {size} to {} bytes, stripping {stripped} entries",
+                            data.len()
+                        );
+                        recompressed += 1;
+                        original_bytes += size;
+                        new_bytes += data.len();
+                        files.add_file(path, data);
+                    },
+                    Ok(Outcome::NotSmaller) => event!(Level::DEBUG, "Kept {path}, which recompressing does not make smaller"),
+                    Ok(Outcome::Signed) => event!(Level::INFO, "Kept {path} untouched, as it is signed"),
+                    Err(e) => event!(Level::WARN, "Kept {path}, which could not be recompressed: {e}"),
+                }
+            }
+            event!(
+                Level::INFO,
+                "Recompressed {recompressed} jars from {original_bytes} to {new_bytes} bytes, saving {} bytes",
+                original_bytes - new_bytes
+            );
+
+            if out_channel.send(files).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::Files(channel(1).0))])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "JarRecompress",
+            vec![Port::required("files", ChannelType::Files)],
+            vec![Port::required("default", ChannelType::Files)],
+        )
+    }
+}
+
+/// Whether a jar entry is part of a signature, whose digests would no longer match if entries
+/// were stripped.
+fn is_signature(name: &str) -> bool {
+    let Some(file) = name.strip_prefix("META-INF/") else {
+        return false;
+    };
+    let upper = file.to_uppercase();
+    !upper.contains('/') && (upper.starts_with("SIG-") || [".SF", ".RSA", ".DSA", ".EC"].iter().any(|ext| upper.ends_with(ext)))
+}
+
+/// Order entries as the JDK expects: the `META-INF/` directory and manifest first, then the rest
+/// of `META-INF/`, then every other entry in its original order.
+fn entry_rank(name: &str) -> u8 {
+    match name {
+        "META-INF/" => 0,
+        _ if name.eq_ignore_ascii_case(MANIFEST) => 1,
+        _ if name.starts_with("META-INF/") => 2,
+        _ => 3,
+    }
+}
+
+/// Rewrite a jar with every entry deflated at the highest level, leaving out entries matching
+/// `strip_entries`. Entry contents, modification times and permissions are kept.
+fn recompress(data: &[u8], strip_entries: &[String]) -> Result<Outcome, String> {
+    let mut archive = ZipArchive::new(Cursor::new(data)).map_err(|e| e.to_string())?;
+    let mut entries = (0..archive.len())
+        .map(|i| Ok((i, archive.by_index_raw(i).map_err(|e| e.to_string())?.name().to_owned())))
+        .collect::<Result<Vec<_>, String>>()?;
+    if entries.iter().any(|(_, name)| is_signature(name)) {
+        return Ok(Outcome::Signed);
+    }
+    entries.sort_by_key(|(i, name)| (entry_rank(name), *i));
+
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    let mut stripped = 0;
+    for (i, name) in entries {
+        if strip_entries.iter().any(|pattern| glob_match(pattern, &name)) {
+            stripped += 1;
+            continue;
+        }
+        let mut entry = archive.by_index(i).map_err(|e| e.to_string())?;
+        let mut options = FileOptions::default()
+            .compression_method(CompressionMethod::Deflated)
+            .compression_level(Some(9))
+            .last_modified_time(entry.last_modified());
+        if let Some(mode) = entry.unix_mode() {
+            options = options.unix_permissions(mode);
+        }
+        if entry.is_dir() {
+            writer.add_directory(name, options).map_err(|e| e.to_string())?;
+            continue;
+        }
+        let mut contents = Vec::with_capacity(entry.size() as usize);
+        entry.read_to_end(&mut contents).map_err(|e| e.to_string())?;
+        writer
+            .start_file(name, options.large_file(contents.len() >= u32::MAX as usize))
+            .map_err(|e| e.to_string())?;
+        writer.write_all(&contents).map_err(|e| e.to_string())?;
+    }
+    let recompressed = writer.finish().map_err(|e| e.to_string())?.into_inner();
+    match recompressed.len() < data.len() {
+        true => Ok(Outcome::Smaller(recompressed, stripped)),
+        false => Ok(Outcome::NotSmaller),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        file::{filestore::FileStore, filetree::FileTree},
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel},
+        },
+    };
+
+    use super::*;
+
+    /// Build a jar with every entry stored uncompressed.
+    fn jar(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = FileOptions::default().compression_method(CompressionMethod::Stored);
+        for (name, contents) in entries {
+            writer.start_file(*name, options).unwrap();
+            writer.write_all(contents).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_jar_recompress() {
+        let node_id = "recompress";
+        let files_channel = channel(1).0;
+        let input_ids = HashMap::from([("files".into(), ChannelId::from_str("pack-files").unwrap())]);
+        let node = NodeConfigTypes::JarRecompress(JarRecompress {
+            patterns: vec!["mods/*.jar".into()],
+            strip_entries: vec![".git/**".into()],
+        });
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("pack-files").unwrap(),
+                InputType::Files(files_channel.clone()),
+            )]))
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Files, ctx);
+
+        let class = "public class Example {}\n".repeat(200).into_bytes();
+        let unsigned = jar(&[
+            ("com/example/Example.class", &class),
+            (".git/config", b"[core]"),
+            (MANIFEST, b"Manifest-Version: 1.0\n"),
+        ]);
+        let signed = jar(&[
+            (MANIFEST, b"Manifest-Version: 1.0\n"),
+            ("META-INF/CERT.SF", b""),
+            ("com/example/Example.class", &class),
+        ]);
+        let mut tree = FileTree::new(FileStore::new());
+        tree.add_file(FilePath::from_str("mods/example.jar").unwrap(), unsigned.clone());
+        tree.add_file(FilePath::from_str("mods/signed.jar").unwrap(), signed.clone());
+        tree.add_file(FilePath::from_str("resourcepacks/example.jar").unwrap(), unsigned.clone());
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        files_channel.send(tree).unwrap();
+        handle.join().unwrap();
+
+        let files = read_channel(&mut out_channel, Duration::from_secs(5)).unwrap();
+        let file = |path: &str| files.get_file(&FilePath::from_str(path).unwrap()).unwrap().to_vec();
+
+        let recompressed = file("mods/example.jar");
+        assert!(recompressed.len() < unsigned.len());
+        let mut archive = ZipArchive::new(Cursor::new(recompressed)).unwrap();
+        assert_eq!(archive.len(), 2);
+        assert_eq!(archive.by_index(0).unwrap().name(), MANIFEST);
+        let mut contents = vec![];
+        archive.by_name("com/example/Example.class").unwrap().read_to_end(&mut contents).unwrap();
+        assert_eq!(contents, class);
+
+        assert_eq!(file("mods/signed.jar"), signed);
+        assert_eq!(file("resourcepacks/example.jar"), unsigned);
+    }
+
+    #[test]
+    fn manifest_cannot_be_stripped() {
+        let node = NodeConfigTypes::JarRecompress(JarRecompress {
+            patterns: vec!["mods/*.jar".into()],
+            strip_entries: vec!["META-INF/**".into()],
+        });
+        let ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels("recompress"))
+            .build();
+        let result = node.validate_and_spawn("recompress".into(), &HashMap::new(), &ctx);
+        assert!(matches!(result, Err(NodeInitError::InvalidOption { option, .. }) if option == "strip_entries"));
+    }
+}
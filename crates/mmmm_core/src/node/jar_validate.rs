@@ -0,0 +1,191 @@
+use std::{
+    collections::HashMap,
+    io::Cursor,
+    thread::{spawn, JoinHandle},
+};
+
+use rayon::prelude::*;
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+use zip::read::ZipArchive;
+
+use crate::di::container::{DiContainer, InputType, OutputType};
+
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port},
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Default)]
+pub struct JarValidate {
+    /// Fail if any jar is malformed, instead of only reporting it.
+    #[serde(default)]
+    pub strict: bool,
+}
+
+impl NodeConfig for JarValidate {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut files_channel = get_input!("files", Files, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Files, ctx)?;
+        let report_channel = get_output!(ChannelId(node_id.clone(), "report".into()), Text, ctx)?;
+        let strict = self.strict;
+        let pool = ctx.get_pool();
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "JarValidate", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let files = files_channel.blocking_recv().expect_or_log("Failed to receive on files input");
+
+            let jars = files
+                .list_files()
+                .into_iter()
+                .filter(|path| path.get_filename().to_lowercase().ends_with(".jar"))
+                .collect::<Vec<_>>();
+            let mut failures: Vec<String> = pool.install(|| {
+                jars.par_iter()
+                    .filter_map(|path| {
+                        let data = files.get_file(path)?;
+                        let error = validate(&data).err()?;
+                        Some(format!("- {path}: {error}"))
+                    })
+                    .collect()
+            });
+            failures.sort();
+
+            let report = if failures.is_empty() {
+                format!("Checked {} jars, all valid.\n", jars.len())
+            } else {
+                format!("Checked {} jars, {} malformed:\n{}\n", jars.len(), failures.len(), failures.join("\n"))
+            };
+
+            if !failures.is_empty() {
+                event!(Level::WARN, "{report}");
+                if strict {
+                    panic!("Malformed jars found in strict mode");
+                }
+            }
+
+            if out_channel.send(files).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+
+            if report_channel.send(report).is_err() {
+                event!(Level::DEBUG, "Channel 'report' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([
+            (ChannelId(node_id.to_owned(), "default".into()), InputType::Files(channel(1).0)),
+            (ChannelId(node_id.to_owned(), "report".into()), InputType::Text(channel(1).0)),
+        ])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "JarValidate",
+            vec![Port::required("files", ChannelType::Files)],
+            vec![Port::required("default", ChannelType::Files), Port::required("report", ChannelType::Text)],
+        )
+    }
+}
+
+/// Check that a jar is a well-formed ZIP file: its central directory can be read, and the local
+/// header and data of every entry lie within the file. Entries are not decompressed.
+fn validate(data: &[u8]) -> Result<(), String> {
+    let mut archive = ZipArchive::new(Cursor::new(data)).map_err(|e| e.to_string())?;
+    if archive.is_empty() {
+        return Err("archive has no entries".to_owned());
+    }
+    for i in 0..archive.len() {
+        // Reading an entry raw parses its local header without decompressing it
+        let entry = archive.by_index_raw(i).map_err(|e| format!("entry {i}: {e}"))?;
+        if entry.data_start() + entry.compressed_size() > data.len() as u64 {
+            return Err(format!("entry '{}' is truncated", entry.name()));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Write, str::FromStr, time::Duration};
+
+    use zip::{write::FileOptions, ZipWriter};
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        file::{filepath::FilePath, filestore::FileStore, filetree::FileTree},
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_jar_validate() {
+        let node_id = "validate";
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer.start_file("META-INF/MANIFEST.MF", FileOptions::default()).unwrap();
+        writer.write_all(b"Manifest-Version: 1.0\n").unwrap();
+        writer.start_file("com/example/Example.class", FileOptions::default()).unwrap();
+        writer.write_all(&[0xca, 0xfe, 0xba, 0xbe].repeat(100)).unwrap();
+        let jar = writer.finish().unwrap().into_inner();
+        // Only the central directory of an archive is at its end, so corrupt an entry's data
+        // offset instead to check entries are validated
+        let mut bad_offset = jar.clone();
+        let central_directory = bad_offset.windows(4).rposition(|w| w == [0x50, 0x4b, 0x01, 0x02]).unwrap();
+        bad_offset[central_directory + 42..central_directory + 46].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        let mut tree = FileTree::new(FileStore::new());
+        for (path, contents) in [
+            ("mods/valid.jar", jar.clone()),
+            ("mods/truncated.jar", jar[..jar.len() / 2].to_vec()),
+            ("mods/bad-offset.JAR", bad_offset),
+            ("mods/not-a-zip.jar", b"<html>Not found</html>".to_vec()),
+            ("config/notes.txt", b"not a jar".to_vec()),
+        ] {
+            tree.add_file(FilePath::from_str(path).unwrap(), contents);
+        }
+        let files_channel = channel(1).0;
+        let input_ids = HashMap::from([("files".into(), ChannelId::from_str("file-source").unwrap())]);
+        let node = NodeConfigTypes::JarValidate(JarValidate::default());
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("file-source").unwrap(),
+                InputType::Files(files_channel.clone()),
+            )]))
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Files, ctx);
+        let mut report_channel = get_output_test!(ChannelId::from_str("validate::report").unwrap(), Text, ctx);
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        files_channel.send(tree).unwrap();
+        handle.join().unwrap();
+
+        let timeout = Duration::from_secs(5);
+        assert_eq!(read_channel(&mut out_channel, timeout).unwrap().list_files().len(), 5);
+        let report = read_channel(&mut report_channel, timeout).unwrap();
+        assert!(report.starts_with("Checked 4 jars, 3 malformed:\n- mods/bad-offset.JAR: "));
+        assert!(report.contains("\n- mods/not-a-zip.jar: "));
+        assert!(report.contains("\n- mods/truncated.jar: "));
+        assert!(!report.contains("valid.jar"));
+    }
+}
@@ -0,0 +1,165 @@
+use std::{
+    collections::HashMap,
+    thread::{spawn, JoinHandle},
+};
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::{OptionExt, ResultExt};
+
+use crate::di::container::{DiContainer, InputType, OutputType};
+
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port},
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct JsonCanonicalize;
+
+impl NodeConfig for JsonCanonicalize {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut files_channel = get_input!("files", Files, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Files, ctx)?;
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "JsonCanonicalize", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let mut files = files_channel.blocking_recv().expect_or_log("Failed to receive on files input");
+
+            let mut paths: Vec<_> = files
+                .list_files()
+                .into_iter()
+                .filter(|path| path.get_filename().to_lowercase().ends_with(".json"))
+                .cloned()
+                .collect();
+            paths.sort_by_key(|path| path.to_string());
+            let mut changed = 0;
+            for path in paths {
+                let data = files.get_file(&path).expect_or_log("File listed in tree is missing from store");
+                let canonical = match canonicalize(&data) {
+                    Ok(canonical) => canonical,
+                    Err(e) => {
+                        event!(Level::WARN, "Skipping {path}, which is not valid JSON: {e}");
+                        continue;
+                    },
+                };
+                if canonical.as_bytes() != &data[..] {
+                    changed += 1;
+                    files.add_file(path, canonical.into_bytes());
+                }
+            }
+            event!(Level::INFO, "Canonicalized {changed} JSON files");
+
+            if out_channel.send(files).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::Files(channel(1).0))])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "JsonCanonicalize",
+            vec![Port::required("files", ChannelType::Files)],
+            vec![Port::required("default", ChannelType::Files)],
+        )
+    }
+}
+
+/// Re-serialize a JSON document with the keys of every object sorted, indented by two spaces and
+/// ending in a newline. A leading byte order mark is dropped.
+fn canonicalize(data: &[u8]) -> Result<String, String> {
+    let text = std::str::from_utf8(data).map_err(|_| "not valid UTF-8".to_owned())?;
+    let json: Value = serde_json::from_str(text.trim_start_matches('\u{feff}')).map_err(|e| e.to_string())?;
+    let mut canonical = serde_json::to_string_pretty(&sort_keys(json)).expect_or_log("Serialization of JSON value failed");
+    canonical.push('\n');
+    Ok(canonical)
+}
+
+/// Sort the keys of every object nested in `json`.
+fn sort_keys(json: Value) -> Value {
+    match json {
+        Value::Object(map) => {
+            let mut entries = map.into_iter().collect::<Vec<_>>();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Value::Object(entries.into_iter().map(|(key, value)| (key, sort_keys(value))).collect::<Map<_, _>>())
+        },
+        Value::Array(values) => Value::Array(values.into_iter().map(sort_keys).collect()),
+        value => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        file::{filepath::FilePath, filestore::FileStore, filetree::FileTree},
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_json_canonicalize() {
+        let node_id = "canonicalize";
+        let files_channel = channel(1).0;
+        let input_ids = HashMap::from([("files".into(), ChannelId::from_str("file-source").unwrap())]);
+        let node = NodeConfigTypes::JsonCanonicalize(JsonCanonicalize);
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("file-source").unwrap(),
+                InputType::Files(files_channel.clone()),
+            )]))
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Files, ctx);
+
+        let mut tree = FileTree::new(FileStore::new());
+        tree.add_file(
+            FilePath::from_str("config/client.json").unwrap(),
+            "\u{feff}{\"zoom\": 1.5, \"hud\": {\"scale\": 2, \"anchor\": \"top\"}, \"keys\": [{\"b\": 1, \"a\": 2}]}"
+                .as_bytes()
+                .to_vec(),
+        );
+        tree.add_file(FilePath::from_str("config/broken.JSON").unwrap(), b"{\"b\": 1,".to_vec());
+        tree.add_file(FilePath::from_str("config/notes.txt").unwrap(), b"{\"b\": 1, \"a\": 2}".to_vec());
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        files_channel.send(tree).unwrap();
+        handle.join().unwrap();
+
+        let files = read_channel(&mut out_channel, Duration::from_secs(10)).unwrap();
+        let file = |path: &str| String::from_utf8(files.get_file(&FilePath::from_str(path).unwrap()).unwrap().to_vec()).unwrap();
+        assert_eq!(
+            file("config/client.json"),
+            "{\n  \"hud\": {\n    \"anchor\": \"top\",\n    \"scale\": 2\n  },\n  \"keys\": [\n    {\n      \"a\": 2,\n      \"b\": 1\n    }\n  ],\n  \"zoom\": 1.5\n}\n"
+        );
+        assert_eq!(file("config/broken.JSON"), "{\"b\": 1,");
+        assert_eq!(file("config/notes.txt"), "{\"b\": 1, \"a\": 2}");
+
+        // Canonical output is a fixed point
+        assert_eq!(canonicalize(file("config/client.json").as_bytes()).unwrap(), file("config/client.json"));
+    }
+}
@@ -0,0 +1,190 @@
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    thread::{spawn, JoinHandle},
+};
+
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::{OptionExt, ResultExt};
+
+use crate::di::container::{DiContainer, InputType, OutputType};
+
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port},
+    utils::{get_input, get_output, OrderedJson},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct JsonValueReplace {
+    /// Regular expression matched against every string value.
+    pub pattern: String,
+    /// Replacement for each match, which may refer to capture groups as `$1` or `${name}`.
+    pub replacement: String,
+}
+
+impl NodeConfig for JsonValueReplace {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut files_channel = get_input!("files", Files, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Files, ctx)?;
+        let pattern = Regex::new(&self.pattern).map_err(|e| NodeInitError::InvalidOption {
+            option: "pattern".into(),
+            reason: e.to_string(),
+        })?;
+        let replacement = self.replacement.clone();
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "JsonValueReplace", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let mut files = files_channel.blocking_recv().expect_or_log("Failed to receive on files input");
+
+            let mut paths: Vec<_> = files
+                .list_files()
+                .into_iter()
+                .filter(|path| path.get_filename().to_lowercase().ends_with(".json"))
+                .cloned()
+                .collect();
+            paths.sort_by_key(|path| path.to_string());
+            for path in paths {
+                let data = files.get_file(&path).expect_or_log("File listed in tree is missing from store");
+                let mut json: OrderedJson = match serde_json::from_slice(&data) {
+                    Ok(json) => json,
+                    Err(e) => {
+                        event!(Level::WARN, "Skipping {path}, which is not valid JSON: {e}");
+                        continue;
+                    },
+                };
+                let count = replace_values(&mut json, &pattern, &replacement);
+                // Files without matches are passed through unchanged, keeping their formatting
+                if count > 0 {
+                    event!(Level::INFO, "Replaced {count} values in {path}");
+                    let json = serde_json::to_string_pretty(&json).expect_or_log("Serialization of JSON value failed");
+                    files.add_file(path, json.into_bytes());
+                }
+            }
+
+            if out_channel.send(files).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::Files(channel(1).0))])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "JsonValueReplace",
+            vec![Port::required("files", ChannelType::Files)],
+            vec![Port::required("default", ChannelType::Files)],
+        )
+    }
+}
+
+/// Apply the replacement to every string value nested in `json`, leaving keys and other values
+/// untouched. Returns the number of values that changed.
+fn replace_values(json: &mut OrderedJson, pattern: &Regex, replacement: &str) -> usize {
+    match json {
+        OrderedJson::Scalar(Value::String(value)) => match pattern.replace_all(value, replacement) {
+            Cow::Owned(replaced) if replaced != *value => {
+                *value = replaced;
+                1
+            },
+            _ => 0,
+        },
+        OrderedJson::Array(values) => values.iter_mut().map(|value| replace_values(value, pattern, replacement)).sum(),
+        OrderedJson::Object(map) => map.values_mut().map(|value| replace_values(value, pattern, replacement)).sum(),
+        OrderedJson::Scalar(_) => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        file::{filepath::FilePath, filestore::FileStore, filetree::FileTree},
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_json_value_replace() {
+        let node_id = "replace";
+        let files_channel = channel(1).0;
+        let input_ids = HashMap::from([("files".into(), ChannelId::from_str("file-source").unwrap())]);
+        let node = NodeConfigTypes::JsonValueReplace(JsonValueReplace {
+            pattern: r"old\.example\.com(:\d+)?".into(),
+            replacement: "new.example.com$1".into(),
+        });
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("file-source").unwrap(),
+                InputType::Files(files_channel.clone()),
+            )]))
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Files, ctx);
+
+        let servers = r#"{"old.example.com": {"address": "old.example.com:25565", "port": 25565, "hidden": false, "icon": null}, "backups": ["old.example.com"]}"#;
+        let untouched = "{\"address\":   \"elsewhere.example.com\"}";
+        let mut tree = FileTree::new(FileStore::new());
+        tree.add_file(FilePath::from_str("config/servers.json").unwrap(), servers.as_bytes().to_vec());
+        tree.add_file(FilePath::from_str("config/other.JSON").unwrap(), untouched.as_bytes().to_vec());
+        tree.add_file(FilePath::from_str("config/broken.json").unwrap(), b"old.example.com {".to_vec());
+        tree.add_file(FilePath::from_str("config/servers.txt").unwrap(), b"old.example.com".to_vec());
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        files_channel.send(tree).unwrap();
+        handle.join().unwrap();
+
+        let files = read_channel(&mut out_channel, Duration::from_secs(10)).unwrap();
+        let file = |path: &str| String::from_utf8(files.get_file(&FilePath::from_str(path).unwrap()).unwrap().to_vec()).unwrap();
+        let expected = serde_json::json!({
+            "old.example.com": {"address": "new.example.com:25565", "port": 25565, "hidden": false, "icon": null},
+            "backups": ["new.example.com"],
+        });
+        assert_eq!(serde_json::from_str::<Value>(&file("config/servers.json")).unwrap(), expected);
+        assert_eq!(file("config/other.JSON"), untouched);
+        assert_eq!(file("config/broken.json"), "old.example.com {");
+        assert_eq!(file("config/servers.txt"), "old.example.com");
+    }
+
+    #[test]
+    fn invalid_pattern() {
+        let node = NodeConfigTypes::JsonValueReplace(JsonValueReplace {
+            pattern: "(".into(),
+            replacement: String::new(),
+        });
+        let ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels("replace"))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("file-source").unwrap(),
+                InputType::Files(channel(1).0),
+            )]))
+            .build();
+        let input_ids = HashMap::from([("files".into(), ChannelId::from_str("file-source").unwrap())]);
+        let result = node.validate_and_spawn("replace".into(), &input_ids, &ctx);
+        assert!(matches!(result, Err(NodeInitError::InvalidOption { option, .. }) if option == "pattern"));
+    }
+}
@@ -0,0 +1,197 @@
+use std::{
+    collections::HashMap,
+    thread::{spawn, JoinHandle},
+};
+
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::di::container::{DiContainer, InputType, OutputType};
+
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port},
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct JunkFilter {
+    /// Glob patterns matched in addition to [`DEFAULT_JUNK_PATTERNS`].
+    #[serde(default)]
+    pub extra_patterns: Vec<String>,
+    /// Remove matched files from the output tree. If unset, matches are only reported.
+    #[serde(default = "default_strip")]
+    pub strip: bool,
+}
+
+fn default_strip() -> bool {
+    true
+}
+
+impl Default for JunkFilter {
+    fn default() -> Self {
+        JunkFilter {
+            extra_patterns: Vec::new(),
+            strip: default_strip(),
+        }
+    }
+}
+
+/// Files that are created by operating systems, archivers, or a running game, and should not be
+/// distributed in a pack.
+pub const DEFAULT_JUNK_PATTERNS: &[&str] = &[
+    "**/.DS_Store",
+    "**/__MACOSX/**",
+    "**/._*",
+    "**/Thumbs.db",
+    "**/desktop.ini",
+    "**/.git/**",
+    "**/*.log",
+    "**/*.log.gz",
+    "**/logs/**",
+    "**/crash-reports/**",
+    "**/.fabric/**",
+    "**/usercache.json",
+    "**/usernamecache.json",
+];
+
+impl NodeConfig for JunkFilter {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut files_channel = get_input!("files", Files, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Files, ctx)?;
+        let removed_channel = get_output!(ChannelId(node_id.clone(), "removed".into()), List, ctx)?;
+        let patterns: Vec<String> = DEFAULT_JUNK_PATTERNS
+            .iter()
+            .map(|p| p.to_string())
+            .chain(self.extra_patterns.iter().cloned())
+            .collect();
+        let strip = self.strip;
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "JunkFilter", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let files = files_channel.blocking_recv().expect_or_log("Failed to receive on files input");
+            let (junk, clean) = files.filter_files(&patterns);
+
+            let mut junk_paths = junk.list_files().into_iter().map(|p| p.to_string()).collect::<Vec<_>>();
+            junk_paths.sort();
+            if !junk_paths.is_empty() {
+                event!(
+                    Level::INFO,
+                    "{} {} junk files: {}",
+                    if strip { "Removed" } else { "Found" },
+                    junk_paths.len(),
+                    junk_paths.join(", ")
+                );
+            }
+
+            if out_channel.send(if strip { clean } else { files }).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+            if removed_channel.send(junk_paths).is_err() {
+                event!(Level::DEBUG, "Channel 'removed' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([
+            (ChannelId(node_id.to_owned(), "default".into()), InputType::Files(channel(1).0)),
+            (ChannelId(node_id.to_owned(), "removed".into()), InputType::List(channel(1).0)),
+        ])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "JunkFilter",
+            vec![Port::required("files", ChannelType::Files)],
+            vec![
+                Port::required("default", ChannelType::Files),
+                Port::required("removed", ChannelType::List),
+            ],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        file::{filepath::FilePath, filetree::FileTree},
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_junk_filter() {
+        let node_id = "junk";
+        let files_channel = channel::<FileTree>(1).0;
+        let input_ids = HashMap::from([("files".into(), ChannelId::from_str("pack-files").unwrap())]);
+        let node = NodeConfigTypes::JunkFilter(JunkFilter {
+            extra_patterns: vec!["**/*.bak".into()],
+            ..Default::default()
+        });
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("pack-files").unwrap(),
+                InputType::Files(files_channel.clone()),
+            )]))
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Files, ctx);
+        let mut removed_channel = get_output_test!(ChannelId::from_str("junk::removed").unwrap(), List, ctx);
+
+        let mut tree = FileTree::new(ctx.get_filestore());
+        for path in [
+            ".DS_Store",
+            "__MACOSX/overrides/._options.txt",
+            "overrides/config/.DS_Store",
+            "overrides/config/mymod.cfg",
+            "overrides/config/mymod.cfg.bak",
+            "overrides/logs/latest.log",
+            "overrides/options.txt",
+        ] {
+            tree.add_file(FilePath::from_str(path).unwrap(), path.into());
+        }
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        files_channel.send(tree).unwrap();
+        handle.join().unwrap();
+
+        let timeout = Duration::from_secs(30);
+        let output = read_channel(&mut out_channel, timeout).unwrap();
+        let removed = read_channel(&mut removed_channel, timeout).unwrap();
+
+        let mut remaining = output.list_files().into_iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        remaining.sort();
+        assert_eq!(remaining, vec!["overrides/config/mymod.cfg", "overrides/options.txt"]);
+        assert_eq!(
+            removed,
+            vec![
+                ".DS_Store",
+                "__MACOSX/overrides/._options.txt",
+                "overrides/config/.DS_Store",
+                "overrides/config/mymod.cfg.bak",
+                "overrides/logs/latest.log",
+            ]
+        );
+    }
+}
@@ -0,0 +1,207 @@
+use std::{
+    collections::HashMap,
+    thread::{spawn, JoinHandle},
+};
+
+use glob_match::glob_match;
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::di::container::{DiContainer, InputType, OutputType};
+
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port, ResolvedMod},
+    utils::{get_input, get_optional_input, get_output},
+};
+
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+pub struct KnownIssues {
+    /// Known problem mods, checked along with any read from the database input.
+    #[serde(default)]
+    pub issues: Vec<KnownIssue>,
+    /// Fail if the pack contains any known problem mod, instead of only reporting it.
+    #[serde(default)]
+    pub strict: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct KnownIssue {
+    /// Name of the mod, as in its mod definition.
+    pub name: String,
+    /// Glob pattern of the affected files. Every version of the mod is affected if not set.
+    #[serde(default = "default_filename")]
+    pub filename: String,
+    /// Why the version is a problem, shown in the report.
+    pub note: String,
+}
+
+fn default_filename() -> String {
+    "*".to_owned()
+}
+
+impl KnownIssue {
+    fn matches(&self, m: &ResolvedMod) -> bool {
+        self.name == m.name && glob_match(&self.filename, &m.filename)
+    }
+}
+
+impl NodeConfig for KnownIssues {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut mods_channel = get_input!("mods", ResolvedMods, ctx, input_ids)?;
+        let mut database_channel = get_optional_input!("database", Text, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), ResolvedMods, ctx)?;
+        let report_channel = get_output!(ChannelId(node_id.clone(), "report".into()), Text, ctx)?;
+        if let Some(issue) = self.issues.iter().find(|issue| issue.name.is_empty()) {
+            return Err(NodeInitError::InvalidOption {
+                option: "issues".into(),
+                reason: format!("Known issue '{}' has no mod name", issue.note),
+            });
+        }
+        let mut issues = self.issues.clone();
+        let strict = self.strict;
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "KnownIssues", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let mods = mods_channel.blocking_recv().expect_or_log("Failed to receive on mods input");
+            if let Some(ref mut channel) = database_channel {
+                let database = channel.blocking_recv().expect_or_log("Failed to receive on database input");
+                let entries: Option<Vec<KnownIssue>> = serde_yaml::from_str(&database).expect_or_log("Failed to parse known issues database");
+                // An empty database parses as no entries
+                issues.extend(entries.unwrap_or_default());
+            }
+
+            let lines = find_issues(&mods, &issues);
+            let report = match lines.is_empty() {
+                true => format!("Checked {} mods against {} known issues, none found.\n", mods.len(), issues.len()),
+                false => format!("Found {} known issues in {} mods:\n{}\n", lines.len(), mods.len(), lines.join("\n")),
+            };
+
+            if !lines.is_empty() {
+                event!(Level::WARN, "{report}");
+                if strict {
+                    panic!("Known problem mods found in strict mode");
+                }
+            }
+
+            if out_channel.send(mods).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+            if report_channel.send(report).is_err() {
+                event!(Level::DEBUG, "Channel 'report' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([
+            (ChannelId(node_id.to_owned(), "default".into()), InputType::ResolvedMods(channel(1).0)),
+            (ChannelId(node_id.to_owned(), "report".into()), InputType::Text(channel(1).0)),
+        ])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "KnownIssues",
+            vec![
+                Port::required("mods", ChannelType::ResolvedMods),
+                Port::optional("database", ChannelType::Text),
+            ],
+            vec![
+                Port::required("default", ChannelType::ResolvedMods),
+                Port::required("report", ChannelType::Text),
+            ],
+        )
+    }
+}
+
+/// List a line of the report for every known issue matching a mod, in the order the mods are
+/// listed.
+fn find_issues(mods: &[ResolvedMod], issues: &[KnownIssue]) -> Vec<String> {
+    mods.iter()
+        .flat_map(|m| {
+            issues
+                .iter()
+                .filter(|issue| issue.matches(m))
+                .map(move |issue| format!("- {} ({}): {}", m.name, m.filename, issue.note))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel, test_mod},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_known_issues() {
+        let node_id = "known-issues";
+        let mods_channel = channel(1).0;
+        let database_channel = channel(1).0;
+        let input_ids = HashMap::from([
+            ("mods".into(), ChannelId::from_str("mod-source").unwrap()),
+            ("database".into(), ChannelId::from_str("database-source").unwrap()),
+        ]);
+        let node = NodeConfigTypes::KnownIssues(KnownIssues {
+            issues: vec![KnownIssue {
+                name: "jei".into(),
+                filename: default_filename(),
+                note: "Crashes on startup".into(),
+            }],
+            strict: false,
+        });
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([
+                (ChannelId::from_str("mod-source").unwrap(), InputType::ResolvedMods(mods_channel.clone())),
+                (ChannelId::from_str("database-source").unwrap(), InputType::Text(database_channel.clone())),
+            ]))
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), ResolvedMods, ctx);
+        let mut report_channel = get_output_test!(ChannelId::from_str("known-issues::report").unwrap(), Text, ctx);
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        let mut sodium = test_mod("sodium");
+        sodium.filename = "sodium-fabric-0.5.2.jar".into();
+        mods_channel.send(vec![sodium, test_mod("jei"), test_mod("create")]).unwrap();
+        database_channel
+            .send(
+                "- name: sodium\n  filename: sodium-*-0.5.2.jar\n  note: Corrupts chunks\n\
+                 - name: create\n  filename: create-0.4.jar\n  note: Fixed in later versions\n"
+                    .into(),
+            )
+            .unwrap();
+        handle.join().unwrap();
+
+        let timeout = Duration::from_secs(5);
+        assert_eq!(read_channel(&mut out_channel, timeout).unwrap().len(), 3);
+        assert_eq!(
+            read_channel(&mut report_channel, timeout).unwrap(),
+            "Found 2 known issues in 3 mods:\n\
+             - sodium (sodium-fabric-0.5.2.jar): Corrupts chunks\n\
+             - jei (jei.jar): Crashes on startup\n"
+        );
+    }
+}
@@ -0,0 +1,134 @@
+use std::{
+    collections::HashMap,
+    thread::{spawn, JoinHandle},
+};
+
+use rayon::prelude::*;
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::di::container::{DiContainer, InputType, OutputType};
+
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port},
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Default)]
+pub struct LinkCheck {
+    /// Fail if any link is unreachable, instead of only reporting it.
+    #[serde(default)]
+    pub strict: bool,
+}
+
+impl NodeConfig for LinkCheck {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut mods_channel = get_input!("mods", ResolvedMods, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Text, ctx)?;
+        let downloader = ctx.get_downloader();
+        let strict = self.strict;
+        let pool = ctx.get_pool();
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "LinkCheck", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let mut mods = mods_channel.blocking_recv().expect_or_log("Failed to receive on mods input");
+            mods.sort_by(|a, b| a.name.cmp(&b.name));
+            event!(Level::INFO, "Checking {} links", mods.len());
+
+            let failures: Vec<String> = pool.install(|| {
+                mods.par_iter()
+                    .filter_map(|m| downloader.check_url(&m.src).err().map(|e| format!("- {}: {}", m.name, e)))
+                    .collect()
+            });
+
+            let report = if failures.is_empty() {
+                format!("Checked {} links, all reachable.\n", mods.len())
+            } else {
+                format!("Checked {} links, {} unreachable:\n{}\n", mods.len(), failures.len(), failures.join("\n"))
+            };
+
+            if !failures.is_empty() {
+                event!(Level::WARN, "{report}");
+                if strict {
+                    panic!("Unreachable links found in strict mode");
+                }
+            }
+
+            if out_channel.send(report).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::Text(channel(1).0))])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "LinkCheck",
+            vec![Port::required("mods", ChannelType::ResolvedMods)],
+            vec![Port::required("default", ChannelType::Text)],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel, serve_http, test_mod},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_link_check() {
+        let node_id = "link-check";
+        let base_url = serve_http(HashMap::from([("/files/present.jar".to_owned(), (200, b"jar".to_vec()))]));
+        let mods_channel = channel(1).0;
+        let input_ids = HashMap::from([("mods".into(), ChannelId::from_str("mod-source").unwrap())]);
+        let node = NodeConfigTypes::LinkCheck(LinkCheck::default());
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("mod-source").unwrap(),
+                InputType::ResolvedMods(mods_channel.clone()),
+            )]))
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Text, ctx);
+
+        let mut present = test_mod("present");
+        present.src = format!("{base_url}/files/present.jar");
+        let mut purged = test_mod("purged");
+        purged.src = format!("{base_url}/files/purged.jar");
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        mods_channel.send(vec![purged, present]).unwrap();
+        handle.join().unwrap();
+
+        let report = read_channel(&mut out_channel, Duration::from_secs(30)).unwrap();
+        assert!(report.starts_with("Checked 2 links, 1 unreachable:\n- purged: "));
+        assert!(report.contains("404"));
+        assert!(!report.contains("present"));
+    }
+}
@@ -0,0 +1,117 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    thread::{spawn, JoinHandle},
+};
+
+use serde::Deserialize;
+use tokio::sync::broadcast::{channel, Receiver};
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::di::container::{DiContainer, InputType, OutputType};
+
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port},
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct ListMerge;
+
+impl NodeConfig for ListMerge {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        // Like DirectoryMerger, any input names are accepted. The output is sorted, so the order
+        // inputs are read in does not matter.
+        let mut input_channels = input_ids
+            .keys()
+            .map(|id| get_input!(id, List, ctx, input_ids).map(|channel| (id.clone(), channel)))
+            .collect::<Result<Vec<(String, Receiver<_>)>, _>>()?;
+        let output_channel = get_output!(ChannelId(node_id.clone(), "default".into()), List, ctx)?;
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "ListMerge", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let mut total = 0;
+            let merged = input_channels
+                .iter_mut()
+                .flat_map(|(id, channel)| channel.blocking_recv().expect_or_log(&format!("Failed to receive on {id} input")))
+                .inspect(|_| total += 1)
+                .collect::<BTreeSet<String>>();
+            event!(
+                Level::INFO,
+                "Merged {} entries from {} inputs into {} unique entries",
+                total,
+                input_channels.len(),
+                merged.len()
+            );
+
+            if output_channel.send(merged.into_iter().collect()).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::List(channel(1).0))])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new("ListMerge", vec![], vec![Port::required("default", ChannelType::List)]).with_any_inputs(ChannelType::List)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn merge_lists() {
+        let node_id = "merge";
+        let node = NodeConfigTypes::ListMerge(ListMerge);
+        let base = channel::<Vec<String>>(1).0;
+        let extra = channel::<Vec<String>>(1).0;
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([
+                (ChannelId::from_str("base").unwrap(), InputType::List(base.clone())),
+                (ChannelId::from_str("extra").unwrap(), InputType::List(extra.clone())),
+            ]))
+            .build();
+        let input_ids = HashMap::from([
+            ("base".into(), ChannelId::from_str("base").unwrap()),
+            ("per-build".into(), ChannelId::from_str("extra").unwrap()),
+        ]);
+
+        let mut out = get_output_test!(&ChannelId::from_str(node_id).unwrap(), List, ctx);
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        base.send(vec!["mods/optifine*.jar".into(), "config/*.bak".into(), "logs/**".into()])
+            .unwrap();
+        extra.send(vec!["logs/**".into(), "Config/*.bak".into(), "config/*.bak".into()]).unwrap();
+        ctx.run().unwrap();
+        handle.join().unwrap();
+
+        assert_eq!(
+            read_channel(&mut out, Duration::from_secs(10)).unwrap(),
+            vec!["Config/*.bak", "config/*.bak", "logs/**", "mods/optifine*.jar"]
+        );
+    }
+}
@@ -0,0 +1,347 @@
+use std::{
+    collections::HashMap,
+    thread::{spawn, JoinHandle},
+};
+
+use api_client::{
+    curse::{model::File, CurseClient},
+    loader::ModLoader,
+    modrinth::ModrinthClient,
+};
+use rayon::prelude::*;
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::di::container::{DiContainer, InputType, OutputType};
+
+use super::{
+    config::{ChannelId, ChannelType, ModSource, NodeConfig, NodeDescription, NodeInitError, Port, ResolvedMod},
+    utils::{get_input, get_output, parse_loader},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Default)]
+pub struct LoaderConsistencyCheck {
+    /// Fail if any mod does not support the pack's loader, instead of only reporting it.
+    #[serde(default)]
+    pub strict: bool,
+}
+
+/// Loaders a mod declares support for, and whether they were looked up or guessed from its
+/// filename.
+struct Loaders {
+    loaders: Vec<String>,
+    guessed: bool,
+}
+
+impl NodeConfig for LoaderConsistencyCheck {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut mods_channel = get_input!("mods", ResolvedMods, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), ResolvedMods, ctx)?;
+        let report_channel = get_output!(ChannelId(node_id.clone(), "report".into()), Text, ctx)?;
+        let modloader = ctx
+            .get_config("modloader")
+            .ok_or_else(|| NodeInitError::MissingConfig("modloader".into()))?;
+        let modloader = parse_loader("modloader", &modloader)?.name();
+        // Loader tags are not cached, so offline every mod falls back to the filename heuristic
+        let clients = match ctx.is_offline() {
+            true => None,
+            false => Some((ctx.get_curse_client(), ctx.get_modrinth_client())),
+        };
+        let strict = self.strict;
+        let pool = ctx.get_pool();
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "LoaderConsistencyCheck", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let mods = mods_channel.blocking_recv().expect_or_log("Failed to receive on mods input");
+            // Resource packs, shaders and data packs are not loaded by the mod loader
+            let checked = mods.iter().filter(|m| m.kind.is_mod()).collect::<Vec<_>>();
+            event!(Level::INFO, "Checking that {} mods support {}", checked.len(), modloader);
+
+            let curse_files = match clients {
+                Some((Some(ref curse), _)) => curse_files(&checked, curse),
+                _ => HashMap::new(),
+            };
+            let modrinth = clients.as_ref().map(|(_, modrinth)| modrinth);
+            let loaders = pool.install(|| checked.par_iter().map(|m| mod_loaders(m, &curse_files, modrinth)).collect::<Vec<_>>());
+
+            let mut incompatible = checked
+                .iter()
+                .zip(loaders)
+                .filter(|(_, loaders)| !supports(&loaders.loaders, modloader))
+                .map(|(m, loaders)| {
+                    let guessed = if loaders.guessed { " (guessed from filename)" } else { "" };
+                    format!("- {}: supports {}{}", m.name, loaders.loaders.join(", "), guessed)
+                })
+                .collect::<Vec<_>>();
+            incompatible.sort();
+
+            let report = if incompatible.is_empty() {
+                format!("Checked {} mods, all support {}.\n", checked.len(), modloader)
+            } else {
+                format!(
+                    "Checked {} mods, {} do not support {}:\n{}\n",
+                    checked.len(),
+                    incompatible.len(),
+                    modloader,
+                    incompatible.join("\n")
+                )
+            };
+
+            if !incompatible.is_empty() {
+                event!(Level::WARN, "{report}");
+                if strict {
+                    panic!("Mods not supporting the pack's loader found in strict mode");
+                }
+            }
+
+            if out_channel.send(mods).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+
+            if report_channel.send(report).is_err() {
+                event!(Level::DEBUG, "Channel 'report' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([
+            (ChannelId(node_id.to_owned(), "default".into()), InputType::ResolvedMods(channel(1).0)),
+            (ChannelId(node_id.to_owned(), "report".into()), InputType::Text(channel(1).0)),
+        ])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "LoaderConsistencyCheck",
+            vec![Port::required("mods", ChannelType::ResolvedMods)],
+            vec![
+                Port::required("default", ChannelType::ResolvedMods),
+                Port::required("report", ChannelType::Text),
+            ],
+        )
+        .with_config_keys(vec!["modloader"])
+    }
+}
+
+/// Look up the files of all Curse mods in a single request. On failure, the mods fall back to the
+/// filename heuristic.
+fn curse_files(mods: &[&ResolvedMod], client: &CurseClient) -> HashMap<u32, File> {
+    let ids = mods
+        .iter()
+        .filter_map(|m| match m.source {
+            Some(ModSource::Curse { file_id, .. }) => Some(file_id),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if ids.is_empty() {
+        return HashMap::new();
+    }
+    match client.get_files(&ids) {
+        Ok(files) => files.into_iter().map(|f| (f.id, f)).collect(),
+        Err(e) => {
+            event!(Level::WARN, "Could not look up Curse files, guessing their loaders from filenames: {}", e);
+            HashMap::new()
+        },
+    }
+}
+
+fn mod_loaders(m: &ResolvedMod, curse_files: &HashMap<u32, File>, modrinth: Option<&ModrinthClient>) -> Loaders {
+    let looked_up = match (&m.source, modrinth) {
+        (Some(ModSource::Curse { file_id, .. }), _) => curse_files.get(file_id).map(curse_loaders),
+        (Some(ModSource::Modrinth { version_id, .. }), Some(client)) => match client.get_version(version_id) {
+            Ok(version) => Some(version.loaders.iter().map(|l| l.to_lowercase()).collect()),
+            Err(e) => {
+                event!(
+                    Level::WARN,
+                    "Could not look up Modrinth version of {}, guessing its loader from its filename: {}",
+                    m.name,
+                    e
+                );
+                None
+            },
+        },
+        _ => None,
+    };
+    match looked_up {
+        Some(loaders) => Loaders { loaders, guessed: false },
+        None => Loaders {
+            loaders: filename_loaders(&m.filename),
+            guessed: true,
+        },
+    }
+}
+
+/// Loaders a Curse file is tagged for, preferring the typed game versions.
+fn curse_loaders(file: &File) -> Vec<String> {
+    match file.sortable_game_versions.is_empty() {
+        true => file
+            .game_versions
+            .iter()
+            .map(|v| v.to_lowercase())
+            .filter(|v| v.parse::<ModLoader>().is_ok())
+            .collect(),
+        false => file
+            .sortable_game_versions
+            .iter()
+            .filter(|v| v.is_modloader())
+            .map(|v| v.game_version_name.to_lowercase())
+            .collect(),
+    }
+}
+
+/// Guess the loaders of a mod from the words in its filename, such as `sodium-fabric-0.5.3.jar`.
+fn filename_loaders(filename: &str) -> Vec<String> {
+    let filename = filename.to_lowercase();
+    let words = filename.split(|c: char| !c.is_ascii_alphanumeric()).collect::<Vec<_>>();
+    ModLoader::ALL
+        .iter()
+        .filter(|loader| words.contains(&loader.name()))
+        .map(|loader| loader.name().to_owned())
+        .collect()
+}
+
+/// Whether a mod supporting `loaders` can be loaded by `modloader`. Mods that declare no loader
+/// are assumed to support every loader, and Quilt loads Fabric mods.
+fn supports(loaders: &[String], modloader: &str) -> bool {
+    loaders.is_empty() || loaders.iter().any(|l| l == modloader || (modloader == "quilt" && l == "fabric"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        node::{
+            config::{NodeConfigTypes, ProjectKind},
+            utils::{get_output_test, read_channel, serve_http, test_mod},
+        },
+    };
+
+    use super::*;
+
+    fn with_source(name: &str, filename: &str, source: Option<ModSource>) -> ResolvedMod {
+        ResolvedMod {
+            filename: filename.into(),
+            source,
+            ..test_mod(name)
+        }
+    }
+
+    #[test]
+    fn test_loader_consistency_check() {
+        let node_id = "loaders";
+        let version = |id: &str, loaders: &str| {
+            format!(
+                r#"{{"name":"v","version_number":"1","game_versions":["1.20.1"],"version_type":"release","loaders":{loaders},"id":"{id}","project_id":"p","files":[],"date_published":"2023-01-01T00:00:00Z"}}"#
+            )
+            .into_bytes()
+        };
+        let base_url = serve_http(HashMap::from([
+            ("/v2/version/sodium".to_owned(), (200, version("sodium", r#"["fabric","quilt"]"#))),
+            ("/v2/version/jei".to_owned(), (200, version("jei", r#"["forge","neoforge"]"#))),
+        ]));
+        let mods_channel = channel(1).0;
+        let input_ids = HashMap::from([("mods".into(), ChannelId::from_str("resolver").unwrap())]);
+        let node = NodeConfigTypes::LoaderConsistencyCheck(LoaderConsistencyCheck::default());
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("resolver").unwrap(),
+                InputType::ResolvedMods(mods_channel.clone()),
+            )]))
+            .modrinth_base_url(&base_url)
+            .set_config("modloader", "Forge")
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), ResolvedMods, ctx);
+        let mut report_channel = get_output_test!(ChannelId::from_str("loaders::report").unwrap(), Text, ctx);
+
+        let modrinth = |id: &str| {
+            Some(ModSource::Modrinth {
+                project_id: "p".into(),
+                version_id: id.into(),
+            })
+        };
+        let mods = vec![
+            with_source("sodium", "sodium-forge-0.5.3.jar", modrinth("sodium")),
+            with_source("jei", "jei-15.2.0.jar", modrinth("jei")),
+            // No Curse client is configured, so the filename is used
+            with_source(
+                "lithium",
+                "lithium-fabric-mc1.20.1-0.11.2.jar",
+                Some(ModSource::Curse { project_id: 1, file_id: 2 }),
+            ),
+            with_source("custom", "custom-1.0.jar", Some(ModSource::Url)),
+            ResolvedMod {
+                kind: ProjectKind::ResourcePack,
+                ..with_source("fabric-textures", "fabric-textures.zip", None)
+            },
+        ];
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        mods_channel.send(mods.clone()).unwrap();
+        handle.join().unwrap();
+
+        let timeout = Duration::from_secs(10);
+        assert_eq!(read_channel(&mut out_channel, timeout).unwrap(), mods);
+        assert_eq!(
+            read_channel(&mut report_channel, timeout).unwrap(),
+            "Checked 4 mods, 2 do not support forge:\n\
+             - lithium: supports fabric (guessed from filename)\n\
+             - sodium: supports fabric, quilt\n"
+        );
+    }
+
+    #[test]
+    fn strict_fails_on_incompatible_mod() {
+        let mods_channel = channel(1).0;
+        let input_ids = HashMap::from([("mods".into(), ChannelId::from_str("resolver").unwrap())]);
+        let node = NodeConfigTypes::LoaderConsistencyCheck(LoaderConsistencyCheck { strict: true });
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels("loaders"))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("resolver").unwrap(),
+                InputType::ResolvedMods(mods_channel.clone()),
+            )]))
+            .set_config("modloader", "fabric")
+            .build();
+
+        let handle = node.validate_and_spawn("loaders".into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        mods_channel.send(vec![with_source("jei", "jei-forge-15.2.0.jar", None)]).unwrap();
+        assert!(handle.join().is_err());
+    }
+
+    #[test]
+    fn loader_detection() {
+        assert_eq!(filename_loaders("Sodium-Fabric-0.5.3+mc1.20.1.jar"), vec!["fabric"]);
+        assert_eq!(filename_loaders("create-1.20.1-0.5.1.f.jar"), Vec::<String>::new());
+        // Loaders only match whole words
+        assert_eq!(filename_loaders("forgeconfigapiport-fabric-8.0.0.jar"), vec!["fabric"]);
+        assert!(supports(&["fabric".into()], "quilt"));
+        assert!(!supports(&["quilt".into()], "fabric"));
+        assert!(supports(&[], "forge"));
+
+        let file: File = serde_json::from_value(serde_json::json!({
+            "id": 1, "modId": 1, "displayName": "", "fileName": "a.jar", "releaseType": 1, "fileStatus": 4,
+            "downloadUrl": null, "gameVersions": ["1.20.1", "Forge", "Client"], "dependencies": [], "hashes": [], "fileDate": "",
+        }))
+        .unwrap();
+        assert_eq!(curse_loaders(&file), vec!["forge"]);
+    }
+}
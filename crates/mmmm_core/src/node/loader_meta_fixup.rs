@@ -0,0 +1,277 @@
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    thread::{spawn, JoinHandle},
+};
+
+use api_client::loader::ModLoader;
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::{
+    di::container::{DiContainer, InputType, OutputType},
+    file::filepath::FilePath,
+};
+
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port},
+    utils::{get_input, get_output, parse_loader, OrderedJson},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Default)]
+pub struct LoaderMetaFixup {
+    /// Loader the pack is being moved to. Falls back to the `modloader` config key.
+    pub loader: Option<String>,
+}
+
+/// MultiMC/Prism component uids belonging to each loader. Intermediary mappings are shared by
+/// Fabric and Quilt.
+const COMPONENTS: [(&str, &[&str]); 5] = [
+    ("net.minecraftforge", &["forge"]),
+    ("net.neoforged", &["neoforge"]),
+    ("net.fabricmc.fabric-loader", &["fabric"]),
+    ("org.quiltmc.quilt-loader", &["quilt"]),
+    ("net.fabricmc.intermediary", &["fabric", "quilt"]),
+];
+
+/// Legacy MultiMC `instance.cfg` keys belonging to each loader.
+const INSTANCE_KEYS: [(&str, &[&str]); 2] = [("ForgeVersion", &["forge"]), ("LiteloaderVersion", &["forge"])];
+
+type Fixup = fn(&str, &str) -> Option<String>;
+
+/// Launcher metadata files, which are only recognized at the root of the pack.
+const FIXUPS: [(&str, Fixup); 3] = [
+    ("mmc-pack.json", fixup_mmc_pack),
+    ("instance.cfg", fixup_instance_cfg),
+    ("manifest.json", fixup_curse_manifest),
+];
+
+impl NodeConfig for LoaderMetaFixup {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut files_channel = get_input!("files", Files, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Files, ctx)?;
+        let loader = match self.loader {
+            Some(ref loader) => parse_loader("loader", loader)?,
+            None => parse_loader(
+                "modloader",
+                &ctx.get_config("modloader")
+                    .ok_or_else(|| NodeInitError::MissingConfig("modloader".into()))?,
+            )?,
+        }
+        .name();
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "LoaderMetaFixup", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let mut files = files_channel.blocking_recv().expect_or_log("Failed to receive on files input");
+
+            for (filename, fixup) in FIXUPS {
+                let path = FilePath::from_str(filename).unwrap_or_log();
+                let Some(data) = files.get_file(&path) else {
+                    continue;
+                };
+                let Ok(text) = std::str::from_utf8(&data) else {
+                    event!(Level::WARN, "Skipping {path}, which is not valid UTF-8");
+                    continue;
+                };
+                if let Some(fixed) = fixup(text, loader) {
+                    event!(Level::INFO, "Removed conflicting loader metadata from {path}");
+                    files.add_file(path, fixed.into_bytes());
+                }
+            }
+
+            if out_channel.send(files).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::Files(channel(1).0))])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "LoaderMetaFixup",
+            vec![Port::required("files", ChannelType::Files)],
+            vec![Port::required("default", ChannelType::Files)],
+        )
+        .with_config_keys(match self.loader {
+            Some(_) => vec![],
+            None => vec!["modloader"],
+        })
+    }
+}
+
+/// Whether an entry owned by `owners` conflicts with the target loader.
+fn conflicts(owners: Option<&&[&str]>, loader: &str) -> bool {
+    owners.is_some_and(|owners| !owners.contains(&loader))
+}
+
+/// Remove loader components from a MultiMC/Prism `mmc-pack.json` that do not belong to `loader`.
+/// Returns `None` if the file is unchanged or not recognized.
+fn fixup_mmc_pack(text: &str, loader: &str) -> Option<String> {
+    let mut pack: OrderedJson = serde_json::from_str(text).ok()?;
+    let components = pack.get_mut("components")?.as_array_mut()?;
+    let before = components.len();
+    components.retain(|c| {
+        let owners = c
+            .get("uid")
+            .and_then(OrderedJson::as_scalar)
+            .and_then(Value::as_str)
+            .and_then(|uid| COMPONENTS.iter().find(|(id, _)| *id == uid).map(|(_, owners)| owners));
+        !conflicts(owners, loader)
+    });
+    (components.len() != before).then(|| serde_json::to_string_pretty(&pack).expect_or_log("Failed to serialize mmc-pack.json"))
+}
+
+/// Remove loader version keys from a legacy MultiMC `instance.cfg` that do not belong to `loader`.
+/// Returns `None` if the file is unchanged.
+fn fixup_instance_cfg(text: &str, loader: &str) -> Option<String> {
+    let mut changed = false;
+    let kept: Vec<&str> = text
+        .lines()
+        .filter(|line| {
+            let key = line.split_once('=').map(|(key, _)| key.trim());
+            let owners = key.and_then(|key| INSTANCE_KEYS.iter().find(|(k, _)| *k == key).map(|(_, owners)| owners));
+            let keep = !conflicts(owners, loader);
+            changed |= !keep;
+            keep
+        })
+        .collect();
+    changed.then(|| kept.join("\n") + if text.ends_with('\n') { "\n" } else { "" })
+}
+
+/// Remove entries from `minecraft.modLoaders` in a CurseForge `manifest.json` that do not belong
+/// to `loader`. If the primary entry is removed, the first remaining entry becomes primary.
+/// Returns `None` if the file is unchanged, not a CurseForge manifest, or has no entry for `loader`
+/// to keep.
+fn fixup_curse_manifest(text: &str, loader: &str) -> Option<String> {
+    let mut manifest: OrderedJson = serde_json::from_str(text).ok()?;
+    let mod_loaders = manifest.get_mut("minecraft")?.get_mut("modLoaders")?.as_array_mut()?;
+    let is_primary = |l: &OrderedJson| l.get("primary").and_then(OrderedJson::as_scalar).and_then(Value::as_bool) == Some(true);
+    let keep = |l: &OrderedJson| {
+        let id = l.get("id").and_then(OrderedJson::as_scalar).and_then(Value::as_str).unwrap_or_default();
+        match ModLoader::ALL.iter().find(|l| id.starts_with(&format!("{}-", l.name()))) {
+            Some(l) => l.name() == loader,
+            None => true,
+        }
+    };
+    if mod_loaders.iter().all(keep) {
+        return None;
+    }
+    if !mod_loaders.iter().any(keep) {
+        event!(Level::WARN, "Leaving manifest.json unchanged, as it has no {loader} entry in modLoaders");
+        return None;
+    }
+    let removed_primary = mod_loaders.iter().any(|l| is_primary(l) && !keep(l));
+    mod_loaders.retain(keep);
+    if removed_primary && !mod_loaders.iter().any(is_primary) {
+        if let OrderedJson::Object(first) = &mut mod_loaders[0] {
+            first.insert("primary".into(), OrderedJson::Scalar(Value::Bool(true)));
+        }
+    }
+    Some(serde_json::to_string_pretty(&manifest).expect_or_log("Failed to serialize manifest.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        file::{filepath::FilePath, filetree::FileTree},
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_loader_meta_fixup() {
+        let node_id = "fixup";
+        let files_channel = channel::<FileTree>(1).0;
+        let input_ids = HashMap::from([("files".into(), ChannelId::from_str("pack-files").unwrap())]);
+        let node = NodeConfigTypes::LoaderMetaFixup(LoaderMetaFixup {
+            loader: Some("neoforge".into()),
+        });
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("pack-files").unwrap(),
+                InputType::Files(files_channel.clone()),
+            )]))
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Files, ctx);
+
+        let mut tree = FileTree::new(ctx.get_filestore());
+        tree.add_file(
+            FilePath::from_str("mmc-pack.json").unwrap(),
+            r#"{"formatVersion":1,"components":[{"uid":"net.minecraft","version":"1.20.1"},{"uid":"net.minecraftforge","version":"47.2.0"},{"uid":"net.neoforged","version":"47.1.79"}]}"#.into(),
+        );
+        tree.add_file(
+            FilePath::from_str("instance.cfg").unwrap(),
+            "InstanceType=OneSix\nForgeVersion=47.2.0\nname=My Pack\n".into(),
+        );
+        tree.add_file(
+            FilePath::from_str("manifest.json").unwrap(),
+            r#"{"minecraft":{"version":"1.20.1","modLoaders":[{"id":"forge-47.2.0","primary":true},{"id":"neoforge-47.1.79","primary":false}]}}"#
+                .into(),
+        );
+        tree.add_file(FilePath::from_str("config/manifest.txt").unwrap(), "untouched".into());
+        // Only metadata at the root of the pack is launcher metadata
+        let nested = r#"{"minecraft":{"modLoaders":[{"id":"forge-47.2.0","primary":true}]}}"#;
+        tree.add_file(FilePath::from_str("config/mod/manifest.json").unwrap(), nested.into());
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        files_channel.send(tree).unwrap();
+        handle.join().unwrap();
+
+        let output = read_channel(&mut out_channel, Duration::from_secs(30)).unwrap();
+        let read = |path: &str| String::from_utf8(output.get_file(&FilePath::from_str(path).unwrap()).unwrap().to_vec()).unwrap();
+
+        let mmc_pack: Value = serde_json::from_str(&read("mmc-pack.json")).unwrap();
+        let uids: Vec<&str> = mmc_pack["components"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| c["uid"].as_str().unwrap())
+            .collect();
+        assert_eq!(uids, vec!["net.minecraft", "net.neoforged"]);
+        // Keys keep their order in the file
+        assert!(read("mmc-pack.json").find("formatVersion") < read("mmc-pack.json").find("components"));
+
+        assert_eq!(read("instance.cfg"), "InstanceType=OneSix\nname=My Pack\n");
+
+        let manifest: Value = serde_json::from_str(&read("manifest.json")).unwrap();
+        assert_eq!(manifest["minecraft"]["modLoaders"].as_array().unwrap().len(), 1);
+        assert_eq!(manifest["minecraft"]["modLoaders"][0]["id"], "neoforge-47.1.79");
+        assert_eq!(manifest["minecraft"]["modLoaders"][0]["primary"], true);
+        assert_eq!(read("config/mod/manifest.json"), nested);
+
+        assert_eq!(read("config/manifest.txt"), "untouched");
+    }
+
+    #[test]
+    fn keeps_manifest_without_target_loader() {
+        let manifest = r#"{"minecraft":{"modLoaders":[{"id":"forge-47.2.0","primary":true}]}}"#;
+        assert_eq!(fixup_curse_manifest(manifest, "fabric"), None);
+        assert_eq!(fixup_curse_manifest(manifest, "forge"), None);
+    }
+}
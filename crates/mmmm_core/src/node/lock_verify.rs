@@ -0,0 +1,223 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::ErrorKind,
+    thread::{spawn, JoinHandle},
+};
+
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::di::container::{DiContainer, InputType, OutputType};
+
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port, ResolvedMod},
+    hash_drift_check::{drifted_mods, hash, version},
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct LockVerify;
+
+impl NodeConfig for LockVerify {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut mods_channel = get_input!("mods", ResolvedMods, ctx, input_ids)?;
+        let mut path_channel = get_input!("path", Text, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Text, ctx)?;
+        let report_channel = get_output!(ChannelId(node_id.clone(), "report".into()), Text, ctx)?;
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "LockVerify", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let mut mods = mods_channel.blocking_recv().expect_or_log("Failed to receive on mods input");
+            let path = path_channel.blocking_recv().expect_or_log("Failed to receive on path input");
+            mods.sort_by(|a, b| a.name.cmp(&b.name));
+
+            let (lock, report) = match std::fs::read_to_string(&path) {
+                Ok(existing) => {
+                    let locked: Vec<ResolvedMod> = serde_json::from_str(&existing).expect_or_log(&format!("Failed to parse lockfile '{path}'"));
+                    let mismatches = mismatches(&locked, &mods);
+                    if !mismatches.is_empty() {
+                        event!(
+                            Level::ERROR,
+                            "Resolved mods do not match lockfile '{path}', {} mismatches:\n{}",
+                            mismatches.len(),
+                            mismatches.join("\n")
+                        );
+                        panic!()
+                    }
+                    (
+                        existing,
+                        format!("Verified {} mods against lockfile '{path}', no mismatches.\n", mods.len()),
+                    )
+                },
+                Err(e) if e.kind() == ErrorKind::NotFound => {
+                    event!(Level::INFO, "Lockfile '{path}' does not exist, writing a new one");
+                    let lock = serde_json::to_string_pretty(&mods).expect_or_log("Serialization of resolved mods to JSON failed");
+                    (lock, format!("Lockfile '{path}' did not exist, locked {} mods.\n", mods.len()))
+                },
+                Err(e) => {
+                    event!(Level::ERROR, "Failed to read lockfile '{path}': {e}");
+                    panic!()
+                },
+            };
+
+            if out_channel.send(lock).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+            if report_channel.send(report).is_err() {
+                event!(Level::DEBUG, "Channel 'report' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([
+            (ChannelId(node_id.to_owned(), "default".into()), InputType::Text(channel(1).0)),
+            (ChannelId(node_id.to_owned(), "report".into()), InputType::Text(channel(1).0)),
+        ])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "LockVerify",
+            vec![
+                Port::required("mods", ChannelType::ResolvedMods),
+                Port::required("path", ChannelType::Text),
+            ],
+            vec![Port::required("default", ChannelType::Text), Port::required("report", ChannelType::Text)],
+        )
+    }
+}
+
+/// Describe every difference between the locked and resolved mods: mods added or removed, mods
+/// resolved to another version, and mods whose file changed hash without changing version.
+fn mismatches(locked: &[ResolvedMod], resolved: &[ResolvedMod]) -> Vec<String> {
+    let locked_by_name = locked.iter().map(|m| (m.name.as_str(), m)).collect::<BTreeMap<_, _>>();
+    let resolved_by_name = resolved.iter().map(|m| (m.name.as_str(), m)).collect::<BTreeMap<_, _>>();
+    let mut mismatches = vec![];
+    for (name, new) in &resolved_by_name {
+        match locked_by_name.get(name) {
+            None => mismatches.push(format!("- {name}: not in lockfile")),
+            Some(old) if version(old) != version(new) => mismatches.push(format!("- {name}: locked {}, resolved {}", version(old), version(new))),
+            Some(_) => {},
+        }
+    }
+    for name in locked_by_name.keys().filter(|name| !resolved_by_name.contains_key(*name)) {
+        mismatches.push(format!("- {name}: in lockfile, but not resolved"));
+    }
+    for (old, new) in drifted_mods(locked, resolved) {
+        mismatches.push(format!("- {} ({}): hash changed, {} → {}", new.name, new.filename, hash(old), hash(new)));
+    }
+    mismatches
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use tokio::sync::broadcast;
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        node::{
+            config::{ModSource, NodeConfigTypes},
+            utils::{get_output_test, read_channel, test_mod},
+        },
+    };
+
+    use super::*;
+
+    fn run(path: &str, mods: Vec<ResolvedMod>) -> (Result<(), ()>, Option<String>, Option<String>) {
+        let node_id = "lock";
+        let mods_channel = broadcast::channel(1).0;
+        let path_channel = broadcast::channel(1).0;
+        let input_ids = HashMap::from([
+            ("mods".into(), ChannelId::from_str("mod-source").unwrap()),
+            ("path".into(), ChannelId::from_str("lock-path").unwrap()),
+        ]);
+        let node = NodeConfigTypes::LockVerify(LockVerify);
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([
+                (ChannelId::from_str("mod-source").unwrap(), InputType::ResolvedMods(mods_channel.clone())),
+                (ChannelId::from_str("lock-path").unwrap(), InputType::Text(path_channel.clone())),
+            ]))
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Text, ctx);
+        let mut report_channel = get_output_test!(ChannelId::from_str("lock::report").unwrap(), Text, ctx);
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        mods_channel.send(mods).unwrap();
+        path_channel.send(path.to_owned()).unwrap();
+
+        let result = handle.join().map_err(|_| ());
+        let timeout = Duration::from_millis(100);
+        (
+            result,
+            read_channel(&mut out_channel, timeout).ok(),
+            read_channel(&mut report_channel, timeout).ok(),
+        )
+    }
+
+    #[test]
+    fn test_lock_verify() {
+        let path = std::env::temp_dir().join(format!("mmmm-lock-verify-{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        let _ = std::fs::remove_file(path);
+        let sourced = |name: &str, version_id: &str, sha256: &str| ResolvedMod {
+            source: Some(ModSource::Modrinth {
+                project_id: name.into(),
+                version_id: version_id.into(),
+            }),
+            sha256: sha256.into(),
+            ..test_mod(name)
+        };
+        let mods = vec![sourced("sodium", "v1", "aa"), sourced("jei", "v1", "bb")];
+
+        // Without a lockfile, the mods are locked
+        let (result, lock, report) = run(path, mods.clone());
+        assert!(result.is_ok());
+        let lock = lock.unwrap();
+        let locked: Vec<ResolvedMod> = serde_json::from_str(&lock).unwrap();
+        assert_eq!(locked.iter().map(|m| m.name.as_str()).collect::<Vec<_>>(), ["jei", "sodium"]);
+        assert!(report.unwrap().contains("did not exist, locked 2 mods"));
+        std::fs::write(path, &lock).unwrap();
+
+        // Matching mods pass, and the lockfile is passed through unchanged
+        let (result, out, report) = run(path, mods.into_iter().rev().collect());
+        assert!(result.is_ok());
+        assert_eq!(out.unwrap(), lock);
+        assert!(report.unwrap().contains("no mismatches"));
+
+        let mismatches = mismatches(
+            &locked,
+            &[sourced("jei", "v1", "cc"), sourced("sodium", "v2", "aa"), sourced("iris", "v1", "dd")],
+        );
+        assert_eq!(
+            mismatches,
+            [
+                "- iris: not in lockfile",
+                "- sodium: locked v1, resolved v2",
+                "- jei (jei.jar): hash changed, bb → cc",
+            ]
+        );
+        let (result, out, _) = run(path, vec![sourced("jei", "v1", "bb")]);
+        assert!(result.is_err());
+        assert!(out.is_none());
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,556 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    str::FromStr,
+    thread::{spawn, JoinHandle},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::{
+    di::container::{DiContainer, InputType, OutputType},
+    file::{filepath::FilePath, filetree::FileTree},
+};
+
+use super::{
+    config::{ChannelId, ChannelType, ModSource, NodeConfig, NodeDescription, NodeInitError, Port, ResolvedMod},
+    curse_resolver::CurseManifestFile,
+    mod_resolver::sha256hash,
+    mod_writer::nix_manifest,
+    mrpack_builder::{build_index, index_dependencies, Index},
+    utils::{get_input, get_output, parse_loader},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct ManifestWriter {
+    /// Formats to write, each sent on the channel of the same name.
+    pub formats: Vec<OutputFormat>,
+    /// Name of the pack, required by the Modrinth, Curse and packwiz formats.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Version of the pack, required by the Modrinth, Curse and packwiz formats.
+    #[serde(default)]
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputFormat {
+    /// The Nix manifest written by ModWriter.
+    Nix,
+    /// The JSON manifest written by ModWriter.
+    Json,
+    /// A `modrinth.index.json` file.
+    MrpackIndex,
+    /// A Curse `manifest.json` file.
+    CurseManifest,
+    /// A packwiz `pack.toml` and `index.toml`, with a metafile for every mod.
+    PackwizIndex,
+}
+
+impl OutputFormat {
+    fn channel_type(self) -> ChannelType {
+        match self {
+            OutputFormat::PackwizIndex => ChannelType::Files,
+            _ => ChannelType::Text,
+        }
+    }
+
+    /// Whether the format describes a whole pack, rather than only its mods.
+    fn is_pack(self) -> bool {
+        matches!(self, OutputFormat::MrpackIndex | OutputFormat::CurseManifest | OutputFormat::PackwizIndex)
+    }
+}
+
+impl Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            OutputFormat::Nix => "nix",
+            OutputFormat::Json => "json",
+            OutputFormat::MrpackIndex => "mrpack-index",
+            OutputFormat::CurseManifest => "curse-manifest",
+            OutputFormat::PackwizIndex => "packwiz-index",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Output channels, one for each requested format.
+#[derive(Default)]
+struct Outputs {
+    nix: Option<(String, tokio::sync::broadcast::Sender<String>)>,
+    json: Option<tokio::sync::broadcast::Sender<String>>,
+    mrpack: Option<(Index, tokio::sync::broadcast::Sender<String>)>,
+    curse: Option<(CurseManifest, tokio::sync::broadcast::Sender<String>)>,
+    packwiz: Option<(PackwizPack, tokio::sync::broadcast::Sender<FileTree>)>,
+}
+
+impl NodeConfig for ManifestWriter {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut mods_channel = get_input!("mods", ResolvedMods, ctx, input_ids)?;
+        if self.formats.is_empty() {
+            return Err(NodeInitError::InvalidOption {
+                option: "formats".into(),
+                reason: "at least one format is required".into(),
+            });
+        }
+        let pack = |option: &str, value: &Option<String>| {
+            value.clone().ok_or_else(|| NodeInitError::InvalidOption {
+                option: option.into(),
+                reason: "required by the mrpack-index, curse-manifest and packwiz-index formats".into(),
+            })
+        };
+        let config = |key: &str| ctx.get_config(key).ok_or_else(|| NodeInitError::MissingConfig(key.into()));
+        let channel_id = |format: OutputFormat| ChannelId(node_id.clone(), format.to_string());
+
+        let mut outputs = Outputs::default();
+        for format in &self.formats {
+            let (name, version) = match format.is_pack() {
+                true => (pack("name", &self.name)?, pack("version", &self.version)?),
+                false => (String::new(), String::new()),
+            };
+            match format {
+                OutputFormat::Nix => outputs.nix = Some((config("minecraft_version")?, get_output!(channel_id(*format), Text, ctx)?)),
+                OutputFormat::Json => outputs.json = Some(get_output!(channel_id(*format), Text, ctx)?),
+                OutputFormat::MrpackIndex => {
+                    // Every file is downloaded to compute the hashes the index needs
+                    if ctx.is_offline() {
+                        return Err(NodeInitError::Offline);
+                    }
+                    let index = Index::new(name, version, None, index_dependencies(ctx)?);
+                    outputs.mrpack = Some((index, get_output!(channel_id(*format), Text, ctx)?));
+                },
+                OutputFormat::CurseManifest => {
+                    let (modloader, modloader_version) = loader(ctx)?;
+                    let manifest = CurseManifest {
+                        minecraft: CurseMinecraft {
+                            version: config("minecraft_version")?,
+                            mod_loaders: vec![CurseModLoader {
+                                id: format!("{modloader}-{modloader_version}"),
+                                primary: true,
+                            }],
+                        },
+                        manifest_type: "minecraftModpack",
+                        manifest_version: 1,
+                        name,
+                        version,
+                        author: String::new(),
+                        files: vec![],
+                        overrides: "overrides",
+                    };
+                    outputs.curse = Some((manifest, get_output!(channel_id(*format), Text, ctx)?));
+                },
+                OutputFormat::PackwizIndex => {
+                    let (modloader, modloader_version) = loader(ctx)?;
+                    let pack = PackwizPack {
+                        name,
+                        version,
+                        pack_format: "packwiz:1.1.0",
+                        index: PackwizIndexRef {
+                            file: "index.toml",
+                            hash_format: "sha256",
+                            hash: String::new(),
+                        },
+                        versions: BTreeMap::from([("minecraft".to_owned(), config("minecraft_version")?), (modloader, modloader_version)]),
+                    };
+                    outputs.packwiz = Some((pack, get_output!(channel_id(*format), Files, ctx)?));
+                },
+            }
+        }
+        let downloader = ctx.get_downloader();
+        let store = ctx.get_filestore();
+        let pool = ctx.get_pool();
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "ManifestWriter", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let mut mods = mods_channel.blocking_recv().expect_or_log("Failed to receive on mods input");
+            mods.sort_by(|a, b| a.name.cmp(&b.name));
+
+            if let Some((minecraft_version, channel)) = outputs.nix {
+                send(&channel, nix_manifest(&minecraft_version, &mods, true), OutputFormat::Nix);
+            }
+            if let Some(channel) = outputs.json {
+                let json = serde_json::to_string_pretty(&mods).expect_or_log("Serialization of resolved mods to JSON failed");
+                send(&channel, json, OutputFormat::Json);
+            }
+            if let Some((index, channel)) = outputs.mrpack {
+                let index = pool.install(|| build_index(index, &mods, &downloader));
+                send(&channel, index, OutputFormat::MrpackIndex);
+            }
+            if let Some((mut manifest, channel)) = outputs.curse {
+                let mut skipped = vec![];
+                for m in &mods {
+                    match m.source {
+                        Some(ModSource::Curse { project_id, file_id }) => manifest.files.push(CurseManifestFile {
+                            project_id,
+                            file_id,
+                            required: m.required,
+                        }),
+                        _ => skipped.push(m.name.as_str()),
+                    }
+                }
+                if !skipped.is_empty() {
+                    event!(
+                        Level::WARN,
+                        "Curse manifests can only list Curse mods, leaving out:\n- {}",
+                        skipped.join("\n- ")
+                    );
+                }
+                let manifest = serde_json::to_string_pretty(&manifest).expect_or_log("Failed to serialize Curse manifest");
+                send(&channel, manifest, OutputFormat::CurseManifest);
+            }
+            if let Some((pack, channel)) = outputs.packwiz {
+                send(&channel, packwiz_tree(pack, &mods, store), OutputFormat::PackwizIndex);
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        self.formats
+            .iter()
+            .map(|format| {
+                let channel = match format.channel_type() {
+                    ChannelType::Files => InputType::Files(channel(1).0),
+                    _ => InputType::Text(channel(1).0),
+                };
+                (ChannelId(node_id.to_owned(), format.to_string()), channel)
+            })
+            .collect()
+    }
+
+    fn describe(&self) -> NodeDescription {
+        let mut config_keys = vec![];
+        if self.formats.iter().any(|format| *format != OutputFormat::Json) {
+            config_keys.push("minecraft_version");
+        }
+        if self.formats.iter().any(|format| format.is_pack()) {
+            config_keys.extend(["modloader", "modloader_version"]);
+        }
+        NodeDescription::new(
+            "ManifestWriter",
+            vec![Port::required("mods", ChannelType::ResolvedMods)],
+            vec![
+                Port::optional("nix", ChannelType::Text),
+                Port::optional("json", ChannelType::Text),
+                Port::optional("mrpack-index", ChannelType::Text),
+                Port::optional("curse-manifest", ChannelType::Text),
+                Port::optional("packwiz-index", ChannelType::Files),
+            ],
+        )
+        .with_config_keys(config_keys)
+    }
+}
+
+fn send<T>(channel: &tokio::sync::broadcast::Sender<T>, value: T, format: OutputFormat) {
+    if channel.send(value).is_err() {
+        event!(Level::DEBUG, "Channel '{}' has no subscribers", format);
+    }
+}
+
+/// The modloader and its version from the pack config, with the loader named as Curse and
+/// packwiz name it.
+fn loader(ctx: &DiContainer) -> Result<(String, String), NodeInitError> {
+    let config = |key: &str| ctx.get_config(key).ok_or_else(|| NodeInitError::MissingConfig(key.into()));
+    let modloader = parse_loader("modloader", &config("modloader")?)?;
+    Ok((modloader.name().to_owned(), config("modloader_version")?))
+}
+
+/// The `manifest.json` file of a Curse pack.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CurseManifest {
+    minecraft: CurseMinecraft,
+    manifest_type: &'static str,
+    manifest_version: u32,
+    name: String,
+    version: String,
+    author: String,
+    files: Vec<CurseManifestFile>,
+    overrides: &'static str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CurseMinecraft {
+    version: String,
+    mod_loaders: Vec<CurseModLoader>,
+}
+
+#[derive(Serialize)]
+struct CurseModLoader {
+    id: String,
+    primary: bool,
+}
+
+/// The `pack.toml` file of a packwiz pack.
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct PackwizPack {
+    name: String,
+    version: String,
+    pack_format: &'static str,
+    index: PackwizIndexRef,
+    versions: BTreeMap<String, String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct PackwizIndexRef {
+    file: &'static str,
+    hash_format: &'static str,
+    hash: String,
+}
+
+/// The `index.toml` file of a packwiz pack.
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct PackwizIndex {
+    hash_format: &'static str,
+    files: Vec<PackwizIndexFile>,
+}
+
+#[derive(Serialize)]
+struct PackwizIndexFile {
+    file: String,
+    hash: String,
+    metafile: bool,
+}
+
+/// A `.pw.toml` metafile describing where a mod is downloaded from.
+#[derive(Serialize)]
+struct PackwizMod<'a> {
+    name: &'a str,
+    filename: &'a str,
+    side: String,
+    download: PackwizDownload<'a>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    option: Option<PackwizOption>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    update: Option<PackwizUpdate>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct PackwizDownload<'a> {
+    url: &'a str,
+    hash_format: &'static str,
+    hash: &'a str,
+}
+
+#[derive(Serialize)]
+struct PackwizOption {
+    optional: bool,
+    default: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "lowercase")]
+enum PackwizUpdate {
+    Modrinth {
+        #[serde(rename = "mod-id")]
+        mod_id: String,
+        version: String,
+    },
+    Curseforge {
+        #[serde(rename = "file-id")]
+        file_id: u32,
+        #[serde(rename = "project-id")]
+        project_id: u32,
+    },
+}
+
+/// Write a packwiz pack with a metafile for every mod, and the index and pack files that list them.
+fn packwiz_tree(mut pack: PackwizPack, mods: &[ResolvedMod], store: crate::file::filestore::FileStore) -> FileTree {
+    let mut tree = FileTree::new(store);
+    let mut index = PackwizIndex {
+        hash_format: "sha256",
+        files: vec![],
+    };
+    for m in mods {
+        let metafile = PackwizMod {
+            name: &m.title,
+            filename: &m.filename,
+            side: m.side.to_string(),
+            download: PackwizDownload {
+                url: &m.src,
+                hash_format: "sha256",
+                hash: &m.sha256,
+            },
+            option: (!m.required).then_some(PackwizOption {
+                optional: true,
+                default: m.default,
+            }),
+            update: match m.source {
+                Some(ModSource::Modrinth {
+                    ref project_id,
+                    ref version_id,
+                }) => Some(PackwizUpdate::Modrinth {
+                    mod_id: project_id.clone(),
+                    version: version_id.clone(),
+                }),
+                Some(ModSource::Curse { project_id, file_id }) => Some(PackwizUpdate::Curseforge { file_id, project_id }),
+                Some(ModSource::Url) | None => None,
+            },
+        };
+        let metafile = toml::to_string(&metafile).expect_or_log("Failed to serialize packwiz metafile");
+        let path = format!("{}/{}.pw.toml", m.kind.directory(), m.name);
+        index.files.push(PackwizIndexFile {
+            file: path.clone(),
+            hash: sha256hash(&metafile),
+            metafile: true,
+        });
+        tree.add_file(
+            FilePath::from_str(&path).expect_or_log("Invalid packwiz metafile path"),
+            metafile.into_bytes(),
+        );
+    }
+    index.files.sort_by(|a, b| a.file.cmp(&b.file));
+    let index = toml::to_string(&index).expect_or_log("Failed to serialize packwiz index");
+    pack.index.hash = sha256hash(&index);
+    let pack = toml::to_string(&pack).expect_or_log("Failed to serialize packwiz pack");
+    tree.add_file(FilePath::from_str("index.toml").unwrap_or_log(), index.into_bytes());
+    tree.add_file(FilePath::from_str("pack.toml").unwrap_or_log(), pack.into_bytes());
+    tree
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json::{json, Value};
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel, test_mod},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_manifest_writer() {
+        let node_id = "manifests";
+        let mods_channel = channel(1).0;
+        let input_ids = HashMap::from([("mods".into(), ChannelId::from_str("mod-source").unwrap())]);
+        let node = NodeConfigTypes::ManifestWriter(ManifestWriter {
+            formats: vec![OutputFormat::Json, OutputFormat::CurseManifest, OutputFormat::PackwizIndex],
+            name: Some("Test Pack".into()),
+            version: Some("1.0.0".into()),
+        });
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("mod-source").unwrap(),
+                InputType::ResolvedMods(mods_channel.clone()),
+            )]))
+            .set_config("minecraft_version", "1.20.1")
+            .set_config("modloader", "forge")
+            .set_config("modloader_version", "47.2.0")
+            .build();
+        assert!(ctx.get_receiver(&ChannelId::from_str("manifests::nix").unwrap()).is_none());
+
+        let mut json_channel = get_output_test!(ChannelId::from_str("manifests::json").unwrap(), Text, ctx);
+        let mut curse_channel = get_output_test!(ChannelId::from_str("manifests::curse-manifest").unwrap(), Text, ctx);
+        let mut packwiz_channel = get_output_test!(ChannelId::from_str("manifests::packwiz-index").unwrap(), Files, ctx);
+
+        let mut curse = test_mod("mouse-tweaks");
+        curse.source = Some(ModSource::Curse {
+            project_id: 60089,
+            file_id: 4581240,
+        });
+        let mut modrinth = test_mod("appleskin");
+        modrinth.required = false;
+        modrinth.sha256 = "abc".into();
+        modrinth.source = Some(ModSource::Modrinth {
+            project_id: "EsAfCjCV".into(),
+            version_id: "pyRMqaEV".into(),
+        });
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        mods_channel.send(vec![curse.clone(), modrinth.clone()]).unwrap();
+        handle.join().unwrap();
+
+        let timeout = Duration::from_secs(10);
+        let json: Vec<ResolvedMod> = serde_json::from_str(&read_channel(&mut json_channel, timeout).unwrap()).unwrap();
+        assert_eq!(json, vec![modrinth, curse]);
+
+        let manifest: Value = serde_json::from_str(&read_channel(&mut curse_channel, timeout).unwrap()).unwrap();
+        assert_eq!(
+            manifest,
+            json!({
+                "minecraft": {"version": "1.20.1", "modLoaders": [{"id": "forge-47.2.0", "primary": true}]},
+                "manifestType": "minecraftModpack",
+                "manifestVersion": 1,
+                "name": "Test Pack",
+                "version": "1.0.0",
+                "author": "",
+                "files": [{"projectID": 60089, "fileID": 4581240, "required": true}],
+                "overrides": "overrides",
+            })
+        );
+
+        let packwiz = read_channel(&mut packwiz_channel, timeout).unwrap();
+        let file = |path: &str| String::from_utf8(packwiz.get_file(&FilePath::from_str(path).unwrap()).unwrap().to_vec()).unwrap();
+        let metafile = file("mods/appleskin.pw.toml");
+        assert_eq!(
+            metafile,
+            r#"name = "appleskin"
+filename = "appleskin.jar"
+side = "both"
+
+[download]
+url = "https://example.com/appleskin.jar"
+hash-format = "sha256"
+hash = "abc"
+
+[option]
+optional = true
+default = true
+
+[update.modrinth]
+mod-id = "EsAfCjCV"
+version = "pyRMqaEV"
+"#
+        );
+        let index = file("index.toml");
+        assert!(index.contains(&format!("file = \"mods/appleskin.pw.toml\"\nhash = \"{}\"", sha256hash(&metafile))));
+        let pack = file("pack.toml");
+        assert!(pack.contains(&format!("hash = \"{}\"", sha256hash(&index))));
+        assert!(pack.contains("[versions]\nforge = \"47.2.0\"\nminecraft = \"1.20.1\""));
+    }
+
+    #[test]
+    fn pack_formats_need_name() {
+        let node = NodeConfigTypes::ManifestWriter(ManifestWriter {
+            formats: vec![OutputFormat::MrpackIndex],
+            name: None,
+            version: Some("1.0.0".into()),
+        });
+        let ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels("manifests"))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("mod-source").unwrap(),
+                InputType::ResolvedMods(channel(1).0),
+            )]))
+            .build();
+        let input_ids = HashMap::from([("mods".into(), ChannelId::from_str("mod-source").unwrap())]);
+        let result = node.validate_and_spawn("manifests".into(), &input_ids, &ctx);
+        assert!(matches!(result, Err(NodeInitError::InvalidOption { option, .. }) if option == "name"));
+    }
+}
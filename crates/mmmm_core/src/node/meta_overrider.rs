@@ -0,0 +1,199 @@
+use std::{
+    collections::HashMap,
+    thread::{spawn, JoinHandle},
+};
+
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+use urlencoding::encode;
+
+use crate::di::container::{DiContainer, InputType, OutputType};
+
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port, ResolvedMod, Side},
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct MetaOverrider;
+
+/// Metadata to override on a single resolved mod. Unset fields are left unchanged.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct MetaOverride {
+    name: String,
+    title: Option<String>,
+    side: Option<Side>,
+    required: Option<bool>,
+    default: Option<bool>,
+    filename: Option<String>,
+    src: Option<String>,
+}
+
+impl MetaOverride {
+    fn apply(&self, m: &mut ResolvedMod) {
+        if let Some(ref title) = self.title {
+            m.title = title.clone();
+        }
+        if let Some(side) = self.side {
+            m.side = side;
+        }
+        if let Some(required) = self.required {
+            m.required = required;
+        }
+        if let Some(default) = self.default {
+            m.default = default;
+        }
+        if let Some(ref filename) = self.filename {
+            m.encoded = encode(filename).into_owned();
+            m.filename = filename.clone();
+        }
+        if let Some(ref src) = self.src {
+            m.src = src.clone();
+        }
+    }
+}
+
+impl NodeConfig for MetaOverrider {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut mods_channel = get_input!("mods", ResolvedMods, ctx, input_ids)?;
+        let mut overrides_channel = get_input!("overrides", Text, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), ResolvedMods, ctx)?;
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "MetaOverrider", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let mut mods = mods_channel.blocking_recv().expect_or_log("Failed to receive on mods input");
+            let overrides = overrides_channel.blocking_recv().expect_or_log("Failed to receive on overrides input");
+
+            let overrides: Vec<MetaOverride> = match serde_yaml::from_str(&overrides) {
+                Ok(overrides) => overrides,
+                Err(e) => {
+                    event!(Level::ERROR, "Failed to parse metadata overrides: {e}");
+                    panic!();
+                },
+            };
+            let mut overrides_map = overrides.into_iter().map(|o| (o.name.clone(), o)).collect::<HashMap<_, _>>();
+
+            mods.iter_mut().for_each(|m| {
+                if let Some(o) = overrides_map.get(&m.name) {
+                    o.apply(m);
+                }
+            });
+
+            overrides_map.retain(|name, _| !mods.iter().any(|m| &m.name == name));
+            if !overrides_map.is_empty() {
+                let mut unused = overrides_map.into_keys().collect::<Vec<_>>();
+                unused.sort();
+                event!(Level::WARN, "Metadata overrides match no mods: {}", unused.join(", "));
+            }
+
+            if out_channel.send(mods).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::ResolvedMods(channel(1).0))])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "MetaOverrider",
+            vec![
+                Port::required("mods", ChannelType::ResolvedMods),
+                Port::required("overrides", ChannelType::Text),
+            ],
+            vec![Port::required("default", ChannelType::ResolvedMods)],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel, test_mod},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_meta_overrider() {
+        let node_id = "overrider";
+        let mods_channel = channel(1).0;
+        let overrides_channel = channel::<String>(1).0;
+        let input_ids = HashMap::from([
+            ("mods".into(), ChannelId::from_str("mod-source").unwrap()),
+            ("overrides".into(), ChannelId::from_str("overrides-source").unwrap()),
+        ]);
+        let node = NodeConfigTypes::MetaOverrider(MetaOverrider);
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([
+                (ChannelId::from_str("mod-source").unwrap(), InputType::ResolvedMods(mods_channel.clone())),
+                (
+                    ChannelId::from_str("overrides-source").unwrap(),
+                    InputType::Text(overrides_channel.clone()),
+                ),
+            ]))
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), ResolvedMods, ctx);
+
+        let overrides = r#"---
+- name: jei
+  title: Just Enough Items
+  filename: jei mirror.jar
+  src: https://mirror.example.com/jei.jar
+- name: sodium
+  side: client
+  required: false
+- name: not-in-pack
+  title: Unused
+"#;
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        mods_channel
+            .send(vec![test_mod("jei"), test_mod("sodium"), test_mod("appleskin")])
+            .unwrap();
+        overrides_channel.send(overrides.into()).unwrap();
+        handle.join().unwrap();
+
+        let output = read_channel(&mut out_channel, Duration::from_secs(30)).unwrap();
+        let expected = vec![
+            ResolvedMod {
+                title: "Just Enough Items".into(),
+                filename: "jei mirror.jar".into(),
+                encoded: "jei%20mirror.jar".into(),
+                src: "https://mirror.example.com/jei.jar".into(),
+                ..test_mod("jei")
+            },
+            ResolvedMod {
+                side: Side::Client,
+                required: false,
+                ..test_mod("sodium")
+            },
+            test_mod("appleskin"),
+        ];
+        assert_eq!(output, expected);
+    }
+}
@@ -1,13 +1,68 @@
 pub mod archive_downloader;
+pub mod badge_writer;
+pub mod categorized_list_writer;
+pub mod cdn_warm_list;
+pub mod changelog_writer;
+pub mod client_pack;
 pub mod config;
+pub mod config_lint;
+pub mod curse_manifest_diff;
+pub mod dependency_graph;
 pub mod dir_merge;
 pub mod mod_merge;
+pub mod extension_whitelist;
+pub mod file_count_guard;
 pub mod file_filter;
 pub mod file_picker;
+pub mod group_split;
+pub mod hash_drift_check;
+pub mod html_viewer;
+pub mod incompatibility_resolver;
+pub mod install_instructions;
+pub mod jar_recompress;
+pub mod jar_validate;
+pub mod json_canonicalize;
+pub mod json_value_replace;
+pub mod junk_filter;
+pub mod known_issues;
+pub mod link_check;
+pub mod list_merge;
+pub mod loader_consistency_check;
+pub mod loader_meta_fixup;
+pub mod lock_verify;
+pub mod manifest_writer;
+pub mod meta_overrider;
+pub mod mod_dedup;
 pub mod mod_resolver;
 pub mod mod_overrider;
 pub mod mod_filter;
+pub mod mod_search;
 pub mod curse_resolver;
 pub mod mod_writer;
+pub mod modlist_html_writer;
+pub mod modlist_to_source;
+pub mod modrinth_collection;
+pub mod modrinth_pack_source;
+pub mod mrpack_builder;
+pub mod optional_mod_index;
+pub mod pack_assembler;
+pub mod pack_format_fixup;
+pub mod pack_meta_reader;
+pub mod pack_stats;
+pub mod pin_audit;
+pub mod remote_mod_list;
+pub mod require_mods;
+pub mod resource_pack_merge;
+pub mod server_script_writer;
+pub mod shadow_report;
+pub mod shell_transform;
+pub mod side_override_split;
 pub mod source;
+pub mod staleness_filter;
+pub mod template_render;
+pub mod three_way_merge;
+pub mod tree_diff;
+pub mod url_list_expander;
+pub mod url_proxy;
+pub mod verified_downloader;
 pub(super) mod utils;
@@ -0,0 +1,185 @@
+use std::{
+    collections::HashMap,
+    thread::{spawn, JoinHandle},
+};
+
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::di::container::{DiContainer, InputType, OutputType};
+
+use super::{
+    config::{ChannelId, ChannelType, ModDefinition, NodeConfig, NodeDescription, NodeInitError, Port},
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct ModDedup;
+
+impl NodeConfig for ModDedup {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut mods_channel = get_input!("mods", Mods, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Mods, ctx)?;
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "ModDedup", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let mods = mods_channel.blocking_recv().expect_or_log("Failed to receive on mods input");
+
+            if out_channel.send(dedup_mods(mods)).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::Mods(channel(1).0))])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "ModDedup",
+            vec![Port::required("mods", ChannelType::Mods)],
+            vec![Port::required("default", ChannelType::Mods)],
+        )
+    }
+}
+
+/// Identities a mod definition can be matched on before it is resolved.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum ModKey {
+    Name(String),
+    Modrinth(String),
+    Curse(u32),
+    Url(String),
+}
+
+/// Normalize a mod name so that differences in case and separators do not hide a duplicate.
+fn normalize_name(name: &str) -> String {
+    name.trim().to_lowercase().replace(['_', ' '], "-")
+}
+
+fn mod_keys(def: &ModDefinition) -> Vec<ModKey> {
+    let name = normalize_name(&def.get_fields().name);
+    let source_key = match def {
+        // Without an ID, the Modrinth resolver looks the project up by its name as a slug
+        ModDefinition::Modrinth { id, .. } => Some(ModKey::Modrinth(id.clone().unwrap_or_else(|| name.clone()))),
+        ModDefinition::Curse { id, .. } => id.map(ModKey::Curse),
+        ModDefinition::Url { location, .. } => Some(ModKey::Url(location.clone())),
+    };
+    std::iter::once(ModKey::Name(name)).chain(source_key).collect()
+}
+
+/// Remove mod definitions that share a normalized name or project ID with an earlier definition,
+/// keeping the first occurrence.
+fn dedup_mods(mods: Vec<ModDefinition>) -> Vec<ModDefinition> {
+    let mut seen: HashMap<ModKey, String> = HashMap::new();
+    let mut deduped = Vec::with_capacity(mods.len());
+    for def in mods {
+        let keys = mod_keys(&def);
+        let name = &def.get_fields().name;
+        if let Some(kept) = keys.iter().find_map(|key| seen.get(key)) {
+            event!(Level::WARN, "Mod '{}' duplicates earlier entry '{}', dropping it", name, kept);
+            continue;
+        }
+        keys.into_iter().for_each(|key| {
+            seen.insert(key, name.clone());
+        });
+        deduped.push(def);
+    }
+    deduped
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use tokio::sync::broadcast;
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_mod_dedup() {
+        let node_id = "dedup";
+        let mods_channel = broadcast::channel(1).0;
+        let input_ids = HashMap::from([("mods".into(), ChannelId::from_str("mod-source").unwrap())]);
+        let node = NodeConfigTypes::ModDedup(ModDedup);
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("mod-source").unwrap(),
+                InputType::Mods(mods_channel.clone()),
+            )]))
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Mods, ctx);
+
+        let mods: Vec<ModDefinition> = serde_yaml::from_str(
+            r#"---
+- name: appleskin
+  source: modrinth
+- name: AppleSkin
+  source: curse
+- name: sodium
+  source: modrinth
+  id: AANobbMI
+- name: sodium-fabric
+  source: modrinth
+  id: AANobbMI
+- name: worldedit
+  source: curse
+  id: 225608
+- name: world-edit
+  source: curse
+  id: 225608
+- name: mouse_tweaks
+  source: curse
+- name: title-changer
+  source: url
+  location: https://example.com/titlechanger.jar
+- name: title-changer-copy
+  source: url
+  location: https://example.com/titlechanger.jar
+- name: jei
+  source: curse
+  id: 238222
+- name: jei-addon
+  source: curse
+  id: 999999
+"#,
+        )
+        .unwrap();
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+
+        ctx.run().unwrap();
+        mods_channel.send(mods).unwrap();
+        handle.join().unwrap();
+
+        let output = read_channel(&mut out_channel, Duration::from_secs(10)).unwrap();
+        let names = output.iter().map(|m| m.get_fields().name.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec!["appleskin", "sodium", "worldedit", "mouse_tweaks", "title-changer", "jei", "jei-addon"]
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use std::{collections::HashMap, thread::{spawn, JoinHandle}};
 
+use glob_match::glob_match;
 use serde::Deserialize;
 use tokio::sync::broadcast::channel;
 use tracing::{event, span, Level};
@@ -7,10 +8,17 @@ use tracing_unwrap::ResultExt;
 
 use crate::di::container::{DiContainer, InputType, OutputType};
 
-use super::{config::{ChannelId, NodeConfig, NodeInitError}, utils::{get_input, get_output}};
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port},
+    utils::{get_input, get_output},
+};
 
-#[derive(Debug, Clone, Deserialize, PartialEq)]
-pub struct ModFilter;
+#[derive(Debug, Clone, Deserialize, PartialEq, Default)]
+pub struct ModFilter {
+    /// Treat filters as glob patterns, such as `jei*`, instead of exact mod names.
+    #[serde(default)]
+    pub glob: bool,
+}
 
 impl NodeConfig for ModFilter {
     fn validate_and_spawn(
@@ -23,6 +31,7 @@ impl NodeConfig for ModFilter {
         let mut filter_channel = get_input!("filters", List, ctx, input_ids)?;
         let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), ResolvedMods, ctx)?;
         let inverse_channel = get_output!(ChannelId(node_id.clone(), "inverse".into()), ResolvedMods, ctx)?;
+        let glob = self.glob;
         let mut waker = ctx.get_waker();
         Ok(spawn(move || {
             let _span = span!(Level::INFO, "ModFilter", nodeid = node_id).entered();
@@ -34,7 +43,10 @@ impl NodeConfig for ModFilter {
             let mut filters = filter_channel.blocking_recv().expect_or_log("Failed to receive on filters input");
             filters.sort();
 
-            let (included, excluded): (Vec<_>, Vec<_>) = mods.clone().into_iter().partition(|m| filters.binary_search(&m.name).is_ok());
+            let (included, excluded): (Vec<_>, Vec<_>) = match glob {
+                true => mods.into_iter().partition(|m| filters.iter().any(|pattern| glob_match(pattern, &m.name))),
+                false => mods.into_iter().partition(|m| filters.binary_search(&m.name).is_ok()),
+            };
 
 
             if out_channel.send(included).is_err() {
@@ -53,6 +65,20 @@ impl NodeConfig for ModFilter {
             (ChannelId(node_id.to_owned(), "inverse".into()), InputType::ResolvedMods(channel(1).0)),
         ])
     }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "ModFilter",
+            vec![
+                Port::required("mods", ChannelType::ResolvedMods),
+                Port::required("filters", ChannelType::List),
+            ],
+            vec![
+                Port::required("default", ChannelType::ResolvedMods),
+                Port::required("inverse", ChannelType::ResolvedMods),
+            ],
+        )
+    }
 }
 
 #[cfg(test)]
@@ -61,7 +87,13 @@ mod tests {
 
     use tokio::sync::broadcast;
 
-    use crate::{di::container::DiContainerBuilder, node::{config::{NodeConfigTypes, ResolvedMod, Side}, utils::{get_output_test, read_channel}}};
+    use crate::{
+        di::container::DiContainerBuilder,
+        node::{
+            config::{NodeConfigTypes, ProjectKind, ResolvedMod, Side},
+            utils::{get_output_test, read_channel, test_mod},
+        },
+    };
 
     use super::*;
 
@@ -74,10 +106,10 @@ mod tests {
             ("mods".into(), ChannelId::from_str("mod-source").unwrap()),
             ("filters".into(), ChannelId::from_str("filter-source").unwrap()),
         ]);
-        let node = NodeConfigTypes::ModFilter(ModFilter);
+        let node = NodeConfigTypes::ModFilter(ModFilter::default());
 
         let mut ctx = DiContainerBuilder::default()
-            .channel_from_node(node.generate_channels(&node_id))
+            .channel_from_node(node.generate_channels(node_id))
             .channel_from_node(HashMap::from([
                     (ChannelId::from_str("mod-source").unwrap(), InputType::ResolvedMods(mods_channel.clone())),
                     (ChannelId::from_str("filter-source").unwrap(), InputType::List(filters_channel.clone())),
@@ -100,6 +132,9 @@ mod tests {
                 size: 33683,
                 md5: "b435860d5cfa23bc53d3b8e120be91d4".to_owned(),
                 sha256: "4bbd37edecff0b420ab0eea166b5d7b4b41a9870bfb8647bf243140dc57f101e".to_owned(),
+                source: None,
+                kind: ProjectKind::Mod,
+                group: None,
             },
             ResolvedMod {
                 title: "Mouse Tweaks".to_owned(),
@@ -113,6 +148,9 @@ mod tests {
                 size: 80528,
                 md5: "a6034d3ff57091c78405e46f1f926282".to_owned(),
                 sha256: "5e13315f4e0d0c96b1f9b800a42fecb89f519aca81d556c91df617c8751aa575".to_owned(),
+                source: None,
+                kind: ProjectKind::Mod,
+                group: None,
             },
             ResolvedMod {
                 title: "title-changer".to_owned(),
@@ -126,6 +164,9 @@ mod tests {
                 size: 5923,
                 md5: "8fda92da93d78919cff1139e847d3e1c".to_owned(),
                 sha256: "78bbe270f2f2ca443a4e794ee1f0c5920ef933ce1030bae0dcff45cb16689eb7".to_owned(),
+                source: None,
+                kind: ProjectKind::Mod,
+                group: None,
             },
         ];
 
@@ -149,4 +190,46 @@ mod tests {
         assert_eq!(output, expected);
         assert_eq!(inverse, inverse_expected);
     }
+
+    #[test]
+    fn test_mod_filter_glob() {
+        let node_id = "filter";
+        let mods_channel = broadcast::channel(1).0;
+        let filters_channel = broadcast::channel(1).0;
+        let input_ids = HashMap::from([
+            ("mods".into(), ChannelId::from_str("mod-source").unwrap()),
+            ("filters".into(), ChannelId::from_str("filter-source").unwrap()),
+        ]);
+        let node = NodeConfigTypes::ModFilter(ModFilter { glob: true });
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([
+                (ChannelId::from_str("mod-source").unwrap(), InputType::ResolvedMods(mods_channel.clone())),
+                (ChannelId::from_str("filter-source").unwrap(), InputType::List(filters_channel.clone())),
+            ]))
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str("filter").unwrap(), ResolvedMods, ctx);
+        let mut inverse_channel = get_output_test!(ChannelId::from_str("filter::inverse").unwrap(), ResolvedMods, ctx);
+
+        let resolved_mods: Vec<ResolvedMod> = ["jei", "jei-addons", "appleskin", "sodium-client", "sodium"]
+            .into_iter()
+            .map(test_mod)
+            .collect();
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+
+        ctx.run().unwrap();
+        mods_channel.send(resolved_mods.clone()).unwrap();
+        filters_channel.send(vec!["jei*".to_owned(), "*-client".to_owned()]).unwrap();
+        handle.join().unwrap();
+
+        let timeout = Duration::from_secs(30);
+        let output: Vec<ResolvedMod> = read_channel(&mut out_channel, timeout).unwrap();
+        let inverse: Vec<ResolvedMod> = read_channel(&mut inverse_channel, timeout).unwrap();
+
+        assert_eq!(output, vec![test_mod("jei"), test_mod("jei-addons"), test_mod("sodium-client")]);
+        assert_eq!(inverse, vec![test_mod("appleskin"), test_mod("sodium")]);
+    }
 }
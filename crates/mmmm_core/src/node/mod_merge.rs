@@ -11,7 +11,7 @@ use tracing_unwrap::{OptionExt, ResultExt};
 use crate::di::container::{DiContainer, InputType, OutputType};
 
 use super::{
-    config::{ChannelId, NodeConfig, NodeInitError, ResolvedMod},
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port, ResolvedMod},
     utils::{get_input, get_output},
 };
 
@@ -52,7 +52,7 @@ impl NodeConfig for ModMerger {
                         .collect::<HashMap<_, _>>()
                 })
                 .reduce(|mut res, cur| {
-                    res.extend(cur.into_iter());
+                    res.extend(cur);
                     res
                 })
                 .expect_or_log(&format!("No inputs passed to ModMerger node with id {node_id}"))
@@ -67,6 +67,11 @@ impl NodeConfig for ModMerger {
     fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
         HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::ResolvedMods(channel(1).0))])
     }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new("ModMerger", vec![], vec![Port::required("default", ChannelType::ResolvedMods)])
+            .with_any_inputs(ChannelType::ResolvedMods)
+    }
 }
 
 #[cfg(test)]
@@ -76,7 +81,7 @@ mod tests {
     use crate::{
         di::container::DiContainerBuilder,
         node::{
-            config::{NodeConfigTypes, Side},
+            config::{NodeConfigTypes, ProjectKind, Side},
             utils::{get_output_test, read_channel},
         },
     };
@@ -98,6 +103,9 @@ mod tests {
             size: 0,
             md5: String::new(),
             sha256: String::new(),
+            source: None,
+            kind: ProjectKind::Mod,
+            group: None,
         }];
 
         let list2: Vec<ResolvedMod> = vec![
@@ -113,6 +121,9 @@ mod tests {
                 size: 0,
                 md5: String::new(),
                 sha256: String::new(),
+                source: None,
+                kind: ProjectKind::Mod,
+                group: None,
             },
             ResolvedMod {
                 name: "thaumcraft7".to_owned(),
@@ -126,6 +137,9 @@ mod tests {
                 size: 0,
                 md5: String::new(),
                 sha256: String::new(),
+                source: None,
+                kind: ProjectKind::Mod,
+                group: None,
             },
         ];
 
@@ -154,6 +168,9 @@ mod tests {
         ctx.run().unwrap();
 
         let mut result = read_channel(&mut out, Duration::from_secs(30)).unwrap();
-        assert_eq!(Vec::from([list1[0].clone(), list2[0].clone()]).sort(), result.sort());
+        let mut expected = Vec::from([list1[0].clone(), list2[0].clone()]);
+        expected.sort();
+        result.sort();
+        assert_eq!(expected, result);
     }
 }
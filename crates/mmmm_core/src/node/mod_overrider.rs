@@ -7,7 +7,10 @@ use tracing_unwrap::ResultExt;
 
 use crate::di::container::{DiContainer, InputType, OutputType};
 
-use super::{config::{ChannelId, NodeConfig, NodeInitError}, utils::{get_input, get_output}};
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port},
+    utils::{get_input, get_optional_input, get_output},
+};
 
 #[derive(Debug, Clone, Deserialize, PartialEq)]
 pub struct ModOverrider;
@@ -20,7 +23,8 @@ impl NodeConfig for ModOverrider {
         ctx: &DiContainer,
     ) -> Result<JoinHandle<()>, NodeInitError> {
         let mut mods_channel = get_input!("mods", ResolvedMods, ctx, input_ids)?;
-        let mut overrides_channel = get_input!("overrides", Mods, ctx, input_ids)?;
+        // The overrides input is optional, and mods are passed through unchanged when it is not connected
+        let mut overrides_channel = get_optional_input!("overrides", Mods, ctx, input_ids)?;
         let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), ResolvedMods, ctx)?;
         let mut waker = ctx.get_waker();
         Ok(spawn(move || {
@@ -30,7 +34,10 @@ impl NodeConfig for ModOverrider {
             }
 
             let mut mods = mods_channel.blocking_recv().expect_or_log("Failed to receive on mods input");
-            let overrides = overrides_channel.blocking_recv().expect_or_log("Failed to receive on overrides input");
+            let overrides = match overrides_channel {
+                Some(ref mut channel) => channel.blocking_recv().expect_or_log("Failed to receive on overrides input"),
+                None => Vec::new(),
+            };
 
             let overrides_map = overrides.into_iter()
                 .map(|m| (m.get_fields().name.clone(), m))
@@ -60,6 +67,17 @@ impl NodeConfig for ModOverrider {
             (ChannelId(node_id.to_owned(), "default".into()), InputType::ResolvedMods(channel(1).0)),
         ])
     }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "ModOverrider",
+            vec![
+                Port::required("mods", ChannelType::ResolvedMods),
+                Port::optional("overrides", ChannelType::Mods),
+            ],
+            vec![Port::required("default", ChannelType::ResolvedMods)],
+        )
+    }
 }
 
 #[cfg(test)]
@@ -68,7 +86,13 @@ mod tests {
 
     use tokio::sync::broadcast;
 
-    use crate::{di::container::DiContainerBuilder, node::{config::{ModDefinition, ModDefinitionFields, NodeConfigTypes, ResolvedMod, Side}, utils::{get_output_test, read_channel}}};
+    use crate::{
+        di::container::DiContainerBuilder,
+        node::{
+            config::{ModDefinition, ModDefinitionFields, NodeConfigTypes, ProjectKind, ResolvedMod, Side},
+            utils::{get_output_test, read_channel, test_mod},
+        },
+    };
 
     use super::*;
 
@@ -84,7 +108,7 @@ mod tests {
         let node = NodeConfigTypes::ModOverrider(ModOverrider);
 
         let mut ctx = DiContainerBuilder::default()
-            .channel_from_node(node.generate_channels(&node_id))
+            .channel_from_node(node.generate_channels(node_id))
             .channel_from_node(HashMap::from([
                     (ChannelId::from_str("mod-source").unwrap(), InputType::ResolvedMods(mods_channel.clone())),
                     (ChannelId::from_str("overrides-source").unwrap(), InputType::Mods(overrides_channel.clone())),
@@ -106,6 +130,9 @@ mod tests {
                 size: 33683,
                 md5: "b435860d5cfa23bc53d3b8e120be91d4".to_owned(),
                 sha256: "4bbd37edecff0b420ab0eea166b5d7b4b41a9870bfb8647bf243140dc57f101e".to_owned(),
+                source: None,
+                kind: ProjectKind::Mod,
+                group: None,
             },
             ResolvedMod {
                 title: "Mouse Tweaks".to_owned(),
@@ -119,6 +146,9 @@ mod tests {
                 size: 80528,
                 md5: "a6034d3ff57091c78405e46f1f926282".to_owned(),
                 sha256: "5e13315f4e0d0c96b1f9b800a42fecb89f519aca81d556c91df617c8751aa575".to_owned(),
+                source: None,
+                kind: ProjectKind::Mod,
+                group: None,
             },
             ResolvedMod {
                 title: "title-changer".to_owned(),
@@ -132,12 +162,37 @@ mod tests {
                 size: 5923,
                 md5: "8fda92da93d78919cff1139e847d3e1c".to_owned(),
                 sha256: "78bbe270f2f2ca443a4e794ee1f0c5920ef933ce1030bae0dcff45cb16689eb7".to_owned(),
+                source: None,
+                kind: ProjectKind::Mod,
+                group: None,
             },
         ];
 
         let filters = vec![
-            ModDefinition::Curse { id: None, file_id: None, fields: ModDefinitionFields { name: "appleskin".into(), side: Side::Client, required: Some(false), default: None } },
-            ModDefinition::Curse { id: None, file_id: None, fields: ModDefinitionFields { name: "mouse-tweaks".into(), side: Side::Client, required: None, default: Some(false) } },
+            ModDefinition::Curse {
+                id: None,
+                file_id: None,
+                exclude_versions: Vec::new(),
+                fields: ModDefinitionFields {
+                    name: "appleskin".into(),
+                    side: Side::Client,
+                    required: Some(false),
+                    default: None,
+                    group: None,
+                },
+            },
+            ModDefinition::Curse {
+                id: None,
+                file_id: None,
+                exclude_versions: Vec::new(),
+                fields: ModDefinitionFields {
+                    name: "mouse-tweaks".into(),
+                    side: Side::Client,
+                    required: None,
+                    default: Some(false),
+                    group: None,
+                },
+            },
         ];
 
         let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
@@ -158,4 +213,33 @@ mod tests {
 
         assert_eq!(output, expected);
     }
+
+    #[test]
+    fn test_mod_overrider_without_overrides() {
+        let node_id = "overrider";
+        let mods_channel = broadcast::channel(1).0;
+        let input_ids = HashMap::from([("mods".into(), ChannelId::from_str("mod-source").unwrap())]);
+        let node = NodeConfigTypes::ModOverrider(ModOverrider);
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("mod-source").unwrap(),
+                InputType::ResolvedMods(mods_channel.clone()),
+            )]))
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str("overrider").unwrap(), ResolvedMods, ctx);
+
+        let resolved_mods = vec![test_mod("appleskin"), test_mod("mouse-tweaks")];
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+
+        ctx.run().unwrap();
+        mods_channel.send(resolved_mods.clone()).unwrap();
+        handle.join().unwrap();
+
+        let output: Vec<ResolvedMod> = read_channel(&mut out_channel, Duration::from_secs(5)).unwrap();
+        assert_eq!(output, resolved_mods);
+    }
 }
@@ -1,18 +1,27 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
     sync::Arc,
     thread::{spawn, JoinHandle},
 };
 
 use api_client::{
-    common::{download_file, ApiError, DownloadError},
-    curse::{model::HashAlgo, CurseClient},
-    modrinth::ModrinthClient,
+    common::{ApiError, DownloadError, Downloader},
+    curse::{
+        model::{FileReleaseType, HashAlgo},
+        CurseClient,
+    },
+    modrinth::{
+        model::{GameVersionType, Project, ProjectType, VersionFile, VersionType},
+        ModrinthClient,
+    },
 };
+use chrono::Utc;
 use digest::Digest;
 use md5::Md5;
 use rayon::prelude::*;
-use serde::Deserialize;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
 use sha2::Sha256;
 use thiserror::Error;
 use tokio::sync::broadcast::channel;
@@ -22,16 +31,46 @@ use urlencoding::encode;
 
 use crate::{
     di::container::{DiContainer, InputType, OutputType},
+    template::{render, TemplateError},
     Cache, CacheError,
 };
 
 use super::{
-    config::{ChannelId, ModDefinition, ModDefinitionFields, NodeConfig, NodeInitError, ResolvedMod},
-    utils::{get_input, get_output},
+    config::{
+        ChannelId, ChannelType, ModDefinition, ModDefinitionFields, ModSource, NodeConfig, NodeDescription, NodeInitError, Port, ProjectKind,
+        ResolvedMod,
+    },
+    utils::{cache_namespace, get_input, get_output, VersionRange, CACHE_PREFIX},
 };
 
 #[derive(Debug, Clone, Deserialize, PartialEq)]
-pub struct ModResolver;
+pub struct ModResolver {
+    /// Filename fragments marking Modrinth files (sources, javadoc, dev jars) that should only be
+    /// picked when a version has no other files.
+    #[serde(default = "default_modrinth_exclusions")]
+    pub modrinth_exclusions: Vec<String>,
+    /// How long to remember that a Curse or Modrinth mod could not be found, in seconds. Until
+    /// then, resolving it fails without querying the API. Unset disables remembering.
+    pub missing_cache_ttl: Option<u64>,
+    /// Fail if any Curse or Modrinth mod would resolve to a beta or alpha file. Unpinned mods use
+    /// their newest release instead, and only fail if they have none.
+    #[serde(default)]
+    pub require_stable: bool,
+}
+
+fn default_modrinth_exclusions() -> Vec<String> {
+    vec!["-sources".into(), "-javadoc".into(), "-dev".into()]
+}
+
+impl Default for ModResolver {
+    fn default() -> Self {
+        ModResolver {
+            modrinth_exclusions: default_modrinth_exclusions(),
+            missing_cache_ttl: None,
+            require_stable: false,
+        }
+    }
+}
 
 impl NodeConfig for ModResolver {
     fn validate_and_spawn(
@@ -43,6 +82,7 @@ impl NodeConfig for ModResolver {
         let mut mod_channel = get_input!("mods", Mods, ctx, input_ids)?;
         let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), ResolvedMods, ctx)?;
 
+        let pool = ctx.get_pool();
         let mut waker = ctx.get_waker();
 
         let minecraft_version = ctx
@@ -51,10 +91,34 @@ impl NodeConfig for ModResolver {
         let modloader = ctx
             .get_config("modloader")
             .ok_or_else(|| NodeInitError::MissingConfig("modloader".into()))?;
+        let version_range = ctx
+            .get_config("minecraft_version_range")
+            .map(|expression| {
+                let range = expression.parse::<VersionRange>().map_err(|reason| NodeInitError::InvalidOption {
+                    option: "minecraft_version_range".into(),
+                    reason,
+                })?;
+                Ok((expression, range))
+            })
+            .transpose()?;
 
         let curse_client_option = ctx.get_curse_client();
         let modrinth_client = ctx.get_modrinth_client();
-        let cache = ctx.get_cache();
+        let mut resolve_ctx = ResolveContext {
+            game_versions: version_range.as_ref().map(|(expression, _)| GameVersions {
+                expression: expression.clone(),
+                versions: vec![minecraft_version.clone()],
+            }),
+            minecraft_version,
+            modloader,
+            modrinth_exclusions: self.modrinth_exclusions.clone(),
+            missing_cache_ttl: self.missing_cache_ttl,
+            require_stable: self.require_stable,
+            downloader: ctx.get_downloader(),
+            cache: ctx.get_cache(),
+            cache_prefix: ctx.get_config(CACHE_PREFIX).filter(|prefix| !prefix.is_empty()),
+            offline: ctx.is_offline(),
+        };
         Ok(spawn(move || {
             let _span = span!(Level::INFO, "ModResolver", nodeid = node_id).entered();
             if !waker.blocking_recv().unwrap_or_log() {
@@ -64,6 +128,23 @@ impl NodeConfig for ModResolver {
             let mods = mod_channel.blocking_recv().expect_or_log("Failed to receive on mods input");
             event!(Level::INFO, "Got {} mods to resolve", mods.len());
 
+            // Offline, only cached results are used, so the range never needs to be expanded
+            if let (Some((_, range)), Some(game_versions), false) = (&version_range, &mut resolve_ctx.game_versions, resolve_ctx.offline) {
+                let known = modrinth_client
+                    .get_game_versions()
+                    .expect_or_log("Failed to get Minecraft versions from Modrinth");
+                let releases = known
+                    .iter()
+                    .filter(|v| v.version_type == GameVersionType::Release)
+                    .map(|v| v.version.as_str());
+                for version in range.expand(releases) {
+                    if !game_versions.versions.contains(&version) {
+                        game_versions.versions.push(version);
+                    }
+                }
+                event!(Level::INFO, "Resolving Modrinth mods for Minecraft {}", game_versions.versions.join(", "));
+            }
+
             // Check if the Curse API is needed, but the client wasn't configured. Logs an error
             // message then terminates the thread, so if execution continues past this block we know it is safe to
             // call .unwrap() on the Curse client Option.
@@ -81,28 +162,52 @@ impl NodeConfig for ModResolver {
                 panic!();
             }
 
-            let resolved: Vec<ResolvedMod> = mods
-                .into_par_iter()
-                .map(|mod_def| match mod_def {
-                    ModDefinition::Modrinth { id, file_id, fields } => {
-                        resolve_modrinth(&modrinth_client, id, file_id, fields, &minecraft_version, &modloader, &cache)
-                            .expect_or_log("Failed to resolve Modrinth mod")
-                    },
-                    ModDefinition::Curse { id, file_id, fields } => resolve_curse(
-                        curse_client_option.as_ref().unwrap(),
-                        id,
-                        file_id,
-                        fields,
-                        &minecraft_version,
-                        &modloader,
-                        &cache,
-                    )
-                    .expect_or_log("Failed to resolve Curse mod"),
-                    ModDefinition::Url { location, filename, fields } => {
-                        resolve_url(location, filename, fields, &cache).expect_or_log("Failed to resolve URL mod")
-                    },
-                })
-                .collect();
+            let results: Vec<Result<ResolvedMod, String>> = pool.install(|| {
+                mods.into_par_iter()
+                    .map(|mod_def| match mod_def {
+                        ModDefinition::Modrinth {
+                            id,
+                            file_id,
+                            exclude_versions,
+                            fields,
+                        } => {
+                            let resolved = resolve_modrinth(&modrinth_client, id, file_id, &exclude_versions, fields, &resolve_ctx);
+                            unstable_or_log(resolved, "Failed to resolve Modrinth mod")
+                        },
+                        ModDefinition::Curse {
+                            id,
+                            file_id,
+                            exclude_versions,
+                            fields,
+                        } => {
+                            let client = curse_client_option.as_ref().unwrap();
+                            let resolved = resolve_curse(client, id, file_id, &exclude_versions, fields, &resolve_ctx);
+                            unstable_or_log(resolved, "Failed to resolve Curse mod")
+                        },
+                        ModDefinition::Url {
+                            location,
+                            filename,
+                            headers,
+                            fields,
+                        } => Ok(resolve_url(location, filename, headers, fields, &resolve_ctx).expect_or_log("Failed to resolve URL mod")),
+                    })
+                    .collect()
+            });
+
+            // Unstable mods are collected so they can all be fixed after a single run
+            let (resolved, unstable): (Vec<_>, Vec<_>) = results.into_iter().partition(Result::is_ok);
+            let resolved: Vec<ResolvedMod> = resolved.into_iter().map(Result::unwrap).collect();
+            let mut unstable: Vec<String> = unstable.into_iter().map(Result::unwrap_err).collect();
+            if !unstable.is_empty() {
+                unstable.sort();
+                event!(
+                    Level::ERROR,
+                    "{} mods have no stable file, and require_stable is set:\n- {}",
+                    unstable.len(),
+                    unstable.join("\n- ")
+                );
+                panic!("Mods without a stable file in require_stable mode");
+            }
 
             if out_channel.send(resolved).is_err() {
                 event!(Level::DEBUG, "Channel 'default' has no subscribers");
@@ -113,6 +218,15 @@ impl NodeConfig for ModResolver {
     fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
         HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::ResolvedMods(channel(1).0))])
     }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "ModResolver",
+            vec![Port::required("mods", ChannelType::Mods)],
+            vec![Port::required("default", ChannelType::ResolvedMods)],
+        )
+        .with_config_keys(vec!["minecraft_version", "modloader"])
+    }
 }
 
 #[derive(Error, Debug)]
@@ -127,32 +241,104 @@ enum ResolveError {
     Cache(#[from] CacheError),
     #[error("Failed to deserialize cached data! Error: {0}")]
     CacheDeserialize(#[from] serde_json::Error),
+    #[error("No cached data for {0}, and network access is disabled in offline mode!")]
+    OfflineCacheMiss(String),
+    #[error("Failed to interpolate environment variables! Error: {0}")]
+    Interpolate(#[from] TemplateError),
+    #[error("File download from {0} failed! Error: {1}")]
+    RedactedDownload(String, String),
+    #[error("Modrinth project {0} is a {1}, which cannot be added to a pack!")]
+    UnsupportedProject(String, String),
+    #[error("Invalid exclude_versions pattern '{0}'! Error: {1}")]
+    InvalidExclusion(String, regex::Error),
+    #[error("{0}")]
+    Unstable(String),
+    #[error("{0} was not found {1} seconds ago, and is not looked up again for another {2} seconds! Error: {3}")]
+    KnownMissing(String, i64, i64, String),
+}
+
+impl ResolveError {
+    /// Whether the mod or a file for it does not exist, so resolving it again will fail the same
+    /// way until something is published.
+    fn is_not_found(&self) -> bool {
+        match self {
+            ResolveError::Api(e) => e.is_not_found(),
+            ResolveError::EmptyOption(_) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Settings and shared resources used when resolving each mod.
+struct ResolveContext {
+    minecraft_version: String,
+    /// Game versions Modrinth files may be published for, when `minecraft_version_range` is set.
+    game_versions: Option<GameVersions>,
+    modloader: String,
+    modrinth_exclusions: Vec<String>,
+    missing_cache_ttl: Option<u64>,
+    require_stable: bool,
+    downloader: Downloader,
+    cache: Option<Arc<dyn Cache>>,
+    cache_prefix: Option<String>,
+    offline: bool,
+}
+
+/// Minecraft versions expanded from a `minecraft_version_range` expression, always including
+/// `minecraft_version` itself.
+struct GameVersions {
+    expression: String,
+    versions: Vec<String>,
 }
 
 struct CacheKey<'a> {
     name: &'a str,
     id: &'a str,
     version: Option<(&'a str, &'a str)>,
+    /// Version exclusions, which change the file picked as the newest.
+    exclusions: &'a [String],
+    /// Whether the file was required to be a release, which also changes the file picked.
+    stable: bool,
+    /// Filename fragments of Modrinth files passed over, which change the file picked from a
+    /// version. Only set for Modrinth mods.
+    file_exclusions: Option<&'a [String]>,
 }
 
-impl ToString for CacheKey<'_> {
-    fn to_string(&self) -> String {
+impl Display for CacheKey<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self.version {
-            Some(version) => format!("{}::{}::{}+{}", self.name, self.id, version.0, version.1),
-            None => format!("{}::{}", self.name, self.id),
+            Some(version) => write!(f, "{}::{}::{}+{}", self.name, self.id, version.0, version.1)?,
+            None => write!(f, "{}::{}", self.name, self.id)?,
         }
+        // Left out when empty, so keys of mods without exclusions are unchanged
+        if !self.exclusions.is_empty() {
+            write!(f, "::exclude={}", self.exclusions.join(","))?;
+        }
+        if self.stable {
+            write!(f, "::stable")?;
+        }
+        // Left out when default, so keys of mods resolved without overriding them are unchanged
+        if let Some(file_exclusions) = self.file_exclusions.filter(|e| *e != default_modrinth_exclusions()) {
+            write!(f, "::skip-files={}", file_exclusions.join(","))?;
+        }
+        Ok(())
     }
 }
 
+/// Look up a resolved mod in the cache. In offline mode a miss is an error, as the mod cannot be
+/// resolved over the network instead.
 fn get_from_cache(
     cache: &Option<Arc<dyn Cache>>,
+    prefix: Option<&str>,
     namespace: &str,
     key: &CacheKey,
     merge_meta: &ModDefinitionFields,
+    offline: bool,
 ) -> Result<Option<ResolvedMod>, ResolveError> {
-    match cache {
+    let namespace = cache_namespace(prefix, namespace);
+    let cached = match cache {
         Some(cache) => {
-            let cache_data = cache.get(namespace, &key.to_string())?;
+            let cache_data = cache.get(&namespace, &key.to_string())?;
             match cache_data {
                 Some(cache_data) => {
                     let mut resolved: Option<ResolvedMod> = serde_json::from_str(&cache_data)?;
@@ -160,78 +346,178 @@ fn get_from_cache(
                         resolved.side = merge_meta.side;
                         resolved.default = merge_meta.default.unwrap_or(true);
                         resolved.required = merge_meta.required.unwrap_or(true);
+                        resolved.group = merge_meta.group.clone();
                     }
-                    Ok(resolved)
+                    resolved
                 },
-                None => Ok(None),
+                None => None,
             }
         },
-        None => Ok(None),
+        None => None,
+    };
+    if cached.is_none() && offline {
+        return Err(ResolveError::OfflineCacheMiss(format!("{namespace}::{key}")));
     }
+    Ok(cached)
 }
 
-fn store_in_cache(cache: &Option<Arc<dyn Cache>>, namespace: &str, key: &CacheKey, value: &ResolvedMod) -> Result<(), ResolveError> {
+fn store_in_cache(
+    cache: &Option<Arc<dyn Cache>>,
+    prefix: Option<&str>,
+    namespace: &str,
+    key: &CacheKey,
+    value: &ResolvedMod,
+) -> Result<(), ResolveError> {
     match cache {
         Some(cache) => {
             let serialized = serde_json::to_string(value)?;
-            cache.put(namespace, &key.to_string(), &serialized)?;
+            cache.put(&cache_namespace(prefix, namespace), &key.to_string(), &serialized)?;
             Ok(())
         },
         None => Ok(()),
     }
 }
 
-const CURSE_CACHE_NAMESPACE: &str = "ModResolver::Curse";
-const MODRINTH_CACHE_NAMESPACE: &str = "ModResolver::Modrinth";
-const URL_CACHE_NAMESPACE: &str = "ModResolver::URL";
+/// Pass through a resolved mod, or the description of an unstable one. Any other error is logged
+/// and ends the thread.
+fn unstable_or_log(result: Result<ResolvedMod, ResolveError>, msg: &str) -> Result<ResolvedMod, String> {
+    match result {
+        Err(ResolveError::Unstable(description)) => Err(description),
+        result => Ok(result.expect_or_log(msg)),
+    }
+}
+
+/// A mod that could not be found, remembered in [`MISSING_CACHE_NAMESPACE`].
+#[derive(Debug, Serialize, Deserialize)]
+struct MissingEntry {
+    /// Unix timestamp of the failed lookup.
+    checked_at: i64,
+    error: String,
+}
+
+/// Fail with [`ResolveError::KnownMissing`] if the mod under `key` was remembered as missing less
+/// than the TTL ago.
+fn check_missing(rctx: &ResolveContext, key: &str) -> Result<(), ResolveError> {
+    let (Some(ttl), Some(cache)) = (rctx.missing_cache_ttl, &rctx.cache) else {
+        return Ok(());
+    };
+    let namespace = cache_namespace(rctx.cache_prefix.as_deref(), MISSING_CACHE_NAMESPACE);
+    let Some(data) = cache.get(&namespace, key)? else {
+        return Ok(());
+    };
+    let entry: MissingEntry = serde_json::from_str(&data)?;
+    let age = Utc::now().timestamp() - entry.checked_at;
+    match age < ttl as i64 {
+        true => Err(ResolveError::KnownMissing(key.to_owned(), age, ttl as i64 - age, entry.error)),
+        false => Ok(()),
+    }
+}
+
+/// Remember the mod under `key` as missing if `result` failed because it does not exist.
+fn remember_missing<T>(rctx: &ResolveContext, key: &str, result: Result<T, ResolveError>) -> Result<T, ResolveError> {
+    if let (Some(_), Some(cache), Err(e)) = (rctx.missing_cache_ttl, &rctx.cache, &result) {
+        if e.is_not_found() {
+            let entry = MissingEntry {
+                checked_at: Utc::now().timestamp(),
+                error: e.to_string(),
+            };
+            let namespace = cache_namespace(rctx.cache_prefix.as_deref(), MISSING_CACHE_NAMESPACE);
+            cache.put(&namespace, key, &serde_json::to_string(&entry)?)?;
+        }
+    }
+    result
+}
+
+const CURSE_CACHE_NAMESPACE: &str = "ModResolver::Curse::v3";
+const MODRINTH_CACHE_NAMESPACE: &str = "ModResolver::Modrinth::v4";
+const URL_CACHE_NAMESPACE: &str = "ModResolver::URL::v3";
+/// Keys are the cache keys of the other namespaces, prefixed with the source.
+const MISSING_CACHE_NAMESPACE: &str = "ModResolver::Missing::v1";
 
 fn resolve_curse(
     client: &CurseClient,
     mod_id: Option<u32>,
     file_id: Option<u32>,
+    exclude_versions: &[String],
     meta: ModDefinitionFields,
-    mcversion: &str,
-    loader: &str,
-    cache: &Option<Arc<dyn Cache>>,
+    rctx: &ResolveContext,
 ) -> Result<ResolvedMod, ResolveError> {
+    let (mcversion, loader, cache) = (rctx.minecraft_version.as_str(), rctx.modloader.as_str(), &rctx.cache);
     let name = meta.name.clone();
     let _span = span!(Level::INFO, "Curse", mod_name = name).entered();
     let cache_key = CacheKey {
         name: &name,
         id: &file_id.unwrap_or_default().to_string(),
-        version: Some((&mcversion, &loader)),
+        version: Some((mcversion, loader)),
+        exclusions: if file_id.is_some() { &[] } else { exclude_versions },
+        stable: rctx.require_stable,
+        file_exclusions: None,
     };
-    if let Some(cached) = get_from_cache(cache, CURSE_CACHE_NAMESPACE, &cache_key, &meta)? {
+    if let Some(cached) = get_from_cache(
+        cache,
+        rctx.cache_prefix.as_deref(),
+        CURSE_CACHE_NAMESPACE,
+        &cache_key,
+        &meta,
+        rctx.offline,
+    )? {
         return Ok(cached);
     }
-    let (mod_response, file_response, file_data) = if let Some(id) = file_id {
-        let file_response = client
-            .get_files(&[id])?
-            .pop()
-            .ok_or_else(|| ResolveError::EmptyOption("popping single file from Curse files by IDs response".to_owned()))?;
-        let file_data = download_file(&file_response.download_url)?;
-        let mod_response = client.find_mod_by_id(file_response.mod_id)?;
-        (mod_response, file_response, file_data)
-    } else {
-        let mod_response = match mod_id {
-            Some(id) => client.find_mod_by_id(id),
-            None => client.find_mod_by_slug(&meta.name),
-        }?;
-
-        let mut filtered_files_response = client
-            .get_mod_files(mod_response.id)?
-            .into_iter()
-            .filter(|f| cf_matches_version(f, mcversion, loader))
-            .collect::<Vec<_>>();
-        filtered_files_response.sort_unstable_by_key(|f| f.file_date.clone());
-        let file_response = filtered_files_response
-            .pop()
-            .ok_or_else(|| ResolveError::EmptyOption("popping latest file from Curse files by mod response".to_owned()))?;
-        let file_data = download_file(&file_response.download_url)?;
-        (mod_response, file_response, file_data)
+    let missing_key = format!("Curse::{cache_key}");
+    check_missing(rctx, &missing_key)?;
+    let lookup = || -> Result<_, ResolveError> {
+        if let Some(id) = file_id {
+            let file_response = client
+                .get_files(&[id])?
+                .pop()
+                .ok_or_else(|| ResolveError::EmptyOption("popping single file from Curse files by IDs response".to_owned()))?;
+            if rctx.require_stable && file_response.release_type != FileReleaseType::Release {
+                let release = release_name(&file_response.release_type);
+                return Err(ResolveError::Unstable(format!(
+                    "{name}: pinned file {} is {release}",
+                    file_response.file_name
+                )));
+            }
+            let mod_response = client.find_mod_by_id(file_response.mod_id)?;
+            Ok((mod_response, file_response))
+        } else {
+            let mod_response = match mod_id {
+                Some(id) => client.find_mod_by_id(id),
+                None => client.find_mod_by_slug(&meta.name),
+            }?;
+
+            let exclusions = compile_exclusions(exclude_versions)?;
+            let mut filtered_files_response = client
+                .get_mod_files(mod_response.id)?
+                .into_iter()
+                .filter(|f| cf_matches_version(f, mcversion, loader))
+                .filter(|f| !is_excluded(&exclusions, [f.display_name.as_str(), f.file_name.as_str()]))
+                .collect::<Vec<_>>();
+            filtered_files_response.sort_unstable_by_key(|f| f.file_date.clone());
+            let newest = filtered_files_response
+                .last()
+                .ok_or_else(|| ResolveError::EmptyOption("popping latest file from Curse files by mod response".to_owned()))?;
+            if rctx.require_stable && !filtered_files_response.iter().any(|f| f.release_type == FileReleaseType::Release) {
+                let release = release_name(&newest.release_type);
+                return Err(ResolveError::Unstable(format!(
+                    "{name}: newest file {} is {release}, and there is no release",
+                    newest.file_name
+                )));
+            }
+            if rctx.require_stable {
+                filtered_files_response.retain(|f| f.release_type == FileReleaseType::Release);
+            }
+            let file_response = filtered_files_response
+                .pop()
+                .ok_or_else(|| ResolveError::EmptyOption("popping latest file from Curse files by mod response".to_owned()))?;
+            Ok((mod_response, file_response))
+        }
     };
+    let (mod_response, file_response) = remember_missing(rctx, &missing_key, lookup())?;
+    let file_data = rctx.downloader.download_file(&file_response.download_url_or_cdn())?;
 
     let sha256hash = sha256hash(&file_data);
+    let src = encode_spaces(&file_response.download_url_or_cdn());
     let md5hash = {
         match file_response.hashes.into_iter().find(|h| h.algo == HashAlgo::Md5) {
             Some(hash) => hash.value,
@@ -242,7 +528,7 @@ fn resolve_curse(
         default: meta.default.unwrap_or(true),
         encoded: encode(&file_response.file_name).into_owned(),
         filename: file_response.file_name,
-        src: encode_spaces(&file_response.download_url),
+        src,
         md5: md5hash,
         side: meta.side,
         title: mod_response.name,
@@ -250,71 +536,165 @@ fn resolve_curse(
         size: file_data.len() as u64,
         sha256: sha256hash,
         required: meta.required.unwrap_or(true),
+        source: Some(ModSource::Curse {
+            project_id: mod_response.id,
+            file_id: file_response.id,
+        }),
+        kind: ProjectKind::Mod,
+        group: meta.group,
     };
-    store_in_cache(cache, CURSE_CACHE_NAMESPACE, &cache_key, &resolved)?;
+    store_in_cache(cache, rctx.cache_prefix.as_deref(), CURSE_CACHE_NAMESPACE, &cache_key, &resolved)?;
     Ok(resolved)
 }
 
+/// Compile `exclude_versions` patterns into case-insensitive regular expressions.
+fn compile_exclusions(patterns: &[String]) -> Result<Vec<Regex>, ResolveError> {
+    patterns
+        .iter()
+        .map(|p| {
+            RegexBuilder::new(p)
+                .case_insensitive(true)
+                .build()
+                .map_err(|e| ResolveError::InvalidExclusion(p.clone(), e))
+        })
+        .collect()
+}
+
+/// Whether any of the version `names`, such as a version number or filename, matches one of the
+/// exclusions.
+fn is_excluded<'a>(exclusions: &[Regex], names: impl IntoIterator<Item = &'a str>) -> bool {
+    !exclusions.is_empty() && names.into_iter().any(|name| exclusions.iter().any(|e| e.is_match(name)))
+}
+
+/// Lowercase name of a release type, such as `beta`.
+fn release_name(release_type: &impl std::fmt::Debug) -> String {
+    format!("{release_type:?}").to_lowercase()
+}
+
 fn encode_spaces(url: &str) -> String {
     url.replace(" ", "%20")
 }
 
-// CF has an awesome API where modloader type is a first-class field. Oh wait, that's Modrinth...
-// We can't check for existence of the modloader in the gameVersions field, as it is optional.
-// Instead, we do a best-effort removal of any files that explicitly support the opposite
-// modloader. Will this break on anyone crazy enough to ship multi-loader JARs? Probably!
+/// Whether a Curse file is for the given Minecraft version and loader. Loader tags are optional
+/// on Curse, so files without any are assumed to support every loader.
 fn cf_matches_version(file: &api_client::curse::model::File, mcversion: &str, loader: &str) -> bool {
-    file.game_versions.iter().any(|v| v == mcversion)
-        && match loader.to_lowercase().as_str() {
+    if !file.game_versions.iter().any(|v| v == mcversion) {
+        return false;
+    }
+    let loader = loader.to_lowercase();
+    if file.sortable_game_versions.is_empty() {
+        // Without typed versions, loader tags can't be told apart from other tags, so we do a
+        // best-effort removal of any files that only explicitly support the opposite modloader.
+        return match loader.as_str() {
             "forge" => file.game_versions.iter().any(|v| v == "Forge") || !file.game_versions.iter().any(|v| v == "Fabric"),
             "fabric" => file.game_versions.iter().any(|v| v == "Fabric") || !file.game_versions.iter().any(|v| v == "Forge"),
             _ => true,
-        }
+        };
+    }
+    let loaders = file
+        .sortable_game_versions
+        .iter()
+        .filter(|v| v.is_modloader())
+        .map(|v| v.game_version_name.to_lowercase())
+        .collect::<Vec<_>>();
+    loaders.is_empty() || loaders.contains(&loader)
 }
 
 fn resolve_modrinth(
     client: &ModrinthClient,
     mod_id: Option<String>,
     file_id: Option<String>,
+    exclude_versions: &[String],
     meta: ModDefinitionFields,
-    mcversion: &str,
-    loader: &str,
-    cache: &Option<Arc<dyn Cache>>,
+    rctx: &ResolveContext,
 ) -> Result<ResolvedMod, ResolveError> {
+    let (mcversion, loader, cache) = (rctx.minecraft_version.as_str(), rctx.modloader.as_str(), &rctx.cache);
     let name = meta.name.clone();
     let _span = span!(Level::INFO, "Modrinth", mod_name = name).entered();
+    // Keys of mods resolved for a range include the expression, so changing it looks them up again
+    let (version_key, game_versions) = match rctx.game_versions {
+        Some(ref game_versions) => (
+            format!("{mcversion}|{}", game_versions.expression),
+            game_versions.versions.iter().map(String::as_str).collect(),
+        ),
+        None => (mcversion.to_owned(), vec![mcversion]),
+    };
     let cache_key = CacheKey {
         name: &name,
         id: &file_id.clone().unwrap_or_default(),
-        version: Some((&mcversion, &loader)),
+        version: Some((&version_key, loader)),
+        exclusions: if file_id.is_some() { &[] } else { exclude_versions },
+        stable: rctx.require_stable,
+        // Applies to pinned versions too, as a version may have several files
+        file_exclusions: Some(&rctx.modrinth_exclusions),
     };
-    if let Some(cached) = get_from_cache(cache, MODRINTH_CACHE_NAMESPACE, &cache_key, &meta)? {
+    if let Some(cached) = get_from_cache(
+        cache,
+        rctx.cache_prefix.as_deref(),
+        MODRINTH_CACHE_NAMESPACE,
+        &cache_key,
+        &meta,
+        rctx.offline,
+    )? {
         return Ok(cached);
     }
-    let (mod_response, file_response) = if let Some(ref id) = file_id {
-        let file_response = client.get_version(id)?;
-        let mod_response = client.get_mod_info(&file_response.project_id)?;
-        (mod_response, file_response)
-    } else {
-        let mod_response = client.get_mod_info(match mod_id {
-            Some(ref id) => id,
-            None => &meta.name,
-        })?;
+    let missing_key = format!("Modrinth::{cache_key}");
+    check_missing(rctx, &missing_key)?;
+    let lookup = || -> Result<_, ResolveError> {
+        if let Some(ref id) = file_id {
+            let file_response = client.get_version(id)?;
+            if rctx.require_stable && file_response.version_type != VersionType::Release {
+                let release = release_name(&file_response.version_type);
+                return Err(ResolveError::Unstable(format!(
+                    "{name}: pinned version {} is {release}",
+                    file_response.version_number
+                )));
+            }
+            let mod_response = client.get_mod_info(&file_response.project_id)?;
+            Ok((mod_response, file_response))
+        } else {
+            let mod_response = client.get_mod_info(match mod_id {
+                Some(ref id) => id,
+                None => &meta.name,
+            })?;
 
-        let mut filtered_files_response = client.get_mod_versions(&mod_response.id, Some(&[loader]), Some(&[mcversion]))?;
-        filtered_files_response.sort_unstable_by_key(|f| f.date_published.clone());
-        let file_response = filtered_files_response
-            .pop()
-            .ok_or_else(|| ResolveError::EmptyOption("popping latest file from Modrinth versions by mod response".to_owned()))?;
-        (mod_response, file_response)
+            // Only mods are published for specific loaders
+            let loaders = [loader];
+            let loaders = (mod_response.project_type == ProjectType::Mod).then_some(&loaders[..]);
+            let exclusions = compile_exclusions(exclude_versions)?;
+            let mut filtered_files_response = client.get_mod_versions(&mod_response.id, loaders, Some(&game_versions))?;
+            filtered_files_response.retain(|v| {
+                let names = std::iter::once(v.version_number.as_str()).chain(v.files.iter().map(|f| f.filename.as_str()));
+                !is_excluded(&exclusions, names)
+            });
+            filtered_files_response.sort_unstable_by_key(|f| f.date_published.clone());
+            let newest = filtered_files_response
+                .last()
+                .ok_or_else(|| ResolveError::EmptyOption("popping latest file from Modrinth versions by mod response".to_owned()))?;
+            if rctx.require_stable && !filtered_files_response.iter().any(|v| v.version_type == VersionType::Release) {
+                let release = release_name(&newest.version_type);
+                return Err(ResolveError::Unstable(format!(
+                    "{name}: newest version {} is {release}, and there is no release",
+                    newest.version_number
+                )));
+            }
+            if rctx.require_stable {
+                filtered_files_response.retain(|v| v.version_type == VersionType::Release);
+            }
+            let file_response = filtered_files_response
+                .pop()
+                .ok_or_else(|| ResolveError::EmptyOption("popping latest file from Modrinth versions by mod response".to_owned()))?;
+            Ok((mod_response, file_response))
+        }
     };
-    let primary_file = file_response
-        .files
-        .iter()
-        .find(|f| f.primary)
-        .or_else(|| file_response.files.first())
-        .ok_or_else(|| ResolveError::EmptyOption("getting primary or first file from Modrinth version by ID response".to_owned()))?;
-    let file_data = download_file(&primary_file.url)?;
+    let (mod_response, file_response) = remember_missing(rctx, &missing_key, lookup())?;
+    let kind = project_kind(&mod_response)?;
+    if !kind.is_mod() {
+        event!(Level::WARN, "{} is a {kind} project, not a mod", mod_response.slug);
+    }
+    let primary_file = select_modrinth_file(&file_response.files, loader, &rctx.modrinth_exclusions)
+        .ok_or_else(|| ResolveError::EmptyOption("getting preferred file from Modrinth version by ID response".to_owned()))?;
+    let file_data = rctx.downloader.download_file(&primary_file.url)?;
     let sha256hash = sha256hash(&file_data);
     let md5hash = md5hash(&file_data);
     let resolved = ResolvedMod {
@@ -329,28 +709,87 @@ fn resolve_modrinth(
         size: primary_file.size,
         md5: md5hash,
         sha256: sha256hash,
+        source: Some(ModSource::Modrinth {
+            project_id: mod_response.id,
+            version_id: file_response.id,
+        }),
+        kind,
+        group: meta.group,
     };
-    store_in_cache(cache, MODRINTH_CACHE_NAMESPACE, &cache_key, &resolved)?;
+    store_in_cache(cache, rctx.cache_prefix.as_deref(), MODRINTH_CACHE_NAMESPACE, &cache_key, &resolved)?;
     Ok(resolved)
 }
 
+/// Get the kind of file a Modrinth project resolves to. Modpacks and server plugins can't be
+/// installed as part of a pack.
+fn project_kind(project: &Project) -> Result<ProjectKind, ResolveError> {
+    match project.project_type {
+        ProjectType::Mod => Ok(ProjectKind::Mod),
+        ProjectType::Resourcepack => Ok(ProjectKind::ResourcePack),
+        ProjectType::Shader => Ok(ProjectKind::Shader),
+        ProjectType::Datapack => Ok(ProjectKind::DataPack),
+        other => Err(ResolveError::UnsupportedProject(
+            project.slug.clone(),
+            format!("{other:?}").to_lowercase(),
+        )),
+    }
+}
+
+/// Pick which file of a Modrinth version to download.
+///
+/// Files not matching any of the exclusions are always preferred. Among those, a file naming the
+/// target loader wins over the primary file, which wins over the rest. A file names the loader if
+/// one of the parts of its filename, split on `-`, `_`, `.` and `+`, is the loader. If every file is excluded,
+/// the same ordering is applied to the excluded files. Ties go to the earliest file in the list.
+fn select_modrinth_file<'a>(files: &'a [VersionFile], loader: &str, exclusions: &[String]) -> Option<&'a VersionFile> {
+    let loader = loader.to_lowercase();
+    files
+        .iter()
+        .enumerate()
+        .min_by_key(|(index, file)| {
+            let filename = file.filename.to_lowercase();
+            let excluded = exclusions.iter().any(|e| filename.contains(&e.to_lowercase()));
+            // Whole parts only, so that `forge` does not match `neoforge`
+            let names_loader = filename.split(['-', '_', '.', '+']).any(|part| part == loader);
+            (excluded, !names_loader, !file.primary, *index)
+        })
+        .map(|(_, file)| file)
+}
+
 fn resolve_url(
     location: String,
     filename: Option<String>,
+    headers: BTreeMap<String, String>,
     meta: ModDefinitionFields,
-    cache: &Option<Arc<dyn Cache>>,
+    rctx: &ResolveContext,
 ) -> Result<ResolvedMod, ResolveError> {
+    let cache = &rctx.cache;
     let name = meta.name.clone();
     let _span = span!(Level::INFO, "URL", mod_name = name).entered();
     let cache_key = CacheKey {
         name: &name,
         id: &location,
         version: None,
+        exclusions: &[],
+        stable: false,
+        file_exclusions: None,
     };
-    if let Some(cached) = get_from_cache(cache, URL_CACHE_NAMESPACE, &cache_key, &meta)? {
+    if let Some(cached) = get_from_cache(cache, rctx.cache_prefix.as_deref(), URL_CACHE_NAMESPACE, &cache_key, &meta, rctx.offline)? {
         return Ok(cached);
     }
-    let file_data = download_file(&location)?;
+    // The cache key and outputs keep the uninterpolated location, so secrets are never stored
+    let url = interpolate_env(&location)?;
+    let headers = headers
+        .into_iter()
+        .map(|(name, value)| Ok((name, interpolate_env(&value)?)))
+        .collect::<Result<Vec<_>, ResolveError>>()?;
+    let file_data = rctx
+        .downloader
+        .download_file_with_headers(&url, &headers)
+        .map_err(|e| match url == location {
+            true => ResolveError::Download(e),
+            false => ResolveError::RedactedDownload(location.clone(), e.without_url()),
+        })?;
     let resolved_filename = match filename {
         Some(value) => value,
         None => get_filename(&location)?,
@@ -369,12 +808,22 @@ fn resolve_url(
         size: file_data.len() as u64,
         md5: md5hash,
         sha256: sha256hash,
+        source: Some(ModSource::Url),
+        kind: ProjectKind::Mod,
+        group: meta.group,
     };
-    store_in_cache(cache, URL_CACHE_NAMESPACE, &cache_key, &resolved)?;
+    store_in_cache(cache, rctx.cache_prefix.as_deref(), URL_CACHE_NAMESPACE, &cache_key, &resolved)?;
     Ok(resolved)
 }
 
-fn sha256hash<T>(data: T) -> String
+/// Replace `${env.VAR}` placeholders with the values of environment variables. Fails if a
+/// referenced variable is unset.
+fn interpolate_env(template: &str) -> Result<String, ResolveError> {
+    let lookup = |key: &str| key.strip_prefix("env.").and_then(|var| std::env::var(var).ok());
+    Ok(render(template, lookup, true)?)
+}
+
+pub(super) fn sha256hash<T>(data: T) -> String
 where
     T: AsRef<[u8]>,
 {
@@ -393,7 +842,7 @@ where
 
 fn get_filename(url: &str) -> Result<String, ResolveError> {
     url.split('/')
-        .last()
+        .next_back()
         .ok_or_else(|| {
             ResolveError::EmptyOption(format!(
                 "getting last part of URL after splitting on / when resolving filename. URL: {url}"
@@ -415,18 +864,34 @@ mod tests {
         di::container::DiContainerBuilder,
         node::{
             config::{ModDefinition, NodeConfigTypes, Side},
-            utils::{get_curse_config, get_output_test, read_channel},
+            utils::{get_curse_config, get_output_test, read_channel, serve_http, serve_http_with, test_mod, TestCache},
         },
     };
 
     use super::*;
 
+    /// Context resolving for Fabric on 1.20.1 without a cache, for tests to override.
+    fn test_rctx() -> ResolveContext {
+        ResolveContext {
+            minecraft_version: "1.20.1".to_owned(),
+            game_versions: None,
+            modloader: "fabric".to_owned(),
+            modrinth_exclusions: default_modrinth_exclusions(),
+            missing_cache_ttl: None,
+            require_stable: false,
+            downloader: Downloader::default(),
+            cache: None,
+            cache_prefix: None,
+            offline: false,
+        }
+    }
+
     #[test]
     fn test_mod_resolver() {
         let node_id = "resolver";
         let mod_channel = broadcast::channel(1).0;
         let input_ids = HashMap::from([("mods".into(), ChannelId::from_str("mod-source").unwrap())]);
-        let node = NodeConfigTypes::ModResolver(ModResolver);
+        let node = NodeConfigTypes::ModResolver(ModResolver::default());
 
         let mut ctx_builder = DiContainerBuilder::default();
         let curse_config = get_curse_config();
@@ -486,6 +951,12 @@ mod tests {
                 size: 33683,
                 md5: "b435860d5cfa23bc53d3b8e120be91d4".to_owned(),
                 sha256: "4bbd37edecff0b420ab0eea166b5d7b4b41a9870bfb8647bf243140dc57f101e".to_owned(),
+                source: Some(ModSource::Modrinth {
+                    project_id: "EsAfCjCV".to_owned(),
+                    version_id: "Tsz4BT2X".to_owned(),
+                }),
+                kind: ProjectKind::Mod,
+                group: None,
             },
             ResolvedMod {
                 title: "Mouse Tweaks".to_owned(),
@@ -499,6 +970,12 @@ mod tests {
                 size: 80528,
                 md5: "a6034d3ff57091c78405e46f1f926282".to_owned(),
                 sha256: "5e13315f4e0d0c96b1f9b800a42fecb89f519aca81d556c91df617c8751aa575".to_owned(),
+                source: Some(ModSource::Curse {
+                    project_id: 60089,
+                    file_id: 3359843,
+                }),
+                kind: ProjectKind::Mod,
+                group: None,
             },
             ResolvedMod {
                 title: "title-changer".to_owned(),
@@ -512,6 +989,9 @@ mod tests {
                 size: 5923,
                 md5: "8fda92da93d78919cff1139e847d3e1c".to_owned(),
                 sha256: "78bbe270f2f2ca443a4e794ee1f0c5920ef933ce1030bae0dcff45cb16689eb7".to_owned(),
+                source: Some(ModSource::Url),
+                kind: ProjectKind::Mod,
+                group: None,
             },
         ];
 
@@ -530,19 +1010,92 @@ mod tests {
         );
     }
 
-    struct TestCache {
-        data: Arc<Mutex<HashMap<(String, String), String>>>,
+    #[test]
+    fn curse_version_matching() {
+        fn file(game_versions: &[&str], loaders: Option<&[&str]>) -> api_client::curse::model::File {
+            let sortable = loaders.map(|loaders| {
+                loaders
+                    .iter()
+                    .map(|l| serde_json::json!({"gameVersionName": l, "gameVersion": "", "gameVersionTypeId": 68441}))
+                    .chain([serde_json::json!({"gameVersionName": "1.20.1", "gameVersion": "1.20.1", "gameVersionTypeId": 75125})])
+                    .collect::<Vec<_>>()
+            });
+            let mut json = serde_json::json!({
+                "id": 1, "modId": 1, "displayName": "", "fileName": "a.jar", "releaseType": 1, "fileStatus": 4,
+                "downloadUrl": null, "gameVersions": game_versions, "dependencies": [], "hashes": [], "fileDate": "",
+            });
+            if let Some(sortable) = sortable {
+                json["sortableGameVersions"] = sortable.into();
+            }
+            serde_json::from_value(json).unwrap()
+        }
+
+        // Typed loader tags are matched exactly, so multi-loader files match every loader they list
+        let multi = file(&["1.20.1", "Forge", "NeoForge"], Some(&["Forge", "NeoForge"]));
+        assert!(cf_matches_version(&multi, "1.20.1", "neoforge"));
+        assert!(cf_matches_version(&multi, "1.20.1", "forge"));
+        assert!(!cf_matches_version(&multi, "1.20.1", "fabric"));
+        assert!(!cf_matches_version(&multi, "1.19.2", "forge"));
+        assert!(!cf_matches_version(&file(&["1.20.1", "Quilt"], Some(&["Quilt"])), "1.20.1", "fabric"));
+        assert!(cf_matches_version(&file(&["1.20.1"], Some(&[])), "1.20.1", "quilt"));
+
+        // Without typed versions, only files tagged for the opposite loader alone are removed
+        assert!(cf_matches_version(&file(&["1.20.1", "Quilt"], None), "1.20.1", "fabric"));
+        assert!(!cf_matches_version(&file(&["1.20.1", "Fabric"], None), "1.20.1", "forge"));
+        assert!(cf_matches_version(&file(&["1.20.1", "Fabric", "Forge"], None), "1.20.1", "forge"));
     }
 
-    impl Cache for TestCache {
-        fn put(&self, namespace: &str, key: &str, data: &str) -> Result<(), CacheError> {
-            self.data.lock().unwrap().insert((namespace.to_owned(), key.to_owned()), data.to_owned());
-            Ok(())
+    #[test]
+    fn modrinth_file_preference() {
+        fn file(filename: &str, primary: bool) -> VersionFile {
+            VersionFile {
+                hashes: api_client::modrinth::model::VersionFileHashes {
+                    sha512: String::new(),
+                    sha1: String::new(),
+                },
+                url: format!("https://cdn.modrinth.com/data/abc/versions/def/{filename}"),
+                filename: filename.to_owned(),
+                primary,
+                size: 0,
+            }
         }
+        let exclusions = default_modrinth_exclusions();
 
-        fn get(&self, namespace: &str, key: &str) -> Result<Option<String>, CacheError> {
-            Ok(self.data.lock().unwrap().get(&(namespace.to_owned(), key.to_owned())).map(|v| v.clone()))
-        }
+        // Primary file built for another loader loses to the secondary file for the target loader
+        let files = vec![file("examplemod-forge-1.0.jar", true), file("examplemod-fabric-1.0.jar", false)];
+        assert_eq!(
+            select_modrinth_file(&files, "fabric", &exclusions).unwrap().filename,
+            "examplemod-fabric-1.0.jar"
+        );
+
+        // Loader names only match whole parts of the filename
+        let files = vec![file("examplemod-neoforge-1.0.jar", true), file("examplemod-1.0+forge.jar", false)];
+        assert_eq!(
+            select_modrinth_file(&files, "forge", &exclusions).unwrap().filename,
+            "examplemod-1.0+forge.jar"
+        );
+        let files = vec![file("examplemod-neoforge-1.0.jar", false), file("examplemod-1.0.jar", true)];
+        assert_eq!(select_modrinth_file(&files, "forge", &exclusions).unwrap().filename, "examplemod-1.0.jar");
+
+        // Primary sources jar loses to the secondary plain jar
+        let files = vec![file("examplemod-1.0-sources.jar", true), file("examplemod-1.0.jar", false)];
+        assert_eq!(select_modrinth_file(&files, "forge", &exclusions).unwrap().filename, "examplemod-1.0.jar");
+
+        // Without any loader names, the primary file wins
+        let files = vec![file("examplemod-1.0-api.jar", false), file("examplemod-1.0.jar", true)];
+        assert_eq!(select_modrinth_file(&files, "forge", &exclusions).unwrap().filename, "examplemod-1.0.jar");
+
+        // Excluded files are still picked if nothing else is available
+        let files = vec![file("examplemod-1.0-dev.jar", false), file("examplemod-1.0-sources.jar", true)];
+        assert_eq!(
+            select_modrinth_file(&files, "forge", &exclusions).unwrap().filename,
+            "examplemod-1.0-sources.jar"
+        );
+
+        // Exclusions can be overridden
+        let files = vec![file("examplemod-1.0-sources.jar", true), file("examplemod-1.0.jar", false)];
+        assert_eq!(select_modrinth_file(&files, "forge", &[]).unwrap().filename, "examplemod-1.0-sources.jar");
+        assert!(select_modrinth_file(&[], "forge", &exclusions).is_none());
     }
 
     #[test]
@@ -550,7 +1103,7 @@ mod tests {
         let node_id = "resolver";
         let mod_channel = broadcast::channel(1).0;
         let input_ids = HashMap::from([("mods".into(), ChannelId::from_str("mod-source").unwrap())]);
-        let node = NodeConfigTypes::ModResolver(ModResolver);
+        let node = NodeConfigTypes::ModResolver(ModResolver::default());
 
         let curse_mod = ResolvedMod {
             name: "fake-mod".to_owned(),
@@ -564,6 +1117,9 @@ mod tests {
             size: 12345,
             md5: "".to_owned(),
             sha256: "".to_owned(),
+            source: None,
+            kind: ProjectKind::Mod,
+            group: None,
         };
 
         let modrinth_mod = ResolvedMod {
@@ -578,27 +1134,34 @@ mod tests {
             size: 12345,
             md5: "".to_owned(),
             sha256: "".to_owned(),
+            source: None,
+            kind: ProjectKind::Mod,
+            group: None,
         };
 
         let mods: Vec<ModDefinition> = vec![
             ModDefinition::Curse {
                 id: None,
                 file_id: Some(12345),
+                exclude_versions: Vec::new(),
                 fields: ModDefinitionFields {
                     name: "fake-mod".to_owned(),
                     side: Side::Both,
                     required: Some(true),
                     default: Some(true),
+                    group: None,
                 },
             },
             ModDefinition::Modrinth {
                 id: None,
                 file_id: Some("abcde".to_owned()),
+                exclude_versions: Vec::new(),
                 fields: ModDefinitionFields {
                     name: "fake-mod-2".to_owned(),
                     side: Side::Server,
                     required: Some(false),
                     default: Some(true),
+                    group: None,
                 },
             },
         ];
@@ -619,11 +1182,11 @@ mod tests {
         let cache = TestCache {
             data: Arc::new(Mutex::new(HashMap::from([
                 (
-                    ("ModResolver::Curse".to_owned(), "fake-mod::12345::1.12.2+forge".to_owned()),
+                    ("ModResolver::Curse::v3".to_owned(), "fake-mod::12345::1.12.2+forge".to_owned()),
                     serde_json::to_string(&curse_mod).unwrap(),
                 ),
                 (
-                    ("ModResolver::Modrinth".to_owned(), "fake-mod-2::abcde::1.12.2+forge".to_owned()),
+                    ("ModResolver::Modrinth::v4".to_owned(), "fake-mod-2::abcde::1.12.2+forge".to_owned()),
                     serde_json::to_string(&modrinth_mod).unwrap(),
                 ),
             ]))),
@@ -653,4 +1216,372 @@ mod tests {
 
         assert_eq!(actual_resolved, expected_resolved);
     }
+
+    #[test]
+    fn offline_cache_miss() {
+        let location = "https://example.com/mods/fake-mod.jar".to_owned();
+        let meta = ModDefinitionFields {
+            name: "fake-mod".to_owned(),
+            side: Side::Both,
+            required: None,
+            default: None,
+            group: None,
+        };
+        let cached = test_mod("fake-mod");
+        let cache = TestCache {
+            data: Arc::new(Mutex::new(HashMap::from([(
+                (URL_CACHE_NAMESPACE.to_owned(), format!("fake-mod::{location}")),
+                serde_json::to_string(&cached).unwrap(),
+            )]))),
+        };
+        let rctx = |cache: Option<Arc<dyn Cache>>| ResolveContext {
+            cache,
+            offline: true,
+            ..test_rctx()
+        };
+
+        // Served from the cache without touching the network
+        let resolved = resolve_url(location.clone(), None, BTreeMap::new(), meta.clone(), &rctx(Some(Arc::new(cache)))).unwrap();
+        assert_eq!(resolved, cached);
+
+        let missing = resolve_url(location, None, BTreeMap::new(), meta, &rctx(None));
+        assert!(
+            matches!(missing, Err(ResolveError::OfflineCacheMiss(key)) if key == "ModResolver::URL::v3::fake-mod::https://example.com/mods/fake-mod.jar")
+        );
+    }
+
+    #[test]
+    fn cache_prefix_isolates_namespaces() {
+        let location = "https://example.com/mods/fake-mod.jar".to_owned();
+        let meta = ModDefinitionFields {
+            name: "fake-mod".to_owned(),
+            side: Side::Both,
+            required: None,
+            default: None,
+            group: None,
+        };
+        let cached = test_mod("fake-mod");
+        let cache: Arc<dyn Cache> = Arc::new(TestCache {
+            data: Arc::new(Mutex::new(HashMap::from([(
+                (format!("pack-a::{URL_CACHE_NAMESPACE}"), format!("fake-mod::{location}")),
+                serde_json::to_string(&cached).unwrap(),
+            )]))),
+        });
+        let rctx = |cache_prefix: Option<&str>| ResolveContext {
+            cache: Some(cache.clone()),
+            cache_prefix: cache_prefix.map(str::to_owned),
+            offline: true,
+            ..test_rctx()
+        };
+
+        let resolved = resolve_url(location.clone(), None, BTreeMap::new(), meta.clone(), &rctx(Some("pack-a"))).unwrap();
+        assert_eq!(resolved, cached);
+
+        // Neither another prefix nor no prefix at all can see the entry
+        let other = resolve_url(location.clone(), None, BTreeMap::new(), meta.clone(), &rctx(Some("pack-b")));
+        assert!(
+            matches!(other, Err(ResolveError::OfflineCacheMiss(key)) if key == "pack-b::ModResolver::URL::v3::fake-mod::https://example.com/mods/fake-mod.jar")
+        );
+        let unprefixed = resolve_url(location, None, BTreeMap::new(), meta, &rctx(None));
+        assert!(matches!(unprefixed, Err(ResolveError::OfflineCacheMiss(_))));
+    }
+
+    #[test]
+    fn modrinth_shader_is_tagged() {
+        let files = serve_http(HashMap::from([("/shaders/complementary.zip".to_owned(), (200, b"shaders".to_vec()))]));
+        let project = r#"{"slug":"complementary","title":"Complementary","client_side":"required","server_side":"unsupported","id":"HVnmMxH1","project_type":"shader"}"#;
+        let versions = format!(
+            r#"[{{"name":"r5.2","version_number":"r5.2","game_versions":["1.20.1"],"version_type":"release","loaders":["iris","optifine"],"id":"k5N2Ww2Q","project_id":"HVnmMxH1","date_published":"2024-01-01T00:00:00Z","files":[{{"hashes":{{"sha512":"","sha1":""}},"url":"{files}/shaders/complementary.zip","filename":"complementary.zip","primary":true,"size":7}}]}}]"#
+        );
+        let api = serve_http(HashMap::from([
+            ("/v2/project/complementary".to_owned(), (200, project.as_bytes().to_vec())),
+            ("/v2/project/HVnmMxH1/version".to_owned(), (200, versions.into_bytes())),
+        ]));
+        let meta = ModDefinitionFields {
+            name: "complementary".to_owned(),
+            side: Side::Client,
+            required: None,
+            default: None,
+            group: None,
+        };
+        let rctx = test_rctx();
+
+        let resolved = resolve_modrinth(&ModrinthClient::from_base_url(&api), None, None, &[], meta, &rctx).unwrap();
+        assert_eq!(resolved.kind, ProjectKind::Shader);
+        assert_eq!(resolved.filename, "complementary.zip");
+        assert_eq!(resolved.sha256, sha256hash(b"shaders"));
+    }
+
+    #[test]
+    fn remembers_missing_mods() {
+        let api = serve_http(HashMap::new());
+        let data = Arc::new(Mutex::new(HashMap::new()));
+        let meta = ModDefinitionFields {
+            name: "ghost-mod".to_owned(),
+            side: Side::Both,
+            required: None,
+            default: None,
+            group: None,
+        };
+        let rctx = ResolveContext {
+            missing_cache_ttl: Some(3600),
+            cache: Some(Arc::new(TestCache { data: data.clone() })),
+            ..test_rctx()
+        };
+        let entry_key = (MISSING_CACHE_NAMESPACE.to_owned(), "Modrinth::ghost-mod::::1.20.1+fabric".to_owned());
+        let checked_at = || {
+            let entry: MissingEntry = serde_json::from_str(&data.lock().unwrap()[&entry_key]).unwrap();
+            entry.checked_at
+        };
+
+        let first = resolve_modrinth(&ModrinthClient::from_base_url(&api), None, None, &[], meta.clone(), &rctx);
+        assert!(matches!(first, Err(ResolveError::Api(_))));
+        assert!(checked_at() > Utc::now().timestamp() - 60);
+
+        // Nothing listens on the discard port, so a lookup would fail with an API error instead
+        let unreachable = ModrinthClient::from_base_url("http://127.0.0.1:9");
+        let second = resolve_modrinth(&unreachable, None, None, &[], meta.clone(), &rctx);
+        assert!(matches!(second, Err(ResolveError::KnownMissing(key, _, _, _)) if key == entry_key.1));
+
+        // Expired entries are looked up again, and refreshed if the mod is still missing
+        let expired = Utc::now().timestamp() - 7200;
+        data.lock()
+            .unwrap()
+            .insert(entry_key.clone(), format!(r#"{{"checked_at":{expired},"error":""}}"#));
+        let third = resolve_modrinth(&ModrinthClient::from_base_url(&api), None, None, &[], meta, &rctx);
+        assert!(matches!(third, Err(ResolveError::Api(_))));
+        assert!(checked_at() > expired);
+    }
+
+    #[test]
+    fn require_stable_versions() {
+        let files = serve_http(HashMap::from([("/mods/examplemod-1.0.jar".to_owned(), (200, b"stable".to_vec()))]));
+        let project = |slug: &str| {
+            format!(r#"{{"slug":"{slug}","title":"{slug}","client_side":"required","server_side":"required","id":"{slug}-id","project_type":"mod"}}"#)
+        };
+        let version = |id: &str, project: &str, number: &str, version_type: &str, date: &str| {
+            format!(
+                r#"{{"name":"{number}","version_number":"{number}","game_versions":["1.20.1"],"version_type":"{version_type}","loaders":["fabric"],"id":"{id}","project_id":"{project}-id","date_published":"{date}","files":[{{"hashes":{{"sha512":"","sha1":""}},"url":"{files}/mods/{project}-{number}.jar","filename":"{project}-{number}.jar","primary":true,"size":6}}]}}"#
+            )
+        };
+        let api = serve_http(HashMap::from([
+            ("/v2/project/examplemod".to_owned(), (200, project("examplemod").into_bytes())),
+            ("/v2/project/betamod".to_owned(), (200, project("betamod").into_bytes())),
+            (
+                "/v2/project/examplemod-id/version".to_owned(),
+                (
+                    200,
+                    format!(
+                        "[{},{}]",
+                        version("v2", "examplemod", "1.1-beta", "beta", "2024-02-01T00:00:00Z"),
+                        version("v1", "examplemod", "1.0", "release", "2024-01-01T00:00:00Z")
+                    )
+                    .into_bytes(),
+                ),
+            ),
+            (
+                "/v2/project/betamod-id/version".to_owned(),
+                (
+                    200,
+                    format!("[{}]", version("b1", "betamod", "0.1", "alpha", "2024-01-01T00:00:00Z")).into_bytes(),
+                ),
+            ),
+            (
+                "/v2/version/v2".to_owned(),
+                (200, version("v2", "examplemod", "1.1-beta", "beta", "2024-02-01T00:00:00Z").into_bytes()),
+            ),
+        ]));
+        let meta = |name: &str| ModDefinitionFields {
+            name: name.to_owned(),
+            side: Side::Both,
+            required: None,
+            default: None,
+            group: None,
+        };
+        let rctx = ResolveContext {
+            require_stable: true,
+            ..test_rctx()
+        };
+        let client = ModrinthClient::from_base_url(&api);
+
+        // The newest release is used over a newer beta
+        let resolved = resolve_modrinth(&client, None, None, &[], meta("examplemod"), &rctx).unwrap();
+        assert_eq!(resolved.filename, "examplemod-1.0.jar");
+
+        let unstable = resolve_modrinth(&client, None, None, &[], meta("betamod"), &rctx);
+        assert!(
+            matches!(unstable, Err(ResolveError::Unstable(ref m)) if m == "betamod: newest version 0.1 is alpha, and there is no release"),
+            "{unstable:?}"
+        );
+        let pinned = resolve_modrinth(&client, None, Some("v2".into()), &[], meta("examplemod"), &rctx);
+        assert!(matches!(pinned, Err(ResolveError::Unstable(ref m)) if m == "examplemod: pinned version 1.1-beta is beta"));
+    }
+
+    #[test]
+    fn modrinth_skips_excluded_versions() {
+        let files = serve_http(HashMap::from([("/mods/examplemod-1.9.jar".to_owned(), (200, b"stable".to_vec()))]));
+        let project =
+            r#"{"slug":"examplemod","title":"Example Mod","client_side":"required","server_side":"required","id":"AABBCCDD","project_type":"mod"}"#;
+        let version = |id: &str, number: &str, date: &str| {
+            format!(
+                r#"{{"name":"{number}","version_number":"{number}","game_versions":["1.20.1"],"version_type":"release","loaders":["fabric"],"id":"{id}","project_id":"AABBCCDD","date_published":"{date}","files":[{{"hashes":{{"sha512":"","sha1":""}},"url":"{files}/mods/examplemod-{number}.jar","filename":"examplemod-{number}.jar","primary":true,"size":6}}]}}"#
+            )
+        };
+        let versions = format!(
+            "[{},{},{}]",
+            version("v3", "2.0-Testing", "2024-03-01T00:00:00Z"),
+            version("v2", "1.9", "2024-02-01T00:00:00Z"),
+            version("v1", "1.8", "2024-01-01T00:00:00Z"),
+        );
+        let api = serve_http(HashMap::from([
+            ("/v2/project/examplemod".to_owned(), (200, project.as_bytes().to_vec())),
+            ("/v2/project/AABBCCDD/version".to_owned(), (200, versions.into_bytes())),
+        ]));
+        let meta = ModDefinitionFields {
+            name: "examplemod".to_owned(),
+            side: Side::Both,
+            required: None,
+            default: None,
+            group: None,
+        };
+        let rctx = test_rctx();
+        let client = ModrinthClient::from_base_url(&api);
+
+        let resolved = resolve_modrinth(&client, None, None, &["testing".to_owned()], meta.clone(), &rctx).unwrap();
+        assert_eq!(resolved.filename, "examplemod-1.9.jar");
+        assert_eq!(
+            resolved.source,
+            Some(ModSource::Modrinth {
+                project_id: "AABBCCDD".into(),
+                version_id: "v2".into()
+            })
+        );
+
+        let invalid = resolve_modrinth(&client, None, None, &["(unclosed".to_owned()], meta, &rctx);
+        assert!(matches!(invalid, Err(ResolveError::InvalidExclusion(pattern, _)) if pattern == "(unclosed"));
+
+        let exclusions = ["testing".to_owned(), r"-rc\d".to_owned()];
+        let key = |exclusions| CacheKey {
+            name: "examplemod",
+            id: "",
+            version: Some(("1.20.1", "fabric")),
+            exclusions,
+            stable: false,
+            file_exclusions: None,
+        };
+        assert_eq!(key(&[]).to_string(), "examplemod::::1.20.1+fabric");
+        assert_eq!(key(&exclusions).to_string(), r"examplemod::::1.20.1+fabric::exclude=testing,-rc\d");
+        // Changing which files of a version are passed over picks the file again
+        let file_exclusions = default_modrinth_exclusions();
+        assert_eq!(
+            CacheKey {
+                file_exclusions: Some(&file_exclusions),
+                ..key(&[])
+            }
+            .to_string(),
+            "examplemod::::1.20.1+fabric"
+        );
+        let file_exclusions = ["-sources".to_owned()];
+        assert_eq!(
+            CacheKey {
+                file_exclusions: Some(&file_exclusions),
+                ..key(&[])
+            }
+            .to_string(),
+            "examplemod::::1.20.1+fabric::skip-files=-sources"
+        );
+    }
+
+    #[test]
+    fn mirrored_download_keeps_source_url() {
+        let mirror = serve_http(HashMap::from([("/mods/fake-mod.jar".to_owned(), (200, b"fake mod".to_vec()))]));
+        let location = "http://mods.invalid/mods/fake-mod.jar".to_owned();
+        let meta = ModDefinitionFields {
+            name: "fake-mod".to_owned(),
+            side: Side::Both,
+            required: None,
+            default: None,
+            group: None,
+        };
+        let ctx = DiContainerBuilder::default()
+            .download_mirrors(HashMap::from([(
+                "mods.invalid".to_owned(),
+                mirror.trim_start_matches("http://").to_owned(),
+            )]))
+            .build();
+        let rctx = ResolveContext {
+            downloader: ctx.get_downloader(),
+            ..test_rctx()
+        };
+
+        let resolved = resolve_url(location.clone(), None, BTreeMap::new(), meta, &rctx).unwrap();
+        assert_eq!(resolved.src, location);
+        assert_eq!(resolved.sha256, sha256hash(b"fake mod"));
+    }
+
+    #[test]
+    fn url_secrets_from_environment() {
+        // Only serve the file to requests carrying the token
+        let server = serve_http_with(|request| {
+            let authorized =
+                request.path == "/private/fake-mod.jar" && request.headers.get("authorization").is_some_and(|value| value == "Bearer hunter2");
+            match authorized {
+                true => (200, b"fake mod".to_vec()),
+                false => (401, Vec::new()),
+            }
+        });
+        std::env::set_var("MMMM_TEST_MIRROR_DIR", "private");
+        std::env::set_var("MMMM_TEST_MIRROR_TOKEN", "hunter2");
+        std::env::set_var("MMMM_TEST_MIRROR_WRONG_TOKEN", "letmein");
+
+        let location = format!("{server}/${{env.MMMM_TEST_MIRROR_DIR}}/fake-mod.jar");
+        let headers = |token: &str| BTreeMap::from([("Authorization".to_owned(), format!("Bearer ${{env.{token}}}"))]);
+        let meta = ModDefinitionFields {
+            name: "fake-mod".to_owned(),
+            side: Side::Both,
+            required: None,
+            default: None,
+            group: None,
+        };
+        let rctx = test_rctx();
+
+        let resolved = resolve_url(location.clone(), None, headers("MMMM_TEST_MIRROR_TOKEN"), meta.clone(), &rctx).unwrap();
+        assert_eq!(resolved.src, location);
+        assert_eq!(resolved.filename, "fake-mod.jar");
+        assert_eq!(resolved.sha256, sha256hash(b"fake mod"));
+
+        // Errors name the uninterpolated location and variables, never their values
+        let rejected = resolve_url(location.clone(), None, headers("MMMM_TEST_MIRROR_WRONG_TOKEN"), meta.clone(), &rctx).unwrap_err();
+        assert_eq!(
+            rejected.to_string(),
+            format!("File download from {location} failed! Error: Failed to download file. Error: status code 401")
+        );
+        let unset = resolve_url(location, None, headers("MMMM_TEST_MIRROR_UNSET"), meta, &rctx).unwrap_err();
+        assert!(matches!(unset, ResolveError::Interpolate(TemplateError::Unresolved(keys)) if keys == ["env.MMMM_TEST_MIRROR_UNSET"]));
+    }
+
+    #[test]
+    fn expand_version_range() {
+        // Newest first, as Modrinth lists them
+        let releases = [
+            "1.21", "1.20.6", "1.20.4", "1.20.3", "1.20.2", "1.20.1", "1.20", "1.19.4", "1.19.2", "1.2.5",
+        ];
+        let expand = |expression: &str| expression.parse::<VersionRange>().unwrap().expand(releases);
+
+        assert_eq!(expand("1.20.x"), ["1.20.6", "1.20.4", "1.20.3", "1.20.2", "1.20.1", "1.20"]);
+        assert_eq!(expand("1.20.*"), expand("1.20.x"));
+        assert_eq!(expand("1.20.1..1.20.3"), ["1.20.3", "1.20.2", "1.20.1"]);
+        assert_eq!(expand("1.19.4 .. 1.20"), ["1.20", "1.19.4"]);
+        assert_eq!(expand("1.2.x"), ["1.2.5"]);
+        // Exact versions are kept even when they are not known releases
+        assert_eq!(
+            expand("1.19.2, 1.20.x, 23w31a"),
+            ["1.20.6", "1.20.4", "1.20.3", "1.20.2", "1.20.1", "1.20", "1.19.2", "23w31a"]
+        );
+
+        assert!("1.20.4..1.20.1".parse::<VersionRange>().is_err());
+        assert!("1.20.x,".parse::<VersionRange>().is_err());
+        assert!("23w31a..1.20.1".parse::<VersionRange>().is_err());
+        assert!("snapshot.x".parse::<VersionRange>().is_err());
+    }
 }
@@ -0,0 +1,254 @@
+use std::{
+    collections::HashMap,
+    thread::{spawn, JoinHandle},
+};
+
+use api_client::{common::SearchSort, curse::CurseClient, modrinth::ModrinthClient};
+use rayon::prelude::*;
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::di::container::{DiContainer, InputType, OutputType};
+
+use super::{
+    config::{ChannelId, ChannelType, ModDefinition, ModDefinitionFields, NodeConfig, NodeDescription, NodeInitError, Port, Side},
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Default)]
+pub struct ModSearch {
+    /// Platform to search.
+    #[serde(default)]
+    pub source: SearchSource,
+    /// How search results are ordered before the first one is picked.
+    #[serde(default)]
+    pub sort: SearchSort,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchSource {
+    #[default]
+    Modrinth,
+    Curse,
+}
+
+/// A search result picked for a query.
+struct Hit {
+    slug: String,
+    title: String,
+    definition: ModDefinition,
+}
+
+impl NodeConfig for ModSearch {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut queries_channel = get_input!("queries", List, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Mods, ctx)?;
+        // Search results are never cached, as they are expected to change
+        if ctx.is_offline() {
+            return Err(NodeInitError::Offline);
+        }
+        let curse_client = match self.source {
+            SearchSource::Curse => Some(ctx.get_curse_client().ok_or(NodeInitError::CurseClientRequired)?),
+            SearchSource::Modrinth => None,
+        };
+        let modrinth_client = ctx.get_modrinth_client();
+        let minecraft_version = ctx.get_config("minecraft_version");
+        let modloader = ctx.get_config("modloader");
+        let sort = self.sort;
+        let pool = ctx.get_pool();
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "ModSearch", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let queries = queries_channel.blocking_recv().expect_or_log("Failed to receive on queries input");
+            let queries = queries
+                .iter()
+                .map(|query| query.trim())
+                .filter(|query| !query.is_empty())
+                .collect::<Vec<_>>();
+            event!(
+                Level::WARN,
+                "Search results change over time, so mods found by searching are not reproducible. Pin the mods logged below to keep them."
+            );
+
+            let results: Vec<Result<Hit, String>> = pool.install(|| {
+                queries
+                    .par_iter()
+                    .map(|query| {
+                        let filters = (minecraft_version.as_deref(), modloader.as_deref());
+                        match curse_client {
+                            Some(ref client) => search_curse(client, query, sort, filters),
+                            None => search_modrinth(&modrinth_client, query, sort, filters),
+                        }
+                    })
+                    .collect()
+            });
+
+            let mut mods = vec![];
+            let mut failures = vec![];
+            for (query, result) in queries.iter().zip(results) {
+                match result {
+                    Ok(hit) => {
+                        event!(Level::INFO, "Search '{}' found {} ({})", query, hit.title, hit.slug);
+                        mods.push(hit.definition);
+                    },
+                    Err(reason) => failures.push(format!("- {query}: {reason}")),
+                }
+            }
+            if !failures.is_empty() {
+                event!(Level::ERROR, "Searches did not find a mod:\n{}", failures.join("\n"));
+                panic!();
+            }
+
+            if out_channel.send(mods).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::Mods(channel(1).0))])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "ModSearch",
+            vec![Port::required("queries", ChannelType::List)],
+            vec![Port::required("default", ChannelType::Mods)],
+        )
+    }
+}
+
+fn search_modrinth(client: &ModrinthClient, query: &str, sort: SearchSort, filters: (Option<&str>, Option<&str>)) -> Result<Hit, String> {
+    let hit = client
+        .search_projects(query, sort, filters.0, filters.1, 1)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .next()
+        .ok_or("no results")?;
+    Ok(Hit {
+        definition: ModDefinition::Modrinth {
+            id: Some(hit.project_id),
+            file_id: None,
+            exclude_versions: Vec::new(),
+            fields: fields(&hit.slug),
+        },
+        slug: hit.slug,
+        title: hit.title,
+    })
+}
+
+fn search_curse(client: &CurseClient, query: &str, sort: SearchSort, filters: (Option<&str>, Option<&str>)) -> Result<Hit, String> {
+    let hit = client
+        .search_mods(query, sort, filters.0, filters.1, 1)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .next()
+        .ok_or("no results")?;
+    Ok(Hit {
+        definition: ModDefinition::Curse {
+            id: Some(hit.id),
+            file_id: None,
+            exclude_versions: Vec::new(),
+            fields: fields(&hit.slug),
+        },
+        slug: hit.slug,
+        title: hit.name,
+    })
+}
+
+fn fields(slug: &str) -> ModDefinitionFields {
+    ModDefinitionFields {
+        name: slug.to_owned(),
+        side: Side::default(),
+        required: None,
+        default: None,
+        group: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel, serve_http},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_mod_search() {
+        let node_id = "search";
+        let base_url = serve_http(HashMap::from([(
+            "/v2/search".to_owned(),
+            (
+                200,
+                br#"{"hits":[{"project_id":"AANobbMI","slug":"sodium","title":"Sodium","downloads":100},{"project_id":"gvQqBUqZ","slug":"lithium","title":"Lithium","downloads":50}],"offset":0,"limit":1,"total_hits":2}"#.to_vec(),
+            ),
+        )]));
+        let queries_channel = channel(1).0;
+        let input_ids = HashMap::from([("queries".into(), ChannelId::from_str("query-source").unwrap())]);
+        let node = NodeConfigTypes::ModSearch(ModSearch::default());
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("query-source").unwrap(),
+                InputType::List(queries_channel.clone()),
+            )]))
+            .modrinth_base_url(&base_url)
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Mods, ctx);
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        queries_channel.send(vec!["rendering optimization".into(), "  ".into()]).unwrap();
+        handle.join().unwrap();
+
+        let expected: Vec<ModDefinition> = serde_yaml::from_str("- name: sodium\n  source: modrinth\n  id: AANobbMI\n").unwrap();
+        assert_eq!(read_channel(&mut out_channel, Duration::from_secs(10)).unwrap(), expected);
+    }
+
+    #[test]
+    fn empty_results_fail() {
+        let node_id = "search";
+        let base_url = serve_http(HashMap::from([(
+            "/v2/search".to_owned(),
+            (200, br#"{"hits":[],"offset":0,"limit":1,"total_hits":0}"#.to_vec()),
+        )]));
+        let queries_channel = channel(1).0;
+        let input_ids = HashMap::from([("queries".into(), ChannelId::from_str("query-source").unwrap())]);
+        let node = NodeConfigTypes::ModSearch(ModSearch::default());
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("query-source").unwrap(),
+                InputType::List(queries_channel.clone()),
+            )]))
+            .modrinth_base_url(&base_url)
+            .build();
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        queries_channel.send(vec!["no such mod".into()]).unwrap();
+        assert!(handle.join().is_err());
+    }
+}
@@ -1,5 +1,6 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
     thread::{spawn, JoinHandle},
 };
 
@@ -7,16 +8,102 @@ use serde::Deserialize;
 use tokio::sync::broadcast::channel;
 use tracing::{event, span, Level};
 use tracing_unwrap::ResultExt;
+use urlencoding::encode;
 
 use crate::di::container::{DiContainer, InputType, OutputType};
 
 use super::{
-    config::{ChannelId, NodeConfig, NodeInitError},
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port, ResolvedMod},
     utils::{get_input, get_output},
 };
 
 #[derive(Debug, Clone, Deserialize, PartialEq)]
-pub struct ModWriter;
+pub struct ModWriter {
+    /// Field used to order mods in the generated manifests.
+    #[serde(default)]
+    pub sort_by: SortBy,
+    /// What to do when filenames differ only by case, which would clobber each other on
+    /// case-insensitive filesystems.
+    #[serde(default)]
+    pub filename_collisions: CollisionPolicy,
+    /// Run the Nix manifest through nixpkgs-fmt. Skipping this is faster for large mod lists,
+    /// and the output is still valid Nix.
+    #[serde(default = "default_format_nix")]
+    pub format_nix: bool,
+    /// Nix expressions listed in the manifest's `imports`, such as paths to other mod sets.
+    #[serde(default)]
+    pub imports: Vec<String>,
+    /// Extra top-level attributes of the Nix manifest, such as `packVersion`.
+    #[serde(default)]
+    pub extra_attrs: BTreeMap<String, NixValue>,
+}
+
+fn default_format_nix() -> bool {
+    true
+}
+
+impl Default for ModWriter {
+    fn default() -> Self {
+        ModWriter {
+            sort_by: SortBy::default(),
+            filename_collisions: CollisionPolicy::default(),
+            format_nix: default_format_nix(),
+            imports: vec![],
+            extra_attrs: BTreeMap::new(),
+        }
+    }
+}
+
+/// A value of an extra attribute in the Nix manifest.
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum NixValue {
+    Bool(bool),
+    Integer(i64),
+    String(String),
+}
+
+impl Display for NixValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NixValue::Bool(value) => write!(f, "{value}"),
+            NixValue::Integer(value) => write!(f, "{value}"),
+            NixValue::String(value) => {
+                let escaped = value.replace('\\', "\\\\").replace('"', "\\\"").replace("${", "\\${");
+                write!(f, "\"{escaped}\"")
+            },
+        }
+    }
+}
+
+/// Top-level attributes of the Nix manifest besides the Minecraft version and the mods.
+#[derive(Debug, Clone, Default)]
+pub(super) struct NixAttrs {
+    pub(super) imports: Vec<String>,
+    pub(super) extra_attrs: BTreeMap<String, NixValue>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CollisionPolicy {
+    /// Append the mod name to every colliding filename but the first.
+    #[default]
+    Rename,
+    /// Fail the node.
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SortBy {
+    /// Sort by mod slug.
+    #[default]
+    Name,
+    /// Sort by display name, then by slug for mods sharing a title.
+    Title,
+    /// Keep the order of the input.
+    None,
+}
 
 impl NodeConfig for ModWriter {
     fn validate_and_spawn(
@@ -29,6 +116,27 @@ impl NodeConfig for ModWriter {
         let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Text, ctx)?;
         let json_out = get_output!(ChannelId(node_id.clone(), "json".into()), Text, ctx)?;
 
+        let sort_by = self.sort_by;
+        let collision_policy = self.filename_collisions;
+        let format_nix = self.format_nix;
+        for name in self.extra_attrs.keys() {
+            if ["version", "imports", "mods"].contains(&name.as_str()) || !is_nix_identifier(name) {
+                return Err(NodeInitError::InvalidOption {
+                    option: "extra_attrs".into(),
+                    reason: format!("'{name}' is not an identifier that can be added to the manifest"),
+                });
+            }
+        }
+        if self.imports.iter().any(|import| import.trim().is_empty()) {
+            return Err(NodeInitError::InvalidOption {
+                option: "imports".into(),
+                reason: "imports cannot be empty".into(),
+            });
+        }
+        let attrs = NixAttrs {
+            imports: self.imports.clone(),
+            extra_attrs: self.extra_attrs.clone(),
+        };
         let mut waker = ctx.get_waker();
 
         let minecraft_version = ctx
@@ -42,21 +150,47 @@ impl NodeConfig for ModWriter {
             }
 
             let mut resolved = resolved_mods_channel.blocking_recv().expect_or_log("Failed to receive on resolved input");
-            resolved.sort_by_key(|r| r.name.clone());
+            match sort_by {
+                SortBy::Name => resolved.sort_by(|a, b| a.name.cmp(&b.name)),
+                SortBy::Title => resolved.sort_by(|a, b| a.title.cmp(&b.title).then_with(|| a.name.cmp(&b.name))),
+                SortBy::None => {},
+            }
 
-            let raw_nix_file = format!(
-                r#"{{
-                version = "{version}";
-                imports = [];
-                mods = {{
-                    {mods}
-                }};
-            }}"#,
-                version = minecraft_version,
-                mods = resolved.iter().map(|s| s.to_string()).collect::<Vec<_>>().join("\n")
-            );
-            let nix_file = nixpkgs_fmt::reformat_string(&raw_nix_file);
+            let collisions = find_filename_collisions(&resolved);
+            if !collisions.is_empty() {
+                let listing = collisions
+                    .iter()
+                    .map(|group| {
+                        group
+                            .iter()
+                            .map(|&i| format!("{} ({})", resolved[i].filename, resolved[i].name))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    })
+                    .collect::<Vec<_>>()
+                    .join("; ");
+                match collision_policy {
+                    CollisionPolicy::Rename => {
+                        event!(Level::WARN, "Renaming mods with filenames that differ only by case: {listing}");
+                        for group in collisions {
+                            for i in group.into_iter().skip(1) {
+                                let filename = disambiguate_filename(&resolved[i].filename, &resolved[i].name);
+                                resolved[i].encoded = encode(&filename).into_owned();
+                                resolved[i].filename = filename;
+                            }
+                        }
+                    },
+                    CollisionPolicy::Error => {
+                        event!(Level::ERROR, "Mods have filenames that differ only by case: {listing}");
+                        panic!();
+                    },
+                }
+            }
 
+            let nix_file = nix_manifest_with(&minecraft_version, &resolved, format_nix, &attrs);
+
+            // The manifest is built in memory rather than streamed to the output file: serializing
+            // 5000 mods takes a few milliseconds and under 3 MB, far less than Nix formatting.
             let json_file = serde_json::to_string_pretty(&resolved).expect_or_log("Serialization of resolved mods to JSON failed");
 
             if out_channel.send(nix_file).is_err() {
@@ -74,6 +208,76 @@ impl NodeConfig for ModWriter {
             (ChannelId(node_id.to_owned(), "json".into()), InputType::Text(channel(1).0)),
         ])
     }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "ModWriter",
+            vec![Port::required("resolved", ChannelType::ResolvedMods)],
+            vec![Port::required("default", ChannelType::Text), Port::required("json", ChannelType::Text)],
+        )
+        .with_config_keys(vec!["minecraft_version"])
+    }
+}
+
+/// Write mods as a Nix manifest for the given Minecraft version, optionally formatted with
+/// nixpkgs-fmt.
+pub(super) fn nix_manifest(minecraft_version: &str, mods: &[ResolvedMod], format: bool) -> String {
+    nix_manifest_with(minecraft_version, mods, format, &NixAttrs::default())
+}
+
+/// Write a Nix manifest with the given imports and extra attributes, which are placed between
+/// the imports and the mods.
+pub(super) fn nix_manifest_with(minecraft_version: &str, mods: &[ResolvedMod], format: bool, attrs: &NixAttrs) -> String {
+    let raw_nix_file = format!(
+        r#"{{
+                version = "{version}";
+                imports = [{imports}];{extra_attrs}
+                mods = {{
+                    {mods}
+                }};
+            }}"#,
+        version = minecraft_version,
+        imports = attrs.imports.join(" "),
+        extra_attrs = attrs
+            .extra_attrs
+            .iter()
+            .map(|(name, value)| format!("\n{name} = {value};"))
+            .collect::<String>(),
+        mods = mods.iter().map(|s| s.to_string()).collect::<Vec<_>>().join("\n")
+    );
+    match format {
+        true => nixpkgs_fmt::reformat_string(&raw_nix_file),
+        false => raw_nix_file,
+    }
+}
+
+/// Whether `name` can be written as an attribute name without quoting.
+fn is_nix_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '\'')
+}
+
+/// Find groups of mods whose filenames are equal when compared case-insensitively. Each group
+/// lists indices into `mods` in order.
+fn find_filename_collisions(mods: &[ResolvedMod]) -> Vec<Vec<usize>> {
+    let mut groups: Vec<(String, Vec<usize>)> = Vec::new();
+    for (i, m) in mods.iter().enumerate() {
+        let key = m.filename.to_lowercase();
+        match groups.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, group)) => group.push(i),
+            None => groups.push((key, vec![i])),
+        }
+    }
+    groups.into_iter().map(|(_, group)| group).filter(|group| group.len() > 1).collect()
+}
+
+/// Insert the mod name before the file extension, e.g. `Example.jar` -> `Example-mymod.jar`.
+fn disambiguate_filename(filename: &str, name: &str) -> String {
+    match filename.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => format!("{stem}-{name}.{extension}"),
+        _ => format!("{filename}-{name}"),
+    }
 }
 
 #[cfg(test)]
@@ -85,8 +289,8 @@ mod tests {
     use crate::{
         di::container::DiContainerBuilder,
         node::{
-            config::{NodeConfigTypes, ResolvedMod, Side},
-            utils::{get_output_test, read_channel},
+            config::{ModSource, NodeConfigTypes, ProjectKind, ResolvedMod, Side},
+            utils::{get_output_test, read_channel, test_mod},
         },
     };
 
@@ -97,7 +301,7 @@ mod tests {
         let node_id = "writer";
         let resolved_mods_channel = broadcast::channel(1).0;
         let input_ids = HashMap::from([("resolved".into(), ChannelId::from_str("mod-source").unwrap())]);
-        let node = NodeConfigTypes::ModWriter(ModWriter);
+        let node = NodeConfigTypes::ModWriter(ModWriter::default());
 
         let mut ctx = DiContainerBuilder::default()
             .channel_from_node(node.generate_channels(node_id))
@@ -125,6 +329,13 @@ mod tests {
             size: 1119478,
             md5: "9df0dc628ebcd787270f487fbbf8157a".to_owned(),
             sha256: "17c589aad9907d4ba56d578d502afa80aac1ba2fa8677e8b4d06c019c41d7731".to_owned(),
+            // Provenance is only written to the JSON manifest
+            source: Some(ModSource::Curse {
+                project_id: 357178,
+                file_id: 3437402,
+            }),
+            kind: ProjectKind::Mod,
+            group: None,
         }];
 
         let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
@@ -171,11 +382,183 @@ mod tests {
     "src": "https://edge.forgecdn.net/files/3437/402/_MixinBootstrap-1.1.0.jar",
     "size": 1119478,
     "md5": "9df0dc628ebcd787270f487fbbf8157a",
-    "sha256": "17c589aad9907d4ba56d578d502afa80aac1ba2fa8677e8b4d06c019c41d7731"
+    "sha256": "17c589aad9907d4ba56d578d502afa80aac1ba2fa8677e8b4d06c019c41d7731",
+    "source": {
+      "type": "curse",
+      "project_id": 357178,
+      "file_id": 3437402
+    }
   }
 ]"#;
 
         assert_eq!(output, expected);
         assert_eq!(json_output, json_expected);
     }
+
+    #[test]
+    fn test_mod_writer_sort_by_title() {
+        let node_id = "writer";
+        let resolved_mods_channel = broadcast::channel(1).0;
+        let input_ids = HashMap::from([("resolved".into(), ChannelId::from_str("mod-source").unwrap())]);
+        let node = NodeConfigTypes::ModWriter(ModWriter {
+            sort_by: SortBy::Title,
+            ..Default::default()
+        });
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("mod-source").unwrap(),
+                InputType::ResolvedMods(resolved_mods_channel.clone()),
+            )]))
+            .set_config("minecraft_version", "1.12.2")
+            .build();
+
+        let mut json_out_channel = get_output_test!(ChannelId::from_str("writer::json").unwrap(), Text, ctx);
+
+        let titled = |name: &str, title: &str| ResolvedMod {
+            title: title.to_owned(),
+            ..test_mod(name)
+        };
+        let resolved_mods = vec![
+            titled("zeta-core", "Core"),
+            titled("jei", "Just Enough Items"),
+            titled("alpha-core", "Core"),
+            titled("appeng", "Applied Energistics 2"),
+        ];
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        resolved_mods_channel.send(resolved_mods).unwrap();
+        handle.join().unwrap();
+
+        let json_output = read_channel(&mut json_out_channel, Duration::from_secs(30)).unwrap();
+        let names: Vec<String> = serde_json::from_str::<Vec<ResolvedMod>>(&json_output)
+            .unwrap()
+            .into_iter()
+            .map(|m| m.name)
+            .collect();
+
+        assert_eq!(names, vec!["appeng", "alpha-core", "zeta-core", "jei"]);
+    }
+
+    #[test]
+    fn test_mod_writer_unformatted() {
+        let node_id = "writer";
+        let resolved_mods_channel = broadcast::channel(1).0;
+        let input_ids = HashMap::from([("resolved".into(), ChannelId::from_str("mod-source").unwrap())]);
+        let node = NodeConfigTypes::ModWriter(ModWriter {
+            format_nix: false,
+            ..Default::default()
+        });
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("mod-source").unwrap(),
+                InputType::ResolvedMods(resolved_mods_channel.clone()),
+            )]))
+            .set_config("minecraft_version", "1.12.2")
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str("writer").unwrap(), Text, ctx);
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        resolved_mods_channel.send(vec![test_mod("jei")]).unwrap();
+        handle.join().unwrap();
+
+        // The raw output is not formatted, but formats to the usual manifest
+        let output = read_channel(&mut out_channel, Duration::from_secs(30)).unwrap();
+        let formatted = nixpkgs_fmt::reformat_string(&output);
+        assert_ne!(output, formatted);
+        assert!(formatted.starts_with("{\n  version = \"1.12.2\";\n  imports = [ ];\n  mods = {\n    \"jei\" = {\n"));
+    }
+
+    #[test]
+    fn test_mod_writer_nix_attrs() {
+        let node_id = "writer";
+        let resolved_mods_channel = broadcast::channel(1).0;
+        let input_ids = HashMap::from([("resolved".into(), ChannelId::from_str("mod-source").unwrap())]);
+        let node = NodeConfigTypes::ModWriter(ModWriter {
+            imports: vec!["./base-mods.nix".into(), "./client-mods.nix".into()],
+            extra_attrs: BTreeMap::from([
+                ("packVersion".into(), NixValue::String("1.2.0".into())),
+                ("description".into(), NixValue::String("Uses \"${quotes}\"".into())),
+                ("serverPort".into(), NixValue::Integer(25565)),
+                ("public".into(), NixValue::Bool(false)),
+            ]),
+            ..Default::default()
+        });
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("mod-source").unwrap(),
+                InputType::ResolvedMods(resolved_mods_channel.clone()),
+            )]))
+            .set_config("minecraft_version", "1.12.2")
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str("writer").unwrap(), Text, ctx);
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        resolved_mods_channel.send(vec![test_mod("jei")]).unwrap();
+        handle.join().unwrap();
+
+        let output = read_channel(&mut out_channel, Duration::from_secs(30)).unwrap();
+        assert!(output.starts_with(
+            r#"{
+  version = "1.12.2";
+  imports = [ ./base-mods.nix ./client-mods.nix ];
+  description = "Uses \"\${quotes}\"";
+  packVersion = "1.2.0";
+  public = false;
+  serverPort = 25565;
+  mods = {
+    "jei" = {
+"#
+        ));
+    }
+
+    #[test]
+    fn reserved_nix_attrs() {
+        let node = NodeConfigTypes::ModWriter(ModWriter {
+            extra_attrs: BTreeMap::from([("mods".into(), NixValue::Bool(true))]),
+            ..Default::default()
+        });
+        let ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels("writer"))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("mod-source").unwrap(),
+                InputType::ResolvedMods(broadcast::channel(1).0),
+            )]))
+            .set_config("minecraft_version", "1.12.2")
+            .build();
+        let input_ids = HashMap::from([("resolved".into(), ChannelId::from_str("mod-source").unwrap())]);
+        let result = node.validate_and_spawn("writer".into(), &input_ids, &ctx);
+        assert!(matches!(result, Err(NodeInitError::InvalidOption { option, .. }) if option == "extra_attrs"));
+        assert!(is_nix_identifier("pack-version'"));
+        assert!(!is_nix_identifier("1st"));
+        assert!(!is_nix_identifier("pack.version"));
+    }
+
+    #[test]
+    fn filename_collisions() {
+        let mods = vec![
+            ResolvedMod {
+                filename: "Example-1.0.jar".into(),
+                ..test_mod("example")
+            },
+            test_mod("other"),
+            ResolvedMod {
+                filename: "example-1.0.jar".into(),
+                ..test_mod("example-fork")
+            },
+        ];
+        assert_eq!(find_filename_collisions(&mods), vec![vec![0, 2]]);
+        assert_eq!(disambiguate_filename("example-1.0.jar", "example-fork"), "example-1.0-example-fork.jar");
+        assert_eq!(disambiguate_filename("README", "docs"), "README-docs");
+    }
 }
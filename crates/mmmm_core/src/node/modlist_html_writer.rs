@@ -0,0 +1,157 @@
+use std::{
+    collections::HashMap,
+    thread::{spawn, JoinHandle},
+};
+
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::di::container::{DiContainer, InputType, OutputType};
+
+use super::{
+    config::{ChannelId, ChannelType, ModSource, NodeConfig, NodeDescription, NodeInitError, Port, ResolvedMod},
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct ModlistHtmlWriter;
+
+impl NodeConfig for ModlistHtmlWriter {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut mods_channel = get_input!("mods", ResolvedMods, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Text, ctx)?;
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "ModlistHtmlWriter", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let mut mods = mods_channel.blocking_recv().expect_or_log("Failed to receive on mods input");
+            mods.sort_by_key(|m| m.title.to_lowercase());
+
+            if out_channel.send(modlist_html(&mods)).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::Text(channel(1).0))])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "ModlistHtmlWriter",
+            vec![Port::required("mods", ChannelType::ResolvedMods)],
+            vec![Port::required("default", ChannelType::Text)],
+        )
+    }
+}
+
+/// Write the list in the format of the `modlist.html` the CurseForge app exports: a `<ul>` with
+/// one linked `<li>` per mod.
+fn modlist_html(mods: &[ResolvedMod]) -> String {
+    let items = mods
+        .iter()
+        .map(|m| format!("<li><a href=\"{}\">{}</a></li>\n", escape(&project_url(m)), escape(&m.title)))
+        .collect::<String>();
+    format!("<ul>\n{items}</ul>\n")
+}
+
+/// Link to a mod's project page. CurseForge redirects project IDs to the project's page, while
+/// mods without a platform project link to the file they were downloaded from.
+pub(super) fn project_url(m: &ResolvedMod) -> String {
+    match &m.source {
+        Some(ModSource::Curse { project_id, .. }) => format!("https://www.curseforge.com/projects/{project_id}"),
+        Some(ModSource::Modrinth { project_id, .. }) => format!("https://modrinth.com/project/{project_id}"),
+        Some(ModSource::Url) | None => m.src.clone(),
+    }
+}
+
+pub(super) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use tokio::sync::broadcast;
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel, test_mod},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_modlist_html_writer() {
+        let node_id = "modlist";
+        let mods_channel = broadcast::channel(1).0;
+        let input_ids = HashMap::from([("mods".into(), ChannelId::from_str("mod-source").unwrap())]);
+        let node = NodeConfigTypes::ModlistHtmlWriter(ModlistHtmlWriter);
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("mod-source").unwrap(),
+                InputType::ResolvedMods(mods_channel.clone()),
+            )]))
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Text, ctx);
+
+        let mods = vec![
+            ResolvedMod {
+                title: "Sodium".into(),
+                source: Some(ModSource::Modrinth {
+                    project_id: "AANobbMI".into(),
+                    version_id: "b4hTi3mo".into(),
+                }),
+                ..test_mod("sodium")
+            },
+            ResolvedMod {
+                title: "Just Enough Items (JEI)".into(),
+                source: Some(ModSource::Curse {
+                    project_id: 238222,
+                    file_id: 4712868,
+                }),
+                ..test_mod("jei")
+            },
+            ResolvedMod {
+                title: "Tom's <Storage> & More".into(),
+                ..test_mod("toms-storage")
+            },
+        ];
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        mods_channel.send(mods).unwrap();
+
+        let html = read_channel(&mut out_channel, Duration::from_secs(5)).unwrap();
+        assert_eq!(
+            html,
+            "<ul>\n\
+             <li><a href=\"https://www.curseforge.com/projects/238222\">Just Enough Items (JEI)</a></li>\n\
+             <li><a href=\"https://modrinth.com/project/AANobbMI\">Sodium</a></li>\n\
+             <li><a href=\"https://example.com/toms-storage.jar\">Tom's &lt;Storage&gt; &amp; More</a></li>\n\
+             </ul>\n"
+        );
+        handle.join().unwrap();
+    }
+}
@@ -0,0 +1,214 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    thread::{spawn, JoinHandle},
+};
+
+use serde::Deserialize;
+use tokio::sync::broadcast::{channel, Receiver};
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::di::container::{DiContainer, InputType, OutputType};
+
+use super::{
+    config::{ChannelId, ChannelType, ModDefinition, ModDefinitionFields, ModSource, NodeConfig, NodeDescription, NodeInitError, Port, ResolvedMod},
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct ModlistToSource;
+
+const MODS: &str = "mods";
+const MANIFEST: &str = "manifest";
+
+/// Where the resolved mods are read from.
+enum ModlistInput {
+    Mods(Receiver<Vec<ResolvedMod>>),
+    /// A JSON manifest, as written by ModWriter's `json` output.
+    Manifest(Receiver<String>),
+}
+
+impl NodeConfig for ModlistToSource {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut input = match (input_ids.contains_key(MODS), input_ids.contains_key(MANIFEST)) {
+            (true, false) => ModlistInput::Mods(get_input!(MODS, ResolvedMods, ctx, input_ids)?),
+            (false, true) => ModlistInput::Manifest(get_input!(MANIFEST, Text, ctx, input_ids)?),
+            (false, false) => return Err(NodeInitError::MissingInputId(MODS.into())),
+            (true, true) => {
+                return Err(NodeInitError::InvalidOption {
+                    option: "input".into(),
+                    reason: format!("connect either {MODS} or {MANIFEST}, not both"),
+                })
+            },
+        };
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Mods, ctx)?;
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "ModlistToSource", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let mods = match input {
+                ModlistInput::Mods(ref mut channel) => channel.blocking_recv().expect_or_log("Failed to receive on mods input"),
+                ModlistInput::Manifest(ref mut channel) => {
+                    let manifest = channel.blocking_recv().expect_or_log("Failed to receive on manifest input");
+                    serde_json::from_str::<Vec<ResolvedMod>>(&manifest).expect_or_log("Failed to deserialize mod manifest")
+                },
+            };
+
+            let unknown = mods.iter().filter(|m| m.source.is_none()).map(|m| m.name.as_str()).collect::<Vec<_>>();
+            if !unknown.is_empty() {
+                event!(
+                    Level::WARN,
+                    "Mods without a recorded source are pinned to their download URL:\n- {}",
+                    unknown.join("\n- ")
+                );
+            }
+
+            if out_channel.send(mods.into_iter().map(to_definition).collect()).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::Mods(channel(1).0))])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "ModlistToSource",
+            vec![
+                Port::optional(MODS, ChannelType::ResolvedMods),
+                Port::optional(MANIFEST, ChannelType::Text),
+            ],
+            vec![Port::required("default", ChannelType::Mods)],
+        )
+    }
+}
+
+/// Turn a resolved mod back into a definition pinned to the same file. Mods without a recorded
+/// source, such as those from manifests written before sources were tracked, become URL mods.
+fn to_definition(resolved: ResolvedMod) -> ModDefinition {
+    let fields = ModDefinitionFields {
+        name: resolved.name,
+        side: resolved.side,
+        required: Some(resolved.required),
+        default: Some(resolved.default),
+        group: resolved.group,
+    };
+    match resolved.source {
+        Some(ModSource::Curse { project_id, file_id }) => ModDefinition::Curse {
+            id: Some(project_id),
+            file_id: Some(file_id),
+            exclude_versions: Vec::new(),
+            fields,
+        },
+        Some(ModSource::Modrinth { project_id, version_id }) => ModDefinition::Modrinth {
+            id: Some(project_id),
+            file_id: Some(version_id),
+            exclude_versions: Vec::new(),
+            fields,
+        },
+        Some(ModSource::Url) | None => ModDefinition::Url {
+            location: resolved.src,
+            filename: Some(resolved.filename),
+            headers: BTreeMap::new(),
+            fields,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        node::{
+            config::{NodeConfigTypes, Side},
+            utils::{get_output_test, read_channel, test_mod},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_modlist_to_source() {
+        let node_id = "to-source";
+        let manifest_channel = channel(1).0;
+        let input_ids = HashMap::from([("manifest".into(), ChannelId::from_str("saved-manifest").unwrap())]);
+        let node = NodeConfigTypes::ModlistToSource(ModlistToSource);
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("saved-manifest").unwrap(),
+                InputType::Text(manifest_channel.clone()),
+            )]))
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Mods, ctx);
+
+        let mut curse = test_mod("mouse-tweaks");
+        curse.side = Side::Client;
+        curse.source = Some(ModSource::Curse {
+            project_id: 60089,
+            file_id: 4581240,
+        });
+        let mut modrinth = test_mod("appleskin");
+        modrinth.required = false;
+        modrinth.source = Some(ModSource::Modrinth {
+            project_id: "EsAfCjCV".into(),
+            version_id: "pyRMqaEV".into(),
+        });
+        let mut url = test_mod("private-mod");
+        url.src = "https://${env.MIRROR}/private-mod.jar".into();
+        url.filename = "private-mod-1.0.jar".into();
+        url.source = Some(ModSource::Url);
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        manifest_channel
+            .send(serde_json::to_string(&vec![curse, modrinth, url]).unwrap())
+            .unwrap();
+        handle.join().unwrap();
+
+        let fields = |name: &str, side: Side, required: bool| ModDefinitionFields {
+            name: name.into(),
+            side,
+            required: Some(required),
+            default: Some(true),
+            group: None,
+        };
+        assert_eq!(
+            read_channel(&mut out_channel, Duration::from_secs(10)).unwrap(),
+            vec![
+                ModDefinition::Curse {
+                    id: Some(60089),
+                    file_id: Some(4581240),
+                    exclude_versions: Vec::new(),
+                    fields: fields("mouse-tweaks", Side::Client, true),
+                },
+                ModDefinition::Modrinth {
+                    id: Some("EsAfCjCV".into()),
+                    file_id: Some("pyRMqaEV".into()),
+                    exclude_versions: Vec::new(),
+                    fields: fields("appleskin", Side::Both, false),
+                },
+                ModDefinition::Url {
+                    location: "https://${env.MIRROR}/private-mod.jar".into(),
+                    filename: Some("private-mod-1.0.jar".into()),
+                    headers: BTreeMap::new(),
+                    fields: fields("private-mod", Side::Both, true),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use std::{
+    collections::HashMap,
+    thread::{spawn, JoinHandle},
+};
+
+use api_client::{common::ApiError, modrinth::ModrinthClient};
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::di::container::{DiContainer, InputType, OutputType};
+
+use super::{
+    config::{ChannelId, ChannelType, ModDefinition, ModDefinitionFields, NodeConfig, NodeDescription, NodeInitError, Port, Side},
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Default)]
+pub struct ModrinthCollection {
+    /// Side applied to every mod in the collection.
+    #[serde(default)]
+    pub side: Side,
+    /// Whether every mod in the collection is required. Unset means required.
+    pub required: Option<bool>,
+    /// Whether every mod in the collection is enabled by default. Unset means enabled.
+    pub default: Option<bool>,
+}
+
+impl NodeConfig for ModrinthCollection {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut collection_channel = get_input!("collection", Text, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Mods, ctx)?;
+        // Collections are not cached, so they can never be expanded offline.
+        if ctx.is_offline() {
+            return Err(NodeInitError::Offline);
+        }
+        let client = ctx.get_modrinth_client();
+        let template = ModDefinitionFields {
+            name: String::new(),
+            side: self.side,
+            required: self.required,
+            default: self.default,
+            group: None,
+        };
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "ModrinthCollection", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let collection = collection_channel.blocking_recv().expect_or_log("Failed to receive on collection input");
+            let mods = match resolve_modrinth_collection(&client, &collection, &template) {
+                Ok(mods) => mods,
+                Err(e) => {
+                    event!(Level::ERROR, "Failed to get Modrinth collection {}: {e}", collection.trim());
+                    panic!();
+                },
+            };
+            event!(Level::INFO, "Expanded collection into {} mods", mods.len());
+
+            if out_channel.send(mods).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::Mods(channel(1).0))])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "ModrinthCollection",
+            vec![Port::required("collection", ChannelType::Text)],
+            vec![Port::required("default", ChannelType::Mods)],
+        )
+    }
+}
+
+/// Expand a Modrinth collection into a Modrinth [`ModDefinition`] for each of its projects, with
+/// fields copied from `template`. Projects are named by ID, as the slug is not known until the mod
+/// is resolved.
+///
+/// `collection` may be either a collection ID or a link to the collection page.
+fn resolve_modrinth_collection(client: &ModrinthClient, collection: &str, template: &ModDefinitionFields) -> Result<Vec<ModDefinition>, ApiError> {
+    let collection = client.get_collection(parse_collection_id(collection))?;
+    let mut projects = collection.projects;
+    projects.sort();
+    Ok(projects
+        .into_iter()
+        .map(|project_id| ModDefinition::Modrinth {
+            fields: ModDefinitionFields {
+                name: project_id.clone(),
+                ..template.clone()
+            },
+            id: Some(project_id),
+            file_id: None,
+            exclude_versions: Vec::new(),
+        })
+        .collect())
+}
+
+/// Get the collection ID from either a bare ID or a URL like
+/// `https://modrinth.com/collection/{id}`.
+fn parse_collection_id(collection: &str) -> &str {
+    let collection = collection.trim().trim_end_matches('/');
+    match collection.rsplit_once("/collection/") {
+        Some((_, rest)) => rest.split(['/', '?', '#']).next().unwrap_or(rest),
+        None => collection,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel, serve_http},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_modrinth_collection() {
+        let node_id = "collection";
+        let base_url = serve_http(HashMap::from([(
+            "/v3/collection/AbCd1234".to_owned(),
+            (
+                200,
+                br#"{"id":"AbCd1234","name":"Performance","description":null,"projects":["gvQqBUqZ","AANobbMI"]}"#.to_vec(),
+            ),
+        )]));
+        let collection_channel = channel::<String>(1).0;
+        let input_ids = HashMap::from([("collection".into(), ChannelId::from_str("collection-url").unwrap())]);
+        let node = NodeConfigTypes::ModrinthCollection(ModrinthCollection {
+            side: Side::Client,
+            required: Some(false),
+            default: None,
+        });
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("collection-url").unwrap(),
+                InputType::Text(collection_channel.clone()),
+            )]))
+            .modrinth_base_url(&base_url)
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Mods, ctx);
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        collection_channel.send("https://modrinth.com/collection/AbCd1234\n".into()).unwrap();
+        handle.join().unwrap();
+
+        let output = read_channel(&mut out_channel, Duration::from_secs(30)).unwrap();
+        let expected: Vec<ModDefinition> = serde_yaml::from_str(
+            r#"---
+- name: AANobbMI
+  source: modrinth
+  id: AANobbMI
+  side: client
+  required: false
+- name: gvQqBUqZ
+  source: modrinth
+  id: gvQqBUqZ
+  side: client
+  required: false
+"#,
+        )
+        .unwrap();
+        assert_eq!(output, expected);
+        assert_eq!(parse_collection_id("AbCd1234"), "AbCd1234");
+        assert_eq!(parse_collection_id("https://modrinth.com/collection/AbCd1234/projects?x=1"), "AbCd1234");
+    }
+}
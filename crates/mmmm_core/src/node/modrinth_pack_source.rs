@@ -0,0 +1,183 @@
+use std::{
+    collections::HashMap,
+    thread::{spawn, JoinHandle},
+};
+
+use api_client::modrinth::{model::Version, ModrinthClient};
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::di::container::{DiContainer, InputType, OutputType};
+
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port},
+    utils::{get_input, get_optional_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct ModrinthPackSource;
+
+const PROJECT: &str = "project";
+const VERSION: &str = "version";
+
+impl NodeConfig for ModrinthPackSource {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut project_channel = get_input!(PROJECT, Text, ctx, input_ids)?;
+        // The version input is optional, and the newest version is used when it is not connected
+        let mut version_channel = get_optional_input!(VERSION, Text, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Text, ctx)?;
+        // Version listings are not cached, so the pack can never be located offline.
+        if ctx.is_offline() {
+            return Err(NodeInitError::Offline);
+        }
+        let client = ctx.get_modrinth_client();
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "ModrinthPackSource", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let project = project_channel.blocking_recv().expect_or_log("Failed to receive on project input");
+            let version = version_channel
+                .as_mut()
+                .map(|channel| channel.blocking_recv().expect_or_log("Failed to receive on version input"));
+            let project = project.trim();
+            let version = version.as_deref().map(str::trim);
+
+            let url = match pack_url(&client, project, version) {
+                Ok(url) => url,
+                Err(e) => {
+                    event!(Level::ERROR, "Failed to find modpack file for Modrinth project {project}: {e}");
+                    panic!();
+                },
+            };
+            event!(Level::INFO, "Found modpack file {url}");
+
+            if out_channel.send(url).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::Text(channel(1).0))])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "ModrinthPackSource",
+            vec![Port::required(PROJECT, ChannelType::Text), Port::optional(VERSION, ChannelType::Text)],
+            vec![Port::required("default", ChannelType::Text)],
+        )
+    }
+}
+
+/// Get the download URL of the `.mrpack` file for a version of a Modrinth project.
+///
+/// `version` may be either a version ID or a version number. When it is not given, the most
+/// recently published version is used.
+fn pack_url(client: &ModrinthClient, project: &str, version: Option<&str>) -> Result<String, String> {
+    let versions = client.get_mod_versions(project, None, None).map_err(|e| e.to_string())?;
+    let version = select_version(&versions, version)?;
+    version
+        .files
+        .iter()
+        .find(|file| file.primary && file.filename.ends_with(".mrpack"))
+        .or_else(|| version.files.iter().find(|file| file.filename.ends_with(".mrpack")))
+        .map(|file| file.url.clone())
+        .ok_or_else(|| format!("version {} has no .mrpack file", version.version_number))
+}
+
+fn select_version<'a>(versions: &'a [Version], version: Option<&str>) -> Result<&'a Version, String> {
+    match version {
+        Some(wanted) => versions
+            .iter()
+            .find(|v| v.id == wanted)
+            .or_else(|| versions.iter().find(|v| v.version_number == wanted))
+            .ok_or_else(|| format!("no version with ID or number {wanted}")),
+        // Publish dates are RFC 3339 timestamps, which sort chronologically as strings
+        None => versions
+            .iter()
+            .max_by(|a, b| a.date_published.cmp(&b.date_published))
+            .ok_or_else(|| "project has no versions".to_owned()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel, serve_http},
+        },
+    };
+
+    use super::*;
+
+    fn version(id: &str, number: &str, date: &str) -> String {
+        format!(
+            r#"{{"name":"v","version_number":"{number}","game_versions":[],"version_type":"release","loaders":[],"id":"{id}","project_id":"pack-id","files":[
+                {{"hashes":{{"sha512":"","sha1":""}},"url":"https://cdn.example.com/{id}/changelog.txt","filename":"changelog.txt","primary":false,"size":1}},
+                {{"hashes":{{"sha512":"","sha1":""}},"url":"https://cdn.example.com/{id}/pack.mrpack","filename":"pack.mrpack","primary":true,"size":1}}
+            ],"date_published":"{date}"}}"#
+        )
+    }
+
+    fn run(base_url: &str, version_input: Option<&str>) -> String {
+        let node_id = "pack";
+        let project_channel = channel(1).0;
+        let version_channel = channel(1).0;
+        let mut input_ids = HashMap::from([(PROJECT.into(), ChannelId::from_str("project").unwrap())]);
+        if version_input.is_some() {
+            input_ids.insert(VERSION.into(), ChannelId::from_str("version").unwrap());
+        }
+        let node = NodeConfigTypes::ModrinthPackSource(ModrinthPackSource);
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([
+                (ChannelId::from_str("project").unwrap(), InputType::Text(project_channel.clone())),
+                (ChannelId::from_str("version").unwrap(), InputType::Text(version_channel.clone())),
+            ]))
+            .modrinth_base_url(base_url)
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Text, ctx);
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        project_channel.send("my-pack".into()).unwrap();
+        if let Some(version) = version_input {
+            version_channel.send(version.into()).unwrap();
+        }
+        handle.join().unwrap();
+
+        read_channel(&mut out_channel, Duration::from_secs(10)).unwrap()
+    }
+
+    #[test]
+    fn test_modrinth_pack_source() {
+        let versions = format!(
+            "[{},{},{}]",
+            version("v2000000", "2.0.0", "2023-06-01T00:00:00Z"),
+            version("v3000000", "3.0.0", "2023-09-01T00:00:00Z"),
+            version("v1000000", "1.0.0", "2023-01-01T00:00:00Z"),
+        );
+        let base_url = serve_http(HashMap::from([("/v2/project/my-pack/version".to_owned(), (200, versions.into_bytes()))]));
+
+        assert_eq!(run(&base_url, None), "https://cdn.example.com/v3000000/pack.mrpack");
+        assert_eq!(run(&base_url, Some("1.0.0")), "https://cdn.example.com/v1000000/pack.mrpack");
+        assert_eq!(run(&base_url, Some("v2000000")), "https://cdn.example.com/v2000000/pack.mrpack");
+    }
+}
@@ -0,0 +1,392 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    str::FromStr,
+    thread::{spawn, JoinHandle},
+};
+
+use digest::Digest;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use api_client::common::Downloader;
+
+use crate::{
+    di::container::{DiContainer, InputType, OutputType},
+    file::{filepath::FilePath, filetree::FileTree},
+};
+
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port, ResolvedMod, Side},
+    utils::{get_input, get_output, parse_loader},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct MrpackBuilder {
+    /// Name of the pack shown by launchers.
+    pub name: String,
+    /// Version of the pack.
+    pub version: String,
+    /// Short description of the pack.
+    #[serde(default)]
+    pub summary: Option<String>,
+}
+
+/// Directories of the overrides input that are copied into the pack.
+const OVERRIDE_DIRECTORIES: [&str; 3] = ["overrides/**", "client-overrides/**", "server-overrides/**"];
+
+/// Hosts Modrinth accepts downloads from when a pack is published to it.
+const MODRINTH_HOSTS: [&str; 4] = ["cdn.modrinth.com", "github.com", "raw.githubusercontent.com", "gitlab.com"];
+
+/// The `modrinth.index.json` file of a Modrinth pack.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct Index {
+    format_version: u32,
+    game: String,
+    version_id: String,
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<String>,
+    files: Vec<IndexFile>,
+    dependencies: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct IndexFile {
+    path: String,
+    hashes: BTreeMap<String, String>,
+    env: BTreeMap<String, String>,
+    downloads: Vec<String>,
+    file_size: u64,
+}
+
+impl NodeConfig for MrpackBuilder {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut mods_channel = get_input!("mods", ResolvedMods, ctx, input_ids)?;
+        let mut overrides_channel = get_input!("overrides", Files, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Files, ctx)?;
+        // Every file is downloaded to compute the hashes the index needs
+        if ctx.is_offline() {
+            return Err(NodeInitError::Offline);
+        }
+        let index = Index::new(self.name.clone(), self.version.clone(), self.summary.clone(), index_dependencies(ctx)?);
+        let downloader = ctx.get_downloader();
+        let store = ctx.get_filestore();
+        let pool = ctx.get_pool();
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "MrpackBuilder", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let mods = mods_channel.blocking_recv().expect_or_log("Failed to receive on mods input");
+            let overrides = overrides_channel.blocking_recv().expect_or_log("Failed to receive on overrides input");
+            let index = pool.install(|| build_index(index, &mods, &downloader));
+
+            let (mut pack, ignored) = overrides.filter_files(&OVERRIDE_DIRECTORIES);
+            let ignored = ignored.list_files().len();
+            if ignored > 0 {
+                event!(Level::WARN, "Ignored {ignored} files outside of the overrides directories");
+            }
+            let mut tree = FileTree::new(store);
+            tree.add_file(FilePath::from_str("modrinth.index.json").unwrap_or_log(), index.into_bytes());
+            pack.add_all(tree);
+
+            if out_channel.send(pack).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::Files(channel(1).0))])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "MrpackBuilder",
+            vec![
+                Port::required("mods", ChannelType::ResolvedMods),
+                Port::required("overrides", ChannelType::Files),
+            ],
+            vec![Port::required("default", ChannelType::Files)],
+        )
+        .with_config_keys(vec!["minecraft_version", "modloader", "modloader_version"])
+    }
+}
+
+impl Index {
+    pub(super) fn new(name: String, version: String, summary: Option<String>, dependencies: BTreeMap<String, String>) -> Self {
+        Index {
+            format_version: 1,
+            game: "minecraft".into(),
+            version_id: version,
+            name,
+            summary,
+            files: vec![],
+            dependencies,
+        }
+    }
+}
+
+/// The game and loader versions an index depends on, from the pack config.
+pub(super) fn index_dependencies(ctx: &DiContainer) -> Result<BTreeMap<String, String>, NodeInitError> {
+    let config = |key: &str| ctx.get_config(key).ok_or_else(|| NodeInitError::MissingConfig(key.into()));
+    let modloader = parse_loader("modloader", &config("modloader")?)?;
+    Ok(BTreeMap::from([
+        ("minecraft".to_owned(), config("minecraft_version")?),
+        (modloader.mrpack_dependency().to_owned(), config("modloader_version")?),
+    ]))
+}
+
+/// Download every mod to hash it, and serialize `index` listing them. Fails the node if any mod
+/// cannot be downloaded, or does not match the file it was resolved to.
+pub(super) fn build_index(mut index: Index, mods: &[ResolvedMod], downloader: &Downloader) -> String {
+    let mut mods = mods.iter().collect::<Vec<_>>();
+    mods.sort_by(|a, b| a.filename.cmp(&b.filename));
+    event!(Level::INFO, "Downloading {} mods to hash", mods.len());
+
+    let results: Vec<Result<IndexFile, String>> = mods
+        .par_iter()
+        .map(|m| {
+            let data = downloader.download_file(&m.src).map_err(|e| e.to_string())?;
+            index_file(m, &data)
+        })
+        .collect();
+
+    let mut failures = vec![];
+    for (m, result) in mods.iter().zip(results) {
+        match result {
+            Ok(file) => index.files.push(file),
+            Err(reason) => failures.push(format!("- {}: {}", m.name, reason)),
+        }
+    }
+    if !failures.is_empty() {
+        event!(Level::ERROR, "Mods could not be downloaded from their URLs:\n{}", failures.join("\n"));
+        panic!();
+    }
+
+    let mut foreign: Vec<&str> = mods
+        .iter()
+        .filter(|m| !MODRINTH_HOSTS.contains(&url_host(&m.src)))
+        .map(|m| m.name.as_str())
+        .collect();
+    if !foreign.is_empty() {
+        foreign.sort();
+        event!(
+            Level::WARN,
+            "Mods are downloaded from hosts Modrinth does not accept for published packs:\n- {}",
+            foreign.join("\n- ")
+        );
+    }
+
+    serde_json::to_string_pretty(&index).expect_or_log("Failed to serialize modrinth.index.json")
+}
+
+/// Describe a downloaded mod file in the index, after checking it is the file that was resolved.
+fn index_file(m: &ResolvedMod, data: &[u8]) -> Result<IndexFile, String> {
+    if data.len() as u64 != m.size || hex_digest::<Sha256>(data) != m.sha256 {
+        return Err("downloaded file does not match the resolved file".into());
+    }
+    let (client, server) = match m.side {
+        Side::Client => ("required", "unsupported"),
+        Side::Server => ("unsupported", "required"),
+        Side::Both => ("required", "required"),
+    };
+    let optional = |env: &str| match (env, m.required) {
+        ("required", false) => "optional".to_owned(),
+        _ => env.to_owned(),
+    };
+    Ok(IndexFile {
+        path: format!("{}/{}", m.kind.directory(), m.filename),
+        hashes: BTreeMap::from([
+            ("sha1".to_owned(), hex_digest::<Sha1>(data)),
+            ("sha512".to_owned(), hex_digest::<Sha512>(data)),
+        ]),
+        env: BTreeMap::from([("client".to_owned(), optional(client)), ("server".to_owned(), optional(server))]),
+        downloads: vec![m.src.clone()],
+        file_size: m.size,
+    })
+}
+
+fn hex_digest<D: Digest>(data: &[u8]) -> String {
+    D::digest(data).iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Get the host of a URL, without any port or user info.
+fn url_host(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or(rest);
+    let host = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+    host.split(':').next().unwrap_or(host)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        file::filestore::FileStore,
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel, serve_http, test_mod},
+        },
+    };
+
+    use super::*;
+
+    fn hosted_mod(base_url: &str, name: &str, data: &[u8]) -> ResolvedMod {
+        ResolvedMod {
+            src: format!("{base_url}/{name}.jar"),
+            size: data.len() as u64,
+            sha256: hex_digest::<Sha256>(data),
+            ..test_mod(name)
+        }
+    }
+
+    fn node() -> NodeConfigTypes {
+        NodeConfigTypes::MrpackBuilder(MrpackBuilder {
+            name: "Example Pack".into(),
+            version: "1.0.0".into(),
+            summary: None,
+        })
+    }
+
+    fn overrides() -> FileTree {
+        let mut tree = FileTree::new(FileStore::new());
+        tree.add_file(FilePath::from_str("overrides/config/a.toml").unwrap(), b"a = 1".to_vec());
+        tree.add_file(FilePath::from_str("client-overrides/options.txt").unwrap(), b"fov:70".to_vec());
+        tree.add_file(FilePath::from_str("manifest.json").unwrap(), b"{}".to_vec());
+        tree
+    }
+
+    #[test]
+    fn test_mrpack_builder() {
+        let node_id = "mrpack";
+        let base_url = serve_http(HashMap::from([
+            ("/lib.jar".to_owned(), (200, b"library".to_vec())),
+            ("/minimap.jar".to_owned(), (200, b"minimap".to_vec())),
+        ]));
+        let mods_channel = channel(1).0;
+        let overrides_channel = channel(1).0;
+        let input_ids = HashMap::from([
+            ("mods".into(), ChannelId::from_str("mod-source").unwrap()),
+            ("overrides".into(), ChannelId::from_str("override-source").unwrap()),
+        ]);
+        let node = node();
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([
+                (ChannelId::from_str("mod-source").unwrap(), InputType::ResolvedMods(mods_channel.clone())),
+                (
+                    ChannelId::from_str("override-source").unwrap(),
+                    InputType::Files(overrides_channel.clone()),
+                ),
+            ]))
+            .set_config("minecraft_version", "1.20.1")
+            .set_config("modloader", "fabric")
+            .set_config("modloader_version", "0.15.7")
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Files, ctx);
+
+        let minimap = ResolvedMod {
+            side: Side::Client,
+            required: false,
+            ..hosted_mod(&base_url, "minimap", b"minimap")
+        };
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        mods_channel.send(vec![minimap, hosted_mod(&base_url, "lib", b"library")]).unwrap();
+        overrides_channel.send(overrides()).unwrap();
+        handle.join().unwrap();
+
+        let pack = read_channel(&mut out_channel, Duration::from_secs(10)).unwrap();
+        let mut paths: Vec<String> = pack.list_files().into_iter().map(|path| path.to_string()).collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec!["client-overrides/options.txt", "modrinth.index.json", "overrides/config/a.toml"]
+        );
+
+        let index: Index = serde_json::from_slice(&pack.get_file(&FilePath::from_str("modrinth.index.json").unwrap()).unwrap()).unwrap();
+        let file = |path: &str, data: &[u8], client: &str, server: &str| IndexFile {
+            path: path.into(),
+            hashes: BTreeMap::from([
+                ("sha1".to_owned(), hex_digest::<Sha1>(data)),
+                ("sha512".to_owned(), hex_digest::<Sha512>(data)),
+            ]),
+            env: BTreeMap::from([("client".to_owned(), client.to_owned()), ("server".to_owned(), server.to_owned())]),
+            downloads: vec![format!("{base_url}{}", path.trim_start_matches("mods"))],
+            file_size: data.len() as u64,
+        };
+        let expected = Index {
+            format_version: 1,
+            game: "minecraft".into(),
+            version_id: "1.0.0".into(),
+            name: "Example Pack".into(),
+            summary: None,
+            files: vec![
+                file("mods/lib.jar", b"library", "required", "required"),
+                file("mods/minimap.jar", b"minimap", "optional", "unsupported"),
+            ],
+            dependencies: BTreeMap::from([
+                ("fabric-loader".to_owned(), "0.15.7".to_owned()),
+                ("minecraft".to_owned(), "1.20.1".to_owned()),
+            ]),
+        };
+        assert_eq!(index, expected);
+    }
+
+    #[test]
+    fn broken_download_fails() {
+        let node_id = "mrpack";
+        let base_url = serve_http(HashMap::from([("/lib.jar".to_owned(), (200, b"library".to_vec()))]));
+        let mods_channel = channel(1).0;
+        let overrides_channel = channel(1).0;
+        let input_ids = HashMap::from([
+            ("mods".into(), ChannelId::from_str("mod-source").unwrap()),
+            ("overrides".into(), ChannelId::from_str("override-source").unwrap()),
+        ]);
+        let node = node();
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([
+                (ChannelId::from_str("mod-source").unwrap(), InputType::ResolvedMods(mods_channel.clone())),
+                (
+                    ChannelId::from_str("override-source").unwrap(),
+                    InputType::Files(overrides_channel.clone()),
+                ),
+            ]))
+            .set_config("minecraft_version", "1.20.1")
+            .set_config("modloader", "fabric")
+            .set_config("modloader_version", "0.15.7")
+            .build();
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        mods_channel
+            .send(vec![
+                hosted_mod(&base_url, "lib", b"library"),
+                hosted_mod(&base_url, "missing", b"missing"),
+            ])
+            .unwrap();
+        overrides_channel.send(overrides()).unwrap();
+        assert!(handle.join().is_err());
+    }
+}
@@ -0,0 +1,171 @@
+use std::{
+    collections::HashMap,
+    thread::{spawn, JoinHandle},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::di::container::{DiContainer, InputType, OutputType};
+
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port, ResolvedMod},
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct OptionalModIndex;
+
+/// Mods grouped by how a launcher should treat them. A mod that is both not required and disabled
+/// by default is listed under both.
+#[derive(Debug, Serialize, PartialEq)]
+struct OptionalIndex<'a> {
+    /// Mods installed but disabled by default, such as by renaming the file to `.disabled`.
+    disabled: Vec<IndexEntry<'a>>,
+    /// Mods the player may choose not to install.
+    optional: Vec<IndexEntry<'a>>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct IndexEntry<'a> {
+    name: &'a str,
+    title: &'a str,
+    /// Path of the file in a game instance, such as `mods/jei.jar`.
+    path: String,
+}
+
+impl NodeConfig for OptionalModIndex {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut mods_channel = get_input!("mods", ResolvedMods, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Text, ctx)?;
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "OptionalModIndex", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let mods = mods_channel.blocking_recv().expect_or_log("Failed to receive on mods input");
+
+            let index = optional_index(&mods);
+            event!(
+                Level::INFO,
+                "Found {} disabled and {} optional mods out of {}",
+                index.disabled.len(),
+                index.optional.len(),
+                mods.len()
+            );
+            let json = serde_json::to_string_pretty(&index).expect_or_log("Failed to serialize optional mod index");
+
+            if out_channel.send(json).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::Text(channel(1).0))])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "OptionalModIndex",
+            vec![Port::required("mods", ChannelType::ResolvedMods)],
+            vec![Port::required("default", ChannelType::Text)],
+        )
+    }
+}
+
+/// Group mods that are disabled by default or not required, each group sorted by name.
+fn optional_index(mods: &[ResolvedMod]) -> OptionalIndex<'_> {
+    let mut sorted = mods.iter().collect::<Vec<_>>();
+    sorted.sort_by(|a, b| a.name.cmp(&b.name));
+    OptionalIndex {
+        disabled: sorted.iter().filter(|m| !m.default).map(|m| index_entry(m)).collect(),
+        optional: sorted.iter().filter(|m| !m.required).map(|m| index_entry(m)).collect(),
+    }
+}
+
+fn index_entry(m: &ResolvedMod) -> IndexEntry<'_> {
+    IndexEntry {
+        name: &m.name,
+        title: &m.title,
+        path: format!("{}/{}", m.kind.directory(), m.filename),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        node::{
+            config::{NodeConfigTypes, ProjectKind},
+            utils::{get_output_test, read_channel, test_mod},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_optional_mod_index() {
+        let node_id = "optional";
+        let mods_channel = channel(1).0;
+        let input_ids = HashMap::from([("mods".into(), ChannelId::from_str("resolver").unwrap())]);
+        let node = NodeConfigTypes::OptionalModIndex(OptionalModIndex);
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("resolver").unwrap(),
+                InputType::ResolvedMods(mods_channel.clone()),
+            )]))
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Text, ctx);
+
+        let flagged = |name: &str, required: bool, default: bool| ResolvedMod {
+            required,
+            default,
+            ..test_mod(name)
+        };
+        let mods = vec![
+            flagged("zoomify", false, true),
+            flagged("jei", true, true),
+            flagged("journeymap", false, false),
+            ResolvedMod {
+                kind: ProjectKind::Shader,
+                ..flagged("complementary", false, false)
+            },
+        ];
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        mods_channel.send(mods).unwrap();
+        handle.join().unwrap();
+
+        let json: serde_json::Value = serde_json::from_str(&read_channel(&mut out_channel, Duration::from_secs(10)).unwrap()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "disabled": [
+                    {"name": "complementary", "title": "complementary", "path": "shaderpacks/complementary.jar"},
+                    {"name": "journeymap", "title": "journeymap", "path": "mods/journeymap.jar"},
+                ],
+                "optional": [
+                    {"name": "complementary", "title": "complementary", "path": "shaderpacks/complementary.jar"},
+                    {"name": "journeymap", "title": "journeymap", "path": "mods/journeymap.jar"},
+                    {"name": "zoomify", "title": "zoomify", "path": "mods/zoomify.jar"},
+                ],
+            })
+        );
+    }
+}
@@ -0,0 +1,190 @@
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    thread::{spawn, JoinHandle},
+};
+
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::{
+    di::container::{DiContainer, InputType, OutputType},
+    file::{filepath::FilePath, filetree::FileTree},
+};
+
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port},
+    mod_writer::nix_manifest,
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct PackAssembler {
+    /// Path of the manifest in the assembled tree.
+    #[serde(default = "default_manifest_path")]
+    pub manifest_path: String,
+    /// Format the manifest is written in.
+    #[serde(default)]
+    pub format: ManifestFormat,
+}
+
+fn default_manifest_path() -> String {
+    "manifest.json".into()
+}
+
+impl Default for PackAssembler {
+    fn default() -> Self {
+        PackAssembler {
+            manifest_path: default_manifest_path(),
+            format: ManifestFormat::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ManifestFormat {
+    /// The JSON manifest written by ModWriter.
+    #[default]
+    Json,
+    /// The Nix manifest written by ModWriter.
+    Nix,
+}
+
+impl NodeConfig for PackAssembler {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut mods_channel = get_input!("mods", ResolvedMods, ctx, input_ids)?;
+        let mut overrides_channel = get_input!("overrides", Files, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Files, ctx)?;
+        let manifest_path = FilePath::from_str(&self.manifest_path).map_err(|_| NodeInitError::InvalidOption {
+            option: "manifest_path".into(),
+            reason: format!("'{}' is not a valid file path", self.manifest_path),
+        })?;
+        let minecraft_version = match self.format {
+            ManifestFormat::Nix => Some(
+                ctx.get_config("minecraft_version")
+                    .ok_or_else(|| NodeInitError::MissingConfig("minecraft_version".into()))?,
+            ),
+            ManifestFormat::Json => None,
+        };
+        let store = ctx.get_filestore();
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "PackAssembler", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let mut mods = mods_channel.blocking_recv().expect_or_log("Failed to receive on mods input");
+            let overrides = overrides_channel.blocking_recv().expect_or_log("Failed to receive on overrides input");
+            mods.sort_by(|a, b| a.name.cmp(&b.name));
+
+            let manifest = match minecraft_version {
+                Some(ref version) => nix_manifest(version, &mods, true),
+                None => serde_json::to_string_pretty(&mods).expect_or_log("Serialization of resolved mods to JSON failed"),
+            };
+            if overrides.get_file(&manifest_path).is_some() {
+                event!(
+                    Level::WARN,
+                    "Replacing '{}' from the overrides with the generated manifest",
+                    manifest_path
+                );
+            }
+
+            let mut pack = FileTree::new(store);
+            pack.add_all(overrides);
+            pack.add_file(manifest_path, manifest.into_bytes());
+
+            if out_channel.send(pack).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::Files(channel(1).0))])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "PackAssembler",
+            vec![
+                Port::required("mods", ChannelType::ResolvedMods),
+                Port::required("overrides", ChannelType::Files),
+            ],
+            vec![Port::required("default", ChannelType::Files)],
+        )
+        .with_config_keys(match self.format {
+            ManifestFormat::Nix => vec!["minecraft_version"],
+            ManifestFormat::Json => vec![],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        file::filestore::FileStore,
+        node::{
+            config::{NodeConfigTypes, ResolvedMod},
+            utils::{get_output_test, read_channel, test_mod},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_pack_assembler() {
+        let node_id = "assembler";
+        let mods_channel = channel(1).0;
+        let overrides_channel = channel(1).0;
+        let input_ids = HashMap::from([
+            ("mods".into(), ChannelId::from_str("mod-source").unwrap()),
+            ("overrides".into(), ChannelId::from_str("override-source").unwrap()),
+        ]);
+        let node = NodeConfigTypes::PackAssembler(PackAssembler {
+            manifest_path: "pack/mods.json".into(),
+            format: ManifestFormat::Json,
+        });
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([
+                (ChannelId::from_str("mod-source").unwrap(), InputType::ResolvedMods(mods_channel.clone())),
+                (
+                    ChannelId::from_str("override-source").unwrap(),
+                    InputType::Files(overrides_channel.clone()),
+                ),
+            ]))
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Files, ctx);
+
+        let mut overrides = FileTree::new(FileStore::new());
+        overrides.add_file(FilePath::from_str("overrides/config/a.toml").unwrap(), b"a = 1".to_vec());
+        overrides.add_file(FilePath::from_str("pack/mods.json").unwrap(), b"[]".to_vec());
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        mods_channel.send(vec![test_mod("sodium"), test_mod("appleskin")]).unwrap();
+        overrides_channel.send(overrides).unwrap();
+        handle.join().unwrap();
+
+        let pack = read_channel(&mut out_channel, Duration::from_secs(10)).unwrap();
+        let mut paths: Vec<String> = pack.list_files().into_iter().map(|path| path.to_string()).collect();
+        paths.sort();
+        assert_eq!(paths, vec!["overrides/config/a.toml", "pack/mods.json"]);
+        let manifest: Vec<ResolvedMod> = serde_json::from_slice(&pack.get_file(&FilePath::from_str("pack/mods.json").unwrap()).unwrap()).unwrap();
+        assert_eq!(manifest, vec![test_mod("appleskin"), test_mod("sodium")]);
+    }
+}
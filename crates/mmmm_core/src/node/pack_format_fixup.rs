@@ -0,0 +1,317 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    thread::{spawn, JoinHandle},
+};
+
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::{OptionExt, ResultExt};
+
+use crate::{
+    di::container::{DiContainer, InputType, OutputType},
+    file::filetree::FileTree,
+};
+
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port},
+    utils::{get_input, get_output, parse_version},
+};
+
+const MCMETA: &str = "pack.mcmeta";
+
+/// First Minecraft release using each resource pack format.
+const RESOURCE_FORMATS: [(&str, u32); 18] = [
+    ("1.6.1", 1),
+    ("1.9", 2),
+    ("1.11", 3),
+    ("1.13", 4),
+    ("1.15", 5),
+    ("1.16.2", 6),
+    ("1.17", 7),
+    ("1.18", 8),
+    ("1.19", 9),
+    ("1.19.3", 12),
+    ("1.19.4", 13),
+    ("1.20", 15),
+    ("1.20.2", 18),
+    ("1.20.3", 22),
+    ("1.20.5", 32),
+    ("1.21", 34),
+    ("1.21.2", 42),
+    ("1.21.4", 46),
+];
+
+/// First Minecraft release using each data pack format.
+const DATA_FORMATS: [(&str, u32); 15] = [
+    ("1.13", 4),
+    ("1.15", 5),
+    ("1.16.2", 6),
+    ("1.17", 7),
+    ("1.18", 8),
+    ("1.18.2", 9),
+    ("1.19", 10),
+    ("1.19.4", 12),
+    ("1.20", 15),
+    ("1.20.2", 18),
+    ("1.20.3", 26),
+    ("1.20.5", 41),
+    ("1.21", 48),
+    ("1.21.2", 57),
+    ("1.21.4", 61),
+];
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Default)]
+pub struct PackFormatFixup {
+    /// Minecraft version the packs are being moved to. Falls back to the `minecraft_version`
+    /// config key.
+    pub minecraft_version: Option<String>,
+    /// Format written to resource packs, instead of the one known for the target version.
+    pub resource_format: Option<u32>,
+    /// Format written to data packs, instead of the one known for the target version.
+    pub data_format: Option<u32>,
+    /// Treat every pack as this type, instead of detecting it from the pack's contents.
+    pub pack_type: Option<PackType>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PackType {
+    Resource,
+    Data,
+}
+
+impl Display for PackType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PackType::Resource => write!(f, "resource pack"),
+            PackType::Data => write!(f, "data pack"),
+        }
+    }
+}
+
+impl NodeConfig for PackFormatFixup {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut files_channel = get_input!("files", Files, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Files, ctx)?;
+        // The version is only needed for formats that were not given explicitly
+        let version = match (self.resource_format, self.data_format) {
+            (Some(_), Some(_)) => None,
+            _ => {
+                let version = match self.minecraft_version {
+                    Some(ref version) => version.clone(),
+                    None => ctx
+                        .get_config("minecraft_version")
+                        .ok_or_else(|| NodeInitError::MissingConfig("minecraft_version".into()))?,
+                };
+                Some(parse_version(&version).ok_or_else(|| NodeInitError::InvalidOption {
+                    option: "minecraft_version".into(),
+                    reason: format!("'{version}' is not a release version, set resource_format and data_format instead"),
+                })?)
+            },
+        };
+        let resource_format = self
+            .resource_format
+            .or_else(|| version.as_deref().and_then(|v| format_for(&RESOURCE_FORMATS, v)));
+        let data_format = self.data_format.or_else(|| version.as_deref().and_then(|v| format_for(&DATA_FORMATS, v)));
+        let pack_type = self.pack_type;
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "PackFormatFixup", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let mut files = files_channel.blocking_recv().expect_or_log("Failed to receive on files input");
+
+            let mut mcmetas: Vec<_> = files
+                .list_files()
+                .into_iter()
+                .filter(|path| path.get_filename() == MCMETA)
+                .cloned()
+                .collect();
+            mcmetas.sort_by_key(|path| path.to_string());
+            for path in mcmetas {
+                let mut root = path.get_components();
+                root.pop();
+                let Some(kind) = pack_type.or_else(|| detect_pack_type(&files, &root)) else {
+                    event!(Level::WARN, "Skipping {path}, which is not clearly a resource pack or a data pack");
+                    continue;
+                };
+                let format = match kind {
+                    PackType::Resource => resource_format,
+                    PackType::Data => data_format,
+                };
+                let Some(format) = format else {
+                    event!(Level::WARN, "Skipping {path}, no {kind} format is known for the target version");
+                    continue;
+                };
+                let data = files.get_file(&path).expect_or_log("File listed in tree is missing from store");
+                match fixup_mcmeta(&data, format) {
+                    Ok(Some(fixed)) => {
+                        event!(Level::INFO, "Set {kind} format of {path} to {format}");
+                        files.add_file(path, fixed.into_bytes());
+                    },
+                    Ok(None) => {},
+                    Err(reason) => event!(Level::WARN, "Skipping {path}: {reason}"),
+                }
+            }
+
+            if out_channel.send(files).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::Files(channel(1).0))])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "PackFormatFixup",
+            vec![Port::required("files", ChannelType::Files)],
+            vec![Port::required("default", ChannelType::Files)],
+        )
+        .with_config_keys(match (self.resource_format, self.data_format, &self.minecraft_version) {
+            (Some(_), Some(_), _) | (_, _, Some(_)) => vec![],
+            _ => vec!["minecraft_version"],
+        })
+    }
+}
+
+/// Get the format used by `version`, or [`None`] if it predates every format in `table`.
+fn format_for(table: &[(&str, u32)], version: &[u32]) -> Option<u32> {
+    table
+        .iter()
+        .rev()
+        .find(|(first, _)| parse_version(first).unwrap_or_log().as_slice() <= version)
+        .map(|(_, format)| *format)
+}
+
+/// Detect whether the pack rooted at `root` holds resources or data, from whether it has an
+/// `assets/` or `data/` directory. Packs with both or neither are ambiguous.
+fn detect_pack_type(files: &FileTree, root: &[String]) -> Option<PackType> {
+    let has_dir = |dir: &str| {
+        files.list_files().into_iter().any(|path| {
+            let components = path.get_components();
+            components.len() > root.len() + 1 && components.starts_with(root) && components[root.len()] == dir
+        })
+    };
+    match (has_dir("assets"), has_dir("data")) {
+        (true, false) => Some(PackType::Resource),
+        (false, true) => Some(PackType::Data),
+        _ => None,
+    }
+}
+
+/// Set `pack.pack_format` to `format`, dropping any `supported_formats` range that no longer
+/// applies. Returns `None` if the file is unchanged.
+fn fixup_mcmeta(data: &[u8], format: u32) -> Result<Option<String>, String> {
+    let text = std::str::from_utf8(data).map_err(|_| "not valid UTF-8".to_owned())?;
+    let mut mcmeta: Value = serde_json::from_str(text.trim_start_matches('\u{feff}')).map_err(|e| e.to_string())?;
+    let pack = mcmeta.get_mut("pack").and_then(Value::as_object_mut).ok_or("missing 'pack' object")?;
+    if pack.get("pack_format").and_then(Value::as_u64) == Some(format.into()) && !pack.contains_key("supported_formats") {
+        return Ok(None);
+    }
+    pack.insert("pack_format".into(), format.into());
+    pack.remove("supported_formats");
+    Ok(Some(
+        serde_json::to_string_pretty(&mcmeta).expect_or_log("Failed to serialize pack.mcmeta"),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        file::{filepath::FilePath, filestore::FileStore},
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_pack_format_fixup() {
+        let node_id = "fixup";
+        let mut tree = FileTree::new(FileStore::new());
+        for (path, contents) in [
+            (
+                "resourcepacks/textures/pack.mcmeta",
+                r#"{"pack":{"pack_format":13,"supported_formats":[13,15],"description":"Textures"}}"#,
+            ),
+            ("resourcepacks/textures/assets/minecraft/textures/block/stone.png", ""),
+            ("datapacks/loot/pack.mcmeta", r#"{"pack":{"pack_format":10,"description":"Loot"}}"#),
+            ("datapacks/loot/data/loot/loot_tables/chest.json", "{}"),
+            ("datapacks/current/pack.mcmeta", r#"{"pack":{"pack_format":15,"description":"Current"}}"#),
+            ("datapacks/current/data/current/functions/load.mcfunction", ""),
+            ("datapacks/empty/pack.mcmeta", r#"{"pack":{"pack_format":1,"description":"Empty"}}"#),
+        ] {
+            tree.add_file(FilePath::from_str(path).unwrap(), contents.into());
+        }
+        let files_channel = channel(1).0;
+        let input_ids = HashMap::from([("files".into(), ChannelId::from_str("file-source").unwrap())]);
+        let node = NodeConfigTypes::PackFormatFixup(PackFormatFixup {
+            minecraft_version: Some("1.20.1".into()),
+            ..Default::default()
+        });
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("file-source").unwrap(),
+                InputType::Files(files_channel.clone()),
+            )]))
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Files, ctx);
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        files_channel.send(tree).unwrap();
+        handle.join().unwrap();
+
+        let files = read_channel(&mut out_channel, Duration::from_secs(10)).unwrap();
+        let mcmeta = |path: &str| -> Value { serde_json::from_slice(&files.get_file(&FilePath::from_str(path).unwrap()).unwrap()).unwrap() };
+        assert_eq!(
+            mcmeta("resourcepacks/textures/pack.mcmeta"),
+            serde_json::json!({"pack": {"pack_format": 15, "description": "Textures"}})
+        );
+        assert_eq!(
+            mcmeta("datapacks/loot/pack.mcmeta"),
+            serde_json::json!({"pack": {"pack_format": 15, "description": "Loot"}})
+        );
+        assert_eq!(
+            mcmeta("datapacks/empty/pack.mcmeta"),
+            serde_json::json!({"pack": {"pack_format": 1, "description": "Empty"}})
+        );
+        // Unchanged packs keep their original formatting
+        assert_eq!(
+            *files.get_file(&FilePath::from_str("datapacks/current/pack.mcmeta").unwrap()).unwrap(),
+            br#"{"pack":{"pack_format":15,"description":"Current"}}"#.to_vec()
+        );
+    }
+
+    #[test]
+    fn formats_by_version() {
+        let format = |table: &[(&str, u32)], version: &str| format_for(table, &parse_version(version).unwrap());
+        assert_eq!(format(&RESOURCE_FORMATS, "1.12.2"), Some(3));
+        assert_eq!(format(&RESOURCE_FORMATS, "1.20.4"), Some(22));
+        assert_eq!(format(&DATA_FORMATS, "1.20.4"), Some(26));
+        assert_eq!(format(&DATA_FORMATS, "1.21"), Some(48));
+        assert_eq!(format(&DATA_FORMATS, "1.12.2"), None);
+        assert_eq!(parse_version("23w31a"), None);
+    }
+}
@@ -0,0 +1,301 @@
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    thread::{spawn, JoinHandle},
+};
+
+use api_client::loader::ModLoader;
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::{
+    di::container::{DiContainer, InputType, OutputType},
+    file::filepath::FilePath,
+};
+
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port},
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct PackMetaReader;
+
+/// Output channels, in the order of the fields of [`PackMeta`].
+const OUTPUTS: [&str; 7] = [
+    "format",
+    "name",
+    "version",
+    "author",
+    "minecraft_version",
+    "modloader",
+    "modloader_version",
+];
+
+/// Metadata files that are read, in order of preference, with the name of their format.
+const META_FILES: [(&str, &str); 3] = [
+    ("modrinth.index.json", "modrinth"),
+    ("mcbbs.packmeta", "mcbbs"),
+    ("manifest.json", "curse"),
+];
+
+/// Fields common to every pack metadata format. Fields a format does not record are empty.
+#[derive(Debug, Default, PartialEq)]
+struct PackMeta {
+    name: String,
+    version: String,
+    author: String,
+    minecraft_version: String,
+    modloader: String,
+    modloader_version: String,
+}
+
+impl NodeConfig for PackMetaReader {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut files_channel = get_input!("files", Files, ctx, input_ids)?;
+        let out_channels = OUTPUTS
+            .iter()
+            .map(|name| get_output!(ChannelId(node_id.clone(), (*name).into()), Text, ctx))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "PackMetaReader", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let files = files_channel.blocking_recv().expect_or_log("Failed to receive on files input");
+
+            let Some((format, data)) = META_FILES
+                .iter()
+                .find_map(|(path, format)| Some((*format, files.get_file(&FilePath::from_str(path).unwrap_or_log())?)))
+            else {
+                event!(
+                    Level::ERROR,
+                    "No pack metadata found! Expected one of: {}",
+                    META_FILES.map(|(path, _)| path).join(", ")
+                );
+                panic!()
+            };
+            event!(Level::INFO, "Reading {} pack metadata", format);
+            let json: Value = serde_json::from_slice(&data).expect_or_log("Failed to parse pack metadata as JSON");
+            let meta = match format {
+                "modrinth" => read_modrinth(&json),
+                "mcbbs" => read_mcbbs(&json),
+                _ => read_curse(&json),
+            };
+
+            let empty = [
+                ("name", &meta.name),
+                ("version", &meta.version),
+                ("minecraft_version", &meta.minecraft_version),
+                ("modloader", &meta.modloader),
+            ]
+            .into_iter()
+            .filter(|(_, value)| value.is_empty())
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>();
+            if !empty.is_empty() {
+                event!(Level::WARN, "Pack metadata is missing {}", empty.join(", "));
+            }
+
+            let values = [
+                format.to_owned(),
+                meta.name,
+                meta.version,
+                meta.author,
+                meta.minecraft_version,
+                meta.modloader,
+                meta.modloader_version,
+            ];
+            for ((name, channel), value) in OUTPUTS.iter().zip(out_channels).zip(values) {
+                if channel.send(value).is_err() {
+                    event!(Level::DEBUG, "Channel '{}' has no subscribers", name);
+                }
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        OUTPUTS
+            .iter()
+            .map(|name| (ChannelId(node_id.to_owned(), (*name).into()), InputType::Text(channel(1).0)))
+            .collect()
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "PackMetaReader",
+            vec![Port::required("files", ChannelType::Files)],
+            OUTPUTS.iter().map(|name| Port::required(name, ChannelType::Text)).collect(),
+        )
+    }
+}
+
+fn string(json: &Value, pointer: &str) -> String {
+    json.pointer(pointer).and_then(Value::as_str).unwrap_or_default().to_owned()
+}
+
+/// Read a Modrinth `modrinth.index.json`, which records the loader as a dependency.
+fn read_modrinth(json: &Value) -> PackMeta {
+    let loader = ModLoader::ALL.into_iter().find_map(|loader| {
+        let version = json.pointer(&format!("/dependencies/{}", loader.mrpack_dependency()))?.as_str()?;
+        Some((loader.name(), version))
+    });
+    PackMeta {
+        name: string(json, "/name"),
+        version: string(json, "/versionId"),
+        author: String::new(),
+        minecraft_version: string(json, "/dependencies/minecraft"),
+        modloader: loader.map(|(id, _)| id.to_owned()).unwrap_or_default(),
+        modloader_version: loader.map(|(_, version)| version.to_owned()).unwrap_or_default(),
+    }
+}
+
+/// Read an MCBBS `mcbbs.packmeta`, which lists the game and loader as addons.
+fn read_mcbbs(json: &Value) -> PackMeta {
+    let addons = json.get("addons").and_then(Value::as_array).map(Vec::as_slice).unwrap_or_default();
+    let addon = |id: &str| addons.iter().find(|addon| addon.get("id").and_then(Value::as_str) == Some(id));
+    let loader = ModLoader::ALL.into_iter().find_map(|loader| Some((loader.name(), addon(loader.name())?)));
+    PackMeta {
+        name: string(json, "/name"),
+        version: string(json, "/version"),
+        author: string(json, "/author"),
+        minecraft_version: addon("game").map(|addon| string(addon, "/version")).unwrap_or_default(),
+        modloader: loader.map(|(id, _)| id.to_owned()).unwrap_or_default(),
+        modloader_version: loader.map(|(_, addon)| string(addon, "/version")).unwrap_or_default(),
+    }
+}
+
+/// Read a CurseForge `manifest.json`, which names the loader and its version together, such as
+/// `forge-47.2.0`.
+fn read_curse(json: &Value) -> PackMeta {
+    let loaders = json
+        .pointer("/minecraft/modLoaders")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let loader = loaders
+        .iter()
+        .find(|loader| loader.get("primary").and_then(Value::as_bool) == Some(true))
+        .or(loaders.first())
+        .map(|loader| string(loader, "/id"));
+    let (modloader, modloader_version) = match loader {
+        Some(ref id) => id.split_once('-').unwrap_or((id, "")),
+        None => ("", ""),
+    };
+    PackMeta {
+        name: string(json, "/name"),
+        version: string(json, "/version"),
+        author: string(json, "/author"),
+        minecraft_version: string(json, "/minecraft/version"),
+        modloader: modloader.to_lowercase(),
+        modloader_version: modloader_version.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        file::{filestore::FileStore, filetree::FileTree},
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_pack_meta_reader() {
+        let node_id = "meta";
+        let files_channel = channel(1).0;
+        let input_ids = HashMap::from([("files".into(), ChannelId::from_str("pack").unwrap())]);
+        let node = NodeConfigTypes::PackMetaReader(PackMetaReader);
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("pack").unwrap(),
+                InputType::Files(files_channel.clone()),
+            )]))
+            .build();
+
+        let mut out_channels = OUTPUTS.map(|name| get_output_test!(ChannelId(node_id.into(), name.into()), Text, ctx));
+
+        // The Modrinth index is preferred over the Curse manifest
+        let index = json!({
+            "formatVersion": 1,
+            "game": "minecraft",
+            "versionId": "2.1.0",
+            "name": "Example Pack",
+            "files": [],
+            "dependencies": {"minecraft": "1.20.1", "fabric-loader": "0.15.7"},
+        });
+        let mut tree = FileTree::new(FileStore::new());
+        tree.add_file(FilePath::from_str("modrinth.index.json").unwrap(), index.to_string().into_bytes());
+        tree.add_file(FilePath::from_str("manifest.json").unwrap(), b"{}".to_vec());
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        files_channel.send(tree).unwrap();
+        handle.join().unwrap();
+
+        let values = out_channels
+            .iter_mut()
+            .map(|channel| read_channel(channel, Duration::from_secs(10)).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(values, vec!["modrinth", "Example Pack", "2.1.0", "", "1.20.1", "fabric", "0.15.7"]);
+    }
+
+    #[test]
+    fn metadata_formats() {
+        let curse = json!({
+            "minecraft": {"version": "1.20.1", "modLoaders": [{"id": "neoforge-47.1.79", "primary": false}, {"id": "forge-47.2.0", "primary": true}]},
+            "manifestType": "minecraftModpack",
+            "name": "Example Pack",
+            "version": "2.1.0",
+            "author": "Someone",
+        });
+        let expected = PackMeta {
+            name: "Example Pack".into(),
+            version: "2.1.0".into(),
+            author: "Someone".into(),
+            minecraft_version: "1.20.1".into(),
+            modloader: "forge".into(),
+            modloader_version: "47.2.0".into(),
+        };
+        assert_eq!(read_curse(&curse), expected);
+
+        let mcbbs = json!({
+            "manifestType": "minecraftModpack",
+            "manifestVersion": 2,
+            "name": "Example Pack",
+            "version": "2.1.0",
+            "author": "Someone",
+            "addons": [{"id": "game", "version": "1.20.1"}, {"id": "forge", "version": "47.2.0"}],
+        });
+        assert_eq!(read_mcbbs(&mcbbs), expected);
+
+        assert_eq!(
+            read_curse(&json!({"name": "Bare"})),
+            PackMeta {
+                name: "Bare".into(),
+                ..Default::default()
+            }
+        );
+    }
+}
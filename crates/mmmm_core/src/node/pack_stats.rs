@@ -0,0 +1,199 @@
+use std::{
+    collections::HashMap,
+    thread::{spawn, JoinHandle},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::di::container::{DiContainer, InputType, OutputType};
+
+use super::{
+    config::{ChannelId, ChannelType, ModSource, NodeConfig, NodeDescription, NodeInitError, Port, ResolvedMod, Side},
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct PackStats;
+
+/// Version of the [`Stats`] JSON schema. Bumped whenever a field is renamed or removed.
+const SCHEMA_VERSION: u32 = 1;
+
+/// Summary of a pack, serialized as the output of [`PackStats`].
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+struct Stats {
+    schema_version: u32,
+    total_mods: usize,
+    /// Sum of all file sizes in bytes.
+    total_size: u64,
+    by_side: SideCounts,
+    by_source: SourceCounts,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+struct SideCounts {
+    client: usize,
+    server: usize,
+    both: usize,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+struct SourceCounts {
+    curse: usize,
+    modrinth: usize,
+    url: usize,
+    /// Mods without provenance, such as those loaded from an older cache.
+    unknown: usize,
+}
+
+impl Stats {
+    fn from_mods(mods: &[ResolvedMod]) -> Stats {
+        let mut stats = Stats {
+            schema_version: SCHEMA_VERSION,
+            total_mods: mods.len(),
+            total_size: mods.iter().map(|m| m.size).sum(),
+            ..Default::default()
+        };
+        for m in mods {
+            match m.side {
+                Side::Client => stats.by_side.client += 1,
+                Side::Server => stats.by_side.server += 1,
+                Side::Both => stats.by_side.both += 1,
+            }
+            match m.source {
+                Some(ModSource::Curse { .. }) => stats.by_source.curse += 1,
+                Some(ModSource::Modrinth { .. }) => stats.by_source.modrinth += 1,
+                Some(ModSource::Url) => stats.by_source.url += 1,
+                None => stats.by_source.unknown += 1,
+            }
+        }
+        stats
+    }
+}
+
+impl NodeConfig for PackStats {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut mods_channel = get_input!("mods", ResolvedMods, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Text, ctx)?;
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "PackStats", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let mods = mods_channel.blocking_recv().expect_or_log("Failed to receive on mods input");
+            let stats = serde_json::to_string_pretty(&Stats::from_mods(&mods)).expect_or_log("Serialization of pack stats to JSON failed");
+
+            if out_channel.send(stats).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::Text(channel(1).0))])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "PackStats",
+            vec![Port::required("mods", ChannelType::ResolvedMods)],
+            vec![Port::required("default", ChannelType::Text)],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        node::{
+            config::{NodeConfigTypes, ProjectKind},
+            utils::{get_output_test, read_channel, test_mod},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_pack_stats() {
+        let node_id = "stats";
+        let mods_channel = channel(1).0;
+        let input_ids = HashMap::from([("mods".into(), ChannelId::from_str("mod-source").unwrap())]);
+        let node = NodeConfigTypes::PackStats(PackStats);
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("mod-source").unwrap(),
+                InputType::ResolvedMods(mods_channel.clone()),
+            )]))
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Text, ctx);
+
+        let mods = vec![
+            ResolvedMod {
+                size: 1000,
+                source: Some(ModSource::Curse { project_id: 1, file_id: 2 }),
+                kind: ProjectKind::Mod,
+                ..test_mod("a")
+            },
+            ResolvedMod {
+                size: 250,
+                side: Side::Client,
+                source: Some(ModSource::Modrinth {
+                    project_id: "b".into(),
+                    version_id: "c".into(),
+                }),
+                kind: ProjectKind::Mod,
+                ..test_mod("b")
+            },
+            ResolvedMod {
+                size: 5,
+                side: Side::Server,
+                source: Some(ModSource::Url),
+                kind: ProjectKind::Mod,
+                ..test_mod("c")
+            },
+            ResolvedMod {
+                side: Side::Client,
+                ..test_mod("d")
+            },
+        ];
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        mods_channel.send(mods).unwrap();
+        handle.join().unwrap();
+
+        let output = read_channel(&mut out_channel, Duration::from_secs(30)).unwrap();
+        let expected = r#"{
+  "schema_version": 1,
+  "total_mods": 4,
+  "total_size": 1255,
+  "by_side": {
+    "client": 2,
+    "server": 1,
+    "both": 1
+  },
+  "by_source": {
+    "curse": 1,
+    "modrinth": 1,
+    "url": 1,
+    "unknown": 1
+  }
+}"#;
+        assert_eq!(output, expected);
+    }
+}
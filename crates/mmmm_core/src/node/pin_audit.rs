@@ -0,0 +1,188 @@
+use std::{
+    collections::HashMap,
+    thread::{spawn, JoinHandle},
+};
+
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::di::container::{DiContainer, InputType, OutputType};
+
+use super::{
+    config::{ChannelId, ChannelType, ModDefinition, NodeConfig, NodeDescription, NodeInitError, Port},
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Default)]
+pub struct PinAudit {
+    /// Fail if any mod is not pinned, instead of only reporting it.
+    #[serde(default)]
+    pub strict: bool,
+}
+
+impl NodeConfig for PinAudit {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut mods_channel = get_input!("mods", Mods, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Text, ctx)?;
+        let unpinned_channel = get_output!(ChannelId(node_id.clone(), "unpinned".into()), List, ctx)?;
+        let strict = self.strict;
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "PinAudit", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let mods = mods_channel.blocking_recv().expect_or_log("Failed to receive on mods input");
+
+            let mut unpinned: Vec<String> = mods.iter().filter(|m| !is_pinned(m)).map(|m| m.get_fields().name.clone()).collect();
+            unpinned.sort();
+
+            let report = if unpinned.is_empty() {
+                format!("Checked {} mods, all pinned.\n", mods.len())
+            } else {
+                let lines = unpinned.iter().map(|name| format!("- {name}")).collect::<Vec<_>>();
+                format!("Checked {} mods, {} not pinned:\n{}\n", mods.len(), unpinned.len(), lines.join("\n"))
+            };
+
+            if !unpinned.is_empty() {
+                event!(Level::WARN, "{report}");
+                if strict {
+                    panic!("Unpinned mods found in strict mode");
+                }
+            }
+
+            if out_channel.send(report).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+
+            if unpinned_channel.send(unpinned).is_err() {
+                event!(Level::DEBUG, "Channel 'unpinned' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([
+            (ChannelId(node_id.to_owned(), "default".into()), InputType::Text(channel(1).0)),
+            (ChannelId(node_id.to_owned(), "unpinned".into()), InputType::List(channel(1).0)),
+        ])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "PinAudit",
+            vec![Port::required("mods", ChannelType::Mods)],
+            vec![
+                Port::required("default", ChannelType::Text),
+                Port::required("unpinned", ChannelType::List),
+            ],
+        )
+    }
+}
+
+/// Whether a mod always resolves to the same file. Mods without a file ID resolve to the latest
+/// compatible version, which can change between builds.
+fn is_pinned(def: &ModDefinition) -> bool {
+    match def {
+        ModDefinition::Modrinth { file_id, .. } => file_id.is_some(),
+        ModDefinition::Curse { file_id, .. } => file_id.is_some(),
+        ModDefinition::Url { .. } => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_pin_audit() {
+        let node_id = "pin-audit";
+        let mods_channel = channel(1).0;
+        let input_ids = HashMap::from([("mods".into(), ChannelId::from_str("mod-source").unwrap())]);
+        let node = NodeConfigTypes::PinAudit(PinAudit::default());
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("mod-source").unwrap(),
+                InputType::Mods(mods_channel.clone()),
+            )]))
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Text, ctx);
+        let mut unpinned_channel = get_output_test!(ChannelId::from_str("pin-audit::unpinned").unwrap(), List, ctx);
+
+        let mods: Vec<ModDefinition> = serde_yaml::from_str(
+            r#"---
+- name: sodium
+  source: modrinth
+- name: fabric-api
+  source: modrinth
+  file_id: Hi8quJUM
+- name: worldedit
+  source: curse
+  id: 225608
+- name: jei
+  source: curse
+  file_id: 4712868
+- name: modmenu
+  source: url
+  location: https://example.com/modmenu-8.0.0.jar
+"#,
+        )
+        .unwrap();
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        mods_channel.send(mods).unwrap();
+        handle.join().unwrap();
+
+        let timeout = Duration::from_secs(10);
+        assert_eq!(
+            read_channel(&mut out_channel, timeout).unwrap(),
+            "Checked 5 mods, 2 not pinned:\n- sodium\n- worldedit\n"
+        );
+        assert_eq!(read_channel(&mut unpinned_channel, timeout).unwrap(), vec!["sodium", "worldedit"]);
+    }
+
+    #[test]
+    fn strict_fails_on_unpinned_mods() {
+        let node_id = "pin-audit";
+        let mods_channel = channel(1).0;
+        let input_ids = HashMap::from([("mods".into(), ChannelId::from_str("mod-source").unwrap())]);
+        let node = NodeConfigTypes::PinAudit(PinAudit { strict: true });
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("mod-source").unwrap(),
+                InputType::Mods(mods_channel.clone()),
+            )]))
+            .build();
+
+        let mods: Vec<ModDefinition> = serde_yaml::from_str("- name: sodium\n  source: modrinth\n").unwrap();
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        mods_channel.send(mods).unwrap();
+        assert!(handle.join().is_err());
+    }
+}
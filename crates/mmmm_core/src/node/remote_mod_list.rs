@@ -0,0 +1,210 @@
+use std::{
+    collections::HashMap,
+    thread::{spawn, JoinHandle},
+};
+
+use api_client::common::Downloader;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::{
+    di::container::{DiContainer, InputType, OutputType},
+    Cache,
+};
+
+use super::{
+    config::{ChannelId, ChannelType, ModDefinition, NodeConfig, NodeDescription, NodeInitError, Port},
+    utils::{cache_namespace, get_input, get_output, CACHE_PREFIX},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Default)]
+pub struct RemoteModList {
+    /// How long a downloaded list is reused from the cache, in seconds. Unset downloads the list
+    /// on every run, only falling back to the cache when offline or when the download fails.
+    pub cache_ttl: Option<u64>,
+}
+
+const CACHE_NAMESPACE: &str = "RemoteModList::v1";
+
+/// A downloaded list, as stored in the cache.
+#[derive(Serialize, Deserialize)]
+struct CachedList {
+    /// Unix timestamp of the download.
+    fetched_at: i64,
+    body: String,
+}
+
+impl NodeConfig for RemoteModList {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut url_channel = get_input!("url", Text, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Mods, ctx)?;
+        let cache_ttl = self.cache_ttl;
+        let cache = ctx.get_cache();
+        let namespace = cache_namespace(
+            ctx.get_config(CACHE_PREFIX).filter(|prefix| !prefix.is_empty()).as_deref(),
+            CACHE_NAMESPACE,
+        );
+        let downloader = ctx.get_downloader();
+        let offline = ctx.is_offline();
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "RemoteModList", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let url = url_channel.blocking_recv().expect_or_log("Failed to receive on url input");
+
+            let body = fetch(&url, &downloader, cache.as_deref(), &namespace, cache_ttl, offline).unwrap_or_else(|e| {
+                event!(Level::ERROR, "Failed to get mod list from {url}: {e}");
+                panic!()
+            });
+            let mods: Vec<ModDefinition> = serde_yaml::from_str(&body).expect_or_log(&format!("Failed to parse mod list from {url}"));
+            event!(Level::INFO, "Got {} mods from {url}", mods.len());
+
+            if out_channel.send(mods).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::Mods(channel(1).0))])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "RemoteModList",
+            vec![Port::required("url", ChannelType::Text)],
+            vec![Port::required("default", ChannelType::Mods)],
+        )
+    }
+}
+
+/// Get the list at `url`, from the cache if it was downloaded less than `ttl` seconds ago, and
+/// otherwise by downloading it. A stale cached list is used when offline, or when the download
+/// fails.
+fn fetch(url: &str, downloader: &Downloader, cache: Option<&dyn Cache>, namespace: &str, ttl: Option<u64>, offline: bool) -> Result<String, String> {
+    let cached = match cache {
+        Some(cache) => match cache.get(namespace, url).map_err(|e| e.to_string())? {
+            Some(data) => Some(serde_json::from_str::<CachedList>(&data).map_err(|e| e.to_string())?),
+            None => None,
+        },
+        None => None,
+    };
+    let now = Utc::now().timestamp();
+    match (cached, ttl) {
+        (Some(cached), Some(ttl)) if now - cached.fetched_at < ttl as i64 => Ok(cached.body),
+        (Some(cached), _) if offline => Ok(cached.body),
+        (None, _) if offline => Err("not cached, and network access is disabled in offline mode".to_owned()),
+        (cached, _) => match downloader.download_file(url) {
+            Ok(data) => {
+                let body = String::from_utf8(data).map_err(|_| "not valid UTF-8".to_owned())?;
+                if let Some(cache) = cache {
+                    let entry = CachedList {
+                        fetched_at: now,
+                        body: body.clone(),
+                    };
+                    cache
+                        .put(namespace, url, &serde_json::to_string(&entry).map_err(|e| e.to_string())?)
+                        .map_err(|e| e.to_string())?;
+                }
+                Ok(body)
+            },
+            Err(e) => match cached {
+                Some(cached) => {
+                    event!(
+                        Level::WARN,
+                        "Failed to download mod list from {url}, using the copy cached {}s ago: {e}",
+                        now - cached.fetched_at
+                    );
+                    Ok(cached.body)
+                },
+                None => Err(e.to_string()),
+            },
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        str::FromStr,
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel, serve_http, TestCache},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_remote_mod_list() {
+        let list = "- name: jei\n  source: curse\n- name: sodium\n  source: modrinth\n  side: client\n";
+        let api = serve_http(HashMap::from([("/mods.yaml".to_owned(), (200, list.as_bytes().to_vec()))]));
+        let url = format!("{api}/mods.yaml");
+        let data = Arc::new(Mutex::new(HashMap::new()));
+
+        let node_id = "remote";
+        let url_channel = channel(1).0;
+        let input_ids = HashMap::from([("url".into(), ChannelId::from_str("list-url").unwrap())]);
+        let node = NodeConfigTypes::RemoteModList(RemoteModList { cache_ttl: Some(3600) });
+        let mut ctx = DiContainerBuilder::default()
+            .set_cache(Box::new(TestCache { data: data.clone() }))
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("list-url").unwrap(),
+                InputType::Text(url_channel.clone()),
+            )]))
+            .build();
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Mods, ctx);
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        url_channel.send(url.clone()).unwrap();
+        handle.join().unwrap();
+
+        let mods = read_channel(&mut out_channel, Duration::from_secs(5)).unwrap();
+        assert_eq!(mods.iter().map(|m| m.get_fields().name.as_str()).collect::<Vec<_>>(), ["jei", "sodium"]);
+        assert!(matches!(mods[1], ModDefinition::Modrinth { .. }));
+
+        // Within the TTL the cached copy is used, even if the list has since changed
+        let cache = TestCache { data: data.clone() };
+        let downloader = Downloader::default();
+        let fetch = |url: &str, ttl, offline| fetch(url, &downloader, Some(&cache), CACHE_NAMESPACE, ttl, offline);
+        let key = (CACHE_NAMESPACE.to_owned(), url.clone());
+        let cached_at =
+            |data: &Mutex<HashMap<(String, String), String>>| serde_json::from_str::<CachedList>(&data.lock().unwrap()[&key]).unwrap().fetched_at;
+        let first_fetch = cached_at(&data);
+        data.lock()
+            .unwrap()
+            .insert(key.clone(), format!(r#"{{"fetched_at":{first_fetch},"body":"[]"}}"#));
+        assert_eq!(fetch(&url, Some(3600), false).unwrap(), "[]");
+
+        // Expired lists are downloaded again
+        assert_eq!(fetch(&url, Some(0), false).unwrap(), list);
+        // Stale lists are used offline, or when the download fails
+        let unreachable = "http://127.0.0.1:9/mods.yaml";
+        data.lock().unwrap().insert(
+            (CACHE_NAMESPACE.to_owned(), unreachable.to_owned()),
+            r#"{"fetched_at":0,"body":"[]"}"#.to_owned(),
+        );
+        assert_eq!(fetch(unreachable, None, true).unwrap(), "[]");
+        assert_eq!(fetch(unreachable, Some(60), false).unwrap(), "[]");
+        assert!(fetch("http://127.0.0.1:9/other.yaml", None, true).is_err());
+    }
+}
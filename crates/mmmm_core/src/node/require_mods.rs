@@ -0,0 +1,146 @@
+use std::{
+    collections::{HashMap, HashSet},
+    thread::{spawn, JoinHandle},
+};
+
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::di::container::{DiContainer, InputType, OutputType};
+
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port, ResolvedMod},
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Default)]
+pub struct RequireMods {
+    /// Names of the mods that must be present, compared case-insensitively.
+    pub mods: Vec<String>,
+}
+
+impl NodeConfig for RequireMods {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut mods_channel = get_input!("mods", ResolvedMods, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), ResolvedMods, ctx)?;
+        if self.mods.is_empty() {
+            return Err(NodeInitError::InvalidOption {
+                option: "mods".into(),
+                reason: "at least one required mod must be listed".into(),
+            });
+        }
+        let required = self.mods.clone();
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "RequireMods", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let mods = mods_channel.blocking_recv().expect_or_log("Failed to receive on mods input");
+
+            let missing = missing_mods(&mods, &required);
+            if !missing.is_empty() {
+                event!(Level::ERROR, "Required mods are missing from the mod list:\n- {}", missing.join("\n- "));
+                panic!()
+            }
+            event!(Level::INFO, "All {} required mods are present", required.len());
+
+            if out_channel.send(mods).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::ResolvedMods(channel(1).0))])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "RequireMods",
+            vec![Port::required("mods", ChannelType::ResolvedMods)],
+            vec![Port::required("default", ChannelType::ResolvedMods)],
+        )
+    }
+}
+
+/// The required names that no mod in `mods` has, in the order they were listed.
+fn missing_mods<'a>(mods: &[ResolvedMod], required: &'a [String]) -> Vec<&'a str> {
+    let present = mods.iter().map(|m| m.name.to_lowercase()).collect::<HashSet<_>>();
+    required
+        .iter()
+        .filter(|name| !present.contains(&name.to_lowercase()))
+        .map(String::as_str)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use tokio::sync::broadcast::Sender;
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel, test_mod},
+        },
+    };
+
+    use super::*;
+
+    fn context(node: &NodeConfigTypes) -> (DiContainer, Sender<Vec<ResolvedMod>>, HashMap<String, ChannelId>) {
+        let mods_channel = channel(1).0;
+        let ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels("require"))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("mod-source").unwrap(),
+                InputType::ResolvedMods(mods_channel.clone()),
+            )]))
+            .build();
+        let input_ids = HashMap::from([("mods".into(), ChannelId::from_str("mod-source").unwrap())]);
+        (ctx, mods_channel, input_ids)
+    }
+
+    #[test]
+    fn test_require_mods() {
+        let node = NodeConfigTypes::RequireMods(RequireMods {
+            mods: vec!["LuckPerms".into(), "grim-anticheat".into()],
+        });
+        let (mut ctx, mods_channel, input_ids) = context(&node);
+        let mut out_channel = get_output_test!(ChannelId::from_str("require").unwrap(), ResolvedMods, ctx);
+
+        let mods = vec![test_mod("luckperms"), test_mod("jei"), test_mod("grim-anticheat")];
+        let handle = node.validate_and_spawn("require".into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        mods_channel.send(mods.clone()).unwrap();
+        handle.join().unwrap();
+
+        assert_eq!(read_channel(&mut out_channel, Duration::from_secs(10)).unwrap(), mods);
+    }
+
+    #[test]
+    fn missing_mod_fails() {
+        let node = NodeConfigTypes::RequireMods(RequireMods {
+            mods: vec!["luckperms".into(), "grim-anticheat".into()],
+        });
+        let (mut ctx, mods_channel, input_ids) = context(&node);
+
+        let mods = vec![test_mod("luckperms"), test_mod("jei")];
+        assert_eq!(missing_mods(&mods, &["jei".into(), "grim-anticheat".into()]), vec!["grim-anticheat"]);
+
+        let handle = node.validate_and_spawn("require".into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        mods_channel.send(mods).unwrap();
+        assert!(handle.join().is_err());
+    }
+}
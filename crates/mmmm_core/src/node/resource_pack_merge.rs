@@ -0,0 +1,243 @@
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    thread::{spawn, JoinHandle},
+};
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::sync::broadcast::{channel, Receiver};
+use tracing::{event, span, Level};
+use tracing_unwrap::{OptionExt, ResultExt};
+
+use crate::{
+    di::container::{DiContainer, InputType, OutputType},
+    file::{filepath::FilePath, filetree::FileTree},
+};
+
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port},
+    utils::{get_input, get_output},
+};
+
+/// Files copied from each resource pack. `pack.mcmeta` is generated separately.
+const PACK_FILES: [&str; 2] = ["assets/**", "pack.png"];
+const MCMETA: &str = "pack.mcmeta";
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Default)]
+pub struct ResourcePackMerge {
+    /// Resource pack format of the merged pack. Defaults to the highest format of any input.
+    #[serde(default)]
+    pub pack_format: Option<u32>,
+    /// Description of the merged pack. Defaults to the description of the highest priority input.
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+impl NodeConfig for ResourcePackMerge {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        // As with DirectoryMerger, inputs are sorted in reverse, so that packs with earlier input
+        // names are merged last and take priority.
+        let mut input_channels = {
+            let mut keys = input_ids.keys().cloned().collect::<Vec<_>>();
+            keys.sort_unstable_by(|a, b| b.cmp(a));
+            keys.into_iter()
+                .map(|id| get_input!(&id, Files, ctx, input_ids).map(|channel| (id, channel)))
+                .collect::<Result<Vec<(String, Receiver<_>)>, _>>()
+        }?;
+        let output_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Files, ctx)?;
+        let fs = ctx.get_filestore();
+        let options = self.clone();
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "ResourcePackMerge", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let mcmeta_path = FilePath::from_str(MCMETA).unwrap_or_log();
+            let mut merged = FileTree::new(fs);
+            let mut highest_format: Option<u32> = None;
+            let mut priority_mcmeta: Option<Value> = None;
+            for (id, channel) in input_channels.iter_mut() {
+                let pack = channel.blocking_recv().expect_or_log(&format!("Failed to receive on {id} input"));
+                match pack.get_file(&mcmeta_path).map(|data| parse_mcmeta(&data)) {
+                    Some(Ok((format, mcmeta))) => {
+                        highest_format = highest_format.max(Some(format));
+                        priority_mcmeta = Some(mcmeta);
+                    },
+                    Some(Err(reason)) => event!(Level::WARN, "Ignoring pack.mcmeta from input '{}': {}", id, reason),
+                    None => event!(Level::WARN, "Input '{}' has no pack.mcmeta", id),
+                }
+                merged.add_all(pack.filter_files(&PACK_FILES).0);
+            }
+
+            let Some(pack_format) = options.pack_format.or(highest_format) else {
+                event!(Level::ERROR, "No input has a valid pack.mcmeta, set the pack_format option");
+                panic!();
+            };
+            let mut mcmeta = priority_mcmeta.unwrap_or_else(|| json!({ "pack": { "description": "" } }));
+            let pack = mcmeta["pack"].as_object_mut().expect_or_log("Validated pack.mcmeta has a pack object");
+            pack.insert("pack_format".into(), pack_format.into());
+            // Format ranges of a single input do not describe the merged pack
+            pack.remove("supported_formats");
+            if let Some(description) = options.description {
+                pack.insert("description".into(), description.into());
+            }
+            let serialized = serde_json::to_string_pretty(&mcmeta).expect_or_log("Failed to serialize pack.mcmeta");
+            merged.add_file(mcmeta_path, serialized.into_bytes());
+
+            if output_channel.send(merged).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::Files(channel(1).0))])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new("ResourcePackMerge", vec![], vec![Port::required("default", ChannelType::Files)]).with_any_inputs(ChannelType::Files)
+    }
+}
+
+/// Parse a `pack.mcmeta` file, returning its pack format along with the whole document.
+fn parse_mcmeta(data: &[u8]) -> Result<(u32, Value), String> {
+    let mcmeta: Value = serde_json::from_slice(data).map_err(|e| e.to_string())?;
+    let format = mcmeta
+        .get("pack")
+        .filter(|pack| pack.is_object())
+        .ok_or("missing 'pack' object")?
+        .get("pack_format")
+        .and_then(Value::as_u64)
+        .ok_or("missing or invalid 'pack_format'")?;
+    let format = u32::try_from(format).map_err(|e| e.to_string())?;
+    Ok((format, mcmeta))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        file::filestore::FileStore,
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel},
+        },
+    };
+
+    use super::*;
+
+    fn resource_pack(store: &FileStore, format: u32, description: &str, files: &[(&str, &str)]) -> FileTree {
+        let mut tree = FileTree::new(store.clone());
+        let mcmeta = json!({ "pack": { "pack_format": format, "description": description, "supported_formats": [format, format] } });
+        tree.add_file(FilePath::from_str(MCMETA).unwrap(), mcmeta.to_string().into_bytes());
+        for (path, contents) in files {
+            tree.add_file(FilePath::from_str(path).unwrap(), contents.as_bytes().to_vec());
+        }
+        tree
+    }
+
+    fn run_merge(node: ResourcePackMerge, packs: Vec<FileTree>) -> FileTree {
+        let node_id = "merge";
+        let node = NodeConfigTypes::ResourcePackMerge(node);
+        let channels = packs.iter().map(|_| channel::<FileTree>(1).0).collect::<Vec<_>>();
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(
+                channels
+                    .iter()
+                    .enumerate()
+                    .map(|(i, c)| (ChannelId::from_str(&format!("pack{i}")).unwrap(), InputType::Files(c.clone())))
+                    .collect(),
+            )
+            .build();
+        let input_ids = (0..packs.len())
+            .map(|i| (format!("pack-{i}"), ChannelId::from_str(&format!("pack{i}")).unwrap()))
+            .collect::<HashMap<_, _>>();
+        let mut out = get_output_test!(&ChannelId::from_str(node_id).unwrap(), Files, ctx);
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        channels.iter().zip(packs).for_each(|(c, pack)| {
+            c.send(pack).unwrap();
+        });
+        ctx.run().unwrap();
+
+        let result = read_channel(&mut out, Duration::from_secs(10)).unwrap();
+        handle.join().unwrap();
+        result
+    }
+
+    fn read_file(tree: &FileTree, path: &str) -> String {
+        String::from_utf8(tree.get_file(&FilePath::from_str(path).unwrap()).unwrap().to_vec()).unwrap()
+    }
+
+    #[test]
+    fn merge_resource_packs() {
+        let store = FileStore::new();
+        let first = resource_pack(
+            &store,
+            15,
+            "First pack",
+            &[
+                ("assets/minecraft/textures/block/stone.png", "first stone"),
+                ("pack.png", "first icon"),
+                ("readme.txt", "not an asset"),
+            ],
+        );
+        let second = resource_pack(
+            &store,
+            18,
+            "Second pack",
+            &[
+                ("assets/minecraft/textures/block/stone.png", "second stone"),
+                ("assets/minecraft/textures/block/dirt.png", "second dirt"),
+                ("pack.png", "second icon"),
+            ],
+        );
+
+        let merged = run_merge(ResourcePackMerge::default(), vec![first, second]);
+
+        let mut paths = merged.list_files().into_iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                "assets/minecraft/textures/block/dirt.png",
+                "assets/minecraft/textures/block/stone.png",
+                "pack.mcmeta",
+                "pack.png"
+            ]
+        );
+        // "pack-0" sorts first, so the first pack takes priority
+        assert_eq!(read_file(&merged, "assets/minecraft/textures/block/stone.png"), "first stone");
+        assert_eq!(read_file(&merged, "pack.png"), "first icon");
+        let mcmeta: Value = serde_json::from_str(&read_file(&merged, MCMETA)).unwrap();
+        assert_eq!(mcmeta, json!({ "pack": { "pack_format": 18, "description": "First pack" } }));
+    }
+
+    #[test]
+    fn merge_resource_packs_with_options() {
+        let store = FileStore::new();
+        let first = resource_pack(&store, 15, "First pack", &[]);
+        let mut second = FileTree::new(store.clone());
+        second.add_file(FilePath::from_str(MCMETA).unwrap(), b"not json".to_vec());
+
+        let node = ResourcePackMerge {
+            pack_format: Some(22),
+            description: Some("Combined".to_owned()),
+        };
+        let merged = run_merge(node, vec![first, second]);
+
+        let mcmeta: Value = serde_json::from_str(&read_file(&merged, MCMETA)).unwrap();
+        assert_eq!(mcmeta, json!({ "pack": { "pack_format": 22, "description": "Combined" } }));
+    }
+}
@@ -0,0 +1,243 @@
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    thread::{spawn, JoinHandle},
+};
+
+use api_client::loader::ModLoader;
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::{
+    di::container::{DiContainer, InputType},
+    file::{filepath::FilePath, filetree::FileTree},
+};
+
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port},
+    utils::{get_output, parse_loader, parse_version},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct ServerScriptWriter {
+    /// Heap size given to the server, as a JVM size such as `4G` or `6144M`.
+    #[serde(default = "default_memory")]
+    pub memory: String,
+    /// JVM flags passed before the server jar, in addition to the heap size.
+    #[serde(default = "default_jvm_args")]
+    pub jvm_args: Vec<String>,
+    /// Java executable used to run the server.
+    #[serde(default = "default_java")]
+    pub java: String,
+}
+
+fn default_memory() -> String {
+    "4G".into()
+}
+
+fn default_jvm_args() -> Vec<String> {
+    ["-XX:+UseG1GC", "-XX:+ParallelRefProcEnabled", "-XX:MaxGCPauseMillis=200"]
+        .map(str::to_owned)
+        .to_vec()
+}
+
+fn default_java() -> String {
+    "java".into()
+}
+
+impl Default for ServerScriptWriter {
+    fn default() -> Self {
+        ServerScriptWriter {
+            memory: default_memory(),
+            jvm_args: default_jvm_args(),
+            java: default_java(),
+        }
+    }
+}
+
+/// How the server is launched, which differs between Unix and Windows for loaders that use
+/// argument files.
+enum Launch {
+    Jar(String),
+    ArgFile { unix: String, windows: String },
+}
+
+impl NodeConfig for ServerScriptWriter {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        _input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Files, ctx)?;
+        let config = |key: &str| ctx.get_config(key).ok_or_else(|| NodeInitError::MissingConfig(key.into()));
+        let minecraft_version = config("minecraft_version")?;
+        let modloader = parse_loader("modloader", &config("modloader")?)?;
+        let launch = match modloader {
+            ModLoader::Fabric => Launch::Jar("fabric-server-launch.jar".into()),
+            ModLoader::Quilt => Launch::Jar("quilt-server-launch.jar".into()),
+            ModLoader::Forge => forge_launch(&minecraft_version, &config("modloader_version")?)?,
+            ModLoader::NeoForge => neoforge_launch(&minecraft_version, &config("modloader_version")?),
+        };
+        if !is_jvm_size(&self.memory) {
+            return Err(NodeInitError::InvalidOption {
+                option: "memory".into(),
+                reason: format!("'{}' is not a JVM size such as 4G or 6144M", self.memory),
+            });
+        }
+        let comment = format!("Starts a Minecraft {minecraft_version} server with {modloader}.");
+        let java = [self.java.clone(), format!("-Xms{}", self.memory), format!("-Xmx{}", self.memory)]
+            .into_iter()
+            .chain(self.jvm_args.iter().cloned())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let store = ctx.get_filestore();
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "ServerScriptWriter", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let (unix, windows) = match launch {
+                Launch::Jar(jar) => (format!("-jar {jar}"), format!("-jar {jar}")),
+                Launch::ArgFile { unix, windows } => (format!("@{unix}"), format!("@{windows}")),
+            };
+            let start_sh = format!("#!/usr/bin/env sh\n# {comment}\n{java} {unix} nogui \"$@\"\n");
+            // Batch files are expected to use Windows line endings
+            let start_bat = format!("@echo off\r\nrem {comment}\r\n{java} {windows} nogui %*\r\npause\r\n");
+
+            let mut tree = FileTree::new(store);
+            tree.add_file(FilePath::from_str("start.sh").unwrap_or_log(), start_sh.into_bytes());
+            tree.add_file(FilePath::from_str("start.bat").unwrap_or_log(), start_bat.into_bytes());
+
+            if out_channel.send(tree).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::Files(channel(1).0))])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        // Forge and NeoForge servers also need modloader_version, which depends on the config
+        NodeDescription::new("ServerScriptWriter", vec![], vec![Port::required("default", ChannelType::Files)])
+            .with_config_keys(vec!["minecraft_version", "modloader"])
+    }
+}
+
+/// Forge servers since 1.17 are launched with an argument file written by the installer, and older
+/// ones with the jar the installer produces.
+fn forge_launch(minecraft_version: &str, forge_version: &str) -> Result<Launch, NodeInitError> {
+    let version = parse_version(minecraft_version).ok_or_else(|| NodeInitError::InvalidOption {
+        option: "minecraft_version".into(),
+        reason: format!("'{minecraft_version}' is not a release version"),
+    })?;
+    Ok(match version.as_slice() >= [1, 17].as_slice() {
+        true => {
+            let dir = format!("libraries/net/minecraftforge/forge/{minecraft_version}-{forge_version}");
+            Launch::ArgFile {
+                unix: format!("{dir}/unix_args.txt"),
+                windows: format!("{dir}/win_args.txt"),
+            }
+        },
+        false => Launch::Jar(format!("forge-{minecraft_version}-{forge_version}.jar")),
+    })
+}
+
+/// NeoForge servers are launched with an argument file written by the installer. NeoForge for
+/// 1.20.1 was still published under the Forge artifact name.
+fn neoforge_launch(minecraft_version: &str, neoforge_version: &str) -> Launch {
+    let dir = match minecraft_version {
+        "1.20.1" => format!("libraries/net/neoforged/forge/{minecraft_version}-{neoforge_version}"),
+        _ => format!("libraries/net/neoforged/neoforge/{neoforge_version}"),
+    };
+    Launch::ArgFile {
+        unix: format!("{dir}/unix_args.txt"),
+        windows: format!("{dir}/win_args.txt"),
+    }
+}
+
+/// Whether a string is a JVM memory size, a number with an optional `K`, `M` or `G` suffix.
+fn is_jvm_size(size: &str) -> bool {
+    let digits = size.strip_suffix(['k', 'K', 'm', 'M', 'g', 'G']).unwrap_or(size);
+    !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        di::container::{DiContainerBuilder, OutputType},
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_server_script_writer() {
+        let node_id = "scripts";
+        let node = NodeConfigTypes::ServerScriptWriter(ServerScriptWriter {
+            memory: "6G".into(),
+            ..Default::default()
+        });
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .set_config("minecraft_version", "1.20.1")
+            .set_config("modloader", "forge")
+            .set_config("modloader_version", "47.2.0")
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Files, ctx);
+
+        let handle = node.validate_and_spawn(node_id.into(), &HashMap::new(), &ctx).unwrap();
+        ctx.run().unwrap();
+        handle.join().unwrap();
+
+        let tree = read_channel(&mut out_channel, Duration::from_secs(10)).unwrap();
+        let file = |path: &str| String::from_utf8(tree.get_file(&FilePath::from_str(path).unwrap()).unwrap().to_vec()).unwrap();
+        let java = "java -Xms6G -Xmx6G -XX:+UseG1GC -XX:+ParallelRefProcEnabled -XX:MaxGCPauseMillis=200";
+        let dir = "libraries/net/minecraftforge/forge/1.20.1-47.2.0";
+        assert_eq!(
+            file("start.sh"),
+            format!("#!/usr/bin/env sh\n# Starts a Minecraft 1.20.1 server with forge.\n{java} @{dir}/unix_args.txt nogui \"$@\"\n")
+        );
+        assert_eq!(
+            file("start.bat"),
+            format!("@echo off\r\nrem Starts a Minecraft 1.20.1 server with forge.\r\n{java} @{dir}/win_args.txt nogui %*\r\npause\r\n")
+        );
+    }
+
+    #[test]
+    fn launch_by_loader() {
+        assert!(matches!(forge_launch("1.12.2", "14.23.5.2860").unwrap(), Launch::Jar(jar) if jar == "forge-1.12.2-14.23.5.2860.jar"));
+        assert!(
+            matches!(neoforge_launch("1.21.1", "21.1.77"), Launch::ArgFile { unix, .. } if unix == "libraries/net/neoforged/neoforge/21.1.77/unix_args.txt")
+        );
+        assert!(is_jvm_size("6144M"));
+        assert!(is_jvm_size("8"));
+        assert!(!is_jvm_size("4GB"));
+        assert!(!is_jvm_size("G"));
+    }
+
+    #[test]
+    fn loader_version_required() {
+        let node = NodeConfigTypes::ServerScriptWriter(ServerScriptWriter::default());
+        let ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels("scripts"))
+            .set_config("minecraft_version", "1.20.1")
+            .set_config("modloader", "neoforge")
+            .build();
+        let result = node.validate_and_spawn("scripts".into(), &HashMap::new(), &ctx);
+        assert!(matches!(result, Err(NodeInitError::MissingConfig(key)) if key == "modloader_version"));
+    }
+}
@@ -0,0 +1,200 @@
+use std::{
+    collections::HashMap,
+    thread::{spawn, JoinHandle},
+};
+
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::di::container::{DiContainer, InputType, OutputType};
+
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port, ResolvedMod},
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct ShadowReport;
+
+impl NodeConfig for ShadowReport {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut files_channel = get_input!("files", Files, ctx, input_ids)?;
+        let mut mods_channel = get_input!("mods", ResolvedMods, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Text, ctx)?;
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "ShadowReport", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let files = files_channel.blocking_recv().expect_or_log("Failed to receive on files input");
+            let mods = mods_channel.blocking_recv().expect_or_log("Failed to receive on mods input");
+
+            let mut configs = files
+                .list_files()
+                .into_iter()
+                .filter_map(|path| config_path(&path.get_components()))
+                .map(|config| {
+                    let owner = owning_mod(&config, &mods).map(|m| m.name.clone());
+                    (config, owner)
+                })
+                .collect::<Vec<_>>();
+            configs.sort();
+
+            let unmatched = configs.iter().filter(|(_, owner)| owner.is_none()).count();
+            if unmatched > 0 {
+                event!(Level::WARN, "{unmatched} override configs do not match any mod in the pack");
+            }
+
+            if out_channel.send(report(&configs, unmatched)).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::Text(channel(1).0))])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "ShadowReport",
+            vec![
+                Port::required("files", ChannelType::Files),
+                Port::required("mods", ChannelType::ResolvedMods),
+            ],
+            vec![Port::required("default", ChannelType::Text)],
+        )
+    }
+}
+
+/// The path of a file from its first `config` directory on, such as `config/jei/jei-client.ini`
+/// for `overrides/config/jei/jei-client.ini`. Files outside a `config` directory give `None`.
+fn config_path(components: &[String]) -> Option<String> {
+    let start = components.iter().position(|component| component == "config")?;
+    match components.len() - start {
+        1 => None,
+        _ => Some(components[start..].join("/")),
+    }
+}
+
+/// Lowercase a name and drop everything but letters and digits, so `Mouse_Tweaks` and
+/// `mouse-tweaks` compare equal.
+fn normalize(name: &str) -> String {
+    name.chars().filter(char::is_ascii_alphanumeric).map(|c| c.to_ascii_lowercase()).collect()
+}
+
+/// Guess which mod generates a config, by matching the first path segment under `config/`, without
+/// its extension, against the names and titles of the mods. A segment such as `jei-client` is
+/// matched by a mod named `jei`; the longest match wins.
+fn owning_mod<'a>(config: &str, mods: &'a [ResolvedMod]) -> Option<&'a ResolvedMod> {
+    let segment = config.split('/').nth(1)?;
+    let stem = normalize(segment.split('.').next().unwrap_or(segment));
+    mods.iter()
+        .filter_map(|m| {
+            [&m.name, &m.title]
+                .into_iter()
+                .map(|name| normalize(name))
+                .filter(|name| *name == stem || (name.len() >= 3 && stem.starts_with(name.as_str())))
+                .map(|name| name.len())
+                .max()
+                .map(|len| (len, m))
+        })
+        .max_by_key(|(len, _)| *len)
+        .map(|(_, m)| m)
+}
+
+fn report(configs: &[(String, Option<String>)], unmatched: usize) -> String {
+    if configs.is_empty() {
+        return "No override files under config/.\n".into();
+    }
+    let mut report = format!("Override files under config/ ({}):\n", configs.len());
+    for (config, owner) in configs {
+        match owner {
+            Some(name) => report.push_str(&format!("- {config} (shadows {name})\n")),
+            None => report.push_str(&format!("- {config} (no matching mod)\n")),
+        }
+    }
+    if unmatched > 0 {
+        report.push_str(&format!(
+            "\n{unmatched} files match no mod in the pack. Check that a mod still reads them, as a mod update may have moved its config.\n"
+        ));
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use tokio::sync::broadcast;
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        file::{filepath::FilePath, filetree::FileTree},
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel, test_mod},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_shadow_report() {
+        let node_id = "shadows";
+        let files_channel = broadcast::channel::<FileTree>(1).0;
+        let mods_channel = broadcast::channel(1).0;
+        let input_ids = HashMap::from([
+            ("files".into(), ChannelId::from_str("overrides").unwrap()),
+            ("mods".into(), ChannelId::from_str("mod-source").unwrap()),
+        ]);
+        let node = NodeConfigTypes::ShadowReport(ShadowReport);
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([
+                (ChannelId::from_str("overrides").unwrap(), InputType::Files(files_channel.clone())),
+                (ChannelId::from_str("mod-source").unwrap(), InputType::ResolvedMods(mods_channel.clone())),
+            ]))
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Text, ctx);
+
+        let mut tree = FileTree::new(ctx.get_filestore());
+        for path in [
+            "overrides/config/jei/jei-client.ini",
+            "overrides/config/MouseTweaks.cfg",
+            "overrides/config/oldmod-common.toml",
+            "overrides/kubejs/server_scripts/recipes.js",
+        ] {
+            tree.add_file(FilePath::from_str(path).unwrap(), vec![]);
+        }
+        let mods = vec![test_mod("jei"), test_mod("mouse-tweaks"), test_mod("appleskin")];
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        files_channel.send(tree).unwrap();
+        mods_channel.send(mods).unwrap();
+
+        let report = read_channel(&mut out_channel, Duration::from_secs(5)).unwrap();
+        assert_eq!(
+            report,
+            "Override files under config/ (3):\n\
+             - config/MouseTweaks.cfg (shadows mouse-tweaks)\n\
+             - config/jei/jei-client.ini (shadows jei)\n\
+             - config/oldmod-common.toml (no matching mod)\n\
+             \n\
+             1 files match no mod in the pack. Check that a mod still reads them, as a mod update may have moved its config.\n"
+        );
+        handle.join().unwrap();
+    }
+}
@@ -0,0 +1,230 @@
+use std::{
+    collections::HashMap,
+    io::Write,
+    process::{Command, Stdio},
+    thread::{spawn, JoinHandle},
+};
+
+use rayon::prelude::*;
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::{
+    di::container::{DiContainer, InputType, OutputType},
+    file::filepath::FilePath,
+};
+
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port},
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Default)]
+pub struct ShellTransform {
+    /// Program and arguments to run for each file. Run directly rather than through a shell.
+    pub command: Vec<String>,
+    /// Glob patterns selecting the files to transform.
+    pub patterns: Vec<String>,
+    /// What to do when the command fails for a file.
+    #[serde(default)]
+    pub on_error: OnError,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum OnError {
+    /// Fail the node, after logging every file the command failed for.
+    #[default]
+    Fail,
+    /// Keep the original contents of the file.
+    Skip,
+}
+
+impl NodeConfig for ShellTransform {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        if !ctx.commands_allowed() {
+            return Err(NodeInitError::CommandsDisabled);
+        }
+        if self.command.is_empty() {
+            return Err(NodeInitError::InvalidOption {
+                option: "command".into(),
+                reason: "a program to run must be given".into(),
+            });
+        }
+        if self.patterns.is_empty() {
+            return Err(NodeInitError::InvalidOption {
+                option: "patterns".into(),
+                reason: "at least one pattern must be given".into(),
+            });
+        }
+        let mut files_channel = get_input!("files", Files, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Files, ctx)?;
+        let command = self.command.clone();
+        let patterns = self.patterns.clone();
+        let on_error = self.on_error;
+        let pool = ctx.get_pool();
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "ShellTransform", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let mut files = files_channel.blocking_recv().expect_or_log("Failed to receive on files input");
+
+            let targets = files
+                .list_files()
+                .into_iter()
+                .filter(|path| path.glob_match(&patterns))
+                .cloned()
+                .collect::<Vec<FilePath>>();
+            let results: Vec<(FilePath, Result<Vec<u8>, String>)> = pool.install(|| {
+                targets
+                    .into_par_iter()
+                    .filter_map(|path| {
+                        let data = files.get_file(&path)?;
+                        let result = run(&command, &path, &data);
+                        Some((path, result))
+                    })
+                    .collect()
+            });
+
+            let mut failures = vec![];
+            for (path, result) in results {
+                match result {
+                    Ok(output) => files.add_file(path, output),
+                    Err(e) => {
+                        event!(Level::ERROR, "Command failed for {path}: {e}");
+                        failures.push(path);
+                    },
+                }
+            }
+            if !failures.is_empty() {
+                match on_error {
+                    OnError::Fail => panic!("Command failed for {} files", failures.len()),
+                    OnError::Skip => event!(Level::WARN, "Command failed for {} files, kept their original contents", failures.len()),
+                }
+            }
+
+            if out_channel.send(files).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::Files(channel(1).0))])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "ShellTransform",
+            vec![Port::required("files", ChannelType::Files)],
+            vec![Port::required("default", ChannelType::Files)],
+        )
+    }
+}
+
+/// Pipe a file through the command, returning what it writes to stdout. The file's path in the
+/// tree is passed in the `MMMM_FILE_PATH` environment variable.
+fn run(command: &[String], path: &FilePath, data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut child = Command::new(&command[0])
+        .args(&command[1..])
+        .env("MMMM_FILE_PATH", path.to_string())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("failed to start {}: {e}", command[0]))?;
+    // Write from another thread, so a command that produces output before reading all of its
+    // input does not deadlock on a full pipe
+    let mut stdin = child.stdin.take().unwrap();
+    let input = data.to_vec();
+    let writer = spawn(move || stdin.write_all(&input));
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    // A command may exit without reading its input, which is only an error if it also failed
+    let _ = writer.join();
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("{}: {}", output.status, stderr.trim()));
+    }
+    Ok(output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        file::filetree::FileTree,
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    #[cfg(unix)]
+    fn test_shell_transform() {
+        let run_node = |node: ShellTransform, allow_commands: bool| {
+            let node_id = "transform";
+            let files_channel = channel::<FileTree>(1).0;
+            let input_ids = HashMap::from([("files".into(), ChannelId::from_str("pack-files").unwrap())]);
+            let node = NodeConfigTypes::ShellTransform(node);
+            let mut ctx = DiContainerBuilder::default()
+                .allow_commands(allow_commands)
+                .channel_from_node(node.generate_channels(node_id))
+                .channel_from_node(HashMap::from([(
+                    ChannelId::from_str("pack-files").unwrap(),
+                    InputType::Files(files_channel.clone()),
+                )]))
+                .build();
+            let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Files, ctx);
+            let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx)?;
+
+            let mut tree = FileTree::new(ctx.get_filestore());
+            tree.add_file(FilePath::from_str("config/a.txt").unwrap(), b"hello".to_vec());
+            tree.add_file(FilePath::from_str("config/b.json").unwrap(), b"{}".to_vec());
+            ctx.run().unwrap();
+            files_channel.send(tree).unwrap();
+            let result = handle.join();
+            let out = read_channel(&mut out_channel, std::time::Duration::from_millis(500)).ok();
+            Ok::<_, NodeInitError>((result.is_ok(), out))
+        };
+        let node = |command: &[&str], on_error| ShellTransform {
+            command: command.iter().map(|arg| arg.to_string()).collect(),
+            patterns: vec!["config/*.txt".into()],
+            on_error,
+        };
+        let read = |tree: &FileTree, path: &str| tree.get_file(&FilePath::from_str(path).unwrap()).unwrap().to_vec();
+
+        assert!(matches!(
+            run_node(node(&["tr", "a-z", "A-Z"], OnError::Fail), false),
+            Err(NodeInitError::CommandsDisabled)
+        ));
+
+        let (ok, out) = run_node(node(&["tr", "a-z", "A-Z"], OnError::Fail), true).unwrap();
+        let out = out.unwrap();
+        assert!(ok);
+        assert_eq!(read(&out, "config/a.txt"), b"HELLO");
+        assert_eq!(read(&out, "config/b.json"), b"{}");
+
+        let (ok, out) = run_node(node(&["sh", "-c", "echo \"$MMMM_FILE_PATH\"; exit 3"], OnError::Skip), true).unwrap();
+        assert!(ok);
+        assert_eq!(read(&out.unwrap(), "config/a.txt"), b"hello");
+
+        let (ok, out) = run_node(node(&["false"], OnError::Fail), true).unwrap();
+        assert!(!ok);
+        assert!(out.is_none());
+    }
+}
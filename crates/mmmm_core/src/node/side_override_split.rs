@@ -0,0 +1,187 @@
+use std::{
+    collections::HashMap,
+    thread::{spawn, JoinHandle},
+};
+
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::{
+    di::container::{DiContainer, InputType, OutputType},
+    file::filetree::FileTree,
+};
+
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port},
+    utils::{get_input, get_output},
+};
+
+/// Directory holding overrides for both sides.
+pub const SHARED_DIR: &str = "overrides";
+/// Directory holding overrides only installed on clients.
+pub const CLIENT_DIR: &str = "client_overrides";
+/// Directory holding overrides only installed on servers.
+pub const SERVER_DIR: &str = "server_overrides";
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct SideOverrideSplit;
+
+impl NodeConfig for SideOverrideSplit {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut files_channel = get_input!("files", Files, ctx, input_ids)?;
+        let client_channel = get_output!(ChannelId(node_id.clone(), "client".into()), Files, ctx)?;
+        let server_channel = get_output!(ChannelId(node_id.clone(), "server".into()), Files, ctx)?;
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "SideOverrideSplit", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let files = files_channel.blocking_recv().expect_or_log("Failed to receive on files input");
+
+            let mut ignored = files
+                .list_files()
+                .into_iter()
+                .filter(|path| {
+                    let components = path.get_components();
+                    components.len() < 2 || ![SHARED_DIR, CLIENT_DIR, SERVER_DIR].contains(&components[0].as_str())
+                })
+                .map(|path| path.to_string())
+                .collect::<Vec<_>>();
+            if !ignored.is_empty() {
+                ignored.sort();
+                event!(
+                    Level::WARN,
+                    "Ignored {} files outside of {SHARED_DIR}/, {CLIENT_DIR}/ and {SERVER_DIR}/: {}",
+                    ignored.len(),
+                    ignored.join(", ")
+                );
+            }
+
+            let client = side_tree(&files, CLIENT_DIR);
+            let server = side_tree(&files, SERVER_DIR);
+
+            if client_channel.send(client).is_err() {
+                event!(Level::DEBUG, "Channel 'client' has no subscribers");
+            }
+            if server_channel.send(server).is_err() {
+                event!(Level::DEBUG, "Channel 'server' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([
+            (ChannelId(node_id.to_owned(), "client".into()), InputType::Files(channel(1).0)),
+            (ChannelId(node_id.to_owned(), "server".into()), InputType::Files(channel(1).0)),
+        ])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "SideOverrideSplit",
+            vec![Port::required("files", ChannelType::Files)],
+            vec![Port::required("client", ChannelType::Files), Port::required("server", ChannelType::Files)],
+        )
+    }
+}
+
+/// Merge the shared overrides with those of one side, which replace shared files at the same path.
+fn side_tree(files: &FileTree, side_dir: &str) -> FileTree {
+    let mut tree = files.subtree(SHARED_DIR);
+    let side = files.subtree(side_dir);
+    let replaced = side.list_files().into_iter().filter(|path| tree.get_hash(path).is_some()).count();
+    if replaced > 0 {
+        event!(Level::INFO, "{side_dir}/ replaces {replaced} files from {SHARED_DIR}/");
+    }
+    tree.add_all(side);
+    tree
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        file::{filepath::FilePath, filestore::FileStore},
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_side_override_split() {
+        let node_id = "split";
+        let files_channel = channel(1).0;
+        let input_ids = HashMap::from([("files".into(), ChannelId::from_str("pack-files").unwrap())]);
+        let node = NodeConfigTypes::SideOverrideSplit(SideOverrideSplit);
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("pack-files").unwrap(),
+                InputType::Files(files_channel.clone()),
+            )]))
+            .build();
+
+        let mut client_channel = get_output_test!(ChannelId::from_str("split::client").unwrap(), Files, ctx);
+        let mut server_channel = get_output_test!(ChannelId::from_str("split::server").unwrap(), Files, ctx);
+
+        let mut tree = FileTree::new(FileStore::new());
+        for (path, contents) in [
+            ("overrides/config/jei.toml", "shared"),
+            ("overrides/options.txt", "shared"),
+            ("client_overrides/options.txt", "client"),
+            ("client_overrides/config/keybinds.json", "client"),
+            ("server_overrides/whitelist.json", "server"),
+            ("manifest.json", "ignored"),
+        ] {
+            tree.add_file(FilePath::from_str(path).unwrap(), contents.into());
+        }
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        files_channel.send(tree).unwrap();
+        handle.join().unwrap();
+
+        let timeout = Duration::from_secs(5);
+        let contents = |tree: &FileTree| {
+            let mut files = tree
+                .list_files()
+                .into_iter()
+                .map(|path| (path.to_string(), String::from_utf8(tree.get_file(path).unwrap().to_vec()).unwrap()))
+                .collect::<Vec<_>>();
+            files.sort();
+            files
+        };
+        let pair = |path: &str, contents: &str| (path.to_owned(), contents.to_owned());
+        assert_eq!(
+            contents(&read_channel(&mut client_channel, timeout).unwrap()),
+            [
+                pair("config/jei.toml", "shared"),
+                pair("config/keybinds.json", "client"),
+                pair("options.txt", "client")
+            ]
+        );
+        assert_eq!(
+            contents(&read_channel(&mut server_channel, timeout).unwrap()),
+            [
+                pair("config/jei.toml", "shared"),
+                pair("options.txt", "shared"),
+                pair("whitelist.json", "server")
+            ]
+        );
+    }
+}
@@ -0,0 +1,281 @@
+use std::{
+    collections::HashMap,
+    thread::{spawn, JoinHandle},
+};
+
+use api_client::{curse::CurseClient, modrinth::ModrinthClient};
+use chrono::{DateTime, Duration, Utc};
+use rayon::prelude::*;
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::di::container::{DiContainer, InputType, OutputType};
+
+use super::{
+    config::{ChannelId, ChannelType, ModSource, NodeConfig, NodeDescription, NodeInitError, Port, ResolvedMod},
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct StalenessFilter {
+    /// Mods whose latest file is older than this many days are considered stale.
+    #[serde(default = "default_max_age_days")]
+    pub max_age_days: u32,
+}
+
+impl Default for StalenessFilter {
+    fn default() -> Self {
+        Self {
+            max_age_days: default_max_age_days(),
+        }
+    }
+}
+
+fn default_max_age_days() -> u32 {
+    365
+}
+
+impl NodeConfig for StalenessFilter {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut mods_channel = get_input!("mods", ResolvedMods, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), ResolvedMods, ctx)?;
+        let inverse_channel = get_output!(ChannelId(node_id.clone(), "inverse".into()), ResolvedMods, ctx)?;
+        let report_channel = get_output!(ChannelId(node_id.clone(), "report".into()), Text, ctx)?;
+        // Release dates are not cached, so they can never be looked up offline.
+        if ctx.is_offline() {
+            return Err(NodeInitError::Offline);
+        }
+        let curse_client = ctx.get_curse_client();
+        let modrinth_client = ctx.get_modrinth_client();
+        let max_age_days = self.max_age_days;
+        let pool = ctx.get_pool();
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "StalenessFilter", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let mods = mods_channel.blocking_recv().expect_or_log("Failed to receive on mods input");
+            event!(Level::INFO, "Checking last update of {} mods", mods.len());
+            let cutoff = Utc::now() - Duration::days(max_age_days.into());
+
+            let updates: Vec<Result<Option<DateTime<Utc>>, String>> =
+                pool.install(|| mods.par_iter().map(|m| last_update(m, &curse_client, &modrinth_client)).collect());
+
+            let mut fresh = vec![];
+            let mut stale = vec![];
+            let mut stale_lines = vec![];
+            let mut unknown_lines = vec![];
+            for (m, update) in mods.into_iter().zip(updates) {
+                match update {
+                    Ok(Some(date)) if date < cutoff => {
+                        stale_lines.push(format!("- {}: last updated {}", m.name, date.format("%Y-%m-%d")));
+                        stale.push(m);
+                    },
+                    Ok(Some(_)) => fresh.push(m),
+                    // Mods that can't be checked are kept, as there is no evidence they are stale
+                    Ok(None) => {
+                        unknown_lines.push(format!("- {}: no Curse or Modrinth provenance", m.name));
+                        fresh.push(m);
+                    },
+                    Err(e) => {
+                        event!(Level::WARN, "Could not determine last update of {}: {}", m.name, e);
+                        unknown_lines.push(format!("- {}: {}", m.name, e));
+                        fresh.push(m);
+                    },
+                }
+            }
+            stale_lines.sort();
+            unknown_lines.sort();
+
+            let mut report = format!(
+                "Checked {} mods, {} not updated in over {} days.\n",
+                fresh.len() + stale.len(),
+                stale.len(),
+                max_age_days
+            );
+            if !stale_lines.is_empty() {
+                report.push_str(&format!("Stale:\n{}\n", stale_lines.join("\n")));
+            }
+            if !unknown_lines.is_empty() {
+                report.push_str(&format!("Unknown:\n{}\n", unknown_lines.join("\n")));
+            }
+
+            if out_channel.send(fresh).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+
+            if inverse_channel.send(stale).is_err() {
+                event!(Level::DEBUG, "Channel 'inverse' has no subscribers");
+            }
+
+            if report_channel.send(report).is_err() {
+                event!(Level::DEBUG, "Channel 'report' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([
+            (ChannelId(node_id.to_owned(), "default".into()), InputType::ResolvedMods(channel(1).0)),
+            (ChannelId(node_id.to_owned(), "inverse".into()), InputType::ResolvedMods(channel(1).0)),
+            (ChannelId(node_id.to_owned(), "report".into()), InputType::Text(channel(1).0)),
+        ])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "StalenessFilter",
+            vec![Port::required("mods", ChannelType::ResolvedMods)],
+            vec![
+                Port::required("default", ChannelType::ResolvedMods),
+                Port::required("inverse", ChannelType::ResolvedMods),
+                Port::required("report", ChannelType::Text),
+            ],
+        )
+    }
+}
+
+/// Get the release date of the latest file of a mod's project, or [`None`] if the mod was not
+/// resolved from a platform that tracks release dates.
+fn last_update(m: &ResolvedMod, curse: &Option<CurseClient>, modrinth: &ModrinthClient) -> Result<Option<DateTime<Utc>>, String> {
+    match m.source {
+        Some(ModSource::Curse { project_id, .. }) => {
+            let client = curse.as_ref().ok_or("Curse client is not configured")?;
+            let date = client
+                .find_mod_by_id(project_id)
+                .map_err(|e| e.to_string())?
+                .date_released
+                .ok_or("Curse project has no release date")?;
+            parse_date(&date).map(Some)
+        },
+        Some(ModSource::Modrinth { ref project_id, .. }) => modrinth
+            .get_mod_versions(project_id, None, None)
+            .map_err(|e| e.to_string())?
+            .iter()
+            .map(|v| parse_date(&v.date_published))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .max()
+            .ok_or_else(|| "Modrinth project has no versions".to_owned())
+            .map(Some),
+        Some(ModSource::Url) | None => Ok(None),
+    }
+}
+
+fn parse_date(date: &str) -> Result<DateTime<Utc>, String> {
+    DateTime::parse_from_rfc3339(date)
+        .map(|date| date.with_timezone(&Utc))
+        .map_err(|e| format!("invalid release date '{date}': {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel, serve_http, test_mod},
+        },
+    };
+
+    use super::*;
+
+    fn versions(dates: &[&str]) -> Vec<u8> {
+        let versions = dates
+            .iter()
+            .map(|date| {
+                format!(
+                    r#"{{"name":"v","version_number":"1","game_versions":[],"version_type":"release","loaders":[],"id":"v","project_id":"p","files":[],"date_published":"{date}"}}"#
+                )
+            })
+            .collect::<Vec<_>>();
+        format!("[{}]", versions.join(",")).into_bytes()
+    }
+
+    #[test]
+    fn test_staleness_filter() {
+        let node_id = "staleness";
+        let base_url = serve_http(HashMap::from([
+            (
+                "/v2/project/abandoned/version".to_owned(),
+                (200, versions(&["2015-03-01T10:00:00Z", "2016-05-04T12:30:00.123456Z"])),
+            ),
+            (
+                "/v2/project/maintained/version".to_owned(),
+                (200, versions(&["2016-01-01T00:00:00Z", "2999-01-01T00:00:00Z"])),
+            ),
+        ]));
+        let mods_channel = channel(1).0;
+        let input_ids = HashMap::from([("mods".into(), ChannelId::from_str("mod-source").unwrap())]);
+        let node = NodeConfigTypes::StalenessFilter(StalenessFilter::default());
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("mod-source").unwrap(),
+                InputType::ResolvedMods(mods_channel.clone()),
+            )]))
+            .modrinth_base_url(&base_url)
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), ResolvedMods, ctx);
+        let mut inverse_channel = get_output_test!(ChannelId::from_str("staleness::inverse").unwrap(), ResolvedMods, ctx);
+        let mut report_channel = get_output_test!(ChannelId::from_str("staleness::report").unwrap(), Text, ctx);
+
+        let with_source = |name: &str, source: Option<ModSource>| {
+            let mut m = test_mod(name);
+            m.source = source;
+            m
+        };
+        let abandoned = with_source(
+            "abandoned",
+            Some(ModSource::Modrinth {
+                project_id: "abandoned".to_owned(),
+                version_id: "v".to_owned(),
+            }),
+        );
+        let maintained = with_source(
+            "maintained",
+            Some(ModSource::Modrinth {
+                project_id: "maintained".to_owned(),
+                version_id: "v".to_owned(),
+            }),
+        );
+        let url_mod = with_source("url-mod", Some(ModSource::Url));
+        let curse_mod = with_source(
+            "curse-mod",
+            Some(ModSource::Curse {
+                project_id: 1234,
+                file_id: 5678,
+            }),
+        );
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        mods_channel
+            .send(vec![abandoned.clone(), maintained.clone(), url_mod.clone(), curse_mod.clone()])
+            .unwrap();
+        handle.join().unwrap();
+
+        let timeout = Duration::from_secs(10);
+        assert_eq!(read_channel(&mut out_channel, timeout).unwrap(), vec![maintained, url_mod, curse_mod]);
+        assert_eq!(read_channel(&mut inverse_channel, timeout).unwrap(), vec![abandoned]);
+        assert_eq!(
+            read_channel(&mut report_channel, timeout).unwrap(),
+            "Checked 4 mods, 1 not updated in over 365 days.\n\
+            Stale:\n- abandoned: last updated 2016-05-04\n\
+            Unknown:\n- curse-mod: Curse client is not configured\n- url-mod: no Curse or Modrinth provenance\n"
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    thread::{spawn, JoinHandle},
+};
+
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::{
+    di::container::{DiContainer, InputType, OutputType},
+    file::{filepath::FilePath, filetree::FileTree},
+    template::render,
+};
+
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port},
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Default)]
+pub struct TemplateRender {
+    /// Fail when a placeholder cannot be resolved, instead of leaving it in the output.
+    #[serde(default)]
+    pub strict: bool,
+    /// If set, the rendered text is also emitted on the `files` channel as a file at this path.
+    pub path: Option<String>,
+}
+
+const TEMPLATE: &str = "template";
+
+impl NodeConfig for TemplateRender {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut template_channel = get_input!(TEMPLATE, Text, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Text, ctx)?;
+        let files_out = match self.path {
+            Some(ref path) => {
+                let path = FilePath::from_str(path).map_err(|e| NodeInitError::InvalidOption {
+                    option: "path".into(),
+                    reason: e.to_string(),
+                })?;
+                Some((path, get_output!(ChannelId(node_id.clone(), "files".into()), Files, ctx)?))
+            },
+            None => None,
+        };
+        let configs = ctx.get_configs();
+        let fs = ctx.get_filestore();
+        let strict = self.strict;
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "TemplateRender", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let template = template_channel.blocking_recv().expect_or_log("Failed to receive on template input");
+
+            let lookup = |key: &str| key.strip_prefix("config.").and_then(|k| configs.get(k).cloned());
+            let rendered = match render(&template, lookup, strict) {
+                Ok(rendered) => rendered,
+                Err(e) => {
+                    event!(Level::ERROR, "Failed to render template: {e}");
+                    panic!();
+                },
+            };
+
+            if let Some((path, files_channel)) = files_out {
+                let mut tree = FileTree::new(fs);
+                tree.add_file(path, rendered.clone().into_bytes());
+                if files_channel.send(tree).is_err() {
+                    event!(Level::DEBUG, "Channel 'files' has no subscribers");
+                }
+            }
+            if out_channel.send(rendered).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        let mut channels = HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::Text(channel(1).0))]);
+        if self.path.is_some() {
+            channels.insert(ChannelId(node_id.to_owned(), "files".into()), InputType::Files(channel(1).0));
+        }
+        channels
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "TemplateRender",
+            vec![Port::required(TEMPLATE, ChannelType::Text)],
+            vec![Port::required("default", ChannelType::Text), Port::optional("files", ChannelType::Files)],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_template_render() {
+        let node_id = "render";
+        let template_channel = channel::<String>(1).0;
+        let input_ids = HashMap::from([("template".into(), ChannelId::from_str("template-source").unwrap())]);
+        let node = NodeConfigTypes::TemplateRender(TemplateRender {
+            strict: true,
+            path: Some("server.properties".into()),
+        });
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("template-source").unwrap(),
+                InputType::Text(template_channel.clone()),
+            )]))
+            .set_config("motd", "A Minecraft Server")
+            .set_config("max_players", "20")
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Text, ctx);
+        let mut files_channel = get_output_test!(ChannelId::from_str("render::files").unwrap(), Files, ctx);
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+
+        ctx.run().unwrap();
+        template_channel
+            .send("motd=${config.motd}\nmax-players=${config.max_players}\n".into())
+            .unwrap();
+        handle.join().unwrap();
+
+        let timeout = Duration::from_secs(30);
+        let expected = "motd=A Minecraft Server\nmax-players=20\n";
+        let output = read_channel(&mut out_channel, timeout).unwrap();
+        let files = read_channel(&mut files_channel, timeout).unwrap();
+
+        assert_eq!(output, expected);
+        assert_eq!(
+            std::str::from_utf8(&files.get_file(&FilePath::from_str("server.properties").unwrap()).unwrap()).unwrap(),
+            expected
+        );
+    }
+}
@@ -0,0 +1,257 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    str::FromStr,
+    thread::{spawn, JoinHandle},
+};
+
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::{
+    di::container::{DiContainer, InputType, OutputType},
+    file::{filepath::FilePath, filestore::FileStore, filetree::FileTree},
+};
+
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port},
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct ThreeWayMerge;
+
+/// Which side a merged file is taken from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Resolution {
+    Ours,
+    Theirs,
+    /// Both sides changed the file differently. Our version is kept.
+    Conflict,
+}
+
+impl NodeConfig for ThreeWayMerge {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut base_channel = get_input!("base", Files, ctx, input_ids)?;
+        let mut ours_channel = get_input!("ours", Files, ctx, input_ids)?;
+        let mut theirs_channel = get_input!("theirs", Files, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Files, ctx)?;
+        let conflicts_channel = get_output!(ChannelId(node_id.clone(), "conflicts".into()), Files, ctx)?;
+        let store = ctx.get_filestore();
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "ThreeWayMerge", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let base = base_channel.blocking_recv().expect_or_log("Failed to receive on base input");
+            let ours = ours_channel.blocking_recv().expect_or_log("Failed to receive on ours input");
+            let theirs = theirs_channel.blocking_recv().expect_or_log("Failed to receive on theirs input");
+
+            let (merged, conflicts, conflicting) = merge(&base, &ours, &theirs, store);
+            if !conflicting.is_empty() {
+                event!(Level::WARN, "Files changed on both sides, keeping ours:\n- {}", conflicting.join("\n- "));
+            }
+
+            if out_channel.send(merged).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+            if conflicts_channel.send(conflicts).is_err() {
+                event!(Level::DEBUG, "Channel 'conflicts' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([
+            (ChannelId(node_id.to_owned(), "default".into()), InputType::Files(channel(1).0)),
+            (ChannelId(node_id.to_owned(), "conflicts".into()), InputType::Files(channel(1).0)),
+        ])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "ThreeWayMerge",
+            vec![
+                Port::required("base", ChannelType::Files),
+                Port::required("ours", ChannelType::Files),
+                Port::required("theirs", ChannelType::Files),
+            ],
+            vec![
+                Port::required("default", ChannelType::Files),
+                Port::required("conflicts", ChannelType::Files),
+            ],
+        )
+    }
+}
+
+/// Decide which side of a three-way merge a file is taken from, given its content hash in each
+/// tree. A missing hash means the file is absent, so deletions merge like any other change.
+fn resolve(base: Option<u128>, ours: Option<u128>, theirs: Option<u128>) -> Resolution {
+    if ours == theirs || ours == base {
+        Resolution::Theirs
+    } else if theirs == base {
+        Resolution::Ours
+    } else {
+        Resolution::Conflict
+    }
+}
+
+/// Merge `ours` and `theirs`, which were both derived from `base`. Returns the merged tree, a tree
+/// holding both versions of each conflicting file as `<path>.ours` and `<path>.theirs`, and the
+/// sorted paths of the conflicting files. A side that deleted a conflicting file has no version in
+/// the conflicts tree.
+fn merge(base: &FileTree, ours: &FileTree, theirs: &FileTree, store: FileStore) -> (FileTree, FileTree, Vec<String>) {
+    let paths: BTreeMap<String, &FilePath> = [base, ours, theirs]
+        .into_iter()
+        .flat_map(|tree| tree.list_files())
+        .map(|path| (path.to_string(), path))
+        .collect();
+
+    let mut merged = FileTree::new(store.clone());
+    let mut conflicts = FileTree::new(store);
+    let mut conflicting = vec![];
+    for (name, path) in paths {
+        let take = |tree: &FileTree, into: &mut FileTree, to: &FilePath| {
+            if let Some(data) = tree.get_file(path) {
+                into.add_file(to.clone(), data.to_vec());
+            }
+        };
+        match resolve(base.get_hash(path), ours.get_hash(path), theirs.get_hash(path)) {
+            Resolution::Theirs => take(theirs, &mut merged, path),
+            Resolution::Ours => take(ours, &mut merged, path),
+            Resolution::Conflict => {
+                take(ours, &mut merged, path);
+                take(ours, &mut conflicts, &FilePath::from_str(&format!("{name}.ours")).unwrap_or_log());
+                take(theirs, &mut conflicts, &FilePath::from_str(&format!("{name}.theirs")).unwrap_or_log());
+                conflicting.push(name);
+            },
+        }
+    }
+    (merged, conflicts, conflicting)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel},
+        },
+    };
+
+    use super::*;
+
+    fn tree(files: &[(&str, &str)]) -> FileTree {
+        let mut tree = FileTree::new(FileStore::new());
+        for (path, contents) in files {
+            tree.add_file(FilePath::from_str(path).unwrap(), contents.as_bytes().to_vec());
+        }
+        tree
+    }
+
+    fn contents(tree: &FileTree) -> BTreeMap<String, String> {
+        tree.list_files()
+            .into_iter()
+            .map(|path| (path.to_string(), String::from_utf8(tree.get_file(path).unwrap().to_vec()).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_three_way_merge() {
+        let node_id = "merge";
+        let channels = [channel(1).0, channel(1).0, channel(1).0];
+        let input_ids = HashMap::from([
+            ("base".into(), ChannelId::from_str("base-files").unwrap()),
+            ("ours".into(), ChannelId::from_str("our-files").unwrap()),
+            ("theirs".into(), ChannelId::from_str("their-files").unwrap()),
+        ]);
+        let node = NodeConfigTypes::ThreeWayMerge(ThreeWayMerge);
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([
+                (ChannelId::from_str("base-files").unwrap(), InputType::Files(channels[0].clone())),
+                (ChannelId::from_str("our-files").unwrap(), InputType::Files(channels[1].clone())),
+                (ChannelId::from_str("their-files").unwrap(), InputType::Files(channels[2].clone())),
+            ]))
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Files, ctx);
+        let mut conflicts_channel = get_output_test!(ChannelId::from_str("merge::conflicts").unwrap(), Files, ctx);
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        channels[0]
+            .send(tree(&[
+                ("unchanged.txt", "a"),
+                ("upstream.txt", "a"),
+                ("local.txt", "a"),
+                ("both-same.txt", "a"),
+                ("conflict.txt", "a"),
+                ("deleted-upstream.txt", "a"),
+                ("deleted-locally.txt", "a"),
+                ("edit-vs-delete.txt", "a"),
+            ]))
+            .unwrap();
+        channels[1]
+            .send(tree(&[
+                ("unchanged.txt", "a"),
+                ("upstream.txt", "a"),
+                ("local.txt", "ours"),
+                ("both-same.txt", "same"),
+                ("conflict.txt", "ours"),
+                ("deleted-upstream.txt", "a"),
+                ("edit-vs-delete.txt", "ours"),
+                ("added-locally.txt", "ours"),
+            ]))
+            .unwrap();
+        channels[2]
+            .send(tree(&[
+                ("unchanged.txt", "a"),
+                ("upstream.txt", "theirs"),
+                ("local.txt", "a"),
+                ("both-same.txt", "same"),
+                ("conflict.txt", "theirs"),
+                ("deleted-locally.txt", "a"),
+                ("added-upstream.txt", "theirs"),
+            ]))
+            .unwrap();
+        handle.join().unwrap();
+
+        let timeout = Duration::from_secs(10);
+        let merged = read_channel(&mut out_channel, timeout).unwrap();
+        assert_eq!(
+            contents(&merged),
+            BTreeMap::from([
+                ("added-locally.txt".to_owned(), "ours".to_owned()),
+                ("added-upstream.txt".to_owned(), "theirs".to_owned()),
+                ("both-same.txt".to_owned(), "same".to_owned()),
+                ("conflict.txt".to_owned(), "ours".to_owned()),
+                ("edit-vs-delete.txt".to_owned(), "ours".to_owned()),
+                ("local.txt".to_owned(), "ours".to_owned()),
+                ("unchanged.txt".to_owned(), "a".to_owned()),
+                ("upstream.txt".to_owned(), "theirs".to_owned()),
+            ])
+        );
+        let conflicts = read_channel(&mut conflicts_channel, timeout).unwrap();
+        assert_eq!(
+            contents(&conflicts),
+            BTreeMap::from([
+                ("conflict.txt.ours".to_owned(), "ours".to_owned()),
+                ("conflict.txt.theirs".to_owned(), "theirs".to_owned()),
+                ("edit-vs-delete.txt.ours".to_owned(), "ours".to_owned()),
+            ])
+        );
+    }
+}
@@ -0,0 +1,204 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    thread::{spawn, JoinHandle},
+};
+
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::{
+    di::container::{DiContainer, InputType, OutputType},
+    file::filetree::FileTree,
+};
+
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port},
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Default)]
+pub struct TreeDiff {
+    /// Fail if the trees differ, instead of only reporting it.
+    #[serde(default)]
+    pub strict: bool,
+}
+
+impl NodeConfig for TreeDiff {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut old_channel = get_input!("old", Files, ctx, input_ids)?;
+        let mut new_channel = get_input!("new", Files, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Text, ctx)?;
+        let strict = self.strict;
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "TreeDiff", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let old = old_channel.blocking_recv().expect_or_log("Failed to receive on old input");
+            let new = new_channel.blocking_recv().expect_or_log("Failed to receive on new input");
+
+            let (compared, differences) = diff_trees(&old, &new);
+            let report = if differences.is_empty() {
+                format!("Compared {compared} files, all identical.\n")
+            } else {
+                format!("Compared {compared} files, {} differ:\n{}\n", differences.len(), differences.join("\n"))
+            };
+
+            if !differences.is_empty() {
+                event!(Level::WARN, "{report}");
+                if strict {
+                    panic!("File trees differ in strict mode");
+                }
+            }
+
+            if out_channel.send(report).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::Text(channel(1).0))])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "TreeDiff",
+            vec![Port::required("old", ChannelType::Files), Port::required("new", ChannelType::Files)],
+            vec![Port::required("default", ChannelType::Text)],
+        )
+    }
+}
+
+/// Compare two trees by the content hashes of their files, without reading the files. Returns the
+/// number of distinct paths compared and a line describing each difference, sorted by path.
+fn diff_trees(old: &FileTree, new: &FileTree) -> (usize, Vec<String>) {
+    let paths: BTreeSet<String> = old
+        .list_files()
+        .into_iter()
+        .chain(new.list_files())
+        .map(|path| path.to_string())
+        .collect();
+    let by_name = |tree: &FileTree| -> HashMap<String, u128> {
+        tree.list_files()
+            .into_iter()
+            .filter_map(|path| tree.get_hash(path).map(|hash| (path.to_string(), hash)))
+            .collect()
+    };
+    let (old_hashes, new_hashes) = (by_name(old), by_name(new));
+    let differences = paths
+        .iter()
+        .filter_map(|path| match (old_hashes.get(path), new_hashes.get(path)) {
+            (Some(_), None) => Some(format!("- {path}: only in old")),
+            (None, Some(_)) => Some(format!("- {path}: only in new")),
+            (Some(a), Some(b)) if a != b => Some(format!("- {path}: contents differ")),
+            _ => None,
+        })
+        .collect();
+    (paths.len(), differences)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        file::{filepath::FilePath, filestore::FileStore},
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel},
+        },
+    };
+
+    use super::*;
+
+    fn tree(files: &[(&str, &str)]) -> FileTree {
+        let mut tree = FileTree::new(FileStore::new());
+        for (path, contents) in files {
+            tree.add_file(FilePath::from_str(path).unwrap(), contents.as_bytes().to_vec());
+        }
+        tree
+    }
+
+    #[test]
+    fn test_tree_diff() {
+        let node_id = "diff";
+        let old_channel = channel(1).0;
+        let new_channel = channel(1).0;
+        let input_ids = HashMap::from([
+            ("old".into(), ChannelId::from_str("old-build").unwrap()),
+            ("new".into(), ChannelId::from_str("new-build").unwrap()),
+        ]);
+        let node = NodeConfigTypes::TreeDiff(TreeDiff::default());
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([
+                (ChannelId::from_str("old-build").unwrap(), InputType::Files(old_channel.clone())),
+                (ChannelId::from_str("new-build").unwrap(), InputType::Files(new_channel.clone())),
+            ]))
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Text, ctx);
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        old_channel
+            .send(tree(&[("mods/a.jar", "a"), ("config/b.toml", "b = 1"), ("config/removed.toml", "")]))
+            .unwrap();
+        // Files stored separately are still compared by content
+        new_channel
+            .send(tree(&[("mods/a.jar", "a"), ("config/b.toml", "b = 2"), ("config/added.toml", "")]))
+            .unwrap();
+        handle.join().unwrap();
+
+        let expected = "Compared 4 files, 3 differ:
+- config/added.toml: only in new
+- config/b.toml: contents differ
+- config/removed.toml: only in old
+";
+        assert_eq!(read_channel(&mut out_channel, Duration::from_secs(10)).unwrap(), expected);
+    }
+
+    #[test]
+    fn strict_fails_on_differences() {
+        let node_id = "diff";
+        let old_channel = channel(1).0;
+        let new_channel = channel(1).0;
+        let input_ids = HashMap::from([
+            ("old".into(), ChannelId::from_str("old-build").unwrap()),
+            ("new".into(), ChannelId::from_str("new-build").unwrap()),
+        ]);
+        let node = NodeConfigTypes::TreeDiff(TreeDiff { strict: true });
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([
+                (ChannelId::from_str("old-build").unwrap(), InputType::Files(old_channel.clone())),
+                (ChannelId::from_str("new-build").unwrap(), InputType::Files(new_channel.clone())),
+            ]))
+            .build();
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        old_channel.send(tree(&[("mods/a.jar", "a")])).unwrap();
+        new_channel.send(tree(&[("mods/a.jar", "b")])).unwrap();
+        assert!(handle.join().is_err());
+    }
+
+    #[test]
+    fn identical_trees() {
+        let files = [("mods/a.jar", "a"), ("config/b.toml", "b = 1")];
+        assert_eq!(diff_trees(&tree(&files), &tree(&files)), (2, vec![]));
+    }
+}
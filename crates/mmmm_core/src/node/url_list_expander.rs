@@ -0,0 +1,249 @@
+use std::{
+    collections::HashMap,
+    thread::{spawn, JoinHandle},
+};
+
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::di::container::{DiContainer, InputType, OutputType};
+
+use super::{
+    config::{ChannelId, ChannelType, ModDefinition, ModDefinitionFields, NodeConfig, NodeDescription, NodeInitError, Port, Side},
+    utils::{get_input, get_output},
+};
+
+/// Modrinth project types that can be resolved as files in a pack.
+const MODRINTH_PROJECT_TYPES: [&str; 5] = ["mod", "plugin", "datapack", "resourcepack", "shader"];
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct UrlListExpander;
+
+impl NodeConfig for UrlListExpander {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut urls_channel = get_input!("urls", List, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Mods, ctx)?;
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "UrlListExpander", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let urls = urls_channel.blocking_recv().expect_or_log("Failed to receive on urls input");
+
+            let mut mods = vec![];
+            let mut failures = vec![];
+            for url in urls.iter().map(|url| url.trim()).filter(|url| !url.is_empty()) {
+                match parse_mod_url(url) {
+                    Ok(def) => mods.push(def),
+                    Err(reason) => failures.push(format!("- {url}: {reason}")),
+                }
+            }
+            if !failures.is_empty() {
+                event!(Level::ERROR, "Unrecognized mod page URLs:\n{}", failures.join("\n"));
+                panic!();
+            }
+
+            if out_channel.send(mods).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::Mods(channel(1).0))])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "UrlListExpander",
+            vec![Port::required("urls", ChannelType::List)],
+            vec![Port::required("default", ChannelType::Mods)],
+        )
+    }
+}
+
+/// Parse a Modrinth or CurseForge project page URL into a mod definition named after the
+/// project's slug. URLs of a specific version or file pin the definition to that file.
+fn parse_mod_url(url: &str) -> Result<ModDefinition, String> {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .ok_or("not an http(s) URL")?;
+    let rest = rest.split(['?', '#']).next().unwrap_or_default();
+    let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+    let segments = path.split('/').filter(|s| !s.is_empty()).collect::<Vec<_>>();
+    match host.to_lowercase().trim_start_matches("www.") {
+        "modrinth.com" => parse_modrinth_path(&segments),
+        "curseforge.com" => parse_curse_path(&segments),
+        _ => Err(format!("unsupported site '{host}'")),
+    }
+}
+
+fn parse_modrinth_path(segments: &[&str]) -> Result<ModDefinition, String> {
+    let (slug, file_id) = match segments {
+        [project_type, slug] if MODRINTH_PROJECT_TYPES.contains(project_type) => (slug, None),
+        [project_type, slug, "version", version] if MODRINTH_PROJECT_TYPES.contains(project_type) => {
+            // Version pages may be linked by version number, which can't be resolved directly
+            if version.len() != 8 || !version.chars().all(|c| c.is_ascii_alphanumeric()) {
+                return Err(format!("'{version}' is not a Modrinth version ID, link the version by its ID instead"));
+            }
+            (slug, Some(version.to_string()))
+        },
+        _ => return Err("not a Modrinth project or version page".to_owned()),
+    };
+    Ok(ModDefinition::Modrinth {
+        id: None,
+        file_id,
+        exclude_versions: Vec::new(),
+        fields: fields(slug),
+    })
+}
+
+fn parse_curse_path(segments: &[&str]) -> Result<ModDefinition, String> {
+    let (slug, file_id) = match segments {
+        ["minecraft", category, slug] if *category != "modpacks" => (slug, None),
+        ["minecraft", category, slug, "files" | "download", file] if *category != "modpacks" => {
+            let file_id = file.parse::<u32>().map_err(|_| format!("'{file}' is not a CurseForge file ID"))?;
+            (slug, Some(file_id))
+        },
+        _ => return Err("not a CurseForge project or file page".to_owned()),
+    };
+    Ok(ModDefinition::Curse {
+        id: None,
+        file_id,
+        exclude_versions: Vec::new(),
+        fields: fields(slug),
+    })
+}
+
+fn fields(slug: &str) -> ModDefinitionFields {
+    ModDefinitionFields {
+        name: slug.to_owned(),
+        side: Side::default(),
+        required: None,
+        default: None,
+        group: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_url_list_expander() {
+        let node_id = "expand";
+        let urls_channel = channel(1).0;
+        let input_ids = HashMap::from([("urls".into(), ChannelId::from_str("url-source").unwrap())]);
+        let node = NodeConfigTypes::UrlListExpander(UrlListExpander);
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("url-source").unwrap(),
+                InputType::List(urls_channel.clone()),
+            )]))
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), Mods, ctx);
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        urls_channel
+            .send(vec![
+                "https://modrinth.com/mod/sodium".into(),
+                "https://modrinth.com/mod/fabric-api/version/Hi8quJUM?tab=changelog".into(),
+                "  https://www.curseforge.com/minecraft/mc-mods/jei  ".into(),
+                "".into(),
+                "https://www.curseforge.com/minecraft/mc-mods/worldedit/files/4586218".into(),
+                "https://curseforge.com/minecraft/texture-packs/faithful-32x/download/4716150".into(),
+            ])
+            .unwrap();
+        handle.join().unwrap();
+
+        let expected: Vec<ModDefinition> = serde_yaml::from_str(
+            r#"---
+- name: sodium
+  source: modrinth
+- name: fabric-api
+  source: modrinth
+  file_id: Hi8quJUM
+- name: jei
+  source: curse
+- name: worldedit
+  source: curse
+  file_id: 4586218
+- name: faithful-32x
+  source: curse
+  file_id: 4716150
+"#,
+        )
+        .unwrap();
+        assert_eq!(read_channel(&mut out_channel, Duration::from_secs(10)).unwrap(), expected);
+    }
+
+    #[test]
+    fn unrecognized_urls_fail() {
+        let node_id = "expand";
+        let urls_channel = channel(1).0;
+        let input_ids = HashMap::from([("urls".into(), ChannelId::from_str("url-source").unwrap())]);
+        let node = NodeConfigTypes::UrlListExpander(UrlListExpander);
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("url-source").unwrap(),
+                InputType::List(urls_channel.clone()),
+            )]))
+            .build();
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        urls_channel
+            .send(vec!["https://modrinth.com/mod/sodium".into(), "https://example.com/mod/sodium".into()])
+            .unwrap();
+        assert!(handle.join().is_err());
+    }
+
+    #[test]
+    fn parse_errors() {
+        let err = |url: &str| parse_mod_url(url).unwrap_err();
+        assert_eq!(err("modrinth.com/mod/sodium"), "not an http(s) URL");
+        assert_eq!(err("https://example.com/mod/sodium"), "unsupported site 'example.com'");
+        assert_eq!(
+            err("https://modrinth.com/modpack/fabulously-optimized"),
+            "not a Modrinth project or version page"
+        );
+        assert_eq!(
+            err("https://modrinth.com/mod/sodium/version/mc1.20.1-0.5.3"),
+            "'mc1.20.1-0.5.3' is not a Modrinth version ID, link the version by its ID instead"
+        );
+        assert_eq!(
+            err("https://www.curseforge.com/minecraft/modpacks/all-the-mods-9"),
+            "not a CurseForge project or file page"
+        );
+        assert_eq!(
+            err("https://www.curseforge.com/minecraft/mc-mods/jei/files/latest"),
+            "'latest' is not a CurseForge file ID"
+        );
+    }
+}
@@ -0,0 +1,217 @@
+use std::{
+    cell::Cell,
+    collections::HashMap,
+    thread::{spawn, JoinHandle},
+};
+
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+use urlencoding::encode;
+
+use crate::{
+    di::container::{DiContainer, InputType, OutputType},
+    template::render,
+};
+
+use super::{
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port, ResolvedMod},
+    utils::{get_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct UrlProxy {
+    /// Template for the proxied URL. See [`url_value`] for the placeholders.
+    pub template: String,
+}
+
+impl NodeConfig for UrlProxy {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let mut mods_channel = get_input!("mods", ResolvedMods, ctx, input_ids)?;
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), ResolvedMods, ctx)?;
+
+        // Render against a sample URL to catch unknown placeholders before any mods arrive
+        let uses_url = Cell::new(false);
+        let lookup = |key: &str| {
+            uses_url.set(true);
+            url_value(key, "https://example.com/mod.jar")
+        };
+        render(&self.template, lookup, true).map_err(|e| NodeInitError::InvalidOption {
+            option: "template".into(),
+            reason: e.to_string(),
+        })?;
+        if !uses_url.get() {
+            return Err(NodeInitError::InvalidOption {
+                option: "template".into(),
+                reason: "must reference the original URL with a placeholder such as ${url}".into(),
+            });
+        }
+
+        let template = self.template.clone();
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "UrlProxy", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let mods = mods_channel.blocking_recv().expect_or_log("Failed to receive on mods input");
+
+            let total = mods.len();
+            let mut proxied = 0;
+            let mods = mods
+                .into_iter()
+                .map(|m| match proxy_url(&template, &m.src) {
+                    Some(src) => {
+                        proxied += 1;
+                        ResolvedMod { src, ..m }
+                    },
+                    None => {
+                        event!(Level::WARN, "Not proxying {}, as its URL '{}' can't be fetched by a proxy", m.name, m.src);
+                        m
+                    },
+                })
+                .collect::<Vec<_>>();
+            event!(Level::INFO, "Proxied {proxied} of {total} mod URLs");
+
+            if out_channel.send(mods).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::ResolvedMods(channel(1).0))])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "UrlProxy",
+            vec![Port::required("mods", ChannelType::ResolvedMods)],
+            vec![Port::required("default", ChannelType::ResolvedMods)],
+        )
+    }
+}
+
+/// Render the proxied form of `url`. Returns [`None`] for URLs a proxy can't fetch, which are
+/// those without a scheme and those still holding `${env.VAR}` placeholders for secrets.
+fn proxy_url(template: &str, url: &str) -> Option<String> {
+    if !url.contains("://") || url.contains("${") {
+        return None;
+    }
+    render(template, |key| url_value(key, url), true).ok()
+}
+
+/// Value of a template placeholder for `url`:
+///
+/// * `url` is the URL percent-encoded for use as a query parameter.
+/// * `url.raw` is the URL as-is.
+/// * `url.scheme` is the scheme, such as `https`.
+/// * `url.host` is the host, with the port if there is one.
+/// * `url.path` is everything after the host, without the leading slash.
+fn url_value(key: &str, url: &str) -> Option<String> {
+    let (scheme, rest) = url.split_once("://").unwrap_or(("", url));
+    let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+    match key {
+        "url" => Some(encode(url).into_owned()),
+        "url.raw" => Some(url.to_owned()),
+        "url.scheme" => Some(scheme.to_owned()),
+        "url.host" => Some(host.to_owned()),
+        "url.path" => Some(path.to_owned()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel, test_mod},
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_url_proxy() {
+        let node_id = "proxy";
+        let mods_channel = channel(1).0;
+        let input_ids = HashMap::from([("mods".into(), ChannelId::from_str("resolver").unwrap())]);
+        let node = NodeConfigTypes::UrlProxy(UrlProxy {
+            template: "http://cache.internal/?url=${url}".into(),
+        });
+
+        let mut ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([(
+                ChannelId::from_str("resolver").unwrap(),
+                InputType::ResolvedMods(mods_channel.clone()),
+            )]))
+            .build();
+
+        let mut out_channel = get_output_test!(ChannelId::from_str(node_id).unwrap(), ResolvedMods, ctx);
+
+        let jei = ResolvedMod {
+            sha256: "abcd".into(),
+            ..test_mod("jei")
+        };
+        let private = ResolvedMod {
+            src: "https://${env.MIRROR_HOST}/private.jar".into(),
+            ..test_mod("private")
+        };
+
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        ctx.run().unwrap();
+        mods_channel.send(vec![jei.clone(), private.clone()]).unwrap();
+        handle.join().unwrap();
+
+        let output = read_channel(&mut out_channel, Duration::from_secs(10)).unwrap();
+        assert_eq!(
+            output,
+            vec![
+                ResolvedMod {
+                    src: "http://cache.internal/?url=https%3A%2F%2Fexample.com%2Fjei.jar".into(),
+                    ..jei
+                },
+                private,
+            ]
+        );
+    }
+
+    #[test]
+    fn path_templates() {
+        let url = "https://cdn.modrinth.com:443/data/AANobbMI/versions/1.0/sodium.jar?x=1";
+        assert_eq!(
+            proxy_url("https://proxy.internal/${url.scheme}/${url.host}/${url.path}", url).as_deref(),
+            Some("https://proxy.internal/https/cdn.modrinth.com:443/data/AANobbMI/versions/1.0/sodium.jar?x=1")
+        );
+        assert_eq!(proxy_url("${url.raw}", "mods/local.jar"), None);
+    }
+
+    #[test]
+    fn rejects_invalid_templates() {
+        let input_ids = HashMap::from([("mods".into(), ChannelId::from_str("resolver").unwrap())]);
+        for template in ["http://cache.internal/${sha}", "http://cache.internal/"] {
+            let node = UrlProxy { template: template.into() };
+            let ctx = DiContainerBuilder::default()
+                .channel_from_node(node.generate_channels("proxy"))
+                .channel_from_node(HashMap::from([(
+                    ChannelId::from_str("resolver").unwrap(),
+                    InputType::ResolvedMods(channel(1).0),
+                )]))
+                .build();
+            let result = node.validate_and_spawn("proxy".into(), &input_ids, &ctx);
+            assert!(matches!(result, Err(NodeInitError::InvalidOption { ref option, .. }) if option == "template"));
+        }
+    }
+}
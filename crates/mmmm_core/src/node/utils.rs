@@ -1,3 +1,12 @@
+use std::str::FromStr;
+
+use api_client::loader::ModLoader;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::config::NodeInitError;
+
 macro_rules! get_output {
     ($channel:expr, $variant:ident, $context:expr) => {
         match $context
@@ -28,18 +37,180 @@ macro_rules! get_input {
     }};
 }
 
+/// Like [`get_input`], but for inputs a node can run without. Returns `None` when the input is
+/// not connected, and fails like [`get_input`] when it is connected to a missing or mistyped channel.
+macro_rules! get_optional_input {
+    ($input_name:expr, $variant:ident, $context:expr, $id_mappings:expr) => {{
+        match $id_mappings.contains_key($input_name) {
+            true => get_input!($input_name, $variant, $context, $id_mappings).map(Some),
+            false => Ok(None),
+        }
+    }};
+}
+
 pub(super) use get_input;
+pub(super) use get_optional_input;
 pub(super) use get_output;
 
+/// Config key holding an optional prefix for cache namespaces.
+pub const CACHE_PREFIX: &str = "cache_prefix";
+
+/// Prepend a cache prefix, if set, to a cache namespace, so that builds using different prefixes
+/// never share cached data.
+pub fn cache_namespace(prefix: Option<&str>, namespace: &str) -> String {
+    match prefix {
+        Some(prefix) => format!("{prefix}::{namespace}"),
+        None => namespace.to_owned(),
+    }
+}
+
+/// Parse the mod loader set in `option`, a node option or config key, which the error names if it
+/// is not a known loader.
+pub fn parse_loader(option: &str, loader: &str) -> Result<ModLoader, NodeInitError> {
+    loader.parse().map_err(|reason| NodeInitError::InvalidOption {
+        option: option.into(),
+        reason,
+    })
+}
+
+/// Parse a Minecraft release version such as `1.20.1` into its numeric parts, which compare in
+/// release order. Returns [`None`] for snapshots and other non-release versions.
+pub fn parse_version(version: &str) -> Option<Vec<u32>> {
+    version.trim().split('.').map(|part| part.parse().ok()).collect()
+}
+
+/// A set of Minecraft versions, written as comma-separated terms. Each term is an exact version
+/// such as `1.20.1`, a wildcard such as `1.20.x` matching every release of 1.20, or an inclusive
+/// range of releases such as `1.20.1..1.20.4`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VersionRange {
+    terms: Vec<VersionTerm>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum VersionTerm {
+    Exact(String),
+    Wildcard(Vec<u32>),
+    Between(Vec<u32>, Vec<u32>),
+}
+
+impl FromStr for VersionRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let release = |version: &str| parse_version(version).ok_or_else(|| format!("'{version}' is not a release version"));
+        let terms = s
+            .split(',')
+            .map(str::trim)
+            .map(|term| {
+                if term.is_empty() {
+                    Err("empty version term".to_owned())
+                } else if let Some((start, end)) = term.split_once("..") {
+                    let (start, end) = (release(start.trim())?, release(end.trim())?);
+                    match start <= end {
+                        true => Ok(VersionTerm::Between(start, end)),
+                        false => Err(format!("range '{term}' ends before it starts")),
+                    }
+                } else if let Some(prefix) = term.strip_suffix(".x").or_else(|| term.strip_suffix(".*")) {
+                    Ok(VersionTerm::Wildcard(release(prefix)?))
+                } else {
+                    Ok(VersionTerm::Exact(term.to_owned()))
+                }
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(VersionRange { terms })
+    }
+}
+
+impl VersionRange {
+    /// List the versions in this set, given every known release version. Known releases are
+    /// listed in the order given, followed by any exact versions that are not releases, such as
+    /// snapshots.
+    pub fn expand<'a>(&self, releases: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+        let mut versions: Vec<String> = releases
+            .into_iter()
+            .filter(|release| {
+                let Some(parts) = parse_version(release) else {
+                    return false;
+                };
+                self.terms.iter().any(|term| match term {
+                    VersionTerm::Exact(version) => version == release,
+                    VersionTerm::Wildcard(prefix) => parts.starts_with(prefix),
+                    VersionTerm::Between(start, end) => (start..=end).contains(&&parts),
+                })
+            })
+            .map(str::to_owned)
+            .collect();
+        for term in &self.terms {
+            if let VersionTerm::Exact(version) = term {
+                if !versions.contains(version) {
+                    versions.push(version.clone());
+                }
+            }
+        }
+        versions
+    }
+}
+
+/// JSON value that keeps object keys in the order they were read, so that a rewritten file only
+/// differs from the original where values were changed. Unlike [`serde_json::Value`], which sorts
+/// object keys.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum OrderedJson {
+    Object(IndexMap<String, OrderedJson>),
+    Array(Vec<OrderedJson>),
+    Scalar(Value),
+}
+
+impl OrderedJson {
+    pub fn get(&self, key: &str) -> Option<&OrderedJson> {
+        match self {
+            OrderedJson::Object(map) => map.get(key),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut OrderedJson> {
+        match self {
+            OrderedJson::Object(map) => map.get_mut(key),
+            _ => None,
+        }
+    }
+
+    pub fn as_array_mut(&mut self) -> Option<&mut Vec<OrderedJson>> {
+        match self {
+            OrderedJson::Array(array) => Some(array),
+            _ => None,
+        }
+    }
+
+    pub fn as_scalar(&self) -> Option<&Value> {
+        match self {
+            OrderedJson::Scalar(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+pub use test_only::*;
+
 #[cfg(test)]
 pub mod test_only {
-    use std::io::Read;
+    use std::collections::HashMap;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
     use std::thread::sleep;
     use std::time::{Duration, Instant};
 
     use serde::Deserialize;
     use thiserror::Error;
     use tokio::sync::broadcast::Receiver;
+
+    use crate::node::config::{ProjectKind, ResolvedMod, Side};
+    use crate::{Cache, CacheError};
     pub fn read_channel<T: Clone>(channel: &mut Receiver<T>, timeout: Duration) -> Result<T, &str> {
         let start = Instant::now();
         let interval = Duration::from_millis(50);
@@ -55,6 +226,102 @@ pub mod test_only {
         }
     }
 
+    /// A request received by a server from [`serve_http_with`].
+    pub struct TestRequest {
+        pub method: String,
+        pub path: String,
+        /// Header names are lowercase.
+        pub headers: HashMap<String, String>,
+    }
+
+    /// Serve HTTP from a local port, answering each request with the status code and body returned
+    /// by `respond`. Returns the base URL of the server.
+    pub fn serve_http_with(respond: impl Fn(&TestRequest) -> (u16, Vec<u8>) + Send + 'static) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().filter_map(Result::ok) {
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut headers = HashMap::new();
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap_or(0) > 2 {
+                    if let Some((name, value)) = line.split_once(':') {
+                        headers.insert(name.trim().to_lowercase(), value.trim().to_owned());
+                    }
+                    line.clear();
+                }
+                let mut parts = request_line.split_whitespace();
+                let request = TestRequest {
+                    method: parts.next().unwrap_or_default().to_owned(),
+                    path: parts.next().unwrap_or_default().to_owned(),
+                    headers,
+                };
+                let (status, body) = respond(&request);
+                write!(
+                    stream,
+                    "HTTP/1.1 {status} Status\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len()
+                )
+                .unwrap();
+                if request.method != "HEAD" {
+                    stream.write_all(&body).unwrap();
+                }
+            }
+        });
+        format!("http://{address}")
+    }
+
+    /// Serve canned HTTP responses from a local port, for tests that would otherwise need network
+    /// access. Each path maps to a status code and body, and unknown paths get a 404.
+    /// Returns the base URL of the server.
+    pub fn serve_http(routes: HashMap<String, (u16, Vec<u8>)>) -> String {
+        serve_http_with(move |request| {
+            // Routes without a query string match any query
+            let path = request.path.as_str();
+            let route = routes.get(path).or_else(|| routes.get(path.split('?').next().unwrap_or_default()));
+            route.cloned().unwrap_or((404, Vec::new()))
+        })
+    }
+
+    /// In-memory [`Cache`], whose entries tests can inspect and modify through `data`.
+    pub struct TestCache {
+        pub data: Arc<Mutex<HashMap<(String, String), String>>>,
+    }
+
+    impl Cache for TestCache {
+        fn put(&self, namespace: &str, key: &str, data: &str) -> Result<(), CacheError> {
+            self.data.lock().unwrap().insert((namespace.to_owned(), key.to_owned()), data.to_owned());
+            Ok(())
+        }
+
+        fn get(&self, namespace: &str, key: &str) -> Result<Option<String>, CacheError> {
+            Ok(self.data.lock().unwrap().get(&(namespace.to_owned(), key.to_owned())).cloned())
+        }
+    }
+
+    /// Build a [`ResolvedMod`] with placeholder metadata, for tests that only care about a few
+    /// fields.
+    pub fn test_mod(name: &str) -> ResolvedMod {
+        ResolvedMod {
+            name: name.to_owned(),
+            title: name.to_owned(),
+            side: Side::Both,
+            required: true,
+            default: true,
+            filename: format!("{name}.jar"),
+            encoded: format!("{name}.jar"),
+            src: format!("https://example.com/{name}.jar"),
+            size: 0,
+            md5: String::new(),
+            sha256: String::new(),
+            source: None,
+            kind: ProjectKind::Mod,
+            group: None,
+        }
+    }
+
     macro_rules! _get_output_test {
         ($channel:expr, $variant:ident, $context:expr) => {
             match $context.get_receiver(&$channel).unwrap() {
@@ -87,6 +354,3 @@ pub mod test_only {
         Ok(toml::from_str::<Config>(&data)?)
     }
 }
-
-#[cfg(test)]
-pub use test_only::*;
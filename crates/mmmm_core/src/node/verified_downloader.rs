@@ -0,0 +1,204 @@
+use std::{
+    collections::HashMap,
+    thread::{spawn, JoinHandle},
+};
+
+use serde::Deserialize;
+use tokio::sync::broadcast::channel;
+use tracing::{event, span, Level};
+use tracing_unwrap::ResultExt;
+
+use crate::di::container::{DiContainer, InputType, OutputType};
+
+use super::{
+    archive_downloader::extract_archive,
+    config::{ChannelId, ChannelType, NodeConfig, NodeDescription, NodeInitError, Port},
+    mod_resolver::sha256hash,
+    utils::{get_input, get_optional_input, get_output},
+};
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct VerifiedDownloader;
+
+const URL: &str = "url";
+const CHECKSUM_URL: &str = "checksum_url";
+const FILTER: &str = "filter";
+
+impl NodeConfig for VerifiedDownloader {
+    fn validate_and_spawn(
+        &self,
+        node_id: String,
+        input_ids: &HashMap<String, ChannelId>,
+        ctx: &DiContainer,
+    ) -> Result<JoinHandle<()>, NodeInitError> {
+        let out_channel = get_output!(ChannelId(node_id.clone(), "default".into()), Files, ctx)?;
+        let mut url_channel = get_input!(URL, Text, ctx, input_ids)?;
+        let mut checksum_channel = get_input!(CHECKSUM_URL, Text, ctx, input_ids)?;
+        let mut filter_channel = get_optional_input!(FILTER, List, ctx, input_ids)?;
+        let fs = ctx.get_filestore();
+        let downloader = ctx.get_downloader();
+        let mut waker = ctx.get_waker();
+        Ok(spawn(move || {
+            let _span = span!(Level::INFO, "VerifiedDownloader", nodeid = node_id).entered();
+            if !waker.blocking_recv().unwrap_or_log() {
+                panic!()
+            }
+
+            let url = url_channel.blocking_recv().expect_or_log("Failed to receive on url input");
+            let checksum_url = checksum_channel.blocking_recv().expect_or_log("Failed to receive on checksum_url input");
+            let filter = filter_channel
+                .as_mut()
+                .map(|channel| channel.blocking_recv().expect_or_log("Failed to receive on filter input"));
+
+            event!(Level::INFO, "Downloading checksums from {}", checksum_url);
+            let checksums = downloader
+                .download_file(&checksum_url)
+                .expect_or_log(&format!("Failed to download checksums from URL {checksum_url}"));
+            let checksums = String::from_utf8_lossy(&checksums);
+            let Some(expected) = find_checksum(&checksums, file_name(&url)) else {
+                event!(Level::ERROR, "No checksum for '{}' found in {}", file_name(&url), checksum_url);
+                panic!()
+            };
+
+            event!(Level::INFO, "Downloading archive from {}", url);
+            let archive = downloader
+                .download_file(&url)
+                .expect_or_log(&format!("Failed to download archive from URL {url}"));
+            let actual = sha256hash(&archive);
+            if !actual.eq_ignore_ascii_case(&expected) {
+                event!(Level::ERROR, "Checksum mismatch for {}: expected {}, got {}", url, expected, actual);
+                panic!()
+            }
+
+            let filetree = extract_archive(archive, filter.as_deref(), &[], fs);
+
+            if out_channel.send(filetree).is_err() {
+                event!(Level::DEBUG, "Channel 'default' has no subscribers");
+            }
+        }))
+    }
+
+    fn generate_channels(&self, node_id: &str) -> HashMap<ChannelId, InputType> {
+        HashMap::from([(ChannelId(node_id.to_owned(), "default".into()), InputType::Files(channel(1).0))])
+    }
+
+    fn describe(&self) -> NodeDescription {
+        NodeDescription::new(
+            "VerifiedDownloader",
+            vec![
+                Port::required(URL, ChannelType::Text),
+                Port::required(CHECKSUM_URL, ChannelType::Text),
+                Port::optional(FILTER, ChannelType::List),
+            ],
+            vec![Port::required("default", ChannelType::Files)],
+        )
+    }
+}
+
+/// The last path segment of a URL, without its query string or fragment.
+fn file_name(url: &str) -> &str {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+/// Find the SHA-256 hash published for `file_name` in a checksum file. Both the `sha256sum` format,
+/// with a `<hash>  <file>` line per file, and a file holding only a bare hash are accepted.
+fn find_checksum(checksums: &str, file_name: &str) -> Option<String> {
+    let is_hash = |hash: &str| hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit());
+    let lines = checksums.lines().map(str::trim).filter(|line| !line.is_empty()).collect::<Vec<_>>();
+    if let [line] = lines.as_slice() {
+        if is_hash(line) {
+            return Some(line.to_string());
+        }
+    }
+    lines.into_iter().find_map(|line| {
+        let (hash, name) = line.split_once(char::is_whitespace)?;
+        // Binary mode entries are marked with a leading asterisk
+        let name = name.trim_start().trim_start_matches('*');
+        let name = name.rsplit('/').next().unwrap_or(name);
+        (is_hash(hash) && name == file_name).then(|| hash.to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Cursor, Write},
+        str::FromStr,
+        time::Duration,
+    };
+
+    use tokio::sync::broadcast::Sender;
+    use zip::{write::FileOptions, ZipWriter};
+
+    use crate::{
+        di::container::DiContainerBuilder,
+        file::filepath::FilePath,
+        node::{
+            config::NodeConfigTypes,
+            utils::{get_output_test, read_channel, serve_http},
+        },
+    };
+
+    use super::*;
+
+    fn archive() -> Vec<u8> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        zip.start_file("manifest.json", FileOptions::default()).unwrap();
+        zip.write_all(b"{}").unwrap();
+        zip.finish().unwrap().into_inner()
+    }
+
+    fn spawn_node(checksums: String) -> (JoinHandle<()>, DiContainer, [Sender<String>; 2]) {
+        let base = serve_http(HashMap::from([
+            ("/pack.zip".into(), (200, archive())),
+            ("/SHA256SUMS".into(), (200, checksums.into_bytes())),
+        ]));
+        let node_id = "verified";
+        let channels = [channel(1).0, channel(1).0];
+        let input_ids = HashMap::from([
+            ("url".into(), ChannelId::from_str("pack-url").unwrap()),
+            ("checksum_url".into(), ChannelId::from_str("checksum-url").unwrap()),
+        ]);
+        let node = NodeConfigTypes::VerifiedDownloader(VerifiedDownloader);
+        let ctx = DiContainerBuilder::default()
+            .channel_from_node(node.generate_channels(node_id))
+            .channel_from_node(HashMap::from([
+                (ChannelId::from_str("pack-url").unwrap(), InputType::Text(channels[0].clone())),
+                (ChannelId::from_str("checksum-url").unwrap(), InputType::Text(channels[1].clone())),
+            ]))
+            .build();
+        let handle = node.validate_and_spawn(node_id.into(), &input_ids, &ctx).unwrap();
+        channels[0].send(format!("{base}/pack.zip?download=1")).unwrap();
+        channels[1].send(format!("{base}/SHA256SUMS")).unwrap();
+        (handle, ctx, channels)
+    }
+
+    #[test]
+    fn test_verified_downloader() {
+        let checksums = format!("{}  other.zip\n{} *dist/pack.zip\n", "0".repeat(64), sha256hash(archive()).to_uppercase());
+        let (handle, mut ctx, _channels) = spawn_node(checksums);
+        let mut out_channel = get_output_test!(ChannelId::from_str("verified").unwrap(), Files, ctx);
+        ctx.run().unwrap();
+        handle.join().unwrap();
+
+        let files = read_channel(&mut out_channel, Duration::from_secs(10)).unwrap();
+        assert_eq!(files.get_file(&FilePath::from_str("manifest.json").unwrap()).unwrap().as_slice(), b"{}");
+    }
+
+    #[test]
+    fn mismatch_fails() {
+        let (handle, mut ctx, _channels) = spawn_node(format!("{}  pack.zip\n", "0".repeat(64)));
+        ctx.run().unwrap();
+        assert!(handle.join().is_err());
+    }
+
+    #[test]
+    fn checksum_lookup() {
+        let hash = "a".repeat(64);
+        assert_eq!(find_checksum(&format!("{hash}\n"), "pack.zip"), Some(hash.clone()));
+        assert_eq!(find_checksum(&format!("{hash}  pack.zip"), "other.zip"), None);
+        assert_eq!(find_checksum("not a hash  pack.zip", "pack.zip"), None);
+        assert_eq!(file_name("https://example.com/files/pack.zip?token=1"), "pack.zip");
+    }
+}
@@ -0,0 +1,105 @@
+use thiserror::Error;
+use tracing::{event, Level};
+
+/// Errors produced while rendering a template.
+#[derive(Debug, Error, PartialEq)]
+pub enum TemplateError {
+    #[error("Template references values that could not be resolved: {0:?}")]
+    Unresolved(Vec<String>),
+    #[error("Template has an unterminated placeholder starting at byte {0}")]
+    Unterminated(usize),
+}
+
+/// Replace each `${key}` placeholder in a template with the value returned by `lookup`.
+///
+/// Keys are namespaced by convention (`${config.minecraft_version}`), and it is up to the caller
+/// to decide which namespaces `lookup` understands. A literal `${` can be written as `$${`.
+///
+/// When `strict` is set, any placeholder that `lookup` cannot resolve makes rendering fail with a
+/// list of every unresolved key, and an unterminated placeholder fails immediately. Otherwise both
+/// are left in the output as-is, with a warning.
+pub fn render<F>(template: &str, lookup: F, strict: bool) -> Result<String, TemplateError>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut output = String::with_capacity(template.len());
+    let mut unresolved = Vec::new();
+    let mut rest = template;
+    let mut offset = 0;
+    while let Some(start) = rest.find("${") {
+        if rest[..start].ends_with('$') {
+            // Escaped placeholder, emit "${" verbatim
+            output.push_str(&rest[..start - 1]);
+            output.push_str("${");
+            rest = &rest[start + 2..];
+            offset += start + 2;
+            continue;
+        }
+        output.push_str(&rest[..start]);
+        let end = match rest[start..].find('}') {
+            Some(end) => end + start,
+            None if strict => return Err(TemplateError::Unterminated(offset + start)),
+            None => {
+                event!(Level::WARN, "Leaving unterminated placeholder at byte {} in template", offset + start);
+                rest = &rest[start..];
+                break;
+            },
+        };
+        let key = rest[start + 2..end].trim();
+        match lookup(key) {
+            Some(value) => output.push_str(&value),
+            None => {
+                unresolved.push(key.to_owned());
+                output.push_str(&rest[start..=end]);
+            },
+        }
+        rest = &rest[end + 1..];
+        offset += end + 1;
+    }
+    output.push_str(rest);
+
+    if unresolved.is_empty() {
+        Ok(output)
+    } else if strict {
+        Err(TemplateError::Unresolved(unresolved))
+    } else {
+        event!(Level::WARN, "Leaving unresolved placeholders in template: {}", unresolved.join(", "));
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn lookup(key: &str) -> Option<String> {
+        let values = HashMap::from([("config.minecraft_version", "1.12.2"), ("config.motd", "Hello")]);
+        values.get(key).map(|v| v.to_string())
+    }
+
+    #[test]
+    fn render_placeholders() {
+        assert_eq!(
+            render("motd=${config.motd}\nversion=${ config.minecraft_version }", lookup, true).unwrap(),
+            "motd=Hello\nversion=1.12.2"
+        );
+        assert_eq!(render("no placeholders", lookup, true).unwrap(), "no placeholders");
+        assert_eq!(render("cost: $${config.motd}", lookup, true).unwrap(), "cost: ${config.motd}");
+    }
+
+    #[test]
+    fn unresolved_placeholders() {
+        assert_eq!(
+            render("${config.missing} ${config.motd} ${env.HOME}", lookup, true).unwrap_err(),
+            TemplateError::Unresolved(vec!["config.missing".into(), "env.HOME".into()])
+        );
+        assert_eq!(
+            render("${config.missing} ${config.motd}", lookup, false).unwrap(),
+            "${config.missing} Hello"
+        );
+        assert_eq!(render("abc ${config.motd", lookup, true).unwrap_err(), TemplateError::Unterminated(4));
+        assert_eq!(render("${config.motd} ${config.motd", lookup, false).unwrap(), "Hello ${config.motd");
+    }
+}
@@ -1,16 +1,29 @@
-use std::{fs, path::Path};
+use std::{
+    fs,
+    io::{Read, Write},
+    path::Path,
+};
 
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
 use mmmm_core::{Cache, CacheError};
 use r2d2::{Pool, PooledConnection};
 use r2d2_sqlite::SqliteConnectionManager;
-use rusqlite::{params, OptionalExtension};
+use rusqlite::{params, types::Value, OptionalExtension};
+
+/// Values shorter than this are stored uncompressed even when compression is enabled, as the gzip
+/// header outweighs the savings. Cached mod metadata is usually around 500 bytes, which
+/// compresses by about a third.
+const COMPRESSION_THRESHOLD: usize = 256;
 
 pub struct SqliteCache {
     pool: Pool<SqliteConnectionManager>,
+    compress: bool,
 }
 
 impl SqliteCache {
-    pub fn new<P>(location: P, clear: bool) -> color_eyre::Result<Self>
+    /// Open the cache database in `location`. If `compress` is set, larger values are stored
+    /// gzip-compressed. Compressed and uncompressed values can be read either way.
+    pub fn new<P>(location: P, clear: bool, compress: bool) -> color_eyre::Result<Self>
     where
         P: AsRef<Path>,
     {
@@ -23,38 +36,151 @@ impl SqliteCache {
         let pool = r2d2::Pool::builder()
             .max_size(1)
             .build(manager)?;
-        pool.get()?
-            .execute("CREATE TABLE IF NOT EXISTS cache (namespace TEXT, key TEXT, data TEXT)", params![])?;
-        Ok(Self { pool })
+        let conn = pool.get()?;
+        conn.execute("CREATE TABLE IF NOT EXISTS cache (namespace TEXT, key TEXT, data TEXT)", params![])?;
+        let indexed: bool = conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'index' AND name = 'cache_key')",
+            params![],
+            |row| row.get(0),
+        )?;
+        if !indexed {
+            // Older caches may hold several rows for a key. Keep the first, which is the one reads
+            // returned, so that the key can be made unique.
+            conn.execute_batch(
+                "BEGIN;
+                DELETE FROM cache WHERE rowid NOT IN (SELECT MIN(rowid) FROM cache GROUP BY namespace, key);
+                CREATE UNIQUE INDEX cache_key ON cache (namespace, key);
+                COMMIT;",
+            )?;
+        }
+        drop(conn);
+        Ok(Self { pool, compress })
     }
 }
 
 impl Cache for SqliteCache {
     fn put(&self, namespace: &str, key: &str, data: &str) -> Result<(), CacheError> {
         let conn: PooledConnection<SqliteConnectionManager> = self.pool.get().map_err(from_r2d2)?;
-        conn.execute("INSERT INTO cache (namespace, key, data) VALUES (?1, ?2, ?3)", (namespace, key, data))
-            .map_err(from_rusqlite)?;
+        // SQLite does not enforce column types, so compressed values are stored as BLOBs in the
+        // TEXT column, and told apart from plain text by their storage class when read.
+        let value = match self.compress && data.len() >= COMPRESSION_THRESHOLD {
+            true => Value::Blob(compress(data).map_err(from_io)?),
+            false => Value::Text(data.to_owned()),
+        };
+        conn.execute(
+            "INSERT OR REPLACE INTO cache (namespace, key, data) VALUES (?1, ?2, ?3)",
+            (namespace, key, value),
+        )
+        .map_err(from_rusqlite)?;
         Ok(())
     }
 
     fn get(&self, namespace: &str, key: &str) -> Result<Option<String>, CacheError> {
         let conn = self.pool.get().map_err(from_r2d2)?;
-        conn.query_row("SELECT data FROM cache WHERE namespace = ?1 AND key = ?2", (namespace, key), |row| {
-            row.get(0)
-        })
-        .optional()
-        .map_err(from_rusqlite)
+        let value: Option<Value> = conn
+            .query_row("SELECT data FROM cache WHERE namespace = ?1 AND key = ?2", (namespace, key), |row| {
+                row.get(0)
+            })
+            .optional()
+            .map_err(from_rusqlite)?;
+        match value {
+            Some(Value::Text(data)) => Ok(Some(data)),
+            Some(Value::Blob(data)) => decompress(&data).map(Some).map_err(from_io),
+            Some(other) => Err(CacheError {
+                msg: format!("unexpected cached value type: {:?}", other.data_type()),
+            }),
+            None => Ok(None),
+        }
     }
 }
 
+fn compress(data: &str) -> std::io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data.as_bytes())?;
+    encoder.finish()
+}
+
+fn decompress(data: &[u8]) -> std::io::Result<String> {
+    let mut decompressed = String::new();
+    GzDecoder::new(data).read_to_string(&mut decompressed)?;
+    Ok(decompressed)
+}
+
 fn from_r2d2(value: r2d2::Error) -> CacheError {
     CacheError {
-        msg: format!("r2d2 error: {}", value.to_string()),
+        msg: format!("r2d2 error: {}", value),
     }
 }
 
 fn from_rusqlite(value: rusqlite::Error) -> CacheError {
     CacheError {
-        msg: format!("rusqlite error: {}", value.to_string()),
+        msg: format!("rusqlite error: {}", value),
+    }
+}
+
+fn from_io(value: std::io::Error) -> CacheError {
+    CacheError {
+        msg: format!("compression error: {}", value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compressed_values_round_trip() {
+        let dir = std::env::temp_dir().join(format!("mmmm-cache-test-{}", std::process::id()));
+        let large = "x".repeat(COMPRESSION_THRESHOLD * 4);
+
+        let compressed = SqliteCache::new(&dir, false, true).unwrap();
+        compressed.put("ns", "small", "small value").unwrap();
+        compressed.put("ns", "large", &large).unwrap();
+        let conn = compressed.pool.get().unwrap();
+        let stored: Value = conn
+            .query_row("SELECT data FROM cache WHERE key = 'large'", [], |row| row.get(0))
+            .unwrap();
+        assert!(matches!(stored, Value::Blob(ref blob) if blob.len() < large.len()));
+        drop(conn);
+        drop(compressed);
+
+        // Values written either way remain readable when compression is turned off
+        let plain = SqliteCache::new(&dir, false, false).unwrap();
+        plain.put("ns", "plain", &large).unwrap();
+        assert_eq!(plain.get("ns", "small").unwrap().as_deref(), Some("small value"));
+        assert_eq!(plain.get("ns", "large").unwrap(), Some(large.clone()));
+        assert_eq!(plain.get("ns", "plain").unwrap(), Some(large));
+        assert_eq!(plain.get("ns", "missing").unwrap(), None);
+
+        plain.put("ns", "small", "replaced").unwrap();
+        assert_eq!(plain.get("ns", "small").unwrap().as_deref(), Some("replaced"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn duplicate_keys_removed_on_open() {
+        let dir = std::env::temp_dir().join(format!("mmmm-cache-dedup-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let conn = rusqlite::Connection::open(dir.join("mmmm.db")).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE cache (namespace TEXT, key TEXT, data TEXT);
+            INSERT INTO cache VALUES ('ns', 'key', 'first'), ('ns', 'key', 'second'), ('other', 'key', 'other');",
+        )
+        .unwrap();
+        drop(conn);
+
+        let cache = SqliteCache::new(&dir, false, false).unwrap();
+        assert_eq!(cache.get("ns", "key").unwrap().as_deref(), Some("first"));
+        assert_eq!(cache.get("other", "key").unwrap().as_deref(), Some("other"));
+        cache.put("ns", "key", "replaced").unwrap();
+        assert_eq!(cache.get("ns", "key").unwrap().as_deref(), Some("replaced"));
+        let conn = cache.pool.get().unwrap();
+        let rows: u32 = conn.query_row("SELECT COUNT(*) FROM cache", [], |row| row.get(0)).unwrap();
+        assert_eq!(rows, 2);
+        drop(conn);
+        drop(cache);
+
+        fs::remove_dir_all(dir).unwrap();
     }
 }
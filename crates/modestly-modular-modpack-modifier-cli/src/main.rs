@@ -1,13 +1,27 @@
-use std::{fs, io::Write, path::PathBuf, thread, time::Duration};
+use std::{
+    fs,
+    io::Write,
+    path::{Component, Path, PathBuf},
+    sync::Mutex,
+    thread,
+    time::Duration,
+};
 
 use cache::SqliteCache;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use color_eyre::{
-    eyre::{eyre, Context, Result},
+    eyre::{eyre, Context, Report, Result},
     Section,
 };
 use directories::ProjectDirs;
-use mmmm_core::orch::MMMMConfig;
+use mmmm_core::{
+    health::{check_apis, CurseMode},
+    node_descriptions,
+    orch::MMMMConfig,
+    FileTree, Port,
+};
+use serde::Deserialize;
+use serde_json::json;
 use tokio::sync::broadcast::error::TryRecvError;
 use tracing::{event, span, Level};
 use tracing_error::ErrorLayer;
@@ -18,28 +32,53 @@ mod cache;
 
 fn main() -> Result<()> {
     color_eyre::install()?;
-    let indicatif_layer = IndicatifLayer::new();
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::fmt::layer()
-                .with_writer(indicatif_layer.get_stderr_writer())
-                .with_filter(LevelFilter::INFO),
-        )
-        .with(indicatif_layer)
-        .with(ErrorLayer::default())
-        .init();
-
     let args = Args::parse();
-    let pack_def = fs::read_to_string(&args.definition)
-        .wrap_err_with(|| format!("Failed to read pack definition YAML from {}", args.definition.display()))
+    init_logging(&args)?;
+
+    match args.command {
+        Some(Command::Check { config_dir }) => return check(config_dir),
+        Some(Command::Nodes) => {
+            nodes();
+            return Ok(());
+        },
+        None => {},
+    }
+    // Clap requires a definition whenever no subcommand is given
+    let definition = args.definition.expect("pack definition path is required");
+    let pack_def = fs::read_to_string(&definition)
+        .wrap_err_with(|| format!("Failed to read pack definition YAML from {}", definition.display()))
         .suggestion("Provide a valid path to a pack definition YAML file")?;
-    let global_config: MMMMConfig = get_config(args.config_dir)?;
+    let CliConfig {
+        core: mut global_config,
+        compress_cache,
+    } = get_config(args.config_dir)?;
+    let jobs = match args.jobs.map(usize::from).or(global_config.jobs) {
+        Some(jobs) => jobs,
+        None => thread::available_parallelism()
+            .wrap_err("Could not determine available parallelism")
+            .suggestion("Set the number of workers with --jobs")?
+            .get(),
+    };
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs)
+        .build_global()
+        .wrap_err("Failed to configure worker thread pool")?;
+    global_config.jobs = Some(jobs);
+    global_config.offline |= args.offline;
+    global_config.cache_prefix_override = args.cache_prefix;
+    if global_config.offline && args.clear_cache {
+        return Err(eyre!("Cannot clear the cache in offline mode, as nothing could be resolved"))
+            .suggestion("Run once without --offline to warm the cache");
+    }
     let project_dirs = get_project_dirs()?;
     let cache_dir = project_dirs.cache_dir();
-    let cache = SqliteCache::new(cache_dir, args.clear_cache)?;
+    let cache = SqliteCache::new(cache_dir, args.clear_cache, compress_cache)?;
     let mut graph = mmmm_core::orch::build_graph(&pack_def, global_config, Some(Box::new(cache)))
         .wrap_err("Failed to construct node graph")
         .suggestion("Confirm that the pack definition is valid")?;
+    for filename in graph.outputs.keys() {
+        check_output_filename(filename)?;
+    }
     graph
         .context
         .run()
@@ -53,6 +92,7 @@ fn main() -> Result<()> {
             .suggestion("Specify config and output paths")?,
     };
 
+    let max_sizes = graph.max_sizes;
     let mut file_outputs = vec![];
     let mut zip_outputs = vec![];
     for output in graph.outputs {
@@ -63,11 +103,19 @@ fn main() -> Result<()> {
         }
     }
     let tick_rate = Duration::from_millis(100);
+    let mut failed_outputs = vec![];
+    // Only the first error writing an output is returned, once the other outputs are written
+    let mut write_error: Option<Report> = None;
     loop {
         file_outputs.retain_mut(|channel| match channel.1.try_recv() {
             Ok(data) => {
                 let out_path = output_dir.join::<PathBuf>(channel.0.clone().into());
                 println!("Output ready, writing to {}", out_path.display());
+                if let Err(error) = create_parent_dir(&out_path) {
+                    event!(Level::ERROR, "Failed to write output {}", channel.0);
+                    write_error.get_or_insert(error);
+                    return false;
+                }
                 fs::write(&out_path, data)
                     .wrap_err(format!("Could not write to file {}", out_path.display()))
                     .suggestion("Ensure the parent directory exists, and that the current user has write access to it")
@@ -75,14 +123,29 @@ fn main() -> Result<()> {
                 println!("Finished writing to {}", out_path.display());
                 false
             },
-            Err(TryRecvError::Closed) => false,
+            Err(TryRecvError::Closed) => {
+                failed_outputs.push(channel.0.clone());
+                false
+            },
             _ => true,
         });
 
         zip_outputs.retain_mut(|channel| match channel.1.try_recv() {
             Ok(data) => {
-                let out_path = output_dir.join::<PathBuf>(channel.0.clone().into()).with_extension("zip");
+                if let Some(max_size) = max_sizes.get(&channel.0) {
+                    if let Err(error) = write_split_zip(&output_dir.join::<PathBuf>(channel.0.clone().into()), &data, *max_size) {
+                        event!(Level::ERROR, "Failed to write output {}", channel.0);
+                        write_error.get_or_insert(error);
+                    }
+                    return false;
+                }
+                let out_path = archive_path(output_dir.join::<PathBuf>(channel.0.clone().into()));
                 writeln!(get_indicatif_stderr_writer().unwrap(), "Output ready, writing to {}", out_path.display()).unwrap();
+                if let Err(error) = create_parent_dir(&out_path) {
+                    event!(Level::ERROR, "Failed to write output {}", channel.0);
+                    write_error.get_or_insert(error);
+                    return false;
+                }
                 let mut out_file = fs::File::create(&out_path)
                     .wrap_err(format!("Could not write to file {}", out_path.display()))
                     .suggestion("Ensure the parent directory exists, and that the current user has write access to it")
@@ -97,7 +160,10 @@ fn main() -> Result<()> {
                 .unwrap();
                 false
             },
-            Err(TryRecvError::Closed) => false,
+            Err(TryRecvError::Closed) => {
+                failed_outputs.push(channel.0.clone());
+                false
+            },
             _ => true,
         });
 
@@ -108,10 +174,151 @@ fn main() -> Result<()> {
 
         thread::sleep(tick_rate);
     }
+
+    // An output channel that closes without sending means its node failed.
+    if !failed_outputs.is_empty() {
+        return Err(eyre!("No data was produced for outputs: {}", failed_outputs.join(", ")));
+    }
+    match write_error {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
+
+/// Output filenames may contain subdirectories, but must stay within the output directory.
+fn check_output_filename(filename: &str) -> Result<()> {
+    if Path::new(filename)
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        Ok(())
+    } else {
+        Err(eyre!("Output filename {filename} is not a relative path inside the output directory"))
+            .suggestion("Remove any leading '/' and '..' from output filenames")
+    }
+}
+
+/// Path of the archive written for a Files output. Modrinth packs keep their `.mrpack` extension,
+/// and any other extension is replaced with `.zip`.
+fn archive_path(path: PathBuf) -> PathBuf {
+    match path.extension().is_some_and(|extension| extension == "mrpack") {
+        true => path,
+        false => path.with_extension("zip"),
+    }
+}
+
+/// Create the parent directories of an output file, for filenames with subdirectories.
+fn create_parent_dir(out_path: &Path) -> Result<()> {
+    if let Some(parent) = out_path.parent() {
+        fs::create_dir_all(parent)
+            .wrap_err_with(|| format!("Could not create output directory {}", parent.display()))
+            .suggestion("Ensure that the current user has write access to the output directory")?;
+    }
+    Ok(())
+}
+
+/// Write a Files output as numbered archives of at most `max_size` bytes each, along with a JSON
+/// manifest listing the files in each archive.
+fn write_split_zip(out_path: &Path, data: &FileTree, max_size: u64) -> Result<()> {
+    let parts = data
+        .partition(max_size)
+        .wrap_err(format!("Could not split {} into archives", out_path.display()))
+        .suggestion("Increase max_size to fit the largest file in the output")?;
+    create_parent_dir(out_path)?;
+    let mut archives = vec![];
+    for (i, part) in parts.iter().enumerate() {
+        let part_path = out_path.with_extension(format!("part{}.zip", i + 1));
+        let mut out_file = fs::File::create(&part_path)
+            .wrap_err(format!("Could not write to file {}", part_path.display()))
+            .suggestion("Ensure that the current user has write access to the output directory")?;
+        let bytes = part.zip(&mut out_file).wrap_err("Failed to write to file buffer")?;
+        writeln!(
+            get_indicatif_stderr_writer().unwrap(),
+            "Finished writing to {}. Wrote {} bytes.",
+            part_path.display(),
+            bytes
+        )?;
+        let mut files = part.list_files().into_iter().map(|path| path.to_string()).collect::<Vec<_>>();
+        files.sort();
+        let filename = part_path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        archives.push(json!({ "filename": filename, "files": files }));
+    }
+    let manifest_path = out_path.with_extension("parts.json");
+    let manifest = serde_json::to_string_pretty(&json!({ "archives": archives })).wrap_err("Failed to serialize archive manifest")?;
+    fs::write(&manifest_path, manifest).wrap_err(format!("Could not write to file {}", manifest_path.display()))?;
+    writeln!(
+        get_indicatif_stderr_writer().unwrap(),
+        "Finished writing archive manifest to {}",
+        manifest_path.display()
+    )?;
     Ok(())
 }
 
-fn get_config(override_dir: Option<PathBuf>) -> Result<MMMMConfig> {
+/// Check connectivity to the Curse and Modrinth APIs with the configured credentials.
+fn check(config_dir: Option<PathBuf>) -> Result<()> {
+    let global_config = get_config(config_dir)?.core;
+    let report = check_apis(&global_config);
+
+    match report.curse_mode {
+        CurseMode::ApiKey => println!("Curse: using API key"),
+        CurseMode::Proxy(ref url) => println!("Curse: using proxy {url}"),
+        CurseMode::None => println!("Curse: not configured, Curse mods cannot be resolved"),
+    }
+    match report.curse {
+        Some(Ok(())) => println!("Curse: OK"),
+        Some(Err(ref e)) => println!("Curse: FAILED ({e})"),
+        None => {},
+    }
+    let modrinth_url = report.modrinth_base_url.as_deref().unwrap_or("official API");
+    match report.modrinth {
+        Ok(()) => println!("Modrinth ({modrinth_url}): OK"),
+        Err(ref e) => println!("Modrinth ({modrinth_url}): FAILED ({e})"),
+    }
+
+    if !report.is_healthy() {
+        return Err(eyre!("One or more APIs could not be reached")).suggestion("Check your network connection and the keys in mmmm.toml");
+    }
+    Ok(())
+}
+
+fn nodes() {
+    let port = |port: &Port| match port.optional {
+        true => format!("{} ({}, optional)", port.name, port.kind),
+        false => format!("{} ({})", port.name, port.kind),
+    };
+    for description in node_descriptions() {
+        println!("{}", description.kind);
+        let mut inputs = description.inputs.iter().map(port).collect::<Vec<_>>();
+        if let Some(kind) = description.any_inputs {
+            inputs.push(format!("any name ({kind}, any number)"));
+        }
+        match inputs.is_empty() {
+            true => println!("  inputs: none"),
+            false => println!("  inputs: {}", inputs.join(", ")),
+        }
+        let mut outputs = description.outputs.iter().map(port).collect::<Vec<_>>();
+        if let Some(kind) = description.any_outputs {
+            outputs.push(format!("named by options ({kind})"));
+        }
+        println!("  outputs: {}", outputs.join(", "));
+        if !description.config_keys.is_empty() {
+            println!("  config: {}", description.config_keys.join(", "));
+        }
+    }
+}
+
+/// Settings read from `mmmm.toml`. Settings used only by the CLI are kept out of the config passed
+/// to the node graph.
+#[derive(Debug, Deserialize, Default)]
+struct CliConfig {
+    #[serde(flatten)]
+    core: MMMMConfig,
+    /// Store larger cached values gzip-compressed.
+    #[serde(default)]
+    compress_cache: bool,
+}
+
+fn get_config(override_dir: Option<PathBuf>) -> Result<CliConfig> {
     let _span = span!(Level::DEBUG, "get_config").entered();
     if let Some(dir) = override_dir {
         event!(Level::INFO, "Loading mmmm.toml from user-provided directory");
@@ -134,7 +341,7 @@ fn get_config(override_dir: Option<PathBuf>) -> Result<MMMMConfig> {
             fs::create_dir_all(config_dir).wrap_err_with(|| format!("Failed to initialize config directory {}", config_dir.display()))?;
             fs::write(&config_path, "# Set one of these keys to enable the Curse client\n# Curse API key from https://console.curseforge.com/#/api-keys\n#curse_api_key = \"\"\n# Base URL of a Curse API proxy service\n#curse_proxy_url = \"\"").wrap_err_with(|| format!("Failed to write example config file to {}", config_path.display()))?;
 
-            Ok(MMMMConfig::default())
+            Ok(CliConfig::default())
         },
     }
 }
@@ -143,14 +350,55 @@ fn get_project_dirs() -> Result<ProjectDirs> {
     ProjectDirs::from("dev", "maxwell-lt", "modestly-modular-modpack-modifier").ok_or_else(|| eyre!("Could not find user config directory!"))
 }
 
+/// Set up logging to stderr, and to the log file if one was given. `--quiet` only affects stderr, so
+/// the log file keeps the detail needed to debug a failed build.
+fn init_logging(args: &Args) -> Result<()> {
+    let level = match args.verbose {
+        0 => LevelFilter::INFO,
+        1 => LevelFilter::DEBUG,
+        _ => LevelFilter::TRACE,
+    };
+    let stderr_level = if args.quiet { LevelFilter::WARN } else { level };
+    let file_layer = match &args.log_file {
+        Some(path) => {
+            let file = fs::File::create(path)
+                .wrap_err_with(|| format!("Failed to create log file {}", path.display()))
+                .suggestion("Confirm that the parent directory of the log file exists and is writable")?;
+            Some(
+                tracing_subscriber::fmt::layer()
+                    .with_writer(Mutex::new(file))
+                    .with_ansi(false)
+                    .with_thread_names(true)
+                    .with_filter(level),
+            )
+        },
+        None => None,
+    };
+    let indicatif_layer = IndicatifLayer::new();
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_writer(indicatif_layer.get_stderr_writer())
+                .with_filter(stderr_level),
+        )
+        .with(file_layer)
+        .with(indicatif_layer)
+        .with(ErrorLayer::default())
+        .init();
+    Ok(())
+}
+
 /// CLI frontend for Modestly Modular Modpack Modifier
 ///
 /// Build modpacks by declaring a graph of processing nodes
 #[derive(Parser, Debug)]
-#[command(author, version)]
+#[command(author, version, args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
     /// Path to pack definition YAML file.
-    definition: PathBuf,
+    #[arg(required = true)]
+    definition: Option<PathBuf>,
     /// Directory where output files should be written. Default is current directory.
     #[arg(short, long)]
     output_dir: Option<PathBuf>,
@@ -163,4 +411,59 @@ struct Args {
     /// Clear all cached data before running.
     #[arg(long)]
     clear_cache: bool,
+    /// Build only from cached data, failing instead of accessing the network.
+    #[arg(long)]
+    offline: bool,
+    /// Maximum number of worker threads and concurrent downloads. Default is the available
+    /// parallelism of the machine.
+    #[arg(short, long, value_parser = clap::value_parser!(u16).range(1..))]
+    jobs: Option<u16>,
+    /// Prefix for cache namespaces. Builds using different prefixes do not share cached data.
+    /// Takes precedence over a `cache_prefix` value in the pack config.
+    #[arg(long)]
+    cache_prefix: Option<String>,
+    /// Log more detail. Pass once for debug logs, and twice for trace logs.
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+    /// Only log warnings and errors to the terminal.
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+    /// Also write logs to this file, without colors. Not affected by --quiet.
+    #[arg(long, global = true)]
+    log_file: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Check that the Curse and Modrinth APIs are reachable with the configured credentials,
+    /// without building a pack.
+    Check {
+        /// Directory where the configuration file is located.
+        #[arg(short, long)]
+        config_dir: Option<PathBuf>,
+    },
+    /// List the kinds of node that can be used in a pack definition, with their inputs and outputs.
+    Nodes,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cli_config_keeps_core_settings() {
+        let data = r#"
+jobs = 4
+compress_cache = true
+download_read_timeout = 30
+
+[mirror_map]
+"cdn.modrinth.com" = "mirror.example.com"
+"#;
+        let config: CliConfig = toml::from_str(data).unwrap();
+        assert!(config.compress_cache);
+        assert_eq!(config.core.jobs, Some(4));
+        assert_eq!(config.core.download_read_timeout, Some(30));
+        assert_eq!(config.core.mirror_map["cdn.modrinth.com"], "mirror.example.com");
+    }
 }